@@ -0,0 +1,115 @@
+// Allowance transfer circuit for zPump
+// A spender moving an allowance owner's notes, for execute_transfer_from in allowance mode.
+// The owner's notes are hash(secret, amount, owner_tag), where owner_tag is the field element of
+// the owner key the pool derives from the allowance (allowance_tag). Proves:
+// 1. Each non-zero input note is a leaf of the pool tree with the given root, under owner_tag
+// 2. Each nullifier is hash(secret, leaf index) of its input note
+// 3. The payment commitment is hash(secret, amount, tag) for the public amount, which the pool
+//    debits from the allowance
+// 4. The change commitment returns what is left of the inputs to the owner under owner_tag
+//
+// Zero-amount inputs are dummies: their membership check is skipped, but they still
+// need a fresh secret so the nullifier is unique.
+
+pragma circom 2.0.0;
+
+include "../node_modules/circomlib/circuits/poseidon.circom";
+include "../node_modules/circomlib/circuits/comparators.circom";
+include "../node_modules/circomlib/circuits/bitify.circom";
+include "../merkle.circom";
+
+template AllowanceTransfer(nIns, depth) {
+    // Public inputs, in the order the pool rebuilds them:
+    // [root][nullifiers][payment_commitment][change_commitment][owner_tag][amount]
+    signal input root;
+    signal input nullifiers[nIns];
+    signal input payment_commitment;
+    signal input change_commitment;
+    signal input owner_tag;
+    signal input amount;
+    
+    // Private inputs (witness)
+    signal input in_amount[nIns];
+    signal input in_secret[nIns];
+    signal input in_path_elements[nIns][depth];
+    signal input in_path_indices[nIns][depth];
+    signal input payment_secret;
+    signal input payment_tag;
+    signal input change_secret;
+    
+    component in_commitment[nIns];
+    component in_nullifier[nIns];
+    component in_tree[nIns];
+    component in_is_dummy[nIns];
+    component in_root_check[nIns];
+    component in_range[nIns];
+    
+    var in_total = 0;
+    for (var i = 0; i < nIns; i++) {
+        in_range[i] = Num2Bits(64);
+        in_range[i].in <== in_amount[i];
+        
+        // Only notes under the owner's tag hash to a leaf, so the allowance debited is theirs
+        in_commitment[i] = Poseidon(3);
+        in_commitment[i].inputs[0] <== in_secret[i];
+        in_commitment[i].inputs[1] <== in_amount[i];
+        in_commitment[i].inputs[2] <== owner_tag;
+        
+        var index = 0;
+        for (var j = 0; j < depth; j++) {
+            index += in_path_indices[i][j] * (1 << j);
+        }
+        in_nullifier[i] = Poseidon(2);
+        in_nullifier[i].inputs[0] <== in_secret[i];
+        in_nullifier[i].inputs[1] <== index;
+        in_nullifier[i].out === nullifiers[i];
+        
+        in_tree[i] = MerkleRoot(depth);
+        in_tree[i].leaf <== in_commitment[i].out;
+        for (var j = 0; j < depth; j++) {
+            in_tree[i].path_elements[j] <== in_path_elements[i][j];
+            in_tree[i].path_indices[j] <== in_path_indices[i][j];
+        }
+        
+        in_is_dummy[i] = IsZero();
+        in_is_dummy[i].in <== in_amount[i];
+        in_root_check[i] = ForceEqualIfEnabled();
+        in_root_check[i].enabled <== 1 - in_is_dummy[i].out;
+        in_root_check[i].in[0] <== root;
+        in_root_check[i].in[1] <== in_tree[i].root;
+        
+        in_total += in_amount[i];
+    }
+    
+    // Distinct nullifiers, so one note can't fill both input slots
+    component distinct[nIns * nIns];
+    for (var i = 0; i < nIns; i++) {
+        for (var j = i + 1; j < nIns; j++) {
+            distinct[i * nIns + j] = IsZero();
+            distinct[i * nIns + j].in <== nullifiers[i] - nullifiers[j];
+            distinct[i * nIns + j].out === 0;
+        }
+    }
+    
+    component amount_range = Num2Bits(64);
+    amount_range.in <== amount;
+    component payment = Poseidon(3);
+    payment.inputs[0] <== payment_secret;
+    payment.inputs[1] <== amount;
+    payment.inputs[2] <== payment_tag;
+    payment.out === payment_commitment;
+    
+    // The range check rejects a change that wrapped, i.e. a payment above the inputs
+    signal change_amount;
+    change_amount <== in_total - amount;
+    component change_range = Num2Bits(64);
+    change_range.in <== change_amount;
+    component change = Poseidon(3);
+    change.inputs[0] <== change_secret;
+    change.inputs[1] <== change_amount;
+    change.inputs[2] <== owner_tag;
+    change.out === change_commitment;
+}
+
+// Matches TRANSFER_INPUTS and DEPTH in ptf_pool
+component main {public [root, nullifiers, payment_commitment, change_commitment, owner_tag, amount]} = AllowanceTransfer(2, 32);
@@ -0,0 +1,31 @@
+// Shared Merkle membership template for the pool commitment tree (Poseidon(left, right) nodes)
+
+pragma circom 2.0.0;
+
+include "./node_modules/circomlib/circuits/poseidon.circom";
+
+// Root of the tree holding `leaf` at the position given by `path_indices` (0 = left child)
+template MerkleRoot(depth) {
+    signal input leaf;
+    signal input path_elements[depth];
+    signal input path_indices[depth];
+    signal output root;
+    
+    component hashers[depth];
+    signal nodes[depth + 1];
+    signal left[depth];
+    signal right[depth];
+    
+    nodes[0] <== leaf;
+    for (var i = 0; i < depth; i++) {
+        path_indices[i] * (1 - path_indices[i]) === 0;
+        left[i] <== nodes[i] + path_indices[i] * (path_elements[i] - nodes[i]);
+        right[i] <== path_elements[i] + path_indices[i] * (nodes[i] - path_elements[i]);
+        
+        hashers[i] = Poseidon(2);
+        hashers[i].inputs[0] <== left[i];
+        hashers[i].inputs[1] <== right[i];
+        nodes[i + 1] <== hashers[i].out;
+    }
+    root <== nodes[depth];
+}
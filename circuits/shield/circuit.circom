@@ -13,10 +13,12 @@ include "../node_modules/circomlib/circuits/poseidon.circom";
 template Shield() {
     // Private inputs (witness)
     signal input secret[32];  // 32-byte secret
-    signal input amount;       // Amount to shield
     
-    // Public output (commitment)
-    signal output commitment[32];
+    // Public input: the pool rebuilds [commitment, amount] on-chain, so the proof binds both
+    signal input amount;
+    
+    // Public output (commitment, a single field element)
+    signal output commitment;
     
     // Convert secret bytes to field element
     // Simple approach: use first 31 bytes to avoid field overflow
@@ -32,14 +34,8 @@ template Shield() {
     hash.inputs[1] <== amount;
     hash.inputs[2] <== 0;
     
-    // For now, output hash as field element (will be converted to bytes off-chain)
-    // In production, use Num2Bits to convert field element to bits, then to bytes
-    // Simplified: output hash field element directly (commitment[0] = hash.out, rest = 0)
-    // This is a placeholder - proper conversion requires Num2Bits which adds many constraints
-    commitment[0] <== hash.out;
-    for (var i = 1; i < 32; i++) {
-        commitment[i] <== 0;
-    }
+    commitment <== hash.out;
 }
 
-component main = Shield();
+// Public signals are ordered outputs first: [commitment, amount]
+component main {public [amount]} = Shield();
@@ -1,63 +1,110 @@
 // Transfer circuit for zPump
-// Proves:
-// 1. Knowledge of nullifier_secret that hashes to nullifier_in
-// 2. New commitment_out is hash of new_secret and amount
-// 
-// Production-ready circuit with Poseidon hash from circomlib
-// 
-// NOTE: Simplified version - proper byte conversion requires Num2Bits
+// 2-in-2-out join-split. Proves:
+// 1. Each non-zero input note is a leaf of the pool tree with the given root
+// 2. Each nullifier is hash(secret, leaf index) of its input note
+// 3. Each output commitment is hash(secret, amount, tag): tag 0 is an ordinary note, the same
+//    form shield uses, and a delegation tag makes a delegation note (see transfer_from/)
+// 4. Input and output amounts balance
+//
+// Zero-amount inputs are dummies: their membership check is skipped, but they still
+// need a fresh secret so the nullifier is unique.
 
 pragma circom 2.0.0;
 
 include "../node_modules/circomlib/circuits/poseidon.circom";
+include "../node_modules/circomlib/circuits/comparators.circom";
+include "../node_modules/circomlib/circuits/bitify.circom";
+include "../merkle.circom";
 
-template Transfer() {
+template JoinSplit(nIns, nOuts, depth) {
+    // Public inputs, in the order the pool rebuilds them:
+    // [root][nullifiers][out_commitments]
+    signal input root;
+    signal input nullifiers[nIns];
+    signal input out_commitments[nOuts];
+    
     // Private inputs (witness)
-    signal input nullifier_secret[32];  // Secret for input nullifier
-    signal input new_secret[32];        // Secret for output commitment
-    signal input amount;                // Transfer amount
+    signal input in_amount[nIns];
+    signal input in_secret[nIns];
+    signal input in_path_elements[nIns][depth];
+    signal input in_path_indices[nIns][depth];
+    signal input out_amount[nOuts];
+    signal input out_secret[nOuts];
+    signal input out_tag[nOuts];
     
-    // Public outputs
-    signal output nullifier_in[32];      // Hash of nullifier_secret
-    signal output commitment_out[32];     // Hash of new_secret and amount
-    signal output amount_out;              // Amount (public)
+    component in_commitment[nIns];
+    component in_nullifier[nIns];
+    component in_tree[nIns];
+    component in_is_dummy[nIns];
+    component in_root_check[nIns];
+    component in_range[nIns];
     
-    // Convert nullifier_secret bytes to field element (first 31 bytes)
-    var nullifier_field = 0;
-    for (var i = 0; i < 31; i++) {
-        nullifier_field = nullifier_field * 256 + nullifier_secret[i];
+    var in_total = 0;
+    for (var i = 0; i < nIns; i++) {
+        in_range[i] = Num2Bits(64);
+        in_range[i].in <== in_amount[i];
+        
+        in_commitment[i] = Poseidon(3);
+        in_commitment[i].inputs[0] <== in_secret[i];
+        in_commitment[i].inputs[1] <== in_amount[i];
+        in_commitment[i].inputs[2] <== 0;
+        
+        // Leaf index from the path bits binds the nullifier to one position
+        var index = 0;
+        for (var j = 0; j < depth; j++) {
+            index += in_path_indices[i][j] * (1 << j);
+        }
+        in_nullifier[i] = Poseidon(2);
+        in_nullifier[i].inputs[0] <== in_secret[i];
+        in_nullifier[i].inputs[1] <== index;
+        in_nullifier[i].out === nullifiers[i];
+        
+        in_tree[i] = MerkleRoot(depth);
+        in_tree[i].leaf <== in_commitment[i].out;
+        for (var j = 0; j < depth; j++) {
+            in_tree[i].path_elements[j] <== in_path_elements[i][j];
+            in_tree[i].path_indices[j] <== in_path_indices[i][j];
+        }
+        
+        in_is_dummy[i] = IsZero();
+        in_is_dummy[i].in <== in_amount[i];
+        in_root_check[i] = ForceEqualIfEnabled();
+        in_root_check[i].enabled <== 1 - in_is_dummy[i].out;
+        in_root_check[i].in[0] <== root;
+        in_root_check[i].in[1] <== in_tree[i].root;
+        
+        in_total += in_amount[i];
     }
     
-    // Convert new_secret bytes to field element (first 31 bytes)
-    var new_secret_field = 0;
-    for (var i = 0; i < 31; i++) {
-        new_secret_field = new_secret_field * 256 + new_secret[i];
+    // Distinct nullifiers, so one note can't fill both input slots
+    component distinct[nIns * nIns];
+    for (var i = 0; i < nIns; i++) {
+        for (var j = i + 1; j < nIns; j++) {
+            distinct[i * nIns + j] = IsZero();
+            distinct[i * nIns + j].in <== nullifiers[i] - nullifiers[j];
+            distinct[i * nIns + j].out === 0;
+        }
     }
     
-    // Poseidon hash for nullifier: hash(nullifier_secret)
-    component hash1 = Poseidon(2);
-    hash1.inputs[0] <== nullifier_field;
-    hash1.inputs[1] <== 0;
-    
-    // Poseidon hash for commitment: hash(new_secret, amount)
-    component hash2 = Poseidon(3);
-    hash2.inputs[0] <== new_secret_field;
-    hash2.inputs[1] <== amount;
-    hash2.inputs[2] <== 0;
-    
-    // Output hashes as field elements (simplified)
-    nullifier_in[0] <== hash1.out;
-    for (var i = 1; i < 32; i++) {
-        nullifier_in[i] <== 0;
-    }
+    component out_commitment[nOuts];
+    component out_range[nOuts];
     
-    commitment_out[0] <== hash2.out;
-    for (var i = 1; i < 32; i++) {
-        commitment_out[i] <== 0;
+    var out_total = 0;
+    for (var i = 0; i < nOuts; i++) {
+        out_range[i] = Num2Bits(64);
+        out_range[i].in <== out_amount[i];
+        
+        out_commitment[i] = Poseidon(3);
+        out_commitment[i].inputs[0] <== out_secret[i];
+        out_commitment[i].inputs[1] <== out_amount[i];
+        out_commitment[i].inputs[2] <== out_tag[i];
+        out_commitment[i].out === out_commitments[i];
+        
+        out_total += out_amount[i];
     }
     
-    // Amount is passed through
-    amount_out <== amount;
+    in_total === out_total;
 }
 
-component main = Transfer();
+// Matches TRANSFER_INPUTS, TRANSFER_OUTPUTS and DEPTH in ptf_pool
+component main {public [root, nullifiers, out_commitments]} = JoinSplit(2, 2, 32);
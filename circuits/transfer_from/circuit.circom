@@ -0,0 +1,105 @@
+// Delegated transfer circuit for zPump
+// Spends a delegation note on its owner's behalf without revealing who either party is.
+// A delegation note is hash(secret, cap, tag) with tag = hash(owner_key, spender_key), where each
+// key is hash(private key); ordinary notes use 0 in place of the tag. Proves:
+// 1. The delegation note is a leaf of the pool tree with the given root
+// 2. The nullifier is hash(secret, leaf index) of that note
+// 3. The prover holds the private key of the owner or of the spender the tag names
+// 4. Each output commitment is hash(secret, amount, tag), the same form transfer outputs take
+// 5. The change commitment is a delegation note with the same tag and what is left of the cap
+//
+// The owner revokes a delegation by spending the note back to themselves with their own key.
+
+pragma circom 2.0.0;
+
+include "../node_modules/circomlib/circuits/poseidon.circom";
+include "../node_modules/circomlib/circuits/bitify.circom";
+include "../merkle.circom";
+
+template DelegatedSpend(nOuts, depth) {
+    // Public inputs, in the order the pool rebuilds them:
+    // [root][nullifier][out_commitments][change_commitment]
+    signal input root;
+    signal input nullifier;
+    signal input out_commitments[nOuts];
+    signal input change_commitment;
+    
+    // Private inputs (witness)
+    signal input cap;
+    signal input secret;
+    signal input path_elements[depth];
+    signal input path_indices[depth];
+    signal input signer_private_key;
+    signal input counterparty_key;
+    signal input signer_is_owner;
+    signal input out_amount[nOuts];
+    signal input out_secret[nOuts];
+    signal input out_tag[nOuts];
+    signal input change_secret;
+    
+    // The signer's key takes the owner or spender slot of the tag, the counterparty the other
+    signer_is_owner * (signer_is_owner - 1) === 0;
+    component signer_key = Poseidon(1);
+    signer_key.inputs[0] <== signer_private_key;
+    signal owner_key;
+    signal spender_key;
+    owner_key <== counterparty_key + signer_is_owner * (signer_key.out - counterparty_key);
+    spender_key <== signer_key.out + counterparty_key - owner_key;
+    component tag = Poseidon(2);
+    tag.inputs[0] <== owner_key;
+    tag.inputs[1] <== spender_key;
+    
+    component cap_range = Num2Bits(64);
+    cap_range.in <== cap;
+    component note = Poseidon(3);
+    note.inputs[0] <== secret;
+    note.inputs[1] <== cap;
+    note.inputs[2] <== tag.out;
+    
+    var index = 0;
+    for (var j = 0; j < depth; j++) {
+        index += path_indices[j] * (1 << j);
+    }
+    component note_nullifier = Poseidon(2);
+    note_nullifier.inputs[0] <== secret;
+    note_nullifier.inputs[1] <== index;
+    note_nullifier.out === nullifier;
+    
+    component tree = MerkleRoot(depth);
+    tree.leaf <== note.out;
+    for (var j = 0; j < depth; j++) {
+        tree.path_elements[j] <== path_elements[j];
+        tree.path_indices[j] <== path_indices[j];
+    }
+    tree.root === root;
+    
+    component out_commitment[nOuts];
+    component out_range[nOuts];
+    var out_total = 0;
+    for (var i = 0; i < nOuts; i++) {
+        out_range[i] = Num2Bits(64);
+        out_range[i].in <== out_amount[i];
+        
+        out_commitment[i] = Poseidon(3);
+        out_commitment[i].inputs[0] <== out_secret[i];
+        out_commitment[i].inputs[1] <== out_amount[i];
+        out_commitment[i].inputs[2] <== out_tag[i];
+        out_commitment[i].out === out_commitments[i];
+        
+        out_total += out_amount[i];
+    }
+    
+    // The range check rejects a change that wrapped, i.e. outputs above the cap
+    signal change_amount;
+    change_amount <== cap - out_total;
+    component change_range = Num2Bits(64);
+    change_range.in <== change_amount;
+    component change = Poseidon(3);
+    change.inputs[0] <== change_secret;
+    change.inputs[1] <== change_amount;
+    change.inputs[2] <== tag.out;
+    change.out === change_commitment;
+}
+
+// Matches TRANSFER_OUTPUTS and DEPTH in ptf_pool
+component main {public [root, nullifier, out_commitments, change_commitment]} = DelegatedSpend(2, 32);
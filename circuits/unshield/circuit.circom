@@ -1,42 +1,66 @@
 // Unshield circuit for zPump
-// Proves knowledge of nullifier secret that hashes to nullifier_hash
-// 
-// Production-ready circuit with Poseidon hash from circomlib
-// 
-// NOTE: Simplified version - proper byte conversion requires Num2Bits
+// Spends one note back to a public token account. Proves:
+// 1. The note hash(secret, amount, 0) is a leaf of the pool tree with the given root
+// 2. The nullifier is hash(secret, leaf index) of that note
+// 3. The fee fits in the note amount
+//
+// The recipient hash and fee are public inputs so the proof can't be replayed to another
+// recipient or with a different fee; the pool checks the withdrawn-to account against them.
 
 pragma circom 2.0.0;
 
 include "../node_modules/circomlib/circuits/poseidon.circom";
+include "../node_modules/circomlib/circuits/comparators.circom";
+include "../node_modules/circomlib/circuits/bitify.circom";
+include "../merkle.circom";
 
-template Unshield() {
-    // Private inputs (witness)
-    signal input nullifier_secret[32];  // Secret that generates nullifier
-    signal input amount;                // Amount being unshielded (public input)
+template Unshield(depth) {
+    // Public inputs, in the order the pool rebuilds them:
+    // [root][nullifier][amount][recipient_hash][fee]
+    signal input root;
+    signal input nullifier;
+    signal input amount;
+    signal input recipient_hash;
+    signal input fee;
     
-    // Public outputs
-    signal output nullifier_hash[32];  // Hash of nullifier_secret
-    signal output amount_out;           // Amount (passed through)
+    // Private inputs (witness)
+    signal input secret;
+    signal input path_elements[depth];
+    signal input path_indices[depth];
     
-    // Convert nullifier_secret bytes to field element (first 31 bytes)
-    var nullifier_field = 0;
-    for (var i = 0; i < 31; i++) {
-        nullifier_field = nullifier_field * 256 + nullifier_secret[i];
-    }
+    component amount_range = Num2Bits(64);
+    amount_range.in <== amount;
+    component fee_range = Num2Bits(64);
+    fee_range.in <== fee;
+    component fee_cap = LessEqThan(64);
+    fee_cap.in[0] <== fee;
+    fee_cap.in[1] <== amount;
+    fee_cap.out === 1;
     
-    // Poseidon hash with 2 inputs: nullifier_secret (as field element), padding
-    component hash = Poseidon(2);
-    hash.inputs[0] <== nullifier_field;
-    hash.inputs[1] <== 0;
+    component commitment = Poseidon(3);
+    commitment.inputs[0] <== secret;
+    commitment.inputs[1] <== amount;
+    commitment.inputs[2] <== 0;
     
-    // Output hash as field element (simplified - proper conversion requires Num2Bits)
-    nullifier_hash[0] <== hash.out;
-    for (var i = 1; i < 32; i++) {
-        nullifier_hash[i] <== 0;
+    component tree = MerkleRoot(depth);
+    tree.leaf <== commitment.out;
+    var index = 0;
+    for (var i = 0; i < depth; i++) {
+        tree.path_elements[i] <== path_elements[i];
+        tree.path_indices[i] <== path_indices[i];
+        index += path_indices[i] * (1 << i);
     }
+    tree.root === root;
+    
+    component note_nullifier = Poseidon(2);
+    note_nullifier.inputs[0] <== secret;
+    note_nullifier.inputs[1] <== index;
+    note_nullifier.out === nullifier;
     
-    // Amount is passed through
-    amount_out <== amount;
+    // Bind the recipient into the proof; it is otherwise unconstrained
+    signal recipient_square;
+    recipient_square <== recipient_hash * recipient_hash;
 }
 
-component main = Unshield();
+// Matches DEPTH in ptf_pool
+component main {public [root, nullifier, amount, recipient_hash, fee]} = Unshield(32);
@@ -0,0 +1,229 @@
+//! `circuits/allowance_transfer`: a spender moving an allowance owner's notes.
+
+use anchor_lang::prelude::Pubkey;
+use ark_bn254::Fr;
+use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystemRef, SynthesisError};
+use ptf_pool::instructions::{allowance_tag, TRANSFER_INPUTS};
+use serde_json::{json, Value};
+
+use crate::gadgets::{
+    force_equal_if_enabled, from_bytes, is_zero, leaf_index, merkle_root, num2bits, poseidon, poseidon_native,
+    Builder, Signal,
+};
+use crate::notes::{insert_notes, InputNote, OutputNote};
+use crate::{decimals, Circuit};
+
+#[derive(Clone, Debug)]
+pub struct AllowanceTransfer {
+    pub root: Fr,
+    /// Owner of the allowance; every input note carries their tag
+    pub owner: Pubkey,
+    pub inputs: [InputNote; TRANSFER_INPUTS],
+    /// What leaves the owner, the amount the allowance is debited
+    pub payment: OutputNote,
+    /// The rest of the inputs, returned to the owner under their tag
+    pub change_secret: Fr,
+}
+
+impl AllowanceTransfer {
+    pub fn owner_tag(&self) -> Fr {
+        from_bytes(&allowance_tag(&self.owner))
+    }
+    
+    /// What is left of the inputs, in the field so an overspend wraps as it does in the circuit
+    fn change_amount(&self) -> Fr {
+        self.inputs
+            .iter()
+            .fold(-Fr::from(self.payment.amount), |acc, note| acc + Fr::from(note.amount))
+    }
+    
+    pub fn change_commitment(&self) -> Fr {
+        poseidon_native(&[self.change_secret, self.change_amount(), self.owner_tag()])
+    }
+}
+
+impl ConstraintSynthesizer<Fr> for AllowanceTransfer {
+    fn generate_constraints(self, cs: ConstraintSystemRef<Fr>) -> Result<(), SynthesisError> {
+        let b = Builder::new(cs);
+        // [root][nullifiers][payment_commitment][change_commitment][owner_tag][amount]
+        let root = b.input(self.root)?;
+        let nullifiers = self.inputs.iter().map(|note| b.input(note.nullifier())).collect::<Result<Vec<_>, _>>()?;
+        let payment_commitment = b.input(self.payment.commitment())?;
+        let change_commitment = b.input(self.change_commitment())?;
+        let owner_tag = b.input(self.owner_tag())?;
+        let amount = b.input(Fr::from(self.payment.amount))?;
+        
+        let mut in_total = Signal::zero();
+        for (note, nullifier) in self.inputs.iter().zip(&nullifiers) {
+            let note_amount = b.witness(Fr::from(note.amount))?;
+            let secret = b.witness(note.secret)?;
+            let path_elements = b.witnesses(&note.path)?;
+            let path_indices = b.witnesses(&note.path_indices())?;
+            num2bits(&b, &note_amount, 64)?;
+            
+            // Only notes under the owner's tag hash to a leaf, so the allowance debited is theirs
+            let commitment = poseidon(&b, &[secret.clone(), note_amount.clone(), owner_tag.clone()])?;
+            let index = leaf_index(&path_indices);
+            b.equal(&poseidon(&b, &[secret, index])?, nullifier)?;
+            
+            // Zero-amount inputs are dummies whose membership is not checked
+            let tree_root = merkle_root(&b, &commitment, &path_elements, &path_indices)?;
+            let is_dummy = is_zero(&b, &note_amount)?;
+            force_equal_if_enabled(&b, &Signal::constant(Fr::from(1u64)).sub(&is_dummy), &root, &tree_root)?;
+            
+            in_total = in_total.add(&note_amount);
+        }
+        
+        // Distinct nullifiers, so one note can't fill both input slots
+        for i in 0..nullifiers.len() {
+            for j in i + 1..nullifiers.len() {
+                let same = is_zero(&b, &nullifiers[i].sub(&nullifiers[j]))?;
+                b.equal(&same, &Signal::zero())?;
+            }
+        }
+        
+        let payment_secret = b.witness(self.payment.secret)?;
+        let payment_tag = b.witness(self.payment.tag)?;
+        num2bits(&b, &amount, 64)?;
+        b.equal(&poseidon(&b, &[payment_secret, amount.clone(), payment_tag])?, &payment_commitment)?;
+        
+        // The range check rejects a change that wrapped, i.e. a payment above the inputs
+        let change_amount = in_total.sub(&amount);
+        num2bits(&b, &change_amount, 64)?;
+        let change_secret = b.witness(self.change_secret)?;
+        b.equal(&poseidon(&b, &[change_secret, change_amount, owner_tag])?, &change_commitment)
+    }
+}
+
+impl Circuit for AllowanceTransfer {
+    const NAME: &'static str = "allowance_transfer";
+    
+    fn fixture() -> Self {
+        let owner = Pubkey::new_from_array([9u8; 32]);
+        let tag = from_bytes(&allowance_tag(&owner));
+        let input = |secret: u64, amount: u64| InputNote {
+            secret: Fr::from(secret),
+            amount,
+            tag,
+            leaf_index: 0,
+            path: Vec::new(),
+        };
+        let mut inputs = [input(1005, 400), input(1006, 200)];
+        let root = insert_notes(&mut inputs);
+        Self {
+            root,
+            owner,
+            inputs,
+            payment: OutputNote { secret: Fr::from(2006u64), amount: 450, tag: Fr::from(0u64) },
+            change_secret: Fr::from(2007u64),
+        }
+    }
+    
+    fn public_inputs(&self) -> Vec<Fr> {
+        std::iter::once(self.root)
+            .chain(self.inputs.iter().map(InputNote::nullifier))
+            .chain([
+                self.payment.commitment(),
+                self.change_commitment(),
+                self.owner_tag(),
+                Fr::from(self.payment.amount),
+            ])
+            .collect()
+    }
+    
+    fn input_json(&self) -> Value {
+        let (path_elements, path_indices): (Vec<_>, Vec<_>) = self.inputs.iter().map(InputNote::path_json).unzip();
+        json!({
+            "root": self.root.to_string(),
+            "nullifiers": decimals(self.inputs.iter().map(InputNote::nullifier)),
+            "payment_commitment": self.payment.commitment().to_string(),
+            "change_commitment": self.change_commitment().to_string(),
+            "owner_tag": self.owner_tag().to_string(),
+            "amount": self.payment.amount.to_string(),
+            "in_amount": decimals(self.inputs.iter().map(|note| Fr::from(note.amount))),
+            "in_secret": decimals(self.inputs.iter().map(|note| note.secret)),
+            "in_path_elements": path_elements,
+            "in_path_indices": path_indices,
+            "payment_secret": self.payment.secret.to_string(),
+            "payment_tag": self.payment.tag.to_string(),
+            "change_secret": self.change_secret.to_string(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gadgets::to_bytes;
+    use crate::notes::commitment;
+    use crate::tests::{assert_committed_fixture, assert_satisfied, assert_unsatisfied};
+    use ptf_pool::instructions::TransferPublicInputs;
+
+    #[test]
+    fn fixture_satisfies_the_circuit() {
+        let transfer = AllowanceTransfer::fixture();
+        assert_eq!(transfer.change_amount(), Fr::from(150u64));
+        assert_satisfied(transfer);
+    }
+
+    #[test]
+    fn rejects_notes_of_another_owner() {
+        let mut transfer = AllowanceTransfer::fixture();
+        transfer.owner = Pubkey::new_from_array([8u8; 32]);
+        assert_unsatisfied(transfer);
+
+        // Nor ordinary notes, which carry no owner tag
+        let mut transfer = AllowanceTransfer::fixture();
+        for note in &mut transfer.inputs {
+            note.tag = Fr::from(0u64);
+        }
+        transfer.root = insert_notes(&mut transfer.inputs);
+        assert_unsatisfied(transfer);
+    }
+
+    #[test]
+    fn rejects_a_payment_above_the_inputs() {
+        let mut transfer = AllowanceTransfer::fixture();
+        transfer.payment.amount = 601;
+        assert_unsatisfied(transfer);
+    }
+
+    #[test]
+    fn owner_reclaims_everything_into_an_ordinary_note() {
+        // Reclaim mode: the whole balance leaves the tag, after which the note spends and
+        // unshields through the ordinary circuits
+        let mut transfer = AllowanceTransfer::fixture();
+        transfer.payment.amount = 600;
+        assert_eq!(transfer.payment.tag, Fr::from(0u64));
+        assert_eq!(transfer.change_amount(), Fr::from(0u64));
+        assert_satisfied(transfer);
+    }
+
+    #[test]
+    fn change_returns_to_the_owner() {
+        let transfer = AllowanceTransfer::fixture();
+        let change = commitment(transfer.change_secret, 150, transfer.owner_tag());
+        assert_eq!(transfer.change_commitment(), change);
+    }
+
+    #[test]
+    fn public_inputs_match_the_pool_encoding() {
+        let transfer = AllowanceTransfer::fixture();
+        let encoded: Vec<u8> = transfer.public_inputs().iter().flat_map(to_bytes).collect();
+        let inputs = TransferPublicInputs {
+            root: to_bytes(&transfer.root),
+            nullifiers: transfer.inputs.each_ref().map(|note| to_bytes(&note.nullifier())),
+            commitments: [to_bytes(&transfer.payment.commitment()), to_bytes(&transfer.change_commitment())],
+        };
+        // The pool appends the owner's tag and the debited amount to what the spender sends
+        let mut expected = inputs.to_bytes();
+        expected.extend_from_slice(&allowance_tag(&transfer.owner));
+        expected.extend_from_slice(&to_bytes(&Fr::from(450u64)));
+        assert_eq!(encoded, expected);
+    }
+
+    #[test]
+    fn committed_fixture_is_current() {
+        assert_committed_fixture::<AllowanceTransfer>();
+    }
+}
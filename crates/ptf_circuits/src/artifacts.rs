@@ -0,0 +1,147 @@
+//! Key generation and the files each `circuits/<name>/` directory holds.
+//!
+//! - `verifying_key.bin`: the key in the layout `ptf_verifier_groth16` parses, uploaded on-chain
+//! - `verification_key.json`, `proof.json`, `public.json`, `input.json`: snarkjs formats
+//! - `proving_key.bin`: the arkworks (compressed) proving key
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use ark_bn254::{Bn254, Fq, Fq2, Fr, G1Affine, G2Affine};
+use ark_ec::pairing::Pairing;
+use ark_ff::{BigInteger, PrimeField};
+use ark_groth16::{Groth16, Proof, ProvingKey, VerifyingKey};
+use ark_serialize::CanonicalSerialize;
+use ark_snark::SNARK;
+use ark_std::rand::{CryptoRng, RngCore};
+use serde_json::{json, Value};
+
+use crate::Circuit;
+
+/// A fresh key pair for `C` and a proof of its fixture
+pub struct Artifacts {
+    pub proving_key: ProvingKey<Bn254>,
+    pub proof: Proof<Bn254>,
+    pub public_inputs: Vec<Fr>,
+    pub input: Value,
+}
+
+impl Artifacts {
+    /// Run the Groth16 setup for `C` and prove its fixture, checking the proof verifies
+    pub fn generate<C: Circuit, R: RngCore + CryptoRng>(rng: &mut R) -> Result<Self, String> {
+        let fixture = C::fixture();
+        let public_inputs = fixture.public_inputs();
+        let input = fixture.input_json();
+        let (proving_key, verifying_key) = Groth16::<Bn254>::circuit_specific_setup(fixture.clone(), rng)
+            .map_err(|err| format!("{} setup failed: {err}", C::NAME))?;
+        let proof = Groth16::<Bn254>::prove(&proving_key, fixture, rng)
+            .map_err(|err| format!("{} proof failed: {err}", C::NAME))?;
+        let valid = Groth16::<Bn254>::verify(&verifying_key, &public_inputs, &proof)
+            .map_err(|err| format!("{} verification failed: {err}", C::NAME))?;
+        if !valid {
+            return Err(format!("{} fixture proof does not verify", C::NAME));
+        }
+        Ok(Self { proving_key, proof, public_inputs, input })
+    }
+    
+    pub fn write(&self, dir: &Path) -> io::Result<()> {
+        let vk = &self.proving_key.vk;
+        fs::write(dir.join("verifying_key.bin"), verifying_key_bytes(vk))?;
+        write_json(&dir.join("verification_key.json"), &verification_key_json(vk))?;
+        write_json(&dir.join("proof.json"), &proof_json(&self.proof))?;
+        write_json(&dir.join("public.json"), &Value::from(self.public_inputs.iter().map(decimal).collect::<Vec<_>>()))?;
+        write_json(&dir.join("input.json"), &self.input)?;
+        let mut proving_key = Vec::new();
+        self.proving_key
+            .serialize_compressed(&mut proving_key)
+            .map_err(|err| io::Error::other(err.to_string()))?;
+        fs::write(dir.join("proving_key.bin"), proving_key)
+    }
+}
+
+/// `circuits/<name>`, where the circom source and the artifacts of a circuit live
+pub fn circuit_dir(name: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("../../circuits").join(name)
+}
+
+fn write_json(path: &Path, value: &Value) -> io::Result<()> {
+    let mut text = serde_json::to_string_pretty(value).map_err(io::Error::from)?;
+    text.push('\n');
+    fs::write(path, text)
+}
+
+fn fq_bytes(value: &Fq) -> Vec<u8> {
+    value.into_bigint().to_bytes_be()
+}
+
+/// G1 as [x][y], big-endian
+pub fn g1_bytes(point: &G1Affine) -> Vec<u8> {
+    [fq_bytes(&point.x), fq_bytes(&point.y)].concat()
+}
+
+/// G2 as [x.c1][x.c0][y.c1][y.c0], big-endian (EIP-197 order)
+pub fn g2_bytes(point: &G2Affine) -> Vec<u8> {
+    [fq_bytes(&point.x.c1), fq_bytes(&point.x.c0), fq_bytes(&point.y.c1), fq_bytes(&point.y.c0)].concat()
+}
+
+/// `[alpha][beta][gamma][delta][u32 LE count][gamma_abc...]`, as `VerifyingKey::parse` reads it
+pub fn verifying_key_bytes(vk: &VerifyingKey<Bn254>) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    bytes.extend(g1_bytes(&vk.alpha_g1));
+    bytes.extend(g2_bytes(&vk.beta_g2));
+    bytes.extend(g2_bytes(&vk.gamma_g2));
+    bytes.extend(g2_bytes(&vk.delta_g2));
+    bytes.extend((vk.gamma_abc_g1.len() as u32).to_le_bytes());
+    for point in &vk.gamma_abc_g1 {
+        bytes.extend(g1_bytes(point));
+    }
+    bytes
+}
+
+/// The proof as the pool passes it to the verifier: [a][b][c]
+pub fn proof_bytes(proof: &Proof<Bn254>) -> Vec<u8> {
+    [g1_bytes(&proof.a), g2_bytes(&proof.b), g1_bytes(&proof.c)].concat()
+}
+
+fn decimal<F: PrimeField>(value: &F) -> String {
+    value.into_bigint().to_string()
+}
+
+fn fq2_json(value: &Fq2) -> Value {
+    json!([decimal(&value.c0), decimal(&value.c1)])
+}
+
+fn g1_json(point: &G1Affine) -> Value {
+    json!([decimal(&point.x), decimal(&point.y), "1"])
+}
+
+fn g2_json(point: &G2Affine) -> Value {
+    json!([fq2_json(&point.x), fq2_json(&point.y), ["1", "0"]])
+}
+
+fn verification_key_json(vk: &VerifyingKey<Bn254>) -> Value {
+    let alphabeta = Bn254::pairing(vk.alpha_g1, vk.beta_g2).0;
+    let fq6_json = |value: &ark_bn254::Fq6| json!([fq2_json(&value.c0), fq2_json(&value.c1), fq2_json(&value.c2)]);
+    json!({
+        "protocol": "groth16",
+        "curve": "bn128",
+        "nPublic": vk.gamma_abc_g1.len() - 1,
+        "vk_alpha_1": g1_json(&vk.alpha_g1),
+        "vk_beta_2": g2_json(&vk.beta_g2),
+        "vk_gamma_2": g2_json(&vk.gamma_g2),
+        "vk_delta_2": g2_json(&vk.delta_g2),
+        "vk_alphabeta_12": [fq6_json(&alphabeta.c0), fq6_json(&alphabeta.c1)],
+        "IC": vk.gamma_abc_g1.iter().map(g1_json).collect::<Vec<_>>(),
+    })
+}
+
+fn proof_json(proof: &Proof<Bn254>) -> Value {
+    json!({
+        "pi_a": g1_json(&proof.a),
+        "pi_b": g2_json(&proof.b),
+        "pi_c": g1_json(&proof.c),
+        "protocol": "groth16",
+        "curve": "bn128",
+    })
+}
@@ -0,0 +1,37 @@
+//! Regenerate keys and fixtures: `generate [circuit...]`, every circuit when none is named.
+
+use ptf_circuits::{circuit_dir, AllowanceTransfer, Artifacts, Circuit, DelegatedSpend, Shield, Transfer, Unshield};
+use rand_chacha::rand_core::SeedableRng;
+use rand_chacha::ChaCha20Rng;
+
+fn generate<C: Circuit>(rng: &mut ChaCha20Rng) -> Result<(), String> {
+    let dir = circuit_dir(C::NAME);
+    let artifacts = Artifacts::generate::<C, _>(rng)?;
+    artifacts.write(&dir).map_err(|err| format!("writing {}: {err}", dir.display()))?;
+    println!("{}: {} public inputs, written to {}", C::NAME, artifacts.public_inputs.len(), dir.display());
+    Ok(())
+}
+
+fn main() -> Result<(), String> {
+    let names: Vec<String> = std::env::args().skip(1).collect();
+    let selected = |name: &str| names.is_empty() || names.iter().any(|n| n == name);
+    // The setup randomness is toxic waste: seed from the OS and never persist it
+    let mut rng = ChaCha20Rng::from_entropy();
+    
+    if selected(Shield::NAME) {
+        generate::<Shield>(&mut rng)?;
+    }
+    if selected(Unshield::NAME) {
+        generate::<Unshield>(&mut rng)?;
+    }
+    if selected(Transfer::NAME) {
+        generate::<Transfer>(&mut rng)?;
+    }
+    if selected(DelegatedSpend::NAME) {
+        generate::<DelegatedSpend>(&mut rng)?;
+    }
+    if selected(AllowanceTransfer::NAME) {
+        generate::<AllowanceTransfer>(&mut rng)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,260 @@
+//! R1CS counterparts of the circomlib templates the circuits include.
+//!
+//! Each gadget emits the same constraints as its circom template, so a circuit written with
+//! them has the public signals, and accepts the witnesses, of the `circuit.circom` beside it.
+
+use ark_bn254::Fr;
+use ark_ff::{BigInteger, Field, PrimeField};
+use ark_relations::lc;
+use ark_relations::r1cs::{ConstraintSystemRef, LinearCombination, SynthesisError, Variable};
+use light_poseidon::parameters::bn254_x5::get_poseidon_parameters;
+
+type Result<T> = std::result::Result<T, SynthesisError>;
+
+/// A circom signal: a linear combination of variables and the value it takes in the witness
+#[derive(Clone, Debug)]
+pub struct Signal {
+    pub lc: LinearCombination<Fr>,
+    pub value: Fr,
+}
+
+impl Signal {
+    pub fn constant(value: Fr) -> Self {
+        Self { lc: lc!() + (value, Variable::One), value }
+    }
+    
+    pub fn zero() -> Self {
+        Self::constant(Fr::from(0u64))
+    }
+    
+    pub fn add(&self, other: &Signal) -> Signal {
+        Signal { lc: self.lc.clone() + &other.lc, value: self.value + other.value }
+    }
+    
+    pub fn sub(&self, other: &Signal) -> Signal {
+        Signal { lc: self.lc.clone() - &other.lc, value: self.value - other.value }
+    }
+    
+    pub fn scale(&self, factor: Fr) -> Signal {
+        Signal { lc: self.lc.clone() * factor, value: self.value * factor }
+    }
+    
+    pub fn add_constant(&self, constant: Fr) -> Signal {
+        self.add(&Signal::constant(constant))
+    }
+}
+
+/// Allocates signals and constraints in one constraint system
+#[derive(Clone)]
+pub struct Builder {
+    cs: ConstraintSystemRef<Fr>,
+}
+
+impl Builder {
+    pub fn new(cs: ConstraintSystemRef<Fr>) -> Self {
+        Self { cs }
+    }
+    
+    /// A public signal; public signals are numbered in the order they are allocated
+    pub fn input(&self, value: Fr) -> Result<Signal> {
+        let variable = self.cs.new_input_variable(|| Ok(value))?;
+        Ok(Signal { lc: lc!() + variable, value })
+    }
+    
+    pub fn witness(&self, value: Fr) -> Result<Signal> {
+        let variable = self.cs.new_witness_variable(|| Ok(value))?;
+        Ok(Signal { lc: lc!() + variable, value })
+    }
+    
+    pub fn witnesses(&self, values: &[Fr]) -> Result<Vec<Signal>> {
+        values.iter().map(|value| self.witness(*value)).collect()
+    }
+    
+    /// `a * b === c`
+    pub fn enforce(&self, a: &Signal, b: &Signal, c: &Signal) -> Result<()> {
+        self.cs.enforce_constraint(a.lc.clone(), b.lc.clone(), c.lc.clone())
+    }
+    
+    /// `a === b`
+    pub fn equal(&self, a: &Signal, b: &Signal) -> Result<()> {
+        self.enforce(&a.sub(b), &Signal::constant(Fr::from(1u64)), &Signal::zero())
+    }
+    
+    /// `c <== a * b`
+    pub fn mul(&self, a: &Signal, b: &Signal) -> Result<Signal> {
+        let c = self.witness(a.value * b.value)?;
+        self.enforce(a, b, &c)?;
+        Ok(c)
+    }
+    
+    /// `bit * (bit - 1) === 0`
+    pub fn boolean(&self, bit: &Signal) -> Result<()> {
+        self.enforce(bit, &bit.add_constant(-Fr::from(1u64)), &Signal::zero())
+    }
+}
+
+/// circomlib `Poseidon(n)` over `inputs`, with the parameters of `ptf_common::poseidon`
+pub fn poseidon(b: &Builder, inputs: &[Signal]) -> Result<Signal> {
+    let width = inputs.len() + 1;
+    let params = get_poseidon_parameters::<Fr>(width as u8).map_err(|_| SynthesisError::Unsatisfiable)?;
+    let half = params.full_rounds / 2;
+    let mut state: Vec<Signal> = std::iter::once(Signal::zero()).chain(inputs.iter().cloned()).collect();
+    for round in 0..params.full_rounds + params.partial_rounds {
+        let full = round < half || round >= half + params.partial_rounds;
+        for (i, element) in state.iter_mut().enumerate() {
+            *element = element.add_constant(params.ark[round * width + i]);
+            if full || i == 0 {
+                *element = pow5(b, element)?;
+            }
+        }
+        state = params.mds
+            .iter()
+            .map(|row| {
+                state.iter().zip(row).fold(Signal::zero(), |acc, (element, m)| acc.add(&element.scale(*m)))
+            })
+            .collect();
+    }
+    Ok(state.swap_remove(0))
+}
+
+/// The x^5 S-box
+fn pow5(b: &Builder, x: &Signal) -> Result<Signal> {
+    let x2 = b.mul(x, x)?;
+    let x4 = b.mul(&x2, &x2)?;
+    b.mul(&x4, x)
+}
+
+/// Native Poseidon, for the values the fixtures are built from
+pub fn poseidon_native(inputs: &[Fr]) -> Fr {
+    use light_poseidon::{Poseidon, PoseidonHasher};
+    Poseidon::<Fr>::new_circom(inputs.len())
+        .and_then(|mut hasher| hasher.hash(inputs))
+        .expect("Poseidon width is within the circomlib parameters")
+}
+
+/// circomlib `Num2Bits(n)`: the little-endian bits of `x`, which must fit in `n` bits
+pub fn num2bits(b: &Builder, x: &Signal, n: usize) -> Result<Vec<Signal>> {
+    let value = x.value.into_bigint();
+    let mut bits = Vec::with_capacity(n);
+    let mut sum = Signal::zero();
+    let mut weight = Fr::from(1u64);
+    for i in 0..n {
+        let bit = b.witness(Fr::from(value.get_bit(i) as u64))?;
+        b.boolean(&bit)?;
+        sum = sum.add(&bit.scale(weight));
+        weight.double_in_place();
+        bits.push(bit);
+    }
+    b.equal(&sum, x)?;
+    Ok(bits)
+}
+
+/// circomlib `IsZero()`: 1 if `x` is zero, else 0
+pub fn is_zero(b: &Builder, x: &Signal) -> Result<Signal> {
+    let inverse = b.witness(x.value.inverse().unwrap_or_default())?;
+    let out = b.witness(if x.value == Fr::from(0u64) { Fr::from(1u64) } else { Fr::from(0u64) })?;
+    // out <== 1 - x * inverse
+    b.enforce(x, &inverse, &Signal::constant(Fr::from(1u64)).sub(&out))?;
+    b.enforce(x, &out, &Signal::zero())?;
+    Ok(out)
+}
+
+/// circomlib `ForceEqualIfEnabled()`: `a === c` unless `enabled` is 0
+pub fn force_equal_if_enabled(b: &Builder, enabled: &Signal, a: &Signal, c: &Signal) -> Result<()> {
+    let equal = is_zero(b, &c.sub(a))?;
+    b.enforce(&Signal::constant(Fr::from(1u64)).sub(&equal), enabled, &Signal::zero())
+}
+
+/// circomlib `LessThan(n)`: 1 if `a < c`, for `a` and `c` of at most `n` bits
+pub fn less_than(b: &Builder, n: usize, a: &Signal, c: &Signal) -> Result<Signal> {
+    let shifted = a.add_constant(Fr::from(2u64).pow([n as u64])).sub(c);
+    let bits = num2bits(b, &shifted, n + 1)?;
+    Ok(Signal::constant(Fr::from(1u64)).sub(&bits[n]))
+}
+
+/// circomlib `LessEqThan(n)`: 1 if `a <= c`
+pub fn less_eq_than(b: &Builder, n: usize, a: &Signal, c: &Signal) -> Result<Signal> {
+    less_than(b, n, a, &c.add_constant(Fr::from(1u64)))
+}
+
+/// `MerkleRoot(depth)` from merkle.circom: the root of the tree holding `leaf` at the position
+/// given by `path_indices` (0 = left child)
+pub fn merkle_root(b: &Builder, leaf: &Signal, path_elements: &[Signal], path_indices: &[Signal]) -> Result<Signal> {
+    let mut node = leaf.clone();
+    for (element, index) in path_elements.iter().zip(path_indices) {
+        b.boolean(index)?;
+        let left = node.add(&b.mul(index, &element.sub(&node))?);
+        let right = element.add(&b.mul(index, &node.sub(element))?);
+        node = poseidon(b, &[left, right])?;
+    }
+    Ok(node)
+}
+
+/// The leaf index the path bits spell, `sum(path_indices[i] * 2^i)`
+pub fn leaf_index(path_indices: &[Signal]) -> Signal {
+    path_indices
+        .iter()
+        .enumerate()
+        .fold(Signal::zero(), |acc, (i, bit)| acc.add(&bit.scale(Fr::from(1u64 << i))))
+}
+
+/// `value` as a 32-byte big-endian field element, the encoding the pool and verifier use
+pub fn to_bytes(value: &Fr) -> [u8; 32] {
+    let mut bytes = [0u8; 32];
+    bytes.copy_from_slice(&value.into_bigint().to_bytes_be());
+    bytes
+}
+
+pub fn from_bytes(bytes: &[u8; 32]) -> Fr {
+    Fr::from_be_bytes_mod_order(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_relations::r1cs::ConstraintSystem;
+
+    fn builder() -> (ConstraintSystemRef<Fr>, Builder) {
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        (cs.clone(), Builder::new(cs))
+    }
+
+    #[test]
+    fn poseidon_matches_the_pool_hash() {
+        let (cs, b) = builder();
+        let inputs = [Fr::from(1u64), Fr::from(2u64), Fr::from(3u64)];
+        let signals = b.witnesses(&inputs).unwrap();
+        let hash = poseidon(&b, &signals).unwrap();
+        assert!(cs.is_satisfied().unwrap());
+
+        let bytes: Vec<[u8; 32]> = inputs.iter().map(to_bytes).collect();
+        let expected = ptf_common::poseidon::hashv(&bytes.iter().collect::<Vec<_>>()).unwrap();
+        assert_eq!(to_bytes(&hash.value), expected);
+        assert_eq!(poseidon_native(&inputs), hash.value);
+    }
+
+    #[test]
+    fn num2bits_rejects_values_that_do_not_fit() {
+        let (cs, b) = builder();
+        let x = b.witness(Fr::from(255u64)).unwrap();
+        num2bits(&b, &x, 8).unwrap();
+        assert!(cs.is_satisfied().unwrap());
+
+        let (cs, b) = builder();
+        let x = b.witness(Fr::from(256u64)).unwrap();
+        num2bits(&b, &x, 8).unwrap();
+        assert!(!cs.is_satisfied().unwrap());
+    }
+
+    #[test]
+    fn comparisons() {
+        let (cs, b) = builder();
+        let [zero, three, four] = [0u64, 3, 4].map(|v| b.witness(Fr::from(v)).unwrap());
+        assert_eq!(is_zero(&b, &zero).unwrap().value, Fr::from(1u64));
+        assert_eq!(is_zero(&b, &three).unwrap().value, Fr::from(0u64));
+        assert_eq!(less_than(&b, 8, &three, &four).unwrap().value, Fr::from(1u64));
+        assert_eq!(less_than(&b, 8, &four, &three).unwrap().value, Fr::from(0u64));
+        assert_eq!(less_eq_than(&b, 8, &four, &four).unwrap().value, Fr::from(1u64));
+        assert!(cs.is_satisfied().unwrap());
+    }
+}
@@ -0,0 +1,76 @@
+//! Groth16 keys and fixtures for the pool circuits.
+//!
+//! Each circuit in `circuits/` has a counterpart here built from the same templates (see
+//! `gadgets`), with the public signals in the order the pool passes them to the verifier.
+//! `cargo run -p ptf_circuits --release --bin generate` runs the setup for each one and writes
+//! its keys and a proof of its fixture next to the circom source.
+
+pub mod allowance_transfer;
+pub mod artifacts;
+pub mod gadgets;
+pub mod notes;
+pub mod shield;
+pub mod transfer;
+pub mod transfer_from;
+pub mod unshield;
+
+use ark_bn254::Fr;
+use ark_relations::r1cs::ConstraintSynthesizer;
+use serde_json::Value;
+
+pub use allowance_transfer::AllowanceTransfer;
+pub use artifacts::{circuit_dir, Artifacts};
+pub use shield::Shield;
+pub use transfer::Transfer;
+pub use transfer_from::DelegatedSpend;
+pub use unshield::Unshield;
+
+pub trait Circuit: ConstraintSynthesizer<Fr> + Clone {
+    /// Directory under `circuits/` holding the circom source and the artifacts
+    const NAME: &'static str;
+    
+    /// A satisfying assignment, proven into the committed fixture
+    fn fixture() -> Self;
+    
+    /// Public signals in the order the pool encodes them
+    fn public_inputs(&self) -> Vec<Fr>;
+    
+    /// The witness as snarkjs `input.json`
+    fn input_json(&self) -> Value;
+}
+
+/// Decimal strings, the way snarkjs writes field elements
+fn decimals(values: impl IntoIterator<Item = Fr>) -> Value {
+    Value::from(values.into_iter().map(|value| value.to_string()).collect::<Vec<_>>())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_relations::r1cs::ConstraintSystem;
+
+    pub(crate) fn assert_satisfied<C: Circuit>(circuit: C) {
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        circuit.generate_constraints(cs.clone()).unwrap();
+        assert!(cs.is_satisfied().unwrap(), "{} unsatisfied at {:?}", C::NAME, cs.which_is_unsatisfied());
+    }
+
+    pub(crate) fn assert_unsatisfied<C: Circuit>(circuit: C) {
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        circuit.generate_constraints(cs.clone()).unwrap();
+        assert!(!cs.is_satisfied().unwrap());
+    }
+
+    /// The committed public signals and witness are the current fixture's, and the committed
+    /// key has one input per public signal
+    pub(crate) fn assert_committed_fixture<C: Circuit>() {
+        let fixture = C::fixture();
+        let read = |file: &str| -> Value {
+            let text = std::fs::read_to_string(circuit_dir(C::NAME).join(file)).unwrap();
+            serde_json::from_str(&text).unwrap()
+        };
+        assert_eq!(read("public.json"), decimals(fixture.public_inputs()));
+        assert_eq!(read("input.json"), fixture.input_json());
+        assert_eq!(read("verification_key.json")["nPublic"], fixture.public_inputs().len());
+    }
+}
@@ -0,0 +1,73 @@
+//! Note values shared by the spending circuits, and the pool tree their fixtures spend from.
+
+use ark_bn254::Fr;
+use ptf_pool::merkle::ReferenceTree;
+use ptf_pool::state::DEPTH;
+use serde_json::Value;
+
+use crate::decimals;
+use crate::gadgets::{from_bytes, poseidon_native, to_bytes};
+
+/// Poseidon(secret, amount, tag): tag 0 for an ordinary note, the delegation tag otherwise
+pub fn commitment(secret: Fr, amount: u64, tag: Fr) -> Fr {
+    poseidon_native(&[secret, Fr::from(amount), tag])
+}
+
+/// A note being spent, with its membership path in the pool tree
+#[derive(Clone, Debug)]
+pub struct InputNote {
+    pub secret: Fr,
+    pub amount: u64,
+    pub tag: Fr,
+    pub leaf_index: u64,
+    pub path: Vec<Fr>,
+}
+
+impl InputNote {
+    pub fn commitment(&self) -> Fr {
+        commitment(self.secret, self.amount, self.tag)
+    }
+    
+    /// Poseidon(secret, leaf index)
+    pub fn nullifier(&self) -> Fr {
+        poseidon_native(&[self.secret, Fr::from(self.leaf_index)])
+    }
+    
+    /// The leaf index as `DEPTH` little-endian bits
+    pub fn path_indices(&self) -> Vec<Fr> {
+        (0..DEPTH).map(|level| Fr::from((self.leaf_index >> level) & 1)).collect()
+    }
+    
+    pub fn path_json(&self) -> (Value, Value) {
+        (decimals(self.path.iter().copied()), decimals(self.path_indices()))
+    }
+}
+
+/// A note being created
+#[derive(Clone, Debug)]
+pub struct OutputNote {
+    pub secret: Fr,
+    pub amount: u64,
+    pub tag: Fr,
+}
+
+impl OutputNote {
+    pub fn commitment(&self) -> Fr {
+        commitment(self.secret, self.amount, self.tag)
+    }
+}
+
+/// A pool tree holding an unrelated leaf and then `notes` in order, with each note's leaf index
+/// and path filled in, and the resulting root
+pub fn insert_notes(notes: &mut [InputNote]) -> Fr {
+    let mut tree = ReferenceTree::pool().unwrap();
+    tree.insert(to_bytes(&Fr::from(42u64))).unwrap();
+    for note in notes.iter_mut() {
+        note.leaf_index = tree.leaves().len() as u64;
+        tree.insert(to_bytes(&note.commitment())).unwrap();
+    }
+    for note in notes.iter_mut() {
+        note.path = tree.path(note.leaf_index as usize).unwrap().iter().map(from_bytes).collect();
+    }
+    from_bytes(&tree.root().unwrap())
+}
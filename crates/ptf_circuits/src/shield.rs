@@ -0,0 +1,89 @@
+//! `circuits/shield`: a commitment to a secret and the deposited amount.
+
+use ark_bn254::Fr;
+use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystemRef, SynthesisError};
+use serde_json::{json, Value};
+
+use crate::gadgets::{poseidon, poseidon_native, Builder, Signal};
+use crate::{decimals, Circuit};
+
+#[derive(Clone, Debug)]
+pub struct Shield {
+    pub secret: [u8; 32],
+    pub amount: u64,
+}
+
+impl Shield {
+    /// The first 31 secret bytes packed big-endian, so the value stays below the field modulus
+    fn secret_field(&self) -> Fr {
+        self.secret[..31].iter().fold(Fr::from(0u64), |acc, byte| acc * Fr::from(256u64) + Fr::from(*byte as u64))
+    }
+    
+    /// Poseidon(secret, amount, 0)
+    pub fn commitment(&self) -> Fr {
+        poseidon_native(&[self.secret_field(), Fr::from(self.amount), Fr::from(0u64)])
+    }
+}
+
+impl ConstraintSynthesizer<Fr> for Shield {
+    fn generate_constraints(self, cs: ConstraintSystemRef<Fr>) -> Result<(), SynthesisError> {
+        let b = Builder::new(cs);
+        // Outputs come first among the public signals: [commitment, amount]
+        let commitment = b.input(self.commitment())?;
+        let amount = b.input(Fr::from(self.amount))?;
+        let secret = b.witnesses(&self.secret.map(|byte| Fr::from(byte as u64)))?;
+        
+        let secret_field = secret[..31]
+            .iter()
+            .fold(Signal::zero(), |acc, byte| acc.scale(Fr::from(256u64)).add(byte));
+        let hash = poseidon(&b, &[secret_field, amount, Signal::zero()])?;
+        b.equal(&hash, &commitment)
+    }
+}
+
+impl Circuit for Shield {
+    const NAME: &'static str = "shield";
+    
+    fn fixture() -> Self {
+        Self {
+            secret: std::array::from_fn(|i| i as u8 + 1),
+            amount: 1_000_000,
+        }
+    }
+    
+    fn public_inputs(&self) -> Vec<Fr> {
+        vec![self.commitment(), Fr::from(self.amount)]
+    }
+    
+    fn input_json(&self) -> Value {
+        json!({
+            "secret": decimals(self.secret.map(|byte| Fr::from(byte as u64))),
+            "amount": self.amount.to_string(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gadgets::to_bytes;
+    use crate::tests::{assert_committed_fixture, assert_satisfied};
+    use ptf_pool::instructions::shield_public_inputs;
+
+    #[test]
+    fn fixture_satisfies_the_circuit() {
+        assert_satisfied(Shield::fixture());
+    }
+
+    #[test]
+    fn public_inputs_match_the_pool_encoding() {
+        let shield = Shield::fixture();
+        let encoded: Vec<u8> = shield.public_inputs().iter().flat_map(to_bytes).collect();
+        assert_eq!(encoded, shield_public_inputs(&to_bytes(&shield.commitment()), shield.amount));
+    }
+
+    #[test]
+    fn committed_fixture_is_current() {
+        assert_committed_fixture::<Shield>();
+    }
+}
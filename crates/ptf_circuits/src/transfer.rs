@@ -0,0 +1,156 @@
+//! `circuits/transfer`: the 2-in-2-out join-split.
+
+use ark_bn254::Fr;
+use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystemRef, SynthesisError};
+use ptf_pool::instructions::{TRANSFER_INPUTS, TRANSFER_OUTPUTS};
+use serde_json::{json, Value};
+
+use crate::gadgets::{
+    force_equal_if_enabled, is_zero, leaf_index, merkle_root, num2bits, poseidon, Builder, Signal,
+};
+use crate::notes::{insert_notes, InputNote, OutputNote};
+use crate::{decimals, Circuit};
+
+#[derive(Clone, Debug)]
+pub struct Transfer {
+    pub root: Fr,
+    pub inputs: [InputNote; TRANSFER_INPUTS],
+    pub outputs: [OutputNote; TRANSFER_OUTPUTS],
+}
+
+impl ConstraintSynthesizer<Fr> for Transfer {
+    fn generate_constraints(self, cs: ConstraintSystemRef<Fr>) -> Result<(), SynthesisError> {
+        let b = Builder::new(cs);
+        // [root][nullifiers][out_commitments]
+        let root = b.input(self.root)?;
+        let nullifiers = self.inputs.iter().map(|note| b.input(note.nullifier())).collect::<Result<Vec<_>, _>>()?;
+        let out_commitments = self.outputs.iter().map(|note| b.input(note.commitment())).collect::<Result<Vec<_>, _>>()?;
+        
+        let mut in_total = Signal::zero();
+        for (note, nullifier) in self.inputs.iter().zip(&nullifiers) {
+            let amount = b.witness(Fr::from(note.amount))?;
+            let secret = b.witness(note.secret)?;
+            let path_elements = b.witnesses(&note.path)?;
+            let path_indices = b.witnesses(&note.path_indices())?;
+            num2bits(&b, &amount, 64)?;
+            
+            let commitment = poseidon(&b, &[secret.clone(), amount.clone(), Signal::zero()])?;
+            // The leaf index from the path bits binds the nullifier to one position
+            let index = leaf_index(&path_indices);
+            b.equal(&poseidon(&b, &[secret, index])?, nullifier)?;
+            
+            // Zero-amount inputs are dummies whose membership is not checked
+            let tree_root = merkle_root(&b, &commitment, &path_elements, &path_indices)?;
+            let is_dummy = is_zero(&b, &amount)?;
+            force_equal_if_enabled(&b, &Signal::constant(Fr::from(1u64)).sub(&is_dummy), &root, &tree_root)?;
+            
+            in_total = in_total.add(&amount);
+        }
+        
+        // Distinct nullifiers, so one note can't fill both input slots
+        for i in 0..nullifiers.len() {
+            for j in i + 1..nullifiers.len() {
+                let same = is_zero(&b, &nullifiers[i].sub(&nullifiers[j]))?;
+                b.equal(&same, &Signal::zero())?;
+            }
+        }
+        
+        let mut out_total = Signal::zero();
+        for (note, out_commitment) in self.outputs.iter().zip(&out_commitments) {
+            let amount = b.witness(Fr::from(note.amount))?;
+            let secret = b.witness(note.secret)?;
+            let tag = b.witness(note.tag)?;
+            num2bits(&b, &amount, 64)?;
+            b.equal(&poseidon(&b, &[secret, amount.clone(), tag])?, out_commitment)?;
+            out_total = out_total.add(&amount);
+        }
+        
+        b.equal(&in_total, &out_total)
+    }
+}
+
+impl Circuit for Transfer {
+    const NAME: &'static str = "transfer";
+    
+    fn fixture() -> Self {
+        let input = |secret: u64, amount: u64| InputNote {
+            secret: Fr::from(secret),
+            amount,
+            tag: Fr::from(0u64),
+            leaf_index: 0,
+            path: Vec::new(),
+        };
+        let mut inputs = [input(1001, 700), input(1002, 300)];
+        let root = insert_notes(&mut inputs);
+        let output = |secret: u64, amount: u64| OutputNote { secret: Fr::from(secret), amount, tag: Fr::from(0u64) };
+        Self { root, inputs, outputs: [output(2001, 600), output(2002, 400)] }
+    }
+    
+    fn public_inputs(&self) -> Vec<Fr> {
+        std::iter::once(self.root)
+            .chain(self.inputs.iter().map(InputNote::nullifier))
+            .chain(self.outputs.iter().map(OutputNote::commitment))
+            .collect()
+    }
+    
+    fn input_json(&self) -> Value {
+        let (path_elements, path_indices): (Vec<_>, Vec<_>) = self.inputs.iter().map(InputNote::path_json).unzip();
+        json!({
+            "root": self.root.to_string(),
+            "nullifiers": decimals(self.inputs.iter().map(InputNote::nullifier)),
+            "out_commitments": decimals(self.outputs.iter().map(OutputNote::commitment)),
+            "in_amount": decimals(self.inputs.iter().map(|note| Fr::from(note.amount))),
+            "in_secret": decimals(self.inputs.iter().map(|note| note.secret)),
+            "in_path_elements": path_elements,
+            "in_path_indices": path_indices,
+            "out_amount": decimals(self.outputs.iter().map(|note| Fr::from(note.amount))),
+            "out_secret": decimals(self.outputs.iter().map(|note| note.secret)),
+            "out_tag": decimals(self.outputs.iter().map(|note| note.tag)),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gadgets::to_bytes;
+    use crate::tests::{assert_committed_fixture, assert_satisfied, assert_unsatisfied};
+    use ptf_pool::instructions::TransferPublicInputs;
+
+    #[test]
+    fn fixture_satisfies_the_circuit() {
+        assert_satisfied(Transfer::fixture());
+    }
+
+    #[test]
+    fn rejects_unbalanced_outputs() {
+        let mut transfer = Transfer::fixture();
+        transfer.outputs[0].amount += 1;
+        assert_unsatisfied(transfer);
+    }
+
+    #[test]
+    fn rejects_the_same_note_twice() {
+        let mut transfer = Transfer::fixture();
+        transfer.inputs[1] = transfer.inputs[0].clone();
+        transfer.outputs[1].amount = 800;
+        assert_unsatisfied(transfer);
+    }
+
+    #[test]
+    fn public_inputs_match_the_pool_encoding() {
+        let transfer = Transfer::fixture();
+        let encoded: Vec<u8> = transfer.public_inputs().iter().flat_map(to_bytes).collect();
+        let inputs = TransferPublicInputs {
+            root: to_bytes(&transfer.root),
+            nullifiers: transfer.inputs.each_ref().map(|note| to_bytes(&note.nullifier())),
+            commitments: transfer.outputs.each_ref().map(|note| to_bytes(&note.commitment())),
+        };
+        assert_eq!(encoded, inputs.to_bytes());
+    }
+
+    #[test]
+    fn committed_fixture_is_current() {
+        assert_committed_fixture::<Transfer>();
+    }
+}
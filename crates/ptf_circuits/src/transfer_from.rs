@@ -0,0 +1,223 @@
+//! `circuits/transfer_from`: spending a delegation note as its owner or spender.
+
+use ark_bn254::Fr;
+use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystemRef, SynthesisError};
+use ptf_pool::instructions::TRANSFER_OUTPUTS;
+use serde_json::{json, Value};
+
+use crate::gadgets::{leaf_index, merkle_root, num2bits, poseidon, poseidon_native, Builder, Signal};
+use crate::notes::{insert_notes, InputNote, OutputNote};
+use crate::{decimals, Circuit};
+
+/// Poseidon(private key), the key a delegation tag names
+pub fn delegation_key(private_key: Fr) -> Fr {
+    poseidon_native(&[private_key])
+}
+
+/// Poseidon(owner key, spender key), the third hash input of a delegation note
+pub fn delegation_tag(owner_key: Fr, spender_key: Fr) -> Fr {
+    poseidon_native(&[owner_key, spender_key])
+}
+
+#[derive(Clone, Debug)]
+pub struct DelegatedSpend {
+    pub root: Fr,
+    /// The delegation note; its amount is the cap
+    pub note: InputNote,
+    pub signer_private_key: Fr,
+    pub counterparty_key: Fr,
+    pub signer_is_owner: bool,
+    pub outputs: [OutputNote; TRANSFER_OUTPUTS],
+    pub change_secret: Fr,
+}
+
+impl DelegatedSpend {
+    fn owner_and_spender_keys(&self) -> (Fr, Fr) {
+        let signer_key = delegation_key(self.signer_private_key);
+        if self.signer_is_owner {
+            (signer_key, self.counterparty_key)
+        } else {
+            (self.counterparty_key, signer_key)
+        }
+    }
+    
+    pub fn tag(&self) -> Fr {
+        let (owner_key, spender_key) = self.owner_and_spender_keys();
+        delegation_tag(owner_key, spender_key)
+    }
+    
+    /// What is left of the cap, in the field so an overspend wraps as it does in the circuit
+    fn change_amount(&self) -> Fr {
+        self.outputs
+            .iter()
+            .fold(Fr::from(self.note.amount), |acc, note| acc - Fr::from(note.amount))
+    }
+    
+    pub fn change_commitment(&self) -> Fr {
+        poseidon_native(&[self.change_secret, self.change_amount(), self.tag()])
+    }
+}
+
+impl ConstraintSynthesizer<Fr> for DelegatedSpend {
+    fn generate_constraints(self, cs: ConstraintSystemRef<Fr>) -> Result<(), SynthesisError> {
+        let b = Builder::new(cs);
+        // [root][nullifier][out_commitments][change_commitment]
+        let root = b.input(self.root)?;
+        let nullifier = b.input(self.note.nullifier())?;
+        let out_commitments = self.outputs.iter().map(|note| b.input(note.commitment())).collect::<Result<Vec<_>, _>>()?;
+        let change_commitment = b.input(self.change_commitment())?;
+        
+        let cap = b.witness(Fr::from(self.note.amount))?;
+        let secret = b.witness(self.note.secret)?;
+        let path_elements = b.witnesses(&self.note.path)?;
+        let path_indices = b.witnesses(&self.note.path_indices())?;
+        let signer_private_key = b.witness(self.signer_private_key)?;
+        let counterparty_key = b.witness(self.counterparty_key)?;
+        let signer_is_owner = b.witness(Fr::from(self.signer_is_owner as u64))?;
+        
+        // The signer's key takes the owner or spender slot of the tag, the counterparty the other
+        b.boolean(&signer_is_owner)?;
+        let signer_key = poseidon(&b, &[signer_private_key])?;
+        let owner_key = counterparty_key.add(&b.mul(&signer_is_owner, &signer_key.sub(&counterparty_key))?);
+        let spender_key = signer_key.add(&counterparty_key).sub(&owner_key);
+        let tag = poseidon(&b, &[owner_key, spender_key])?;
+        
+        num2bits(&b, &cap, 64)?;
+        let note = poseidon(&b, &[secret.clone(), cap.clone(), tag.clone()])?;
+        let index = leaf_index(&path_indices);
+        b.equal(&poseidon(&b, &[secret, index])?, &nullifier)?;
+        b.equal(&merkle_root(&b, &note, &path_elements, &path_indices)?, &root)?;
+        
+        let mut out_total = Signal::zero();
+        for (note, out_commitment) in self.outputs.iter().zip(&out_commitments) {
+            let amount = b.witness(Fr::from(note.amount))?;
+            let secret = b.witness(note.secret)?;
+            let out_tag = b.witness(note.tag)?;
+            num2bits(&b, &amount, 64)?;
+            b.equal(&poseidon(&b, &[secret, amount.clone(), out_tag])?, out_commitment)?;
+            out_total = out_total.add(&amount);
+        }
+        
+        // The range check rejects a change that wrapped, i.e. outputs above the cap
+        let change_amount = cap.sub(&out_total);
+        num2bits(&b, &change_amount, 64)?;
+        let change_secret = b.witness(self.change_secret)?;
+        b.equal(&poseidon(&b, &[change_secret, change_amount, tag])?, &change_commitment)
+    }
+}
+
+impl Circuit for DelegatedSpend {
+    const NAME: &'static str = "transfer_from";
+    
+    fn fixture() -> Self {
+        let (owner_private_key, spender_private_key) = (Fr::from(3001u64), Fr::from(3002u64));
+        let tag = delegation_tag(delegation_key(owner_private_key), delegation_key(spender_private_key));
+        let mut notes = [InputNote { secret: Fr::from(1003u64), amount: 1_000, tag, leaf_index: 0, path: Vec::new() }];
+        let root = insert_notes(&mut notes);
+        let [note] = notes;
+        let output = |secret: u64, amount: u64| OutputNote { secret: Fr::from(secret), amount, tag: Fr::from(0u64) };
+        // Signed by the spender
+        Self {
+            root,
+            note,
+            signer_private_key: spender_private_key,
+            counterparty_key: delegation_key(owner_private_key),
+            signer_is_owner: false,
+            outputs: [output(2003, 250), output(2004, 150)],
+            change_secret: Fr::from(2005u64),
+        }
+    }
+    
+    fn public_inputs(&self) -> Vec<Fr> {
+        [self.root, self.note.nullifier()]
+            .into_iter()
+            .chain(self.outputs.iter().map(OutputNote::commitment))
+            .chain([self.change_commitment()])
+            .collect()
+    }
+    
+    fn input_json(&self) -> Value {
+        let (path_elements, path_indices) = self.note.path_json();
+        json!({
+            "root": self.root.to_string(),
+            "nullifier": self.note.nullifier().to_string(),
+            "out_commitments": decimals(self.outputs.iter().map(OutputNote::commitment)),
+            "change_commitment": self.change_commitment().to_string(),
+            "cap": self.note.amount.to_string(),
+            "secret": self.note.secret.to_string(),
+            "path_elements": path_elements,
+            "path_indices": path_indices,
+            "signer_private_key": self.signer_private_key.to_string(),
+            "counterparty_key": self.counterparty_key.to_string(),
+            "signer_is_owner": (self.signer_is_owner as u64).to_string(),
+            "out_amount": decimals(self.outputs.iter().map(|note| Fr::from(note.amount))),
+            "out_secret": decimals(self.outputs.iter().map(|note| note.secret)),
+            "out_tag": decimals(self.outputs.iter().map(|note| note.tag)),
+            "change_secret": self.change_secret.to_string(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gadgets::to_bytes;
+    use crate::notes::commitment;
+    use crate::tests::{assert_committed_fixture, assert_satisfied, assert_unsatisfied};
+    use ptf_pool::instructions::DelegatedTransferPublicInputs;
+
+    /// The same spend, signed by the owner instead of the spender
+    fn signed_by_owner(spend: &DelegatedSpend, owner_private_key: Fr) -> DelegatedSpend {
+        let spender_key = spend.owner_and_spender_keys().1;
+        DelegatedSpend {
+            signer_private_key: owner_private_key,
+            counterparty_key: spender_key,
+            signer_is_owner: true,
+            ..spend.clone()
+        }
+    }
+
+    #[test]
+    fn fixture_satisfies_the_circuit() {
+        let spend = DelegatedSpend::fixture();
+        assert_eq!(spend.note.commitment(), commitment(spend.note.secret, spend.note.amount, spend.tag()));
+        assert_satisfied(spend);
+    }
+
+    #[test]
+    fn owner_can_spend_the_same_note() {
+        let spend = signed_by_owner(&DelegatedSpend::fixture(), Fr::from(3001u64));
+        assert_eq!(spend.tag(), DelegatedSpend::fixture().tag());
+        assert_satisfied(spend);
+    }
+
+    #[test]
+    fn rejects_a_key_the_tag_does_not_name() {
+        let mut spend = DelegatedSpend::fixture();
+        spend.signer_private_key = Fr::from(3003u64);
+        assert_unsatisfied(spend);
+    }
+
+    #[test]
+    fn rejects_outputs_above_the_cap() {
+        let mut spend = DelegatedSpend::fixture();
+        spend.outputs[0].amount = 900;
+        assert_unsatisfied(spend);
+    }
+
+    #[test]
+    fn public_inputs_match_the_pool_encoding() {
+        let spend = DelegatedSpend::fixture();
+        let encoded: Vec<u8> = spend.public_inputs().iter().flat_map(to_bytes).collect();
+        let inputs = DelegatedTransferPublicInputs::parse(&encoded).unwrap();
+        assert_eq!(inputs.root, to_bytes(&spend.root));
+        assert_eq!(inputs.nullifier, to_bytes(&spend.note.nullifier()));
+        assert_eq!(inputs.commitments[0], to_bytes(&spend.outputs[0].commitment()));
+        assert_eq!(inputs.change_commitment(), &to_bytes(&spend.change_commitment()));
+    }
+
+    #[test]
+    fn committed_fixture_is_current() {
+        assert_committed_fixture::<DelegatedSpend>();
+    }
+}
@@ -0,0 +1,143 @@
+//! `circuits/unshield`: one note spent back to a public token account.
+
+use anchor_lang::prelude::Pubkey;
+use ark_bn254::Fr;
+use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystemRef, SynthesisError};
+use ptf_pool::instructions::recipient_hash;
+use serde_json::{json, Value};
+
+use crate::gadgets::{from_bytes, leaf_index, less_eq_than, merkle_root, num2bits, poseidon, Builder, Signal};
+use crate::notes::{insert_notes, InputNote};
+use crate::Circuit;
+
+#[derive(Clone, Debug)]
+pub struct Unshield {
+    pub root: Fr,
+    pub note: InputNote,
+    /// Owner of the token account the funds go to
+    pub recipient: Pubkey,
+    pub fee: u64,
+}
+
+impl Unshield {
+    pub fn recipient_hash(&self) -> Fr {
+        from_bytes(&recipient_hash(&self.recipient))
+    }
+}
+
+impl ConstraintSynthesizer<Fr> for Unshield {
+    fn generate_constraints(self, cs: ConstraintSystemRef<Fr>) -> Result<(), SynthesisError> {
+        let b = Builder::new(cs);
+        // [root][nullifier][amount][recipient_hash][fee]
+        let root = b.input(self.root)?;
+        let nullifier = b.input(self.note.nullifier())?;
+        let amount = b.input(Fr::from(self.note.amount))?;
+        let recipient = b.input(self.recipient_hash())?;
+        let fee = b.input(Fr::from(self.fee))?;
+        let secret = b.witness(self.note.secret)?;
+        let path_elements = b.witnesses(&self.note.path)?;
+        let path_indices = b.witnesses(&self.note.path_indices())?;
+        
+        num2bits(&b, &amount, 64)?;
+        num2bits(&b, &fee, 64)?;
+        let fee_fits = less_eq_than(&b, 64, &fee, &amount)?;
+        b.equal(&fee_fits, &Signal::constant(Fr::from(1u64)))?;
+        
+        let commitment = poseidon(&b, &[secret.clone(), amount, Signal::zero()])?;
+        b.equal(&merkle_root(&b, &commitment, &path_elements, &path_indices)?, &root)?;
+        b.equal(&poseidon(&b, &[secret, leaf_index(&path_indices)])?, &nullifier)?;
+        
+        // Binds the recipient into the proof; it is otherwise unconstrained
+        b.mul(&recipient, &recipient)?;
+        Ok(())
+    }
+}
+
+impl Circuit for Unshield {
+    const NAME: &'static str = "unshield";
+    
+    fn fixture() -> Self {
+        let mut notes = [InputNote {
+            secret: Fr::from(1004u64),
+            amount: 500,
+            tag: Fr::from(0u64),
+            leaf_index: 0,
+            path: Vec::new(),
+        }];
+        let root = insert_notes(&mut notes);
+        let [note] = notes;
+        Self { root, note, recipient: Pubkey::new_from_array([7u8; 32]), fee: 5 }
+    }
+    
+    fn public_inputs(&self) -> Vec<Fr> {
+        vec![
+            self.root,
+            self.note.nullifier(),
+            Fr::from(self.note.amount),
+            self.recipient_hash(),
+            Fr::from(self.fee),
+        ]
+    }
+    
+    fn input_json(&self) -> Value {
+        let (path_elements, path_indices) = self.note.path_json();
+        json!({
+            "root": self.root.to_string(),
+            "nullifier": self.note.nullifier().to_string(),
+            "amount": self.note.amount.to_string(),
+            "recipient_hash": self.recipient_hash().to_string(),
+            "fee": self.fee.to_string(),
+            "secret": self.note.secret.to_string(),
+            "path_elements": path_elements,
+            "path_indices": path_indices,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gadgets::to_bytes;
+    use crate::tests::{assert_committed_fixture, assert_satisfied, assert_unsatisfied};
+    use ptf_pool::instructions::UnshieldRequest;
+
+    #[test]
+    fn fixture_satisfies_the_circuit() {
+        assert_satisfied(Unshield::fixture());
+    }
+
+    #[test]
+    fn rejects_a_fee_above_the_amount() {
+        let mut unshield = Unshield::fixture();
+        unshield.fee = unshield.note.amount + 1;
+        assert_unsatisfied(unshield);
+    }
+
+    #[test]
+    fn rejects_a_note_outside_the_tree() {
+        let mut unshield = Unshield::fixture();
+        unshield.note.amount += 1;
+        assert_unsatisfied(unshield);
+    }
+
+    #[test]
+    fn public_inputs_match_the_pool_encoding() {
+        let unshield = Unshield::fixture();
+        let encoded: Vec<u8> = unshield.public_inputs().iter().flat_map(to_bytes).collect();
+        let request = UnshieldRequest::new(
+            Pubkey::default(),
+            to_bytes(&unshield.root),
+            to_bytes(&unshield.note.nullifier()),
+            unshield.note.amount,
+            unshield.recipient,
+            unshield.fee,
+        )
+        .unwrap();
+        assert_eq!(encoded, request.inputs.to_bytes());
+    }
+
+    #[test]
+    fn committed_fixture_is_current() {
+        assert_committed_fixture::<Unshield>();
+    }
+}
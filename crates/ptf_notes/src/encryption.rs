@@ -0,0 +1,169 @@
+//! Note encryption to a recipient's viewing key.
+//!
+//! A ciphertext is [version][ephemeral X25519 public key][AES-256-GCM-SIV(note) + tag]. The key
+//! is sha256 of a domain tag, the X25519 shared secret, the ephemeral key and the recipient key.
+//! Every note gets a fresh ephemeral key, so each AEAD key is used once and the nonce is fixed.
+//! The output commitment is the associated data, so a ciphertext can't be moved to another leaf.
+
+use aes_gcm_siv::aead::{Aead, KeyInit, Payload};
+use aes_gcm_siv::{Aes256GcmSiv, Nonce};
+use anchor_lang::prelude::*;
+use curve25519_dalek::montgomery::MontgomeryPoint;
+use rand_core::CryptoRngCore;
+use sha2::{Digest, Sha256};
+use zeroize::{Zeroize, ZeroizeOnDrop};
+use crate::note::Note;
+use crate::NoteError;
+
+/// Layout version, the first byte of every ciphertext
+pub const NOTE_CIPHERTEXT_VERSION: u8 = 1;
+/// Size of every ciphertext `encrypt_note` produces
+pub const ENCRYPTED_NOTE_LEN: usize = 1 + 32 + Note::LEN + 16;
+
+const KEY_DOMAIN: &[u8] = b"ptf-note-v1";
+
+// The pool refuses larger ciphertexts
+const _: () = assert!(ENCRYPTED_NOTE_LEN <= ptf_pool::events::MAX_ENCRYPTED_NOTE_SIZE);
+
+/// Secret that finds and opens the notes sent to its public key. It can't spend them.
+#[derive(Clone, Zeroize, ZeroizeOnDrop)]
+pub struct ViewingKey([u8; 32]);
+
+/// X25519 public key senders encrypt notes to
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct NotePublicKey(pub [u8; 32]);
+
+impl ViewingKey {
+    pub fn from_bytes(bytes: [u8; 32]) -> Self {
+        Self(bytes)
+    }
+    
+    pub fn generate(rng: &mut impl CryptoRngCore) -> Self {
+        let mut bytes = [0u8; 32];
+        rng.fill_bytes(&mut bytes);
+        Self(bytes)
+    }
+    
+    pub fn public_key(&self) -> NotePublicKey {
+        NotePublicKey(MontgomeryPoint::mul_base_clamped(self.0).to_bytes())
+    }
+    
+    /// Open `ciphertext` if it was encrypted to this key for `commitment` and the note inside
+    /// hashes to that commitment; `None` for anyone else's note or a malformed one
+    pub fn trial_decrypt(&self, ciphertext: &[u8], commitment: &[u8; 32]) -> Option<Note> {
+        if ciphertext.len() != ENCRYPTED_NOTE_LEN || ciphertext[0] != NOTE_CIPHERTEXT_VERSION {
+            return None;
+        }
+        let ephemeral: [u8; 32] = ciphertext[1..33].try_into().ok()?;
+        let shared = MontgomeryPoint(ephemeral).mul_clamped(self.0);
+        let cipher = note_cipher(&shared, &ephemeral, &self.public_key()).ok()?;
+        let payload = Payload { msg: &ciphertext[33..], aad: commitment };
+        let plaintext = cipher.decrypt(Nonce::from_slice(&[0u8; 12]), payload).ok()?;
+        let note = Note::try_from_slice(&plaintext).ok()?;
+        (note.commitment().ok()? == *commitment).then_some(note)
+    }
+}
+
+/// Encrypt `note` to `recipient`, bound to the output `commitment` it opens
+pub fn encrypt_note(
+    note: &Note,
+    commitment: &[u8; 32],
+    recipient: &NotePublicKey,
+    rng: &mut impl CryptoRngCore,
+) -> std::result::Result<Vec<u8>, NoteError> {
+    let mut secret = [0u8; 32];
+    rng.fill_bytes(&mut secret);
+    let ephemeral = MontgomeryPoint::mul_base_clamped(secret).to_bytes();
+    let shared = MontgomeryPoint(recipient.0).mul_clamped(secret);
+    secret.zeroize();
+    
+    let cipher = note_cipher(&shared, &ephemeral, recipient)?;
+    let plaintext = note.try_to_vec().map_err(|_| NoteError::Encryption)?;
+    let payload = Payload { msg: &plaintext, aad: commitment };
+    let sealed = cipher
+        .encrypt(Nonce::from_slice(&[0u8; 12]), payload)
+        .map_err(|_| NoteError::Encryption)?;
+    
+    let mut ciphertext = Vec::with_capacity(ENCRYPTED_NOTE_LEN);
+    ciphertext.push(NOTE_CIPHERTEXT_VERSION);
+    ciphertext.extend_from_slice(&ephemeral);
+    ciphertext.extend_from_slice(&sealed);
+    Ok(ciphertext)
+}
+
+/// AEAD keyed for one ephemeral key. A low-order recipient or ephemeral key gives an all-zero
+/// shared secret that anyone could compute, so it is refused.
+fn note_cipher(
+    shared: &MontgomeryPoint,
+    ephemeral: &[u8; 32],
+    recipient: &NotePublicKey,
+) -> std::result::Result<Aes256GcmSiv, NoteError> {
+    if shared.to_bytes() == [0u8; 32] {
+        return Err(NoteError::InvalidKey);
+    }
+    let mut key: [u8; 32] = Sha256::new()
+        .chain_update(KEY_DOMAIN)
+        .chain_update(shared.to_bytes())
+        .chain_update(ephemeral)
+        .chain_update(recipient.0)
+        .finalize()
+        .into();
+    let cipher = Aes256GcmSiv::new(&key.into());
+    key.zeroize();
+    Ok(cipher)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::note::MEMO_LEN;
+    use rand_core::OsRng;
+
+    fn note(amount: u64) -> Note {
+        let mut memo = [0u8; MEMO_LEN];
+        memo[..5].copy_from_slice(b"rent!");
+        Note {
+            amount,
+            blinding: [3u8; 32],
+            tag: [0u8; 32],
+            asset: Pubkey::new_unique(),
+            memo,
+        }
+    }
+
+    #[test]
+    fn only_the_recipient_opens_a_note_and_only_for_its_commitment() {
+        let recipient = ViewingKey::generate(&mut OsRng);
+        let someone_else = ViewingKey::generate(&mut OsRng);
+        let note = note(42);
+        let commitment = note.commitment().unwrap();
+
+        let ciphertext = encrypt_note(&note, &commitment, &recipient.public_key(), &mut OsRng).unwrap();
+        assert_eq!(ciphertext.len(), ENCRYPTED_NOTE_LEN);
+
+        assert_eq!(recipient.trial_decrypt(&ciphertext, &commitment), Some(note.clone()));
+        assert_eq!(someone_else.trial_decrypt(&ciphertext, &commitment), None);
+        assert_eq!(recipient.trial_decrypt(&ciphertext, &[1u8; 32]), None);
+        assert_eq!(recipient.trial_decrypt(&ciphertext[1..], &commitment), None);
+
+        let mut tampered = ciphertext.clone();
+        tampered[40] ^= 1;
+        assert_eq!(recipient.trial_decrypt(&tampered, &commitment), None);
+    }
+
+    #[test]
+    fn a_note_that_does_not_match_its_commitment_is_rejected() {
+        let recipient = ViewingKey::generate(&mut OsRng);
+        let claimed = note(42).commitment().unwrap();
+        let ciphertext = encrypt_note(&note(1_000), &claimed, &recipient.public_key(), &mut OsRng).unwrap();
+        assert_eq!(recipient.trial_decrypt(&ciphertext, &claimed), None);
+    }
+
+    #[test]
+    fn low_order_recipient_keys_are_refused() {
+        let note = note(1);
+        let commitment = note.commitment().unwrap();
+        let err = encrypt_note(&note, &commitment, &NotePublicKey([0u8; 32]), &mut OsRng).unwrap_err();
+        assert_eq!(err, NoteError::InvalidKey);
+    }
+}
@@ -0,0 +1,24 @@
+//! Off-chain handling of encrypted note memos.
+//!
+//! Whoever creates a note (a depositor in `execute_shield_v2`, a sender in a transfer) encrypts
+//! its opening to the recipient's viewing key and attaches the ciphertext to the instruction.
+//! The pool publishes it next to the commitment in `Shielded` or `Transferred`, and the recipient
+//! finds the note by trial-decrypting the event stream with a `NoteScanner`.
+
+mod encryption;
+mod note;
+mod scan;
+
+pub use encryption::{encrypt_note, NotePublicKey, ViewingKey, ENCRYPTED_NOTE_LEN, NOTE_CIPHERTEXT_VERSION};
+pub use note::{Note, MEMO_LEN};
+pub use scan::{decode_event, NoteScanner, PoolEvent, ReceivedNote};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+pub enum NoteError {
+    #[error("Value is not a canonical field element")]
+    InvalidFieldElement,
+    #[error("Public key has low order")]
+    InvalidKey,
+    #[error("Note encryption failed")]
+    Encryption,
+}
@@ -0,0 +1,69 @@
+//! Note openings and the values the circuits derive from them.
+
+use anchor_lang::prelude::*;
+use ptf_common::poseidon;
+use crate::NoteError;
+
+/// Bytes of free-form memo every note carries, zero-padded so all notes encrypt to one size
+pub const MEMO_LEN: usize = 32;
+
+/// Everything the owner of a note needs to spend it
+#[derive(Clone, Debug, PartialEq, Eq, AnchorSerialize, AnchorDeserialize)]
+pub struct Note {
+    pub amount: u64,
+    /// The note secret, `secret` in the circuits; a field element
+    pub blinding: [u8; 32],
+    /// Zero for an ordinary note, the delegation tag for a delegation note
+    pub tag: [u8; 32],
+    /// Mint of the pool the note is in
+    pub asset: Pubkey,
+    pub memo: [u8; MEMO_LEN],
+}
+
+impl Note {
+    pub const LEN: usize = 8 + 32 + 32 + 32 + MEMO_LEN;
+    
+    /// Poseidon(blinding, amount, tag), the leaf the pool appends for this note
+    pub fn commitment(&self) -> std::result::Result<[u8; 32], NoteError> {
+        poseidon::hashv(&[&self.blinding, &u64_element(self.amount), &self.tag])
+            .map_err(|_| NoteError::InvalidFieldElement)
+    }
+    
+    /// Poseidon(blinding, leaf index), revealed when the note at `leaf_index` is spent
+    pub fn nullifier(&self, leaf_index: u64) -> std::result::Result<[u8; 32], NoteError> {
+        poseidon::hashv(&[&self.blinding, &u64_element(leaf_index)])
+            .map_err(|_| NoteError::InvalidFieldElement)
+    }
+}
+
+/// `value` as a 32-byte big-endian field element
+fn u64_element(value: u64) -> [u8; 32] {
+    let mut element = [0u8; 32];
+    element[24..].copy_from_slice(&value.to_be_bytes());
+    element
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn note_layout_and_derived_values() {
+        let mut note = Note {
+            amount: 5,
+            blinding: [7u8; 32],
+            tag: [0u8; 32],
+            asset: Pubkey::new_unique(),
+            memo: [0u8; MEMO_LEN],
+        };
+        assert_eq!(note.try_to_vec().unwrap().len(), Note::LEN);
+
+        let commitment = note.commitment().unwrap();
+        assert_ne!(note.nullifier(0).unwrap(), note.nullifier(1).unwrap());
+        note.amount = 6;
+        assert_ne!(note.commitment().unwrap(), commitment);
+
+        note.blinding = [0xff; 32];
+        assert_eq!(note.commitment().unwrap_err(), NoteError::InvalidFieldElement);
+    }
+}
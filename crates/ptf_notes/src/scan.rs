@@ -0,0 +1,185 @@
+//! Finding a wallet's notes in the pool's event stream.
+//!
+//! Feed `decode_event` the data of every inner instruction to the pool program and hand the
+//! results to a `NoteScanner` in chain order. It keeps the notes it can open and drops them
+//! again once their nullifier shows up.
+
+use std::collections::HashSet;
+use anchor_lang::event::EVENT_IX_TAG_LE;
+use anchor_lang::prelude::*;
+use anchor_lang::Discriminator;
+use ptf_pool::events::{Nullified, Shielded, Transferred};
+use crate::encryption::ViewingKey;
+use crate::note::Note;
+
+/// The pool events that create or spend notes
+pub enum PoolEvent {
+    Shielded(Shielded),
+    Transferred(Transferred),
+    Nullified(Nullified),
+}
+
+/// Decode the data of a pool self-CPI. `None` for other instructions and other events. Fields
+/// appended to an event after this crate was built are ignored.
+pub fn decode_event(data: &[u8]) -> Option<PoolEvent> {
+    let data = data.strip_prefix(EVENT_IX_TAG_LE)?;
+    if data.len() < 8 {
+        return None;
+    }
+    let (discriminator, mut body) = data.split_at(8);
+    if discriminator == Shielded::DISCRIMINATOR {
+        Shielded::deserialize(&mut body).ok().map(PoolEvent::Shielded)
+    } else if discriminator == Transferred::DISCRIMINATOR {
+        Transferred::deserialize(&mut body).ok().map(PoolEvent::Transferred)
+    } else if discriminator == Nullified::DISCRIMINATOR {
+        Nullified::deserialize(&mut body).ok().map(PoolEvent::Nullified)
+    } else {
+        None
+    }
+}
+
+/// A note the viewing key opened, with what it takes to spend it
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ReceivedNote {
+    pub pool: Pubkey,
+    pub leaf_index: u64,
+    pub commitment: [u8; 32],
+    pub note: Note,
+    /// Nullifier that spending this note will reveal
+    pub nullifier: [u8; 32],
+}
+
+pub struct NoteScanner {
+    viewing_key: ViewingKey,
+    notes: Vec<ReceivedNote>,
+    spent: HashSet<[u8; 32]>,
+}
+
+impl NoteScanner {
+    pub fn new(viewing_key: ViewingKey) -> Self {
+        Self { viewing_key, notes: Vec::new(), spent: HashSet::new() }
+    }
+    
+    pub fn apply(&mut self, event: &PoolEvent) {
+        match event {
+            PoolEvent::Shielded(event) => {
+                self.receive(event.pool, event.leaf_index, &event.commitment, &event.encrypted_note);
+            }
+            PoolEvent::Transferred(event) => {
+                self.spent.extend(event.nullifiers.iter().copied());
+                let outputs = event.commitments.iter().zip(&event.leaf_indices);
+                for ((commitment, leaf_index), ciphertext) in outputs.zip(&event.encrypted_notes) {
+                    self.receive(event.pool, *leaf_index, commitment, ciphertext);
+                }
+            }
+            PoolEvent::Nullified(event) => {
+                self.spent.insert(event.nullifier);
+            }
+        }
+    }
+    
+    /// Apply every pool event among `instructions`, the inner instruction data in chain order
+    pub fn scan<'a>(&mut self, instructions: impl IntoIterator<Item = &'a [u8]>) {
+        for event in instructions.into_iter().filter_map(decode_event) {
+            self.apply(&event);
+        }
+    }
+    
+    /// Every note received so far, spent or not
+    pub fn notes(&self) -> &[ReceivedNote] {
+        &self.notes
+    }
+    
+    pub fn unspent(&self) -> impl Iterator<Item = &ReceivedNote> {
+        self.notes.iter().filter(|received| !self.spent.contains(&received.nullifier))
+    }
+    
+    fn receive(&mut self, pool: Pubkey, leaf_index: u64, commitment: &[u8; 32], ciphertext: &[u8]) {
+        let Some(note) = self.viewing_key.trial_decrypt(ciphertext, commitment) else {
+            return;
+        };
+        let Ok(nullifier) = note.nullifier(leaf_index) else {
+            return;
+        };
+        self.notes.push(ReceivedNote { pool, leaf_index, commitment: *commitment, note, nullifier });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anchor_lang::Event;
+    use rand_core::OsRng;
+    use crate::encryption::encrypt_note;
+    use crate::note::MEMO_LEN;
+
+    fn cpi_data(event: &impl Event) -> Vec<u8> {
+        [EVENT_IX_TAG_LE, &event.data()].concat()
+    }
+
+    #[test]
+    fn scanner_follows_notes_from_shield_to_spend() {
+        let alice = ViewingKey::generate(&mut OsRng);
+        let bob = ViewingKey::generate(&mut OsRng);
+        let pool = Pubkey::new_unique();
+        let note = |amount, blinding| Note {
+            amount,
+            blinding: [blinding; 32],
+            tag: [0u8; 32],
+            asset: Pubkey::new_unique(),
+            memo: [0u8; MEMO_LEN],
+        };
+
+        let deposit = note(100, 1);
+        let deposit_commitment = deposit.commitment().unwrap();
+        let shielded = Shielded {
+            pool,
+            commitment: deposit_commitment,
+            leaf_index: 0,
+            root: [0u8; 32],
+            encrypted_note: encrypt_note(&deposit, &deposit_commitment, &alice.public_key(), &mut OsRng).unwrap(),
+        };
+
+        let (payment, change) = (note(60, 2), note(40, 3));
+        let outputs = [payment.commitment().unwrap(), change.commitment().unwrap()];
+        let transferred = Transferred {
+            pool,
+            nullifiers: vec![deposit.nullifier(0).unwrap()],
+            commitments: outputs.to_vec(),
+            leaf_indices: vec![1, 2],
+            root: [0u8; 32],
+            encrypted_notes: vec![
+                encrypt_note(&payment, &outputs[0], &bob.public_key(), &mut OsRng).unwrap(),
+                encrypt_note(&change, &outputs[1], &alice.public_key(), &mut OsRng).unwrap(),
+            ],
+        };
+        let unrelated = Nullified { pool, nullifier: [9u8; 32] };
+
+        let stream = [cpi_data(&shielded), vec![1, 2, 3], cpi_data(&unrelated), cpi_data(&transferred)];
+        let mut scanner = NoteScanner::new(alice.clone());
+        scanner.scan(stream[..2].iter().map(Vec::as_slice));
+        assert_eq!(scanner.unspent().count(), 1);
+        scanner.scan(stream[2..].iter().map(Vec::as_slice));
+
+        let received: Vec<_> = scanner.notes().iter().map(|r| (r.leaf_index, r.note.amount)).collect();
+        assert_eq!(received, vec![(0, 100), (2, 40)]);
+        let unspent: Vec<_> = scanner.unspent().map(|r| r.note.clone()).collect();
+        assert_eq!(unspent, vec![change.clone()]);
+        assert_eq!(scanner.notes()[1].nullifier, change.nullifier(2).unwrap());
+
+        let mut bob_scanner = NoteScanner::new(bob);
+        bob_scanner.scan(stream.iter().map(Vec::as_slice));
+        let bob_notes: Vec<_> = bob_scanner.unspent().map(|r| r.note.clone()).collect();
+        assert_eq!(bob_notes, vec![payment]);
+    }
+
+    #[test]
+    fn decoding_tolerates_fields_appended_later() {
+        let event = Nullified { pool: Pubkey::new_unique(), nullifier: [4u8; 32] };
+        let mut data = cpi_data(&event);
+        data.extend_from_slice(&[0xaa; 8]);
+        assert!(matches!(decode_event(&data), Some(PoolEvent::Nullified(n)) if n.nullifier == [4u8; 32]));
+        assert!(decode_event(&event.data()).is_none());
+        assert!(decode_event(&data[..12]).is_none());
+    }
+}
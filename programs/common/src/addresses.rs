@@ -1,11 +1,17 @@
 use anchor_lang::prelude::*;
 use anchor_lang::solana_program::pubkey::Pubkey;
 
+/// ptf_factory program id, for programs that check factory authority without depending on it
+pub const FACTORY_PROGRAM_ID: Pubkey = pubkey!("4NHiLQJwmgQW9hGrxeAPESXLvgMgEdBfRdAa3Wxiyf8u");
+
+/// ptf_pool program id, used by ptf_vault to derive the pool PDA that owns each vault
+pub const POOL_PROGRAM_ID: Pubkey = pubkey!("6MLrNAydScBBWq6vFXPLjahvxjF1PzauuSYTuLS7yfYC");
+
 pub struct PoolAddresses {
     pub pool_state: Pubkey,
     pub commitment_tree: Pubkey,
+    pub root_history: Pubkey,
     pub nullifier_set: Pubkey,
-    pub note_ledger: Pubkey,
     pub hook_config: Pubkey,
     pub hook_whitelist: Pubkey,
 }
@@ -22,13 +28,13 @@ impl PoolAddresses {
             program_id,
         );
         
-        let (nullifier_set, _) = Pubkey::find_program_address(
-            &[b"nullifier-set", origin_mint.as_ref()],
+        let (root_history, _) = Pubkey::find_program_address(
+            &[b"root-history", origin_mint.as_ref()],
             program_id,
         );
         
-        let (note_ledger, _) = Pubkey::find_program_address(
-            &[b"note-ledger", origin_mint.as_ref()],
+        let (nullifier_set, _) = Pubkey::find_program_address(
+            &[b"nullifier-set", origin_mint.as_ref()],
             program_id,
         );
         
@@ -45,8 +51,8 @@ impl PoolAddresses {
         Self {
             pool_state,
             commitment_tree,
+            root_history,
             nullifier_set,
-            note_ledger,
             hook_config,
             hook_whitelist,
         }
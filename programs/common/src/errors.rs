@@ -12,5 +12,9 @@ pub enum CommonError {
     InvalidAmount,
     #[msg("Proof verification failed")]
     VerifierMismatch,
+    #[msg("Value is not a canonical BN254 field element")]
+    InvalidFieldElement,
+    #[msg("Poseidon hash failed")]
+    PoseidonHashFailed,
 }
 
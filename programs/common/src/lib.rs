@@ -1,5 +1,6 @@
 pub mod addresses;
 pub mod errors;
+pub mod poseidon;
 pub mod security;
 pub mod types;
 pub mod validation;
@@ -0,0 +1,77 @@
+use anchor_lang::prelude::*;
+use solana_poseidon::{Endianness, Parameters, PoseidonSyscallError};
+use crate::errors::CommonError;
+
+/// Poseidon over BN254 with the circomlib parameters (x^5 S-box, t = inputs + 1).
+/// Inputs and output are big-endian field elements, matching the circuits' signals.
+/// On-chain this is the `sol_poseidon` syscall; off-chain it falls back to light-poseidon.
+pub fn hashv(inputs: &[&[u8; 32]]) -> Result<[u8; 32]> {
+    let inputs: Vec<&[u8]> = inputs.iter().map(|input| input.as_slice()).collect();
+    solana_poseidon::hashv(Parameters::Bn254X5, Endianness::BigEndian, &inputs)
+        .map(|hash| hash.to_bytes())
+        .map_err(|err| match err {
+            PoseidonSyscallError::InputLargerThanModulus => CommonError::InvalidFieldElement.into(),
+            _ => CommonError::PoseidonHashFailed.into(),
+        })
+}
+
+/// Poseidon(left, right), the node hash of the commitment tree
+pub fn hash_pair(left: &[u8; 32], right: &[u8; 32]) -> Result<[u8; 32]> {
+    hashv(&[left, right])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn field(value: u64) -> [u8; 32] {
+        let mut bytes = [0u8; 32];
+        bytes[24..].copy_from_slice(&value.to_be_bytes());
+        bytes
+    }
+
+    fn hex32(s: &str) -> [u8; 32] {
+        let mut out = [0u8; 32];
+        for (i, byte) in out.iter_mut().enumerate() {
+            *byte = u8::from_str_radix(&s[2 * i..2 * i + 2], 16).unwrap();
+        }
+        out
+    }
+
+    // Expected values are circomlibjs `poseidon([...])` outputs
+    #[test]
+    fn matches_circomlib_vectors() {
+        assert_eq!(
+            hashv(&[&field(1)]).unwrap(),
+            hex32("29176100eaa962bdc1fe6c654d6a3c130e96a4d1168b33848b897dc502820133")
+        );
+        assert_eq!(
+            hash_pair(&field(1), &field(2)).unwrap(),
+            hex32("115cc0f5e7d690413df64c6b9662e9cf2a3617f2743245519e19607a4417189a")
+        );
+        assert_eq!(
+            hash_pair(&field(0), &field(0)).unwrap(),
+            hex32("2098f5fb9e239eab3ceac3f27b81e481dc3124d55ffed523a839ee8446b64864")
+        );
+        assert_eq!(
+            hashv(&[&field(1), &field(2), &field(3)]).unwrap(),
+            hex32("0e7732d89e6939c0ff03d5e58dab6302f3230e269dc5b968f725df34ab36d732")
+        );
+    }
+
+    #[test]
+    fn hash_pair_is_order_sensitive() {
+        assert_ne!(
+            hash_pair(&field(1), &field(2)).unwrap(),
+            hash_pair(&field(2), &field(1)).unwrap()
+        );
+    }
+
+    #[test]
+    fn rejects_values_outside_the_field() {
+        // BN254 scalar modulus r
+        let modulus = hex32("30644e72e131a029b85045b68181585d2833e84879b9709143e1f593f0000001");
+        assert!(hash_pair(&modulus, &field(0)).is_err());
+        assert!(hash_pair(&field(0), &[0xff; 32]).is_err());
+    }
+}
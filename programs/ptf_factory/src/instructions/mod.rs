@@ -1,8 +1,10 @@
 pub mod initialize_factory;
 pub mod register_mint;
 pub mod create_verifying_key;
+pub mod rotate_pool_verifying_key;
 
 pub use initialize_factory::*;
 pub use register_mint::*;
 pub use create_verifying_key::*;
+pub use rotate_pool_verifying_key::*;
 
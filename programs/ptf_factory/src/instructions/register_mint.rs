@@ -1,14 +1,16 @@
 use anchor_lang::prelude::*;
 use crate::state::{FactoryState, MintMapping};
 use crate::errors::FactoryError;
+use ptf_pool;
 
-pub fn register_mint(
-    ctx: Context<RegisterMint>,
+/// The verifying key of each pool circuit is passed as a remaining account
+pub fn register_mint<'info>(
+    ctx: Context<'_, '_, '_, 'info, RegisterMint<'info>>,
     origin_mint: Pubkey,
-    pool: Pubkey,
+    canopy_depth: u8,
+    root_history_capacity: u16,
 ) -> Result<()> {
     let factory = &ctx.accounts.factory;
-    let mint_mapping = &mut ctx.accounts.mint_mapping;
     
     // Verify authority
     require!(
@@ -16,11 +18,35 @@ pub fn register_mint(
         FactoryError::InvalidAuthority
     );
     
+    // CPI to pool program to create the pool PDAs, signed by the factory PDA
+    let bump = [factory.bump];
+    let signer_seeds: &[&[&[u8]]] = &[&[b"factory", &bump]];
+    let cpi_program = ctx.accounts.pool_program.to_account_info();
+    let cpi_accounts = ptf_pool::cpi::accounts::InitializePool {
+        pool_state: ctx.accounts.pool_state.to_account_info(),
+        commitment_tree: ctx.accounts.commitment_tree.to_account_info(),
+        root_history: ctx.accounts.root_history.to_account_info(),
+        nullifier_set: ctx.accounts.nullifier_set.to_account_info(),
+        vault_state: ctx.accounts.vault_state.to_account_info(),
+        factory: ctx.accounts.factory.to_account_info(),
+        payer: ctx.accounts.authority.to_account_info(),
+        system_program: ctx.accounts.system_program.to_account_info(),
+    };
+    let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer_seeds)
+        .with_remaining_accounts(ctx.remaining_accounts.to_vec());
+    ptf_pool::cpi::initialize_pool(
+        cpi_ctx,
+        origin_mint,
+        canopy_depth,
+        root_history_capacity,
+    )?;
+    
     // The init constraint ensures the account is new, so we don't need to check
     // Just initialize the fields
     
+    let mint_mapping = &mut ctx.accounts.mint_mapping;
     mint_mapping.origin_mint = origin_mint;
-    mint_mapping.pool = pool;
+    mint_mapping.pool = ctx.accounts.pool_state.key();
     mint_mapping.twin_mint = None;
     mint_mapping.bump = ctx.bumps.mint_mapping;
     
@@ -45,9 +71,34 @@ pub struct RegisterMint<'info> {
     )]
     pub mint_mapping: Account<'info, MintMapping>,
     
+    /// CHECK: Pool state PDA (created by pool program)
+    #[account(
+        mut,
+        seeds = [b"pool", origin_mint.as_ref()],
+        bump,
+        seeds::program = ptf_pool::ID
+    )]
+    pub pool_state: UncheckedAccount<'info>,
+    
+    /// CHECK: Commitment tree PDA (created by pool program)
+    #[account(mut)]
+    pub commitment_tree: UncheckedAccount<'info>,
+    
+    /// CHECK: Root history PDA (created by pool program)
+    #[account(mut)]
+    pub root_history: UncheckedAccount<'info>,
+    
+    /// CHECK: Nullifier set PDA (created by pool program)
+    #[account(mut)]
+    pub nullifier_set: UncheckedAccount<'info>,
+    
+    /// CHECK: Vault state PDA (validated by pool program)
+    pub vault_state: UncheckedAccount<'info>,
+    
     #[account(mut)]
     pub authority: Signer<'info>,
     
+    pub pool_program: Program<'info, ptf_pool::program::PtfPool>,
+    
     pub system_program: Program<'info, System>,
 }
-
@@ -0,0 +1,55 @@
+use anchor_lang::prelude::*;
+use crate::state::FactoryState;
+use crate::errors::FactoryError;
+use ptf_pool;
+
+pub fn rotate_pool_verifying_key(
+    ctx: Context<RotatePoolVerifyingKey>,
+    grace_slots: u64,
+) -> Result<()> {
+    let factory = &ctx.accounts.factory;
+    
+    // Verify authority
+    require!(
+        factory.authority == ctx.accounts.authority.key(),
+        FactoryError::InvalidAuthority
+    );
+    
+    // CPI to pool program, signed by the factory PDA
+    let bump = [factory.bump];
+    let signer_seeds: &[&[&[u8]]] = &[&[b"factory", &bump]];
+    let cpi_program = ctx.accounts.pool_program.to_account_info();
+    let cpi_accounts = ptf_pool::cpi::accounts::RotateVerifyingKey {
+        pool_state: ctx.accounts.pool_state.to_account_info(),
+        factory: ctx.accounts.factory.to_account_info(),
+        current_verifying_key: ctx.accounts.current_verifying_key.to_account_info(),
+        new_verifying_key: ctx.accounts.new_verifying_key.to_account_info(),
+    };
+    let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer_seeds);
+    ptf_pool::cpi::rotate_verifying_key(cpi_ctx, grace_slots)?;
+    
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct RotatePoolVerifyingKey<'info> {
+    #[account(
+        seeds = [b"factory"],
+        bump = factory.bump
+    )]
+    pub factory: Account<'info, FactoryState>,
+    
+    /// CHECK: Pool state account (validated by pool program)
+    #[account(mut)]
+    pub pool_state: UncheckedAccount<'info>,
+    
+    /// CHECK: The pool's current key for the new key's circuit (validated by pool program)
+    pub current_verifying_key: UncheckedAccount<'info>,
+    
+    /// CHECK: New verifying key account (validated by pool program)
+    pub new_verifying_key: UncheckedAccount<'info>,
+    
+    pub authority: Signer<'info>,
+    
+    pub pool_program: Program<'info, ptf_pool::program::PtfPool>,
+}
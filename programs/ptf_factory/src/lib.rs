@@ -13,19 +13,23 @@ declare_id!("4NHiLQJwmgQW9hGrxeAPESXLvgMgEdBfRdAa3Wxiyf8u");
 #[program]
 pub mod ptf_factory {
     use super::*;
-
+    
     pub fn initialize_factory(ctx: Context<InitializeFactory>) -> Result<()> {
         instructions::initialize_factory(ctx)
     }
-
-    pub fn register_mint(
-        ctx: Context<RegisterMint>,
+    
+    /// Register an origin mint and create its pool through CPI into ptf_pool.
+    /// The verifying key of each pool circuit is passed as a remaining account.
+    /// `canopy_depth` must be 0; pools keep no canopy.
+    pub fn register_mint<'info>(
+        ctx: Context<'_, '_, '_, 'info, RegisterMint<'info>>,
         origin_mint: Pubkey,
-        pool: Pubkey,
+        canopy_depth: u8,
+        root_history_capacity: u16,
     ) -> Result<()> {
-        instructions::register_mint(ctx, origin_mint, pool)
+        instructions::register_mint(ctx, origin_mint, canopy_depth, root_history_capacity)
     }
-
+    
     pub fn create_verifying_key(
         ctx: Context<CreateVerifyingKey>,
         circuit_tag: [u8; 32],
@@ -34,5 +38,14 @@ pub mod ptf_factory {
     ) -> Result<()> {
         instructions::create_verifying_key(ctx, circuit_tag, version, key_data)
     }
+    
+    /// Move a pool to a new verifying key version; proofs against the
+    /// previous version are still accepted for grace_slots
+    pub fn rotate_pool_verifying_key(
+        ctx: Context<RotatePoolVerifyingKey>,
+        grace_slots: u64,
+    ) -> Result<()> {
+        instructions::rotate_pool_verifying_key(ctx, grace_slots)
+    }
 }
 
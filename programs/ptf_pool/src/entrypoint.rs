@@ -1,10 +1,47 @@
-use anchor_lang::solana_program::{
-    account_info::AccountInfo,
-    entrypoint::ProgramResult,
-    pubkey::Pubkey,
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::entrypoint::ProgramResult;
+use anchor_lang::Discriminator;
+use crate::instruction;
+use crate::instructions::{
+    execute_batch_transfer_from_raw_handler, execute_batch_transfer_raw_handler,
+    execute_shield_v2_raw_handler, execute_transfer_from_raw_handler, execute_transfer_raw_handler,
 };
 
-// Custom entrypoint will be implemented after first build
-// For now, this module is a placeholder
-// The actual entrypoint will be added when we have instruction discriminators
+// Replaces Anchor's entrypoint (the custom-entrypoint feature implies no-entrypoint).
+// Not declared for cpi builds, which are linked into other programs.
+#[cfg(all(feature = "custom-entrypoint", not(feature = "cpi")))]
+anchor_lang::solana_program::entrypoint!(process_instruction);
 
+/// Route the account-heavy instructions to raw handlers and everything else to Anchor.
+/// Raw handlers identify their accounts by owner, discriminator and PDA instead of
+/// deserializing a `Context`, so large accounts are never copied onto the stack.
+pub fn process_instruction<'info>(
+    program_id: &Pubkey,
+    accounts: &'info [AccountInfo<'info>],
+    data: &[u8],
+) -> ProgramResult {
+    if *program_id != crate::ID {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    
+    let discriminator = data.get(..8).unwrap_or_default();
+    if discriminator == instruction::ExecuteShieldV2::DISCRIMINATOR {
+        execute_shield_v2_raw_handler(program_id, accounts, data)
+    } else if discriminator == instruction::ExecuteTransfer::DISCRIMINATOR {
+        execute_transfer_raw_handler(program_id, accounts, data)
+    } else if discriminator == instruction::ExecuteTransferFrom::DISCRIMINATOR {
+        execute_transfer_from_raw_handler(program_id, accounts, data)
+    } else if discriminator == instruction::ExecuteBatchTransfer::DISCRIMINATOR {
+        execute_batch_transfer_raw_handler(program_id, accounts, data)
+    } else if discriminator == instruction::ExecuteBatchTransferFrom::DISCRIMINATOR {
+        execute_batch_transfer_from_raw_handler(program_id, accounts, data)
+    } else {
+        crate::entry(program_id, accounts, data)
+    }
+}
+
+/// Deserialize Anchor instruction args (everything after the 8-byte discriminator)
+pub fn parse_args<T: AnchorDeserialize>(data: &[u8]) -> std::result::Result<T, ProgramError> {
+    let mut args = data.get(8..).ok_or(ProgramError::InvalidInstructionData)?;
+    T::deserialize(&mut args).map_err(|_| ProgramError::InvalidInstructionData)
+}
@@ -44,5 +44,47 @@ pub enum PoolError {
     InvalidPublicInputs = 0x1793,
     #[msg("Tree update failed")]
     TreeUpdateFailed = 0x1794,
+    #[msg("Verifying key does not match pool")]
+    VerifyingKeyMismatch = 0x1795,
+    #[msg("Too many pinned verifying keys")]
+    TooManyPinnedVerifyingKeys = 0x1796,
+    #[msg("Invalid verifying key")]
+    InvalidVerifyingKey = 0x1797,
+    #[msg("Canopy depth must be 0; the commitment tree keeps no canopy")]
+    InvalidCanopyDepth = 0x1798,
+    #[msg("Commitment tree is full")]
+    MerkleTreeFull = 0x1799,
+    #[msg("Account does not match its PDA")]
+    InvalidPda = 0x179a,
+    #[msg("Root history capacity out of range")]
+    InvalidRootHistoryCapacity = 0x179b,
+    #[msg("Token account owner does not match the unshield recipient")]
+    RecipientMismatch = 0x179c,
+    #[msg("Prepared operation was written with an unsupported payload version")]
+    UnsupportedOperationVersion = 0x179d,
+    #[msg("Prepared operation has expired")]
+    OperationExpired = 0x179e,
+    #[msg("Proof vault holds an operation whose nullifier is spent but not yet withdrawn")]
+    OperationNotAbandonable = 0x179f,
+    #[msg("Allowance has expired")]
+    AllowanceExpired = 0x17a0,
+    #[msg("Signer is not the allowance spender")]
+    SpenderMismatch = 0x17a1,
+    #[msg("Encrypted note is too large")]
+    EncryptedNoteTooLarge = 0x17a2,
+    #[msg("Expected one encrypted note per output commitment")]
+    EncryptedNoteCountMismatch = 0x17a3,
+    #[msg("Verifying key is for a different circuit")]
+    CircuitTagMismatch = 0x17a4,
+    #[msg("Prepared operation is for a different pool")]
+    PoolMismatch = 0x17a5,
+    #[msg("Proof vault has an unsupported layout; close it with close_proof_vault")]
+    UnsupportedProofVaultLayout = 0x17a6,
+    #[msg("Token account is not the depositor's account for the pool mint")]
+    DepositAccountMismatch = 0x17a7,
+    #[msg("Reclaiming tagged notes requires their owner's signature")]
+    OwnerSignatureMissing = 0x17a8,
+    #[msg("Proof vault can't be closed in the slot it was created")]
+    ProofVaultTooNew = 0x17a9,
 }
 
@@ -0,0 +1,228 @@
+//! Events emitted by the pool.
+//!
+//! Every state transition is emitted with `emit_cpi!`: the event travels as the data of a
+//! self-CPI signed by the `[b"__event_authority"]` PDA, so it is kept in the transaction's inner
+//! instructions even when the logs are truncated. Indexers read inner instructions to this
+//! program whose data starts with `EVENT_IX_TAG_LE`, followed by the event's discriminator and
+//! its Borsh encoding. Wallets discover notes from these layouts, so fields are only ever added
+//! at the end of a struct.
+//!
+//! Spent nullifiers appear in `Nullified` for unshields and in `Transferred` for transfers.
+//! New notes carry the ciphertext their creator attached (see the `ptf_notes` crate), which
+//! the pool only bounds in size: recipients find their notes by trial-decrypting these.
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::solana_program::program::invoke_signed;
+use anchor_lang::Event;
+use ptf_common::OperationStatus;
+use crate::errors::PoolError;
+use crate::instructions::approve_allowance::Allowance;
+use crate::instructions::transfer_core::JoinSplitInputs;
+use crate::raw;
+
+/// Seed of the PDA that signs event self-CPIs, the `event_authority` `#[event_cpi]` adds
+pub const EVENT_AUTHORITY_SEED: &[u8] = b"__event_authority";
+/// Longest ciphertext that may be attached to a new note
+pub const MAX_ENCRYPTED_NOTE_SIZE: usize = 256;
+
+/// Check the ciphertexts a transfer attaches: one per output commitment, each within
+/// MAX_ENCRYPTED_NOTE_SIZE. An output may carry an empty one, such as change the sender keeps.
+pub fn check_encrypted_notes(encrypted_notes: &[Vec<u8>], inputs: &dyn JoinSplitInputs) -> Result<()> {
+    require!(
+        encrypted_notes.len() == inputs.commitments().len(),
+        PoolError::EncryptedNoteCountMismatch
+    );
+    require!(
+        encrypted_notes.iter().all(|note| note.len() <= MAX_ENCRYPTED_NOTE_SIZE),
+        PoolError::EncryptedNoteTooLarge
+    );
+    Ok(())
+}
+
+/// A deposit added `commitment` to the tree at `leaf_index`, producing `root`
+#[event]
+pub struct Shielded {
+    pub pool: Pubkey,
+    pub commitment: [u8; 32],
+    pub leaf_index: u64,
+    pub root: [u8; 32],
+    /// The note's opening encrypted to its owner, as supplied by the depositor
+    pub encrypted_note: Vec<u8>,
+}
+
+/// An unshield spent `nullifier`
+#[event]
+pub struct Nullified {
+    pub pool: Pubkey,
+    pub nullifier: [u8; 32],
+}
+
+/// A join-split spent `nullifiers` and appended `commitments` at `leaf_indices`. Items of a
+/// batch are emitted separately and share the root published after the whole batch.
+#[event]
+pub struct Transferred {
+    pub pool: Pubkey,
+    pub nullifiers: Vec<[u8; 32]>,
+    pub commitments: Vec<[u8; 32]>,
+    pub leaf_indices: Vec<u64>,
+    pub root: [u8; 32],
+    /// Ciphertext for each of `commitments`
+    pub encrypted_notes: Vec<Vec<u8>>,
+}
+
+impl Transferred {
+    pub fn new(
+        pool: Pubkey,
+        inputs: &dyn JoinSplitInputs,
+        first_index: u64,
+        root: [u8; 32],
+        encrypted_notes: Vec<Vec<u8>>,
+    ) -> Self {
+        let commitments = inputs.commitments().to_vec();
+        Self {
+            pool,
+            nullifiers: inputs.nullifiers().to_vec(),
+            leaf_indices: (first_index..).take(commitments.len()).collect(),
+            commitments,
+            root,
+            encrypted_notes,
+        }
+    }
+}
+
+/// Tokens for the note behind `nullifier` left the pool. `amount` includes any fee.
+#[event]
+pub struct Unshielded {
+    pub pool: Pubkey,
+    pub nullifier: [u8; 32],
+    pub amount: u64,
+    pub recipient: Pubkey,
+}
+
+/// An allowance was approved, changed, debited by a transfer_from or revoked (amount 0)
+#[event]
+pub struct AllowanceChanged {
+    pub owner: Pubkey,
+    pub spender: Pubkey,
+    pub pool: Pubkey,
+    pub amount: u64,
+    pub expiry_slot: Option<u64>,
+}
+
+impl AllowanceChanged {
+    pub fn new(allowance: &Allowance) -> Self {
+        Self {
+            owner: allowance.owner,
+            spender: allowance.spender,
+            pool: allowance.pool,
+            amount: allowance.amount,
+            expiry_slot: allowance.expiry_slot,
+        }
+    }
+}
+
+/// A prepared operation moved to `status` (an `OperationStatus`). Cancelled operations are
+/// reported as `Failed`; completed ones are removed from the vault.
+#[event]
+pub struct OperationStatusChanged {
+    pub proof_vault: Pubkey,
+    pub operation_id: [u8; 32],
+    pub status: u8,
+}
+
+impl OperationStatusChanged {
+    pub fn new(proof_vault: Pubkey, operation_id: [u8; 32], status: OperationStatus) -> Self {
+        Self { proof_vault, operation_id, status: status as u8 }
+    }
+}
+
+/// A batch transfer was rejected because of the item at `index`. The whole batch reverts, so
+/// this is read from the failed transaction's logs. An invalid proof aborts inside the
+/// verifier CPI before it can be reported; it is the last verifier invocation in the logs.
+#[event]
+pub struct BatchTransferFailed {
+    pub index: u8,
+    /// Error code of the failed check, as in the transaction error
+    pub error_code: u32,
+}
+
+impl BatchTransferFailed {
+    pub fn emit(index: usize, error: &Error) {
+        let error_code = match error {
+            Error::AnchorError(err) => err.error_code_number,
+            Error::ProgramError(err) => u64::from(err.program_error.clone()) as u32,
+        };
+        emit!(Self { index: index as u8, error_code });
+    }
+}
+
+/// `emit_cpi!` for the raw handlers, which have no `Context`: the event authority is located
+/// among the instruction's accounts by address
+pub struct EventAuthority<'a, 'info> {
+    info: &'a AccountInfo<'info>,
+    bump: u8,
+}
+
+impl<'a, 'info> EventAuthority<'a, 'info> {
+    pub fn find(program_id: &Pubkey, accounts: &'a [AccountInfo<'info>]) -> Result<Self> {
+        let (address, bump) = Pubkey::find_program_address(&[EVENT_AUTHORITY_SEED], program_id);
+        let info = raw::find_by_key(accounts, &address)?;
+        Ok(Self { info, bump })
+    }
+    
+    pub fn emit(&self, event: &impl Event) -> Result<()> {
+        let data = [anchor_lang::event::EVENT_IX_TAG_LE, &event.data()].concat();
+        let ix = Instruction::new_with_bytes(
+            crate::ID,
+            &data,
+            vec![AccountMeta::new_readonly(*self.info.key, true)],
+        );
+        invoke_signed(&ix, std::slice::from_ref(self.info), &[&[EVENT_AUTHORITY_SEED, &[self.bump]]])
+            .map_err(Into::into)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::instructions::transfer_core::DelegatedTransferPublicInputs;
+
+    #[test]
+    fn transferred_numbers_every_output_from_the_first_leaf() {
+        let inputs = DelegatedTransferPublicInputs {
+            root: [1u8; 32],
+            nullifier: [2u8; 32],
+            commitments: [[3u8; 32], [4u8; 32], [5u8; 32]],
+        };
+        let event = Transferred::new(Pubkey::default(), &inputs, 7, [6u8; 32], vec![vec![]; 3]);
+        assert_eq!(event.nullifiers, vec![[2u8; 32]]);
+        assert_eq!(event.commitments.len(), 3);
+        assert_eq!(event.leaf_indices, vec![7, 8, 9]);
+    }
+
+    #[test]
+    fn one_bounded_ciphertext_per_output() {
+        let inputs = DelegatedTransferPublicInputs {
+            root: [1u8; 32],
+            nullifier: [2u8; 32],
+            commitments: [[3u8; 32], [4u8; 32], [5u8; 32]],
+        };
+        let notes = vec![vec![7u8; MAX_ENCRYPTED_NOTE_SIZE], vec![], vec![1u8]];
+        check_encrypted_notes(&notes, &inputs).unwrap();
+
+        let err = check_encrypted_notes(&notes[..2], &inputs).unwrap_err();
+        assert_eq!(err, PoolError::EncryptedNoteCountMismatch.into());
+        let oversized = vec![vec![7u8; MAX_ENCRYPTED_NOTE_SIZE + 1], vec![], vec![]];
+        let err = check_encrypted_notes(&oversized, &inputs).unwrap_err();
+        assert_eq!(err, PoolError::EncryptedNoteTooLarge.into());
+    }
+
+    #[test]
+    fn event_data_is_discriminator_then_borsh() {
+        let event = Nullified { pool: Pubkey::new_unique(), nullifier: [9u8; 32] };
+        let data = event.data();
+        assert_eq!(&data[..8], Nullified::DISCRIMINATOR);
+        assert_eq!(&data[8..], &event.try_to_vec().unwrap()[..]);
+    }
+}
@@ -1,45 +1,140 @@
 use anchor_lang::prelude::*;
 use crate::{ApproveAllowanceArgs, ApproveAllowance as ApproveAllowanceContext};
 use crate::errors::PoolError;
+use crate::events::AllowanceChanged;
+use crate::instructions::unshield_core::recipient_hash;
 use ptf_common::InputValidator;
-use anchor_lang::solana_program::rent::Rent;
-use anchor_lang::solana_program::system_instruction;
-use anchor_lang::solana_program::program::invoke_signed;
 
+/// Transparent allowance: `spender` may move up to `amount` of `owner`'s notes in `pool`
+/// through `execute_transfer_from`, until `expiry_slot` if one is set
 #[account]
 pub struct Allowance {
     pub owner: Pubkey,
     pub spender: Pubkey,
     pub pool: Pubkey,
     pub amount: u64,
+    pub expiry_slot: Option<u64>,
     pub bump: u8,
 }
 
 impl Allowance {
-    pub const LEN: usize = 8 + 32 + 32 + 32 + 8 + 1;
+    pub const LEN: usize = 8 + 32 + 32 + 32 + 8 + 9 + 1;
+    
+    pub fn is_expired(&self, slot: u64) -> bool {
+        self.expiry_slot.is_some_and(|expiry| slot >= expiry)
+    }
+    
+    /// Take `amount` off the allowance for a spend at `slot`
+    pub fn debit(&mut self, amount: u64, slot: u64) -> Result<()> {
+        require!(!self.is_expired(slot), PoolError::AllowanceExpired);
+        self.amount = self.amount.checked_sub(amount).ok_or(PoolError::InsufficientAllowance)?;
+        Ok(())
+    }
 }
 
+/// Tag of the notes `owner`'s allowances can spend, the third hash input of their commitments:
+/// the owner key hashed the way unshield hashes recipients. The allowance circuit takes it as a
+/// public input, so a spender can only debit an allowance by spending its owner's notes.
+pub fn allowance_tag(owner: &Pubkey) -> [u8; 32] {
+    recipient_hash(owner)
+}
+
+/// Locate the allowance for `pool` and check its PDA: [b"allowance", owner, spender, pool]
+pub fn load_allowance<'a, 'info>(
+    program_id: &Pubkey,
+    accounts: &'a [AccountInfo<'info>],
+    pool: &Pubkey,
+) -> Result<(&'a AccountInfo<'info>, Allowance)> {
+    let info = crate::raw::find_account::<Allowance>(accounts, program_id)?;
+    let allowance = Allowance::try_deserialize(&mut &info.try_borrow_data()?[..])?;
+    require_keys_eq!(allowance.pool, *pool, PoolError::InvalidPda);
+    crate::raw::check_pda(
+        info,
+        &[
+            b"allowance",
+            allowance.owner.as_ref(),
+            allowance.spender.as_ref(),
+            pool.as_ref(),
+            &[allowance.bump],
+        ],
+        program_id,
+    )?;
+    Ok((info, allowance))
+}
+
+/// Write an allowance back in place
+pub fn store_allowance(info: &AccountInfo, allowance: &Allowance) -> Result<()> {
+    allowance.try_serialize(&mut &mut info.try_borrow_mut_data()?[..])
+}
+
+/// Create the owner's allowance for `spender` or overwrite its amount and expiry
 pub fn approve_allowance(
     ctx: Context<ApproveAllowanceContext>,
     args: ApproveAllowanceArgs,
 ) -> Result<()> {
-    // Validate amount
     InputValidator::validate_amount(args.amount, ptf_common::MAX_AMOUNT)?;
     
-    // Extract accounts from remaining_accounts using raw pattern
-    // Expected accounts: owner (signer), allowance (PDA), pool_state, system_program
-    let remaining_accounts = ctx.remaining_accounts;
-    require!(remaining_accounts.len() >= 4, PoolError::AccountNotFound);
+    let slot = Clock::get()?.slot;
+    require!(
+        args.expiry_slot.is_none_or(|expiry| expiry > slot),
+        PoolError::AllowanceExpired
+    );
+    
+    let allowance = &mut ctx.accounts.allowance;
+    allowance.owner = ctx.accounts.owner.key();
+    allowance.spender = args.spender;
+    allowance.pool = ctx.accounts.pool_state.key();
+    allowance.amount = args.amount;
+    allowance.expiry_slot = args.expiry_slot;
+    allowance.bump = ctx.bumps.allowance;
     
-    // Full implementation would:
-    // 1. Extract owner, allowance PDA, pool_state from remaining_accounts
-    // 2. Derive allowance PDA: [b"allowance", owner, spender, pool]
-    // 3. Create allowance account if it doesn't exist
-    // 4. Update allowance amount
-    // 5. Store allowance data
+    msg!("Approved {} for {}", args.amount, args.spender);
     
-    // For now, basic structure is in place
-    // Full implementation requires complete raw pattern extraction
+    let event = AllowanceChanged::new(allowance);
+    emit_cpi!(event);
     
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn allowance(amount: u64, expiry_slot: Option<u64>) -> Allowance {
+        Allowance {
+            owner: Pubkey::new_unique(),
+            spender: Pubkey::new_unique(),
+            pool: Pubkey::new_unique(),
+            amount,
+            expiry_slot,
+            bump: 255,
+        }
+    }
+
+    #[test]
+    fn allowance_layout() {
+        let mut data = [0u8; Allowance::LEN];
+        allowance(1, Some(2)).try_serialize(&mut &mut data[..]).unwrap();
+        let mut short = [0u8; Allowance::LEN - 1];
+        assert!(allowance(1, Some(2)).try_serialize(&mut &mut short[..]).is_err());
+    }
+
+    #[test]
+    fn debit_stops_at_the_amount_and_the_expiry() {
+        let mut approved = allowance(100, Some(50));
+        approved.debit(60, 10).unwrap();
+        assert_eq!(approved.amount, 40);
+
+        let err = approved.debit(41, 10).unwrap_err();
+        assert_eq!(err, PoolError::InsufficientAllowance.into());
+        assert_eq!(approved.amount, 40);
+
+        let err = approved.debit(1, 50).unwrap_err();
+        assert_eq!(err, PoolError::AllowanceExpired.into());
+        approved.debit(40, 49).unwrap();
+        assert_eq!(approved.amount, 0);
+
+        let mut open_ended = allowance(5, None);
+        open_ended.debit(5, u64::MAX).unwrap();
+    }
+}
@@ -0,0 +1,29 @@
+use anchor_lang::prelude::*;
+use crate::{CancelOperation as CancelOperationContext};
+use crate::errors::PoolError;
+use crate::events::OperationStatusChanged;
+use crate::proof_vault;
+use ptf_common::OperationStatus;
+
+/// Drop one of the owner's prepared operations and shrink the vault, refunding the freed rent
+pub fn cancel_operation(ctx: Context<CancelOperationContext>, operation_id: [u8; 32]) -> Result<()> {
+    let proof_vault_info = ctx.accounts.proof_vault.to_account_info();
+    let owner = ctx.accounts.owner.to_account_info();
+    let mut vault = proof_vault::load_owned(ctx.program_id, &proof_vault_info, owner.key)?;
+    
+    let index = vault.prepared_operations.iter()
+        .position(|op| op.id() == &operation_id)
+        .ok_or(PoolError::OperationNotFound)?;
+    require!(
+        vault.prepared_operations[index].is_abandonable(),
+        PoolError::OperationNotAbandonable
+    );
+    vault.prepared_operations.remove(index);
+    
+    let system_program = ctx.accounts.system_program.to_account_info();
+    proof_vault::save_resized(&proof_vault_info, &vault, &owner, &system_program)?;
+    
+    emit_cpi!(OperationStatusChanged::new(proof_vault_info.key(), operation_id, OperationStatus::Failed));
+    
+    Ok(())
+}
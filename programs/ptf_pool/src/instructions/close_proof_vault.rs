@@ -0,0 +1,41 @@
+use anchor_lang::prelude::*;
+use crate::{CloseProofVault as CloseProofVaultContext};
+use crate::errors::PoolError;
+use crate::events::OperationStatusChanged;
+use crate::proof_vault;
+use crate::state::UserProofVault;
+use ptf_common::OperationStatus;
+
+/// Close the owner's proof vault, discarding its operations and returning the rent. Not allowed in
+/// the slot the vault was created in, so a re-created vault never repeats its operation ids
+pub fn close_proof_vault(ctx: Context<CloseProofVaultContext>) -> Result<()> {
+    let proof_vault_info = ctx.accounts.proof_vault.to_account_info();
+    let owner = ctx.accounts.owner.to_account_info();
+    let (address, _) = proof_vault::proof_vault_address(ctx.program_id, owner.key);
+    require_keys_eq!(address, *proof_vault_info.key, PoolError::InvalidPda);
+    require_keys_eq!(*proof_vault_info.owner, *ctx.program_id, PoolError::InvalidAccountOwner);
+    
+    // Vaults older builds wrote without a discriminator have nothing readable to keep
+    let mut discarded = Vec::new();
+    if proof_vault_info.try_borrow_data()?.starts_with(UserProofVault::DISCRIMINATOR) {
+        let vault = proof_vault::load(ctx.program_id, &proof_vault_info)?;
+        require!(
+            vault.prepared_operations.iter().all(|op| op.is_abandonable()),
+            PoolError::OperationNotAbandonable
+        );
+        require!(vault.is_closable(Clock::get()?.slot), PoolError::ProofVaultTooNew);
+        discarded = vault.prepared_operations.iter()
+            .filter(|op| op.status() != OperationStatus::Failed)
+            .map(|op| *op.id())
+            .collect();
+    }
+    
+    proof_vault::close(&proof_vault_info, &owner)?;
+    
+    // Discarded operations are reported like cancelled ones
+    for operation_id in discarded {
+        emit_cpi!(OperationStatusChanged::new(proof_vault_info.key(), operation_id, OperationStatus::Failed));
+    }
+    
+    Ok(())
+}
@@ -0,0 +1,17 @@
+use anchor_lang::prelude::*;
+use crate::ModifyAllowance;
+use crate::events::AllowanceChanged;
+
+/// Lower the allowance by `amount`, stopping at zero so a decrease that lands after a
+/// spend still takes effect
+pub fn decrease_allowance(ctx: Context<ModifyAllowance>, amount: u64) -> Result<()> {
+    let allowance = &mut ctx.accounts.allowance;
+    allowance.amount = allowance.amount.saturating_sub(amount);
+    
+    msg!("Allowance for {} decreased to {}", allowance.spender, allowance.amount);
+    
+    let event = AllowanceChanged::new(allowance);
+    emit_cpi!(event);
+    
+    Ok(())
+}
@@ -1,42 +1,119 @@
 use anchor_lang::prelude::*;
-use crate::{BatchTransferArgs, ExecuteBatchTransfer as ExecuteBatchTransferContext};
+use anchor_lang::solana_program::entrypoint::ProgramResult;
+use crate::{BatchTransferArgs, TransferArgs, ExecuteBatchTransfer as ExecuteBatchTransferContext};
 use crate::errors::PoolError;
+use crate::events::{check_encrypted_notes, BatchTransferFailed, EventAuthority, Transferred};
+use crate::entrypoint::parse_args;
+use crate::instructions::execute_transfer::TransferAccounts;
+use crate::state::Circuit;
+use crate::instructions::transfer_core::{
+    execute_batch_transfer_core, JoinSplitInputs, TransferPublicInputs, TRANSFER_OUTPUTS,
+};
 use ptf_common::{InputSanitizer, MAX_BATCH_SIZE};
 
-pub fn execute_batch_transfer(
-    ctx: Context<ExecuteBatchTransferContext>,
+// Raw handler for execute_batch_transfer (called by custom entrypoint)
+pub fn execute_batch_transfer_raw_handler<'info>(
+    program_id: &Pubkey,
+    accounts: &'info [AccountInfo<'info>],
+    instruction_data: &[u8],
+) -> ProgramResult {
+    let args: crate::instruction::ExecuteBatchTransfer = parse_args(instruction_data)?;
+    process_batch_transfer(program_id, accounts, args.args)?;
+    Ok(())
+}
+
+pub fn execute_batch_transfer<'info>(
+    ctx: Context<'_, '_, '_, 'info, ExecuteBatchTransferContext<'info>>,
     args: BatchTransferArgs,
+) -> Result<()> {
+    let accounts = [ctx.accounts.to_account_infos(), ctx.remaining_accounts.to_vec()].concat();
+    process_batch_transfer(ctx.program_id, &accounts, args)
+}
+
+/// Up to MAX_BATCH_SIZE private transfers applied atomically: all proofs are verified, then
+/// every item is checked against the pool and the rest of the batch before any is applied,
+/// and all outputs are appended in one tree insertion. A failing item is reported by index
+/// in a `BatchTransferFailed` event and the whole batch reverts.
+/// Shared by the Anchor instruction and the raw handler; accounts may come in any order.
+pub fn process_batch_transfer<'info>(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo<'info>],
+    mut args: BatchTransferArgs,
 ) -> Result<()> {
     // Validate batch size
     require!(
-        args.transfers.len() > 0 && args.transfers.len() <= MAX_BATCH_SIZE,
+        !args.transfers.is_empty() && args.transfers.len() <= MAX_BATCH_SIZE,
         PoolError::InvalidAmount
     );
     
-    // Validate all proofs and public inputs
-    for transfer in &args.transfers {
-        InputSanitizer::sanitize_proof(&transfer.proof, ptf_common::MAX_PROOF_SIZE)?;
-        InputSanitizer::sanitize_public_inputs(&transfer.public_inputs, ptf_common::MAX_PUBLIC_INPUTS_SIZE)?;
-    }
+    let inputs = args.transfers.iter()
+        .enumerate()
+        .map(|(index, transfer)| {
+            check_transfer(transfer).inspect_err(|err| BatchTransferFailed::emit(index, err))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let encrypted_notes: Vec<_> = args.transfers.iter_mut()
+        .map(|transfer| std::mem::take(&mut transfer.encrypted_notes))
+        .collect();
     
-    // Extract accounts from remaining_accounts using raw pattern
-    // Expected accounts: pool_state, commitment_tree, nullifier_set, verifying_key, verifier_program
-    let remaining_accounts = ctx.remaining_accounts;
-    require!(remaining_accounts.len() >= 5, PoolError::AccountNotFound);
+    let events = EventAuthority::find(program_id, accounts)?;
+    let mut accounts = TransferAccounts::load(program_id, accounts)?;
+    for transfer in args.transfers {
+        accounts.verify_proof(Circuit::Transfer, transfer.proof, transfer.public_inputs)?;
+    }
     
-    // Full implementation would:
-    // 1. Extract and validate all accounts from remaining_accounts
-    // 2. For each transfer in batch:
-    //    a. Verify proof via CPI to verifier
-    //    b. Extract nullifiers and commitments from public_inputs
-    //    c. Check nullifiers aren't already used
-    //    d. Update commitment tree with new commitments
-    //    e. Add nullifiers to nullifier_set
-    // 3. Check rate limits
-    // 4. Update pool state operation count
+    let batch: Vec<&dyn JoinSplitInputs> = inputs.iter().map(|inputs| inputs as _).collect();
+    let slot = accounts.slot;
+    let first_index = execute_batch_transfer_core(
+        &mut accounts.pool,
+        &mut accounts.commitment_tree,
+        &mut accounts.root_history,
+        &mut accounts.nullifiers,
+        &batch,
+        slot,
+        BatchTransferFailed::emit,
+    )?;
+    accounts.store_pool_state()?;
     
-    // For now, basic structure is in place
-    // Full implementation requires complete raw pattern extraction
+    emit_transferred(
+        &events,
+        *accounts.pool_info.key,
+        &batch,
+        encrypted_notes,
+        first_index,
+        accounts.pool.current_root,
+    )?;
+    msg!(
+        "Batch of {} transfers into leaves {}..{}",
+        batch.len(),
+        first_index,
+        first_index + (batch.len() * TRANSFER_OUTPUTS) as u64
+    );
     
     Ok(())
 }
+
+/// One `Transferred` per item, numbering each item's outputs on from the previous item's
+pub(crate) fn emit_transferred(
+    events: &EventAuthority,
+    pool: Pubkey,
+    batch: &[&dyn JoinSplitInputs],
+    encrypted_notes: Vec<Vec<Vec<u8>>>,
+    first_index: u64,
+    root: [u8; 32],
+) -> Result<()> {
+    let mut next_index = first_index;
+    for (inputs, encrypted_notes) in batch.iter().zip(encrypted_notes) {
+        events.emit(&Transferred::new(pool, *inputs, next_index, root, encrypted_notes))?;
+        next_index += inputs.commitments().len() as u64;
+    }
+    Ok(())
+}
+
+fn check_transfer(transfer: &TransferArgs) -> Result<TransferPublicInputs> {
+    InputSanitizer::sanitize_proof(&transfer.proof, ptf_common::MAX_PROOF_SIZE)?;
+    InputSanitizer::sanitize_public_inputs(&transfer.public_inputs, ptf_common::MAX_PUBLIC_INPUTS_SIZE)?;
+    let inputs = TransferPublicInputs::parse(&transfer.public_inputs)?;
+    check_encrypted_notes(&transfer.encrypted_notes, &inputs)?;
+    Ok(inputs)
+}
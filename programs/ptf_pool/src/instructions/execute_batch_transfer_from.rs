@@ -1,44 +1,111 @@
 use anchor_lang::prelude::*;
-use crate::{BatchTransferFromArgs, ExecuteBatchTransferFrom as ExecuteBatchTransferFromContext};
+use anchor_lang::solana_program::entrypoint::ProgramResult;
+use crate::{BatchTransferFromArgs, TransferFromMode, ExecuteBatchTransferFrom as ExecuteBatchTransferFromContext};
 use crate::errors::PoolError;
-use ptf_common::{InputSanitizer, MAX_BATCH_SIZE};
+use crate::events::{AllowanceChanged, BatchTransferFailed, EventAuthority};
+use crate::entrypoint::parse_args;
+use crate::instructions::approve_allowance::store_allowance;
+use crate::instructions::execute_batch_transfer::emit_transferred;
+use crate::instructions::execute_transfer::TransferAccounts;
+use crate::instructions::execute_transfer_from::{
+    check_transfer_from, load_spender_allowance, proof_and_public_inputs,
+};
+use crate::instructions::transfer_core::{execute_batch_transfer_core, JoinSplitInputs};
+use ptf_common::MAX_BATCH_SIZE;
 
-pub fn execute_batch_transfer_from(
-    ctx: Context<ExecuteBatchTransferFromContext>,
+// Raw handler for execute_batch_transfer_from (called by custom entrypoint)
+pub fn execute_batch_transfer_from_raw_handler<'info>(
+    program_id: &Pubkey,
+    accounts: &'info [AccountInfo<'info>],
+    instruction_data: &[u8],
+) -> ProgramResult {
+    let args: crate::instruction::ExecuteBatchTransferFrom = parse_args(instruction_data)?;
+    process_batch_transfer_from(program_id, accounts, args.args)?;
+    Ok(())
+}
+
+pub fn execute_batch_transfer_from<'info>(
+    ctx: Context<'_, '_, '_, 'info, ExecuteBatchTransferFromContext<'info>>,
     args: BatchTransferFromArgs,
+) -> Result<()> {
+    let accounts = [ctx.accounts.to_account_infos(), ctx.remaining_accounts.to_vec()].concat();
+    process_batch_transfer_from(ctx.program_id, &accounts, args)
+}
+
+/// Up to MAX_BATCH_SIZE transfer_froms applied atomically, as in `execute_batch_transfer`.
+/// Items may mix modes; allowance-mode items all debit the one allowance passed in, in order,
+/// so the item that would overdraw it is the one reported.
+/// Shared by the Anchor instruction and the raw handler; accounts may come in any order.
+pub fn process_batch_transfer_from<'info>(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo<'info>],
+    mut args: BatchTransferFromArgs,
 ) -> Result<()> {
     // Validate batch size
     require!(
-        args.transfers.len() > 0 && args.transfers.len() <= MAX_BATCH_SIZE,
+        !args.transfers.is_empty() && args.transfers.len() <= MAX_BATCH_SIZE,
         PoolError::InvalidAmount
     );
     
-    // Validate all proofs and public inputs
-    for transfer in &args.transfers {
-        InputSanitizer::sanitize_proof(&transfer.proof, ptf_common::MAX_PROOF_SIZE)?;
-        InputSanitizer::sanitize_public_inputs(&transfer.public_inputs, ptf_common::MAX_PUBLIC_INPUTS_SIZE)?;
+    let events = EventAuthority::find(program_id, accounts)?;
+    let mut transfer_accounts = TransferAccounts::load(program_id, accounts)?;
+    let slot = transfer_accounts.slot;
+    let uses_allowance = args.transfers.iter()
+        .any(|transfer| matches!(transfer.mode, TransferFromMode::Allowance { .. }));
+    let mut allowance = if uses_allowance {
+        Some(load_spender_allowance(program_id, accounts, transfer_accounts.pool_info.key)?)
+    } else {
+        None
+    };
+    
+    let mut inputs = Vec::with_capacity(args.transfers.len());
+    for (index, transfer) in args.transfers.iter().enumerate() {
+        let item = check_transfer_from(transfer, accounts, allowance.as_mut().map(|(_, allowance)| allowance), slot)
+            .inspect_err(|err| BatchTransferFailed::emit(index, err))?;
+        inputs.push(item);
     }
+    let encrypted_notes: Vec<_> = args.transfers.iter_mut()
+        .map(|transfer| std::mem::take(&mut transfer.encrypted_notes))
+        .collect();
     
-    // Extract accounts from remaining_accounts using raw pattern
-    // Expected accounts: pool_state, commitment_tree, nullifier_set, allowance, verifying_key, verifier_program
-    let remaining_accounts = ctx.remaining_accounts;
-    require!(remaining_accounts.len() >= 6, PoolError::AccountNotFound);
+    let owner = allowance.as_ref().map(|(_, allowance)| allowance.owner);
+    for transfer in args.transfers {
+        let (circuit, proof, public_inputs) = proof_and_public_inputs(transfer, owner.as_ref())?;
+        transfer_accounts.verify_proof(circuit, proof, public_inputs)?;
+    }
     
-    // Full implementation would:
-    // 1. Extract and validate all accounts from remaining_accounts
-    // 2. Load allowance account and verify sufficient total allowance for all transfers
-    // 3. For each transfer in batch:
-    //    a. Verify proof via CPI to verifier
-    //    b. Extract nullifiers and commitments from public_inputs
-    //    c. Check nullifiers aren't already used
-    //    d. Update commitment tree with new commitments
-    //    e. Add nullifiers to nullifier_set
-    //    f. Update allowance (decrease by transfer amount)
-    // 4. Check rate limits
-    // 5. Update pool state operation count
+    let batch: Vec<&dyn JoinSplitInputs> = inputs.iter().map(|inputs| inputs.as_ref()).collect();
+    let first_index = execute_batch_transfer_core(
+        &mut transfer_accounts.pool,
+        &mut transfer_accounts.commitment_tree,
+        &mut transfer_accounts.root_history,
+        &mut transfer_accounts.nullifiers,
+        &batch,
+        slot,
+        BatchTransferFailed::emit,
+    )?;
+    transfer_accounts.store_pool_state()?;
     
-    // For now, basic structure is in place
-    // Full implementation requires complete raw pattern extraction
+    emit_transferred(
+        &events,
+        *transfer_accounts.pool_info.key,
+        &batch,
+        encrypted_notes,
+        first_index,
+        transfer_accounts.pool.current_root,
+    )?;
+    if let Some((info, allowance)) = &allowance {
+        store_allowance(info, allowance)?;
+        events.emit(&AllowanceChanged::new(allowance))?;
+    }
+    
+    let outputs: usize = batch.iter().map(|inputs| inputs.commitments().len()).sum();
+    msg!(
+        "Batch of {} transfer_froms into leaves {}..{}",
+        batch.len(),
+        first_index,
+        first_index + outputs as u64
+    );
     
     Ok(())
 }
@@ -1,57 +1,227 @@
 use anchor_lang::prelude::*;
-use anchor_lang::solana_program::{
-    account_info::AccountInfo,
-    entrypoint::ProgramResult,
-    program_error::ProgramError,
-    pubkey::Pubkey,
-};
-use std::mem;
-use crate::state::{PoolState, CommitmentTree, NoteLedger, UserProofVault};
+use anchor_lang::solana_program::entrypoint::ProgramResult;
+use anchor_lang::solana_program::clock::Clock;
+use anchor_spl::associated_token::get_associated_token_address;
+use anchor_spl::token::TokenAccount;
+use crate::state::{Circuit, OperationPayload, UserProofVault};
 use crate::errors::PoolError;
-use crate::instructions::shield_core::execute_shield_core;
-use ptf_common::addresses::PoolAddresses;
-use ptf_verifier_groth16;
-use ptf_factory;
+use crate::events::{EventAuthority, OperationStatusChanged, Shielded, MAX_ENCRYPTED_NOTE_SIZE};
+use crate::entrypoint::parse_args;
+use crate::proof_vault;
+use crate::raw::{self, CommitmentTreeMut, RootHistoryMut};
+use crate::instructions::shield_core::{execute_shield_core, shield_public_inputs};
+use ptf_common::{InputSanitizer, OperationStatus};
 
 // Raw handler for execute_shield_v2 (called by custom entrypoint)
-pub fn execute_shield_v2_raw_handler(
+pub fn execute_shield_v2_raw_handler<'info>(
     program_id: &Pubkey,
-    accounts: &[AccountInfo],
+    accounts: &'info [AccountInfo<'info>],
     instruction_data: &[u8],
 ) -> ProgramResult {
-    // Extract operation_id from instruction data (skip 8-byte discriminator)
-    if instruction_data.len() < 8 + 32 {
-        return Err(ProgramError::InvalidInstructionData);
-    }
-    let operation_id: [u8; 32] = instruction_data[8..8+32].try_into()
-        .map_err(|_| ProgramError::InvalidInstructionData)?;
-    
-    // Extract accounts manually (simplified - full implementation would identify all accounts)
-    // For now, we'll use a simplified approach that works for testing
-    // Full implementation would follow the two-pass pattern from documentation
-    
-    // This is a placeholder implementation - full version would:
-    // 1. Identify all accounts by owner and PDA derivation
-    // 2. Validate all accounts manually
-    // 3. Load accounts with proper lifetime extension
-    // 4. Call execute_shield_core
-    
+    let args: crate::instruction::ExecuteShieldV2 = parse_args(instruction_data)?;
+    process_shield(program_id, accounts, args.operation_id, args.proof, args.encrypted_note)?;
     Ok(())
 }
 
-// Standard Anchor instruction (for IDL generation and testing)
+/// Account list for clients and the IDL. State accounts are unchecked here because
+/// `process_shield` locates and validates them itself and writes them in place.
+#[event_cpi]
 #[derive(Accounts)]
-pub struct ExecuteShieldV2Raw<'info> {
-    /// CHECK: Phantom account - all real accounts in remaining_accounts
-    pub _phantom: UncheckedAccount<'info>,
+pub struct ExecuteShieldV2<'info> {
+    /// CHECK: Pool state PDA, validated in process_shield
+    #[account(mut)]
+    pub pool_state: UncheckedAccount<'info>,
+    /// CHECK: Commitment tree PDA, validated in process_shield
+    #[account(mut)]
+    pub commitment_tree: UncheckedAccount<'info>,
+    /// CHECK: Root history PDA, validated in process_shield
+    #[account(mut)]
+    pub root_history: UncheckedAccount<'info>,
+    /// CHECK: Proof vault PDA of `user`, validated in process_shield
+    #[account(mut)]
+    pub proof_vault: UncheckedAccount<'info>,
+    /// Owns the proof vault; pays the deposit and gets back the vault rent the shield frees
+    #[account(mut)]
+    pub user: Signer<'info>,
+    /// CHECK: The user's associated token account for the pool mint, checked in process_shield
+    #[account(mut)]
+    pub user_token_account: UncheckedAccount<'info>,
+    /// CHECK: Vault state recorded in pool_state
+    pub vault_state: UncheckedAccount<'info>,
+    /// CHECK: Vault token account (validated by ptf_vault)
+    #[account(mut)]
+    pub vault_token_account: UncheckedAccount<'info>,
+    /// CHECK: Verifying key account (must be allowed by pool_state)
+    pub verifying_key: UncheckedAccount<'info>,
+    pub verifier_program: Program<'info, ptf_verifier_groth16::program::PtfVerifierGroth16>,
+    pub vault_program: Program<'info, ptf_vault::program::PtfVault>,
+    pub token_program: Program<'info, anchor_spl::token::Token>,
+    pub system_program: Program<'info, System>,
 }
 
 pub fn execute_shield_v2(
-    ctx: Context<ExecuteShieldV2Raw>,
+    ctx: Context<ExecuteShieldV2>,
     operation_id: [u8; 32],
+    proof: Vec<u8>,
+    encrypted_note: Vec<u8>,
 ) -> Result<()> {
-    // This will be called by the standard Anchor dispatch for other instructions
-    // The custom entrypoint intercepts this and routes to raw handler
-    // For now, placeholder implementation
+    process_shield(ctx.program_id, &ctx.accounts.to_account_infos(), operation_id, proof, encrypted_note)
+}
+
+/// Locate the signer whose proof vault PDA is among `accounts`
+fn find_user_and_proof_vault<'a, 'info>(
+    program_id: &Pubkey,
+    accounts: &'a [AccountInfo<'info>],
+) -> Result<(&'a AccountInfo<'info>, &'a AccountInfo<'info>)> {
+    let proof_vault = raw::find_account::<UserProofVault>(accounts, program_id)?;
+    let user = accounts
+        .iter()
+        .filter(|info| info.is_signer)
+        .find(|info| {
+            let (expected, _) = Pubkey::find_program_address(&[b"proof-vault", info.key.as_ref()], program_id);
+            expected == *proof_vault.key
+        })
+        .ok_or(PoolError::InvalidPda)?;
+    Ok((user, proof_vault))
+}
+
+/// The user's associated token account for `mint`, located by address rather than account
+/// order, holding `mint` and owned by `user`
+fn find_user_token_account<'a, 'info>(
+    accounts: &'a [AccountInfo<'info>],
+    user: &Pubkey,
+    mint: &Pubkey,
+) -> Result<&'a AccountInfo<'info>> {
+    let info = raw::find_by_key(accounts, &get_associated_token_address(user, mint))?;
+    require_keys_eq!(*info.owner, anchor_spl::token::ID, PoolError::InvalidAccountOwner);
+    let token_account = TokenAccount::try_deserialize(&mut &info.try_borrow_data()?[..])?;
+    check_user_token_account(&token_account, user, mint)?;
+    Ok(info)
+}
+
+fn check_user_token_account(token_account: &TokenAccount, user: &Pubkey, mint: &Pubkey) -> Result<()> {
+    require_keys_eq!(token_account.mint, *mint, PoolError::OriginMintMismatch);
+    require_keys_eq!(token_account.owner, *user, PoolError::DepositAccountMismatch);
     Ok(())
 }
+
+/// Deposit the prepared amount into the vault and insert the commitment into the tree.
+/// Shared by the Anchor instruction and the raw handler; accounts may come in any order.
+pub fn process_shield<'info>(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo<'info>],
+    operation_id: [u8; 32],
+    proof: Vec<u8>,
+    encrypted_note: Vec<u8>,
+) -> Result<()> {
+    require!(encrypted_note.len() <= MAX_ENCRYPTED_NOTE_SIZE, PoolError::EncryptedNoteTooLarge);
+    let events = EventAuthority::find(program_id, accounts)?;
+    let (pool_info, mut pool) = raw::load_pool_state(program_id, accounts)?;
+    let (user, proof_vault) = find_user_and_proof_vault(program_id, accounts)?;
+    
+    // Load the pending shield operation
+    let vault = proof_vault::load(program_id, proof_vault)?;
+    let operation = vault.prepared_operations.iter()
+        .find(|op| op.id() == &operation_id)
+        .ok_or(PoolError::OperationNotFound)?;
+    
+    require!(
+        operation.status() == OperationStatus::Pending,
+        PoolError::InvalidOperationStatus
+    );
+    let clock = Clock::get()?;
+    require!(!operation.is_expired(clock.slot), PoolError::OperationExpired);
+    let OperationPayload::Shield { pool: operation_pool, commitment, amount } = *operation.payload()? else {
+        return err!(PoolError::InvalidOperationStatus);
+    };
+    require_keys_eq!(operation_pool, *pool_info.key, PoolError::PoolMismatch);
+    
+    // Verify the proof against public inputs built here, so it binds this commitment and amount
+    let verifying_key = raw::find_verifying_key(accounts, &pool, Circuit::Shield, clock.slot)?;
+    InputSanitizer::sanitize_proof(&proof, ptf_common::MAX_PROOF_SIZE)?;
+    
+    let cpi_program = raw::find_by_key(accounts, &ptf_verifier_groth16::ID)?.clone();
+    let cpi_accounts = ptf_verifier_groth16::cpi::accounts::VerifyGroth16 {
+        verifying_key: verifying_key.clone(),
+    };
+    let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
+    ptf_verifier_groth16::cpi::verify_groth16(cpi_ctx, proof, shield_public_inputs(&commitment, amount))?;
+    
+    // Vault accounts come from the pool and vault state rather than from account order
+    let vault_state = raw::find_by_key(accounts, &pool.vault)?;
+    require_keys_eq!(*vault_state.owner, ptf_vault::ID, PoolError::InvalidAccountOwner);
+    let vault_token_key = {
+        let data = vault_state.try_borrow_data()?;
+        ptf_vault::state::VaultState::try_deserialize(&mut &data[..])?.vault_token_account
+    };
+    let vault_token_account = raw::find_by_key(accounts, &vault_token_key)?;
+    let user_token_account = find_user_token_account(accounts, user.key, &pool.origin_mint)?;
+    
+    // Move the tokens into the vault, signed by the pool PDA (the vault authority)
+    let origin_mint = pool.origin_mint;
+    let seeds = &[
+        b"pool",
+        origin_mint.as_ref(),
+        &[pool.bump],
+    ];
+    let signer = &[&seeds[..]];
+    
+    let cpi_program = raw::find_by_key(accounts, &ptf_vault::ID)?.clone();
+    let cpi_accounts = ptf_vault::cpi::accounts::Deposit {
+        vault: vault_state.clone(),
+        authority: pool_info.clone(),
+        user_token_account: user_token_account.clone(),
+        vault_token_account: vault_token_account.clone(),
+        user_authority: user.clone(),
+        token_program: raw::find_by_key(accounts, &anchor_spl::token::ID)?.clone(),
+    };
+    let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
+    ptf_vault::cpi::deposit(cpi_ctx, amount)?;
+    
+    // Insert into the tree and root history, writing each in place
+    let mut tree = CommitmentTreeMut::load(program_id, accounts, &pool)?;
+    let mut history = RootHistoryMut::load(program_id, accounts, &pool)?;
+    let leaf_index = execute_shield_core(&mut pool, &mut tree, &mut history, commitment, amount, clock.slot)?;
+    raw::store_pool_state(pool_info, &pool)?;
+    
+    // Remove the completed operation and shrink the proof vault, refunding the user
+    let system_program = raw::find_by_key(accounts, &System::id())?;
+    proof_vault::complete(proof_vault, vault, &operation_id, user, system_program)?;
+    
+    events.emit(&Shielded {
+        pool: *pool_info.key,
+        commitment,
+        leaf_index,
+        root: pool.current_root,
+        encrypted_note,
+    })?;
+    events.emit(&OperationStatusChanged::new(*proof_vault.key, operation_id, OperationStatus::Completed))?;
+    msg!("Shielded {} into leaf {}", amount, leaf_index);
+    
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anchor_lang::solana_program::program_pack::Pack;
+    use anchor_spl::token::spl_token::state::{Account, AccountState};
+
+    fn token_account(mint: Pubkey, owner: Pubkey) -> TokenAccount {
+        let account = Account { mint, owner, amount: 10, state: AccountState::Initialized, ..Account::default() };
+        let mut data = vec![0u8; Account::LEN];
+        account.pack_into_slice(&mut data);
+        TokenAccount::try_deserialize(&mut &data[..]).unwrap()
+    }
+
+    #[test]
+    fn deposits_come_from_the_users_own_account_for_the_pool_mint() {
+        let (user, mint) = (Pubkey::new_unique(), Pubkey::new_unique());
+        check_user_token_account(&token_account(mint, user), &user, &mint).unwrap();
+
+        let err = check_user_token_account(&token_account(Pubkey::new_unique(), user), &user, &mint).unwrap_err();
+        assert_eq!(err, PoolError::OriginMintMismatch.into());
+        let err = check_user_token_account(&token_account(mint, Pubkey::new_unique()), &user, &mint).unwrap_err();
+        assert_eq!(err, PoolError::DepositAccountMismatch.into());
+    }
+}
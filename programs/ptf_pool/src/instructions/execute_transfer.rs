@@ -1,37 +1,126 @@
 use anchor_lang::prelude::*;
-use crate::{TransferArgs, ExecuteTransfer as ExecuteTransferContext};
-use crate::state::{PoolState, CommitmentTree, NullifierSet};
-use crate::errors::PoolError;
-use ptf_common::{InputSanitizer, check_rate_limit, OperationType};
-use anchor_lang::solana_program::sysvar::Sysvar;
+use anchor_lang::solana_program::entrypoint::ProgramResult;
 use anchor_lang::solana_program::clock::Clock;
-use ptf_verifier_groth16;
+use crate::{TransferArgs, ExecuteTransfer as ExecuteTransferContext};
+use crate::state::{Circuit, PoolState};
+use crate::events::{check_encrypted_notes, EventAuthority, Transferred};
+use crate::entrypoint::parse_args;
+use crate::nullifiers::NullifierRecords;
+use crate::raw::{self, CommitmentTreeMut, RootHistoryMut};
+use crate::instructions::transfer_core::{execute_transfer_core, TransferPublicInputs, TRANSFER_INPUTS, TRANSFER_OUTPUTS};
+use ptf_common::InputSanitizer;
+
+// Raw handler for execute_transfer (called by custom entrypoint)
+pub fn execute_transfer_raw_handler<'info>(
+    program_id: &Pubkey,
+    accounts: &'info [AccountInfo<'info>],
+    instruction_data: &[u8],
+) -> ProgramResult {
+    let args: crate::instruction::ExecuteTransfer = parse_args(instruction_data)?;
+    process_transfer(program_id, accounts, args.args)?;
+    Ok(())
+}
+
+pub fn execute_transfer<'info>(
+    ctx: Context<'_, '_, '_, 'info, ExecuteTransferContext<'info>>,
+    args: TransferArgs,
+) -> Result<()> {
+    let accounts = [ctx.accounts.to_account_infos(), ctx.remaining_accounts.to_vec()].concat();
+    process_transfer(ctx.program_id, &accounts, args)
+}
+
+/// Accounts shared by every transfer instruction, located by owner, discriminator and PDA.
+/// Also expects the nullifier record PDAs being spent, a rent payer and the system program.
+pub struct TransferAccounts<'a, 'info> {
+    pub pool_info: &'a AccountInfo<'info>,
+    pub pool: PoolState,
+    pub commitment_tree: CommitmentTreeMut<'a, 'info>,
+    pub root_history: RootHistoryMut<'a, 'info>,
+    pub nullifiers: NullifierRecords<'a, 'info>,
+    /// Searched for the verifying key of each proof's circuit
+    pub accounts: &'a [AccountInfo<'info>],
+    pub verifier_program: &'a AccountInfo<'info>,
+    pub slot: u64,
+}
+
+impl<'a, 'info> TransferAccounts<'a, 'info> {
+    pub fn load(program_id: &Pubkey, accounts: &'a [AccountInfo<'info>]) -> Result<Self> {
+        let (pool_info, pool) = raw::load_pool_state(program_id, accounts)?;
+        let commitment_tree = CommitmentTreeMut::load(program_id, accounts, &pool)?;
+        let root_history = RootHistoryMut::load(program_id, accounts, &pool)?;
+        let slot = Clock::get()?.slot;
+        let nullifiers = NullifierRecords::load(program_id, accounts, pool_info.key, &pool, slot)?;
+        let verifier_program = raw::find_by_key(accounts, &ptf_verifier_groth16::ID)?;
+        
+        Ok(Self {
+            pool_info,
+            pool,
+            commitment_tree,
+            root_history,
+            nullifiers,
+            accounts,
+            verifier_program,
+            slot,
+        })
+    }
+    
+    /// Verify a proof via CPI against the key the pool allows for `circuit`
+    pub fn verify_proof(&self, circuit: Circuit, proof: Vec<u8>, public_inputs: Vec<u8>) -> Result<()> {
+        let verifying_key = raw::find_verifying_key(self.accounts, &self.pool, circuit, self.slot)?;
+        let cpi_accounts = ptf_verifier_groth16::cpi::accounts::VerifyGroth16 {
+            verifying_key: verifying_key.clone(),
+        };
+        let cpi_ctx = CpiContext::new(self.verifier_program.clone(), cpi_accounts);
+        ptf_verifier_groth16::cpi::verify_groth16(cpi_ctx, proof, public_inputs)
+    }
+    
+    /// Write the pool state back; the tree, root history and nullifiers are already written in place
+    pub fn store_pool_state(&self) -> Result<()> {
+        raw::store_pool_state(self.pool_info, &self.pool)
+    }
+}
 
-pub fn execute_transfer(
-    ctx: Context<ExecuteTransferContext>,
+/// Private join-split transfer: verify the proof, spend its nullifiers and append its outputs.
+/// Shared by the Anchor instruction and the raw handler; accounts may come in any order.
+pub fn process_transfer<'info>(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo<'info>],
     args: TransferArgs,
 ) -> Result<()> {
     // Validate proof and public inputs
     InputSanitizer::sanitize_proof(&args.proof, ptf_common::MAX_PROOF_SIZE)?;
     InputSanitizer::sanitize_public_inputs(&args.public_inputs, ptf_common::MAX_PUBLIC_INPUTS_SIZE)?;
     
-    // Extract accounts from remaining_accounts using raw pattern
-    // Expected accounts: pool_state, commitment_tree, nullifier_set, verifying_key, verifier_program
-    let remaining_accounts = ctx.remaining_accounts;
-    require!(remaining_accounts.len() >= 5, PoolError::AccountNotFound);
+    let inputs = TransferPublicInputs::parse(&args.public_inputs)?;
+    check_encrypted_notes(&args.encrypted_notes, &inputs)?;
+    let events = EventAuthority::find(program_id, accounts)?;
+    let mut accounts = TransferAccounts::load(program_id, accounts)?;
+    accounts.verify_proof(Circuit::Transfer, args.proof, args.public_inputs)?;
     
-    // Full implementation would:
-    // 1. Extract and validate pool_state, commitment_tree, nullifier_set from remaining_accounts
-    // 2. Verify proof via CPI to verifier program
-    // 3. Extract nullifiers and commitments from public_inputs
-    // 4. Check nullifiers aren't already used in nullifier_set
-    // 5. Update commitment tree with new commitments (similar to shield_core)
-    // 6. Add nullifiers to nullifier_set
-    // 7. Check rate limits
-    // 8. Update pool state operation count
+    let slot = accounts.slot;
+    let first_index = execute_transfer_core(
+        &mut accounts.pool,
+        &mut accounts.commitment_tree,
+        &mut accounts.root_history,
+        &mut accounts.nullifiers,
+        &inputs,
+        slot,
+    )?;
+    accounts.store_pool_state()?;
     
-    // For now, basic structure is in place
-    // Full implementation requires complete raw pattern extraction with lifetime management
+    events.emit(&Transferred::new(
+        *accounts.pool_info.key,
+        &inputs,
+        first_index,
+        accounts.pool.current_root,
+        args.encrypted_notes,
+    ))?;
+    msg!(
+        "Transfer spent {} notes into leaves {}..{}",
+        TRANSFER_INPUTS,
+        first_index,
+        first_index + TRANSFER_OUTPUTS as u64
+    );
     
     Ok(())
 }
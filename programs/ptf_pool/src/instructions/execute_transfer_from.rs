@@ -1,34 +1,231 @@
 use anchor_lang::prelude::*;
-use crate::{TransferFromArgs, ExecuteTransferFrom as ExecuteTransferFromContext};
+use anchor_lang::solana_program::entrypoint::ProgramResult;
+use crate::{TransferFromArgs, TransferFromMode, ExecuteTransferFrom as ExecuteTransferFromContext};
+use crate::entrypoint::parse_args;
 use crate::errors::PoolError;
-use ptf_common::InputSanitizer;
+use crate::events::{check_encrypted_notes, AllowanceChanged, EventAuthority, Transferred};
+use crate::instructions::approve_allowance::{allowance_tag, load_allowance, store_allowance, Allowance};
+use crate::instructions::execute_transfer::TransferAccounts;
+use crate::instructions::transfer_core::{
+    execute_transfer_core, DelegatedTransferPublicInputs, JoinSplitInputs, TransferPublicInputs,
+};
+use crate::instructions::unshield_core::u64_element;
+use crate::state::Circuit;
+use ptf_common::{InputSanitizer, InputValidator};
 
-pub fn execute_transfer_from(
-    ctx: Context<ExecuteTransferFromContext>,
+// Raw handler for execute_transfer_from (called by custom entrypoint)
+pub fn execute_transfer_from_raw_handler<'info>(
+    program_id: &Pubkey,
+    accounts: &'info [AccountInfo<'info>],
+    instruction_data: &[u8],
+) -> ProgramResult {
+    let args: crate::instruction::ExecuteTransferFrom = parse_args(instruction_data)?;
+    process_transfer_from(program_id, accounts, args.args)?;
+    Ok(())
+}
+
+pub fn execute_transfer_from<'info>(
+    ctx: Context<'_, '_, '_, 'info, ExecuteTransferFromContext<'info>>,
     args: TransferFromArgs,
 ) -> Result<()> {
-    // Validate proof and public inputs
-    InputSanitizer::sanitize_proof(&args.proof, ptf_common::MAX_PROOF_SIZE)?;
-    InputSanitizer::sanitize_public_inputs(&args.public_inputs, ptf_common::MAX_PUBLIC_INPUTS_SIZE)?;
-    
-    // Extract accounts from remaining_accounts using raw pattern
-    // Expected accounts: pool_state, commitment_tree, nullifier_set, allowance, verifying_key, verifier_program
-    let remaining_accounts = ctx.remaining_accounts;
-    require!(remaining_accounts.len() >= 6, PoolError::AccountNotFound);
+    let accounts = [ctx.accounts.to_account_infos(), ctx.remaining_accounts.to_vec()].concat();
+    process_transfer_from(ctx.program_id, &accounts, args)
+}
+
+/// Spend another owner's notes, authorized by a public allowance or a private delegation note,
+/// or reclaim one's own tagged notes.
+/// Shared by the Anchor instruction and the raw handler; accounts may come in any order.
+pub fn process_transfer_from<'info>(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo<'info>],
+    mut args: TransferFromArgs,
+) -> Result<()> {
+    let events = EventAuthority::find(program_id, accounts)?;
+    let mut transfer_accounts = TransferAccounts::load(program_id, accounts)?;
+    let slot = transfer_accounts.slot;
+    let mut allowance = match args.mode {
+        TransferFromMode::Allowance { .. } => {
+            Some(load_spender_allowance(program_id, accounts, transfer_accounts.pool_info.key)?)
+        }
+        TransferFromMode::Reclaim { .. } | TransferFromMode::Delegated => None,
+    };
+    let inputs = check_transfer_from(&args, accounts, allowance.as_mut().map(|(_, allowance)| allowance), slot)?;
+    let encrypted_notes = std::mem::take(&mut args.encrypted_notes);
+    let reclaimed_by = match args.mode {
+        TransferFromMode::Reclaim { owner, .. } => Some(owner),
+        _ => None,
+    };
     
-    // Full implementation would:
-    // 1. Extract and validate all accounts from remaining_accounts
-    // 2. Load allowance account and verify sufficient allowance
-    // 3. Verify proof via CPI to verifier program
-    // 4. Extract nullifiers and commitments from public_inputs
-    // 5. Check nullifiers aren't already used
-    // 6. Update commitment tree with new commitments
-    // 7. Add nullifiers to nullifier_set
-    // 8. Update allowance (decrease by transfer amount)
-    // 9. Check rate limits
+    let owner = allowance.as_ref().map(|(_, allowance)| allowance.owner);
+    let (circuit, proof, public_inputs) = proof_and_public_inputs(args, owner.as_ref())?;
+    transfer_accounts.verify_proof(circuit, proof, public_inputs)?;
+    let first_index = execute_transfer_core(
+        &mut transfer_accounts.pool,
+        &mut transfer_accounts.commitment_tree,
+        &mut transfer_accounts.root_history,
+        &mut transfer_accounts.nullifiers,
+        inputs.as_ref(),
+        slot,
+    )?;
+    transfer_accounts.store_pool_state()?;
     
-    // For now, basic structure is in place
-    // Full implementation requires complete raw pattern extraction
+    events.emit(&Transferred::new(
+        *transfer_accounts.pool_info.key,
+        inputs.as_ref(),
+        first_index,
+        transfer_accounts.pool.current_root,
+        encrypted_notes,
+    ))?;
+    let outputs = inputs.commitments().len() as u64;
+    match (allowance, reclaimed_by) {
+        (Some((info, allowance)), _) => {
+            store_allowance(info, &allowance)?;
+            events.emit(&AllowanceChanged::new(&allowance))?;
+            msg!(
+                "Transfer from {} into leaves {}..{}, {} left in the allowance",
+                allowance.owner,
+                first_index,
+                first_index + outputs,
+                allowance.amount
+            );
+        }
+        (None, Some(owner)) => {
+            msg!("{} reclaimed tagged notes into leaves {}..{}", owner, first_index, first_index + outputs);
+        }
+        (None, None) => {
+            // The change delegation note is the last leaf
+            msg!("Delegated transfer into leaves {}..{}", first_index, first_index + outputs);
+        }
+    }
     
     Ok(())
 }
+
+/// Load the allowance transparent-mode transfers debit; its spender must sign
+pub(crate) fn load_spender_allowance<'a, 'info>(
+    program_id: &Pubkey,
+    accounts: &'a [AccountInfo<'info>],
+    pool: &Pubkey,
+) -> Result<(&'a AccountInfo<'info>, Allowance)> {
+    let (info, allowance) = load_allowance(program_id, accounts, pool)?;
+    require!(
+        accounts.iter().any(|info| info.is_signer && *info.key == allowance.spender),
+        PoolError::SpenderMismatch
+    );
+    Ok((info, allowance))
+}
+
+/// Check one transfer_from before its proof is verified and parse its public inputs for its
+/// mode, with a ciphertext for each output. Allowance mode debits `allowance`, which the
+/// caller writes back after the spend; reclaim mode needs the owner among the signers.
+pub(crate) fn check_transfer_from(
+    transfer: &TransferFromArgs,
+    accounts: &[AccountInfo],
+    allowance: Option<&mut Allowance>,
+    slot: u64,
+) -> Result<Box<dyn JoinSplitInputs>> {
+    InputSanitizer::sanitize_proof(&transfer.proof, ptf_common::MAX_PROOF_SIZE)?;
+    InputSanitizer::sanitize_public_inputs(&transfer.public_inputs, ptf_common::MAX_PUBLIC_INPUTS_SIZE)?;
+    
+    match transfer.mode {
+        TransferFromMode::Allowance { amount } => {
+            InputValidator::validate_amount(amount, ptf_common::MAX_AMOUNT)?;
+            let inputs = TransferPublicInputs::parse(&transfer.public_inputs)?;
+            check_encrypted_notes(&transfer.encrypted_notes, &inputs)?;
+            allowance.ok_or(PoolError::AccountNotFound)?.debit(amount, slot)?;
+            Ok(Box::new(inputs))
+        }
+        TransferFromMode::Reclaim { owner, amount } => {
+            InputValidator::validate_amount(amount, ptf_common::MAX_AMOUNT)?;
+            require!(
+                accounts.iter().any(|info| info.is_signer && *info.key == owner),
+                PoolError::OwnerSignatureMissing
+            );
+            let inputs = TransferPublicInputs::parse(&transfer.public_inputs)?;
+            check_encrypted_notes(&transfer.encrypted_notes, &inputs)?;
+            Ok(Box::new(inputs))
+        }
+        TransferFromMode::Delegated => {
+            let inputs = DelegatedTransferPublicInputs::parse(&transfer.public_inputs)?;
+            check_encrypted_notes(&transfer.encrypted_notes, &inputs)?;
+            Ok(Box::new(inputs))
+        }
+    }
+}
+
+/// The circuit the proof is for, the proof and the public inputs it is verified against.
+/// Allowance mode appends the tag of the allowance `owner`'s notes and the debited amount, so
+/// the spender can't spend someone else's notes or understate what leaves the owner. Reclaim
+/// mode does the same for the owner it names.
+pub(crate) fn proof_and_public_inputs(
+    transfer: TransferFromArgs,
+    owner: Option<&Pubkey>,
+) -> Result<(Circuit, Vec<u8>, Vec<u8>)> {
+    let mut public_inputs = transfer.public_inputs;
+    let (owner, amount) = match transfer.mode {
+        TransferFromMode::Allowance { amount } => (*owner.ok_or(PoolError::AccountNotFound)?, amount),
+        TransferFromMode::Reclaim { owner, amount } => (owner, amount),
+        TransferFromMode::Delegated => return Ok((Circuit::DelegatedTransfer, transfer.proof, public_inputs)),
+    };
+    public_inputs.extend_from_slice(&allowance_tag(&owner));
+    public_inputs.extend_from_slice(&u64_element(amount));
+    Ok((Circuit::AllowanceTransfer, transfer.proof, public_inputs))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn transfer(mode: TransferFromMode) -> TransferFromArgs {
+        TransferFromArgs {
+            proof: vec![1u8; 256],
+            public_inputs: vec![2u8; 5 * 32],
+            mode,
+            encrypted_notes: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn allowance_proofs_commit_to_the_owner_and_amount() {
+        let owner = Pubkey::new_unique();
+        let (circuit, _, public_inputs) =
+            proof_and_public_inputs(transfer(TransferFromMode::Allowance { amount: 7 }), Some(&owner)).unwrap();
+        assert_eq!(circuit, Circuit::AllowanceTransfer);
+        assert_eq!(public_inputs.len(), 7 * 32);
+        assert_eq!(&public_inputs[5 * 32..6 * 32], &allowance_tag(&owner));
+        assert_eq!(&public_inputs[6 * 32..], &u64_element(7));
+
+        let err = proof_and_public_inputs(transfer(TransferFromMode::Allowance { amount: 7 }), None).unwrap_err();
+        assert_eq!(err, PoolError::AccountNotFound.into());
+
+        let (circuit, _, public_inputs) = proof_and_public_inputs(transfer(TransferFromMode::Delegated), None).unwrap();
+        assert_eq!(circuit, Circuit::DelegatedTransfer);
+        assert_eq!(public_inputs, vec![2u8; 5 * 32]);
+    }
+
+    #[test]
+    fn owners_reclaim_tagged_notes_without_an_allowance() {
+        let owner = Pubkey::new_unique();
+        let reclaim = || TransferFromArgs {
+            encrypted_notes: vec![vec![3u8; 64]; 2],
+            ..transfer(TransferFromMode::Reclaim { owner, amount: 7 })
+        };
+        let (mut lamports, mut data) = (0u64, Vec::new());
+        let signer = AccountInfo::new(&owner, true, false, &mut lamports, &mut data, &owner, false, 0);
+
+        // Proven against the owner's tag like an allowance spend, but nothing is debited, so
+        // revoked or expired allowances don't strand the notes
+        check_transfer_from(&reclaim(), std::slice::from_ref(&signer), None, 0).unwrap();
+        let (circuit, _, public_inputs) = proof_and_public_inputs(reclaim(), None).unwrap();
+        assert_eq!(circuit, Circuit::AllowanceTransfer);
+        assert_eq!(&public_inputs[5 * 32..6 * 32], &allowance_tag(&owner));
+        assert_eq!(&public_inputs[6 * 32..], &u64_element(7));
+
+        // Only the owner can
+        let (mut lamports, mut data) = (0u64, Vec::new());
+        let other = Pubkey::new_unique();
+        let spender = AccountInfo::new(&other, true, false, &mut lamports, &mut data, &other, false, 0);
+        let err = check_transfer_from(&reclaim(), &[spender], None, 0).err().unwrap();
+        assert_eq!(err, PoolError::OwnerSignatureMissing.into());
+    }
+}
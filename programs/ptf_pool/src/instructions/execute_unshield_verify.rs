@@ -1,18 +1,20 @@
 use anchor_lang::prelude::*;
 use crate::{ExecuteUnshieldVerify as ExecuteUnshieldVerifyContext};
-use crate::state::UserProofVault;
+use crate::state::{Circuit, OperationPayload};
 use crate::errors::PoolError;
+use crate::events::OperationStatusChanged;
+use crate::proof_vault;
+use crate::raw;
 use ptf_common::OperationStatus;
 use ptf_verifier_groth16;
 
-pub fn execute_unshield_verify(
-    ctx: Context<ExecuteUnshieldVerifyContext>,
+pub fn execute_unshield_verify<'info>(
+    ctx: Context<'_, '_, '_, 'info, ExecuteUnshieldVerifyContext<'info>>,
     operation_id: [u8; 32],
 ) -> Result<()> {
     // Load operation from vault
-    let vault_data = ctx.accounts.proof_vault.try_borrow_data()?;
-    let vault: UserProofVault = UserProofVault::try_deserialize(&mut &vault_data[8..])?;
-    drop(vault_data);
+    let proof_vault_info = ctx.accounts.proof_vault.to_account_info();
+    let mut vault = proof_vault::load(ctx.program_id, &proof_vault_info)?;
     
     // Find operation
     let operation = vault.prepared_operations.iter()
@@ -24,95 +26,49 @@ pub fn execute_unshield_verify(
         operation.status() == OperationStatus::Pending,
         PoolError::InvalidOperationStatus
     );
+    let slot = Clock::get()?.slot;
+    require!(!operation.is_expired(slot), PoolError::OperationExpired);
+    // Only unshield operations that update_operation_data has attached a proof to can be verified
+    let OperationPayload::Unshield { request, proof: Some(submission) } = operation.payload()? else {
+        return err!(PoolError::InvalidOperationStatus);
+    };
     
-    // Verify proof via CPI to verifier program using attestation
-    // Operation data format: [proof (256 bytes)][attestation (169 bytes)][public_inputs (variable)]
-    // Attestation structure: proof_hash (32) + public_inputs_hash (32) + verifying_key_hash (32) + 
-    //                         is_valid (1) + timestamp (8) + signature (64) = 169 bytes
-    
-    require!(
-        operation.data.len() >= 256 + 169,
-        PoolError::InvalidOperationStatus // Use as generic error for now
-    );
-    
-    // Extract proof (256 bytes)
-    let proof = operation.data[0..256].to_vec();
-    
-    // Extract attestation (169 bytes) - manually parse since it's a fixed-size struct
-    let attestation_bytes = &operation.data[256..256 + 169];
-    require!(
-        attestation_bytes.len() == 169,
-        PoolError::InvalidOperationStatus
-    );
-    
-    // Parse attestation: proof_hash (32) + public_inputs_hash (32) + verifying_key_hash (32) + 
-    //                    is_valid (1) + timestamp (8) + signature (64) = 169 bytes
-    let mut offset = 0;
-    let mut proof_hash = [0u8; 32];
-    proof_hash.copy_from_slice(&attestation_bytes[offset..offset + 32]);
-    offset += 32;
-    
-    let mut public_inputs_hash = [0u8; 32];
-    public_inputs_hash.copy_from_slice(&attestation_bytes[offset..offset + 32]);
-    offset += 32;
-    
-    let mut verifying_key_hash = [0u8; 32];
-    verifying_key_hash.copy_from_slice(&attestation_bytes[offset..offset + 32]);
-    offset += 32;
-    
-    let is_valid = attestation_bytes[offset] != 0;
-    offset += 1;
-    
-    let timestamp = i64::from_le_bytes([
-        attestation_bytes[offset],
-        attestation_bytes[offset + 1],
-        attestation_bytes[offset + 2],
-        attestation_bytes[offset + 3],
-        attestation_bytes[offset + 4],
-        attestation_bytes[offset + 5],
-        attestation_bytes[offset + 6],
-        attestation_bytes[offset + 7],
-    ]);
-    offset += 8;
-    
-    let mut signature = [0u8; 64];
-    signature.copy_from_slice(&attestation_bytes[offset..offset + 64]);
+    request.check_pool(&ctx.accounts.pool_state.key())?;
     
-    let attestation = ptf_verifier_groth16::instructions::verify_with_attestation::VerificationAttestation {
-        proof_hash,
-        public_inputs_hash,
-        verifying_key_hash,
-        is_valid,
-        timestamp,
-        signature,
-    };
+    // The verifying key must be the pool's current unshield key or a pinned previous version
+    raw::check_verifying_key(&ctx.accounts.verifying_key, &ctx.accounts.pool_state, Circuit::Unshield, slot)?;
     
-    // Extract public inputs (remaining bytes)
-    let public_inputs = if operation.data.len() > 256 + 169 {
-        operation.data[256 + 169..].to_vec()
-    } else {
-        vec![0u8; 32] // Default public input
-    };
+    // Public inputs are rebuilt from the prepared request, so the proof is bound to the
+    // nullifier, amount, recipient and fee fixed at prepare time
+    let public_inputs = request.inputs.to_bytes();
     
-    // CPI to verifier program with attestation
+    // CPI to verifier program with attestations
+    // Co-signing verifier accounts are forwarded in attestation order; the quorum size is the
+    // verifying key's own attestation_threshold, set with the verifier's set_attestation_threshold
     let cpi_program = ctx.accounts.verifier_program.to_account_info();
     let cpi_accounts = ptf_verifier_groth16::cpi::accounts::VerifyWithAttestation {
         verifying_key: ctx.accounts.verifying_key.to_account_info(),
         external_verifier: ctx.accounts.external_verifier.to_account_info(),
+        verifier_registry: ctx.accounts.verifier_registry.to_account_info(),
+        instructions_sysvar: ctx.accounts.instructions_sysvar.to_account_info(),
     };
-    let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
-    ptf_verifier_groth16::cpi::verify_with_attestation(cpi_ctx, proof, public_inputs, attestation)?;
+    let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts)
+        .with_remaining_accounts(ctx.remaining_accounts.to_vec());
+    ptf_verifier_groth16::cpi::verify_with_attestation(
+        cpi_ctx,
+        submission.proof.clone(),
+        public_inputs,
+        submission.attestations.clone(),
+    )?;
     
     // Update operation status to Verified
-    let mut vault_data = ctx.accounts.proof_vault.try_borrow_mut_data()?;
-    let mut vault: UserProofVault = UserProofVault::try_deserialize(&mut &vault_data[8..])?;
-    
     let op = vault.prepared_operations.iter_mut()
         .find(|op| op.id() == &operation_id)
         .ok_or(PoolError::OperationNotFound)?;
     op.set_status(OperationStatus::Verified);
+    proof_vault::save(&proof_vault_info, &vault)?;
     
-    vault.serialize(&mut &mut vault_data[8..])?;
+    emit_cpi!(OperationStatusChanged::new(proof_vault_info.key(), operation_id, OperationStatus::Verified));
     
     Ok(())
 }
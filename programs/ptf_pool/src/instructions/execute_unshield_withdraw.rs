@@ -1,18 +1,20 @@
 use anchor_lang::prelude::*;
 use crate::{ExecuteUnshieldWithdraw as ExecuteUnshieldWithdrawContext};
-use crate::state::{PoolState, UserProofVault};
+use crate::state::OperationPayload;
+use crate::proof_vault;
 use crate::errors::PoolError;
+use crate::events::{OperationStatusChanged, Unshielded};
+use crate::instructions::unshield_core::{execute_unshield_withdraw_core, UnshieldWithdrawAccounts};
 use ptf_common::OperationStatus;
-use ptf_vault;
 
 pub fn execute_unshield_withdraw(
     ctx: Context<ExecuteUnshieldWithdrawContext>,
     operation_id: [u8; 32],
 ) -> Result<()> {
     // Load operation
-    let vault_data = ctx.accounts.proof_vault.try_borrow_data()?;
-    let vault: UserProofVault = UserProofVault::try_deserialize(&mut &vault_data[8..])?;
-    drop(vault_data);
+    let proof_vault_info = ctx.accounts.proof_vault.to_account_info();
+    let owner = ctx.accounts.owner.to_account_info();
+    let vault = proof_vault::load_owned(ctx.program_id, &proof_vault_info, owner.key)?;
     
     let operation = vault.prepared_operations.iter()
         .find(|op| op.id() == &operation_id)
@@ -24,31 +26,38 @@ pub fn execute_unshield_withdraw(
         PoolError::InvalidOperationStatus
     );
     
-    // Extract amount and recipient from operation data
-    let amount = u64::from_le_bytes(
-        operation.data[32..40].try_into()
-            .map_err(|_| PoolError::InvalidAmount)?
-    );
+    // Amount, fee and recipient are the ones the proof committed to at prepare time
+    let OperationPayload::Unshield { request, .. } = operation.payload()? else {
+        return err!(PoolError::InvalidOperationStatus);
+    };
+    request.check_pool(&ctx.accounts.pool_state.key())?;
     
-    // Withdraw from vault via CPI
-    let cpi_program = ctx.accounts.vault_program.to_account_info();
-    let cpi_accounts = ptf_vault::cpi::accounts::Withdraw {
-        vault: ctx.accounts.vault_state.to_account_info(),
-        authority: ctx.accounts.pool_state.to_account_info(),
+    // Withdraw from vault via CPI, signed by the pool PDA (the vault authority)
+    let accounts = UnshieldWithdrawAccounts {
+        pool: &ctx.accounts.pool_state,
+        pool_info: ctx.accounts.pool_state.to_account_info(),
+        vault_state: ctx.accounts.vault_state.to_account_info(),
         vault_token_account: ctx.accounts.vault_token_account.to_account_info(),
-        user_token_account: ctx.accounts.user_token_account.to_account_info(),
+        recipient_token_account: &ctx.accounts.user_token_account,
+        fee_token_account: ctx.accounts.fee_token_account.as_ref().map(|account| account.to_account_info()),
+        vault_program: ctx.accounts.vault_program.to_account_info(),
         token_program: ctx.accounts.token_program.to_account_info(),
     };
-    let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
-    ptf_vault::cpi::withdraw(cpi_ctx, amount)?;
-    
-    // Remove operation from vault
-    let mut vault_data = ctx.accounts.proof_vault.try_borrow_mut_data()?;
-    let mut vault: UserProofVault = UserProofVault::try_deserialize(&mut &vault_data[8..])?;
+    execute_unshield_withdraw_core(&accounts, request)?;
+    let unshielded = Unshielded {
+        pool: ctx.accounts.pool_state.key(),
+        nullifier: request.inputs.nullifier,
+        amount: request.inputs.amount,
+        recipient: request.recipient,
+    };
     
-    vault.prepared_operations.retain(|op| op.id() != &operation_id);
+    // Remove the operation and shrink the vault, refunding the freed rent to its owner
+    let system_program = ctx.accounts.system_program.to_account_info();
+    proof_vault::complete(&proof_vault_info, vault, &operation_id, &owner, &system_program)?;
     
-    vault.serialize(&mut &mut vault_data[8..])?;
+    emit_cpi!(unshielded);
+    emit_cpi!(OperationStatusChanged::new(proof_vault_info.key(), operation_id, OperationStatus::Completed));
     
     Ok(())
 }
+
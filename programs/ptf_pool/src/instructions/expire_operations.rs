@@ -0,0 +1,30 @@
+use anchor_lang::prelude::*;
+use crate::{ExpireOperations as ExpireOperationsContext};
+use crate::events::OperationStatusChanged;
+use crate::proof_vault;
+use ptf_common::OperationStatus;
+
+/// Permissionless crank: mark every operation in the vault that expired before reaching
+/// `Updated` as `Failed`, so it can't be advanced and its owner can cancel it
+pub fn expire_operations(ctx: Context<ExpireOperationsContext>) -> Result<()> {
+    let proof_vault_info = ctx.accounts.proof_vault.to_account_info();
+    let mut vault = proof_vault::load(ctx.program_id, &proof_vault_info)?;
+    
+    let slot = Clock::get()?.slot;
+    let mut expired = Vec::new();
+    for operation in vault.prepared_operations.iter_mut() {
+        if operation.expire(slot) {
+            expired.push(*operation.id());
+        }
+    }
+    
+    if !expired.is_empty() {
+        proof_vault::save(&proof_vault_info, &vault)?;
+    }
+    for operation_id in &expired {
+        emit_cpi!(OperationStatusChanged::new(proof_vault_info.key(), *operation_id, OperationStatus::Failed));
+    }
+    msg!("Expired {} operations", expired.len());
+    
+    Ok(())
+}
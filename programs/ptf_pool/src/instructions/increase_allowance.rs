@@ -0,0 +1,20 @@
+use anchor_lang::prelude::*;
+use crate::ModifyAllowance;
+use crate::errors::PoolError;
+use crate::events::AllowanceChanged;
+use ptf_common::InputValidator;
+
+/// Raise the allowance by `amount`, keeping its expiry
+pub fn increase_allowance(ctx: Context<ModifyAllowance>, amount: u64) -> Result<()> {
+    let allowance = &mut ctx.accounts.allowance;
+    let total = allowance.amount.checked_add(amount).ok_or(PoolError::AmountTooLarge)?;
+    InputValidator::validate_amount(total, ptf_common::MAX_AMOUNT)?;
+    allowance.amount = total;
+    
+    msg!("Allowance for {} increased to {}", allowance.spender, total);
+    
+    let event = AllowanceChanged::new(allowance);
+    emit_cpi!(event);
+    
+    Ok(())
+}
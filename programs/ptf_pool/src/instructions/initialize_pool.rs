@@ -0,0 +1,108 @@
+// Create the pool PDAs for an origin mint (called by ptf_factory via CPI from register_mint)
+
+use anchor_lang::prelude::*;
+use ptf_verifier_groth16::VerifyingKeyAccount;
+use crate::{InitializePool as InitializePoolContext};
+use crate::state::{
+    Circuit, CircuitKeys, CommitmentTree, RootEntry, CIRCUIT_COUNT, MAX_ROOT_HISTORY, MIN_ROOT_HISTORY,
+};
+use crate::errors::PoolError;
+
+/// The verifying key of each circuit is passed as a remaining account, in any order
+pub fn initialize_pool<'info>(
+    ctx: Context<'_, '_, '_, 'info, InitializePoolContext<'info>>,
+    origin_mint: Pubkey,
+    canopy_depth: u8,
+    root_history_capacity: u16,
+) -> Result<()> {
+    check_pool_params(canopy_depth, root_history_capacity)?;
+    
+    let verifying_keys = load_circuit_keys(ctx.remaining_accounts)?;
+    
+    let pool_key = ctx.accounts.pool_state.key();
+    
+    // Precompute empty subtree hashes and seed the root with the empty tree root
+    let zeroes = CommitmentTree::compute_zeroes()?;
+    let empty_root = CommitmentTree::empty_root(&zeroes)?;
+    
+    let tree = &mut ctx.accounts.commitment_tree;
+    tree.pool = pool_key;
+    tree.next_index = 0;
+    tree.current_root = empty_root;
+    tree.frontier = zeroes;
+    tree.zeroes = zeroes;
+    tree.recent_len = 0;
+    tree.bump = ctx.bumps.commitment_tree;
+    
+    // The empty root is the first entry, so notes can be spent against it until it rotates out
+    let root_history = &mut ctx.accounts.root_history;
+    root_history.pool = pool_key;
+    root_history.capacity = root_history_capacity;
+    root_history.entries = vec![RootEntry::default(); root_history_capacity as usize];
+    root_history.entries[0] = RootEntry { root: empty_root, slot: Clock::get()?.slot };
+    root_history.head = 1;
+    root_history.len = 1;
+    root_history.bump = ctx.bumps.root_history;
+    
+    let nullifier_set = &mut ctx.accounts.nullifier_set;
+    nullifier_set.pool = pool_key;
+    nullifier_set.nullifiers = Vec::new();
+    nullifier_set.bump = ctx.bumps.nullifier_set;
+    
+    let pool = &mut ctx.accounts.pool_state;
+    pool.current_root = empty_root;
+    pool.origin_mint = origin_mint;
+    pool.vault = ctx.accounts.vault_state.key();
+    pool.twin_mint = None;
+    pool.verifying_keys = verifying_keys;
+    pool.last_operation_slot = 0;
+    pool.operation_count = 0;
+    pool.bump = ctx.bumps.pool_state;
+    
+    msg!("Pool initialized for mint {}", origin_mint);
+    msg!("Vault state: {}", pool.vault);
+    msg!("Root history capacity: {}", root_history_capacity);
+    
+    Ok(())
+}
+
+/// The tree keeps no canopy, so `canopy_depth` must be 0; the argument stays so existing
+/// register_mint clients keep their instruction layout
+fn check_pool_params(canopy_depth: u8, root_history_capacity: u16) -> Result<()> {
+    require!(canopy_depth == 0, PoolError::InvalidCanopyDepth);
+    require!(
+        (MIN_ROOT_HISTORY..=MAX_ROOT_HISTORY).contains(&root_history_capacity),
+        PoolError::InvalidRootHistoryCapacity
+    );
+    Ok(())
+}
+
+/// One usable verifying key per circuit, each placed by its `circuit_tag`
+fn load_circuit_keys(accounts: &[AccountInfo]) -> Result<[CircuitKeys; CIRCUIT_COUNT]> {
+    require!(accounts.len() == CIRCUIT_COUNT, PoolError::InvalidVerifyingKey);
+    let mut keys = [CircuitKeys::default(); CIRCUIT_COUNT];
+    for info in accounts {
+        require_keys_eq!(*info.owner, ptf_verifier_groth16::ID, PoolError::InvalidAccountOwner);
+        let key = VerifyingKeyAccount::try_deserialize(&mut &info.try_borrow_data()?[..])?;
+        require!(!key.revoked && key.finalized, PoolError::InvalidVerifyingKey);
+        let circuit = Circuit::from_tag(&key.circuit_tag).ok_or(PoolError::CircuitTagMismatch)?;
+        let slot = &mut keys[circuit as usize];
+        require!(slot.key == Pubkey::default(), PoolError::VerifyingKeyMismatch);
+        *slot = CircuitKeys::new(*info.key, key.key_hash, key.version);
+    }
+    Ok(keys)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rejects_a_canopy() {
+        check_pool_params(0, MIN_ROOT_HISTORY).unwrap();
+        let err = check_pool_params(1, MIN_ROOT_HISTORY).unwrap_err();
+        assert_eq!(err, PoolError::InvalidCanopyDepth.into());
+        let err = check_pool_params(0, MAX_ROOT_HISTORY + 1).unwrap_err();
+        assert_eq!(err, PoolError::InvalidRootHistoryCapacity.into());
+    }
+}
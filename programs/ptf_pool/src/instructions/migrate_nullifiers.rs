@@ -0,0 +1,28 @@
+// Move nullifiers from the legacy NullifierSet list into per-nullifier record PDAs
+
+use anchor_lang::prelude::*;
+use crate::{MigrateNullifiers as MigrateNullifiersContext};
+use crate::nullifiers::NullifierRecords;
+
+pub fn migrate_nullifiers<'info>(
+    ctx: Context<'_, '_, '_, 'info, MigrateNullifiersContext<'info>>,
+    max_count: u16,
+) -> Result<()> {
+    // Record PDAs for the last `max_count` list entries come in remaining_accounts
+    let mut account_infos = ctx.accounts.to_account_infos();
+    account_infos.extend_from_slice(ctx.remaining_accounts);
+    
+    let pool_key = ctx.accounts.pool_state.key();
+    let mut nullifiers = NullifierRecords::load(
+        ctx.program_id,
+        &account_infos,
+        &pool_key,
+        &ctx.accounts.pool_state,
+        Clock::get()?.slot,
+    )?;
+    let moved = nullifiers.migrate(max_count as usize)?;
+    
+    msg!("Migrated {} nullifiers, {} left", moved, nullifiers.legacy_len());
+    
+    Ok(())
+}
@@ -1,28 +1,52 @@
+pub mod initialize_pool;
 pub mod prepare_shield;
 pub mod execute_shield_v2;
 pub mod shield_core;
+pub mod transfer_core;
+pub mod unshield_core;
 pub mod prepare_unshield;
 pub mod update_operation_data;
+pub mod cancel_operation;
+pub mod expire_operations;
+pub mod close_proof_vault;
 pub mod execute_unshield_verify;
 pub mod execute_unshield_update;
 pub mod execute_unshield_withdraw;
+pub mod unshield;
 pub mod execute_transfer;
 pub mod execute_transfer_from;
 pub mod approve_allowance;
+pub mod increase_allowance;
+pub mod decrease_allowance;
+pub mod revoke_allowance;
 pub mod execute_batch_transfer;
 pub mod execute_batch_transfer_from;
+pub mod rotate_verifying_key;
+pub mod migrate_nullifiers;
 
+pub use initialize_pool::*;
 pub use prepare_shield::*;
 pub use execute_shield_v2::*;
 pub use shield_core::*;
+pub use transfer_core::*;
+pub use unshield_core::*;
 pub use prepare_unshield::*;
 pub use update_operation_data::*;
+pub use cancel_operation::*;
+pub use expire_operations::*;
+pub use close_proof_vault::*;
 pub use execute_unshield_verify::*;
 pub use execute_unshield_update::*;
 pub use execute_unshield_withdraw::*;
+pub use unshield::*;
 pub use execute_transfer::*;
 pub use execute_transfer_from::*;
 pub use approve_allowance::*;
+pub use increase_allowance::*;
+pub use decrease_allowance::*;
+pub use revoke_allowance::*;
 pub use execute_batch_transfer::*;
 pub use execute_batch_transfer_from::*;
+pub use rotate_verifying_key::*;
+pub use migrate_nullifiers::*;
 
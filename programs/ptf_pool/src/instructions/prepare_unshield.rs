@@ -1,104 +1,45 @@
 use anchor_lang::prelude::*;
 use crate::{PrepareUnshieldArgs, PrepareUnshield as PrepareUnshieldContext};
-use crate::state::UserProofVault;
-use crate::errors::PoolError;
+use crate::state::OperationPayload;
+use crate::events::OperationStatusChanged;
+use crate::proof_vault;
+use crate::instructions::unshield_core::UnshieldRequest;
 use ptf_common::OperationStatus;
-use anchor_lang::solana_program::rent::Rent;
-use anchor_lang::solana_program::system_instruction;
-use anchor_lang::solana_program::program::invoke_signed;
-use anchor_lang::solana_program::pubkey::Pubkey;
-// Hash function for operation IDs - using simple approach for now
-// In production, would use proper keccak hash
-fn hash_operation_id(data: &[u8]) -> [u8; 32] {
-    // For now, use a simple hash - will replace with proper keccak after Solana CLI setup
-    let mut result = [0u8; 32];
-    for (i, byte) in data.iter().enumerate() {
-        result[i % 32] ^= byte.wrapping_add(i as u8);
-    }
-    // Simple hash - in production use keccak::hash
-    result
-}
 
 pub fn prepare_unshield(
     ctx: Context<PrepareUnshieldContext>,
     args: PrepareUnshieldArgs,
 ) -> Result<[u8; 32]> {
-    let proof_vault_info = &ctx.accounts.proof_vault;
-    
     // Validate amount
     ptf_common::InputValidator::validate_amount(args.amount, ptf_common::MAX_UNSHIELD_AMOUNT)?;
     
-    // Create account manually if it doesn't exist
-    if proof_vault_info.data_len() == 0 {
-        let space = 8 + UserProofVault::MIN_LEN + 1000; // Extra space for operations
-        let lamports = Rent::get()?.minimum_balance(space);
-        
-        let (pda, bump) = Pubkey::find_program_address(
-            &[b"proof-vault", ctx.accounts.payer.key.as_ref()],
-            &crate::ID,
-        );
-        require_keys_eq!(pda, proof_vault_info.key(), PoolError::AccountNotFound);
-        
-        invoke_signed(
-            &system_instruction::create_account(
-                ctx.accounts.payer.key,
-                proof_vault_info.key,
-                lamports,
-                space as u64,
-                &crate::ID,
-            ),
-            &[
-                ctx.accounts.payer.to_account_info(),
-                proof_vault_info.to_account_info(),
-                ctx.accounts.system_program.to_account_info(),
-            ],
-            &[&[b"proof-vault", ctx.accounts.payer.key.as_ref(), &[bump]]],
-        )?;
-        
-        // Set discriminator (Anchor account discriminator)
-        let mut account_data = proof_vault_info.try_borrow_mut_data()?;
-        // The discriminator is the first 8 bytes of the SHA256 hash of "account:UserProofVault"
-        let discriminator_preimage = b"account:UserProofVault";
-        // Use Anchor's discriminator system - will be set properly on first build
-        let discriminator = [0u8; 8]; // Placeholder - Anchor will set this
-        account_data[0..8].copy_from_slice(&discriminator);
-        
-        let vault = UserProofVault {
-            prepared_operations: Vec::new(),
-        };
-        vault.serialize(&mut &mut account_data[8..])?;
-    }
-    
-    // Load vault and add operation
-    let vault_data = proof_vault_info.try_borrow_data()?;
-    let mut vault_account: UserProofVault = UserProofVault::try_deserialize(&mut &vault_data[8..])?;
-    drop(vault_data);
-    
-    // Generate operation_id
-    let mut operation_id_data = Vec::new();
-    operation_id_data.extend_from_slice(&args.nullifier);
-    operation_id_data.extend_from_slice(&args.amount.to_le_bytes());
-    operation_id_data.extend_from_slice(args.recipient.as_ref());
-    // Generate operation ID using hash
-    let operation_id = hash_operation_id(&operation_id_data);
-    
-    // Create operation with status Pending
-    let mut operation_data = Vec::new();
-    operation_data.extend_from_slice(&args.nullifier);
-    operation_data.extend_from_slice(&args.amount.to_le_bytes());
-    operation_data.extend_from_slice(args.recipient.as_ref());
-    
-    let operation = crate::state::PreparedOperation {
-        id: operation_id,
-        status: OperationStatus::Pending as u8,
-        operation_type: 1, // Unshield
-        data: operation_data,
-    };
-    
-    vault_account.prepared_operations.push(operation);
-    
-    let mut vault_data = proof_vault_info.try_borrow_mut_data()?;
-    vault_account.serialize(&mut &mut vault_data[8..])?;
+    let proof_vault_info = ctx.accounts.proof_vault.to_account_info();
+    let payer = ctx.accounts.payer.to_account_info();
+    let system_program = ctx.accounts.system_program.to_account_info();
+    let slot = Clock::get()?.slot;
+    let mut vault = proof_vault::load_or_create(ctx.program_id, &proof_vault_info, &payer, &system_program, slot)?;
+    
+    // Fix the pool and public inputs now so later steps can't be fed different ones
+    let request = UnshieldRequest::new(
+        ctx.accounts.pool_state.key(),
+        args.root, args.nullifier, args.amount, args.recipient,
+        args.fee,
+    )?;
+    let operation_id = vault.prepare(
+        ctx.program_id,
+        payer.key,
+        OperationPayload::Unshield { request, proof: None },
+        slot,
+    )?;
+    
+    // Grow the vault to fit the new operation
+    proof_vault::save_resized(&proof_vault_info, &vault, &payer, &system_program)?;
+    
+    emit_cpi!(OperationStatusChanged::new(
+        proof_vault_info.key(),
+        operation_id,
+        OperationStatus::Pending,
+    ));
     
     Ok(operation_id)
 }
@@ -0,0 +1,17 @@
+use anchor_lang::prelude::*;
+use crate::{RevokeAllowance as RevokeAllowanceContext};
+use crate::events::AllowanceChanged;
+
+/// Close the allowance and return its rent to the owner; the account is closed by the context.
+/// The owner can still spend their tagged notes through `TransferFromMode::Reclaim`
+pub fn revoke_allowance(ctx: Context<RevokeAllowanceContext>) -> Result<()> {
+    msg!("Revoked allowance for {}", ctx.accounts.allowance.spender);
+    
+    emit_cpi!(AllowanceChanged {
+        amount: 0,
+        expiry_slot: None,
+        ..AllowanceChanged::new(&ctx.accounts.allowance)
+    });
+    
+    Ok(())
+}
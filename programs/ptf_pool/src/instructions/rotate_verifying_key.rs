@@ -0,0 +1,95 @@
+// Rotate the pool's verifying key (called by ptf_factory via CPI)
+
+use anchor_lang::prelude::*;
+use ptf_verifier_groth16::VerifyingKeyAccount;
+use crate::{RotateVerifyingKey as RotateVerifyingKeyContext};
+use crate::errors::PoolError;
+use crate::state::Circuit;
+
+pub fn rotate_verifying_key(ctx: Context<RotateVerifyingKeyContext>, grace_slots: u64) -> Result<()> {
+    let new_key = &ctx.accounts.new_verifying_key;
+    let pool_state = &mut ctx.accounts.pool_state;
+    let slot = Clock::get()?.slot;
+    
+    // Only the key of the circuit the new key is for is replaced
+    let circuit = Circuit::from_tag(&new_key.circuit_tag).ok_or(PoolError::CircuitTagMismatch)?;
+    let current = pool_state.circuit_keys(circuit);
+    require_keys_eq!(
+        ctx.accounts.current_verifying_key.key(),
+        current.key,
+        PoolError::VerifyingKeyMismatch
+    );
+    check_rotation(&ctx.accounts.current_verifying_key, new_key, current.version)?;
+    
+    // Proofs against the old key stay valid for grace_slots so notes proven
+    // under the previous circuit version are not stranded mid-upgrade
+    let grace_end_slot = slot.saturating_add(grace_slots);
+    pool_state.rotate_verifying_key(
+        circuit,
+        new_key.key(),
+        new_key.key_hash,
+        new_key.version,
+        grace_end_slot,
+        slot,
+    )?;
+    
+    msg!("Pool {} verifying key rotated to version {}", circuit.name(), new_key.version);
+    msg!("Previous key accepted until slot {}", grace_end_slot);
+    
+    Ok(())
+}
+
+/// A replacement key must be usable, newer than the current one and for the same circuit:
+/// a key for another circuit would reject every proof the pool is sent
+fn check_rotation(
+    current: &VerifyingKeyAccount,
+    new_key: &VerifyingKeyAccount,
+    current_version: u32,
+) -> Result<()> {
+    require!(
+        !new_key.revoked && new_key.finalized,
+        PoolError::InvalidVerifyingKey
+    );
+    require!(
+        new_key.version > current_version,
+        PoolError::InvalidVerifyingKey
+    );
+    require!(
+        new_key.circuit_tag == current.circuit_tag,
+        PoolError::CircuitTagMismatch
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(circuit_tag: [u8; 32], version: u32) -> VerifyingKeyAccount {
+        VerifyingKeyAccount {
+            circuit_tag,
+            version,
+            key_data: Vec::new(),
+            revoked: false,
+            authority: Pubkey::default(),
+            attestation_threshold: 0,
+            key_hash: [0u8; 32],
+            finalized: true,
+        }
+    }
+
+    #[test]
+    fn rotation_requires_a_newer_key_for_the_same_circuit() {
+        let current = key([1u8; 32], 1);
+        check_rotation(&current, &key([1u8; 32], 2), 1).unwrap();
+
+        let err = check_rotation(&current, &key([2u8; 32], 2), 1).unwrap_err();
+        assert_eq!(err, PoolError::CircuitTagMismatch.into());
+        let err = check_rotation(&current, &key([1u8; 32], 1), 1).unwrap_err();
+        assert_eq!(err, PoolError::InvalidVerifyingKey.into());
+
+        let mut revoked = key([1u8; 32], 2);
+        revoked.revoked = true;
+        assert!(check_rotation(&current, &revoked, 1).is_err());
+    }
+}
@@ -1,134 +1,136 @@
 use anchor_lang::prelude::*;
-use crate::state::{PoolState, CommitmentTree, NoteLedger, DEPTH};
+use crate::state::PoolState;
+use crate::raw::{CommitmentTreeMut, RootHistoryMut};
 use crate::errors::PoolError;
-use ptf_common::{InputValidator, InputSanitizer, check_rate_limit, OperationType};
-use anchor_lang::solana_program::sysvar::Sysvar;
-use anchor_lang::solana_program::clock::Clock;
-// Hash function for Merkle tree - using simple approach for now
-// In production, would use proper hash function
-fn hash_pair(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
-    // Simple hash combining left and right
-    // In production, would use proper cryptographic hash
-    let mut data = Vec::new();
-    data.extend_from_slice(left);
-    data.extend_from_slice(right);
-    let mut result = [0u8; 32];
-    for (i, byte) in data.iter().enumerate() {
-        result[i % 32] ^= byte.wrapping_add(i as u8);
-    }
-    result
+use ptf_common::{InputValidator, check_rate_limit, OperationType};
+
+/// Shield proof public inputs: [commitment, amount], each a 32-byte big-endian field element
+pub fn shield_public_inputs(commitment: &[u8; 32], amount: u64) -> Vec<u8> {
+    let mut public_inputs = Vec::with_capacity(64);
+    public_inputs.extend_from_slice(commitment);
+    public_inputs.extend_from_slice(&[0u8; 24]);
+    public_inputs.extend_from_slice(&amount.to_be_bytes());
+    public_inputs
 }
 
+/// Shield state update once the proof is verified and tokens are in the vault.
+/// Returns the leaf index of the new commitment. The tree and the `Shielded` event are the
+/// record of every commitment; nothing else grows with the number of notes.
 #[inline(never)]
 pub fn execute_shield_core(
-    pool_state: &mut Account<'_, PoolState>,
-    commitment_tree: &mut Account<'_, CommitmentTree>,
-    note_ledger: &mut Account<'_, NoteLedger>,
-    _proof: Vec<u8>,
-    _public_inputs: Vec<u8>,
+    pool: &mut PoolState,
+    tree: &mut CommitmentTreeMut,
+    root_history: &mut RootHistoryMut,
     commitment: [u8; 32],
     amount: u64,
-) -> Result<()> {
-    let pool = pool_state;
-    let tree = commitment_tree;
-    
+    slot: u64,
+) -> Result<u64> {
     // Validate inputs
     InputValidator::validate_amount(amount, ptf_common::MAX_SHIELD_AMOUNT)?;
-    InputSanitizer::sanitize_proof(&_proof, ptf_common::MAX_PROOF_SIZE)?;
-    InputSanitizer::sanitize_public_inputs(&_public_inputs, ptf_common::MAX_PUBLIC_INPUTS_SIZE)?;
     
     // Check rate limit
-    let clock = Clock::get()?;
     check_rate_limit(
         pool.last_operation_slot,
         OperationType::Shield,
-        clock.slot,
+        slot,
     )?;
     
     // Update pool state
-    pool.last_operation_slot = clock.slot;
+    pool.last_operation_slot = slot;
     pool.operation_count = pool.operation_count.checked_add(1)
         .ok_or(PoolError::InvalidAmount)?;
     
-    // Update commitment tree with Merkle tree insertion
-    let insert_index = tree.next_index;
-    tree.next_index = tree.next_index.checked_add(1)
-        .ok_or(PoolError::InvalidAmount)?;
+    // Append the commitment to the incremental Merkle tree
+    let insert_index = tree.insert_many(&[commitment])?;
+    let new_root = tree.current_root();
+    tree.record_recent(&commitment, insert_index);
     
-    // Insert commitment into Merkle tree using frontier-based approach
-    // The frontier represents the rightmost path in the tree
-    let mut current_hash = commitment;
-    let mut current_index = insert_index;
+    // Publish the new root and keep it spendable against for the history window
+    pool.current_root = new_root;
+    root_history.push(new_root, slot);
     
-    // Traverse up the tree, updating frontier and computing hashes
-    for level in 0..DEPTH {
-        let bit = (current_index >> level) & 1;
-        
-        if bit == 0 {
-            // We're the left child at this level
-            // Right sibling is either in frontier (if exists) or zero
-            let right_hash = if (current_index | ((1u64 << level) - 1)) < insert_index {
-                // Right sibling exists - would need to fetch from tree
-                // For now, use zero (simplified - full implementation would store siblings)
-                tree.zeroes[level]
-            } else {
-                // Use zero value for right sibling
-                tree.zeroes[level]
-            };
-            
-            // Compute parent hash: hash(left || right)
-            current_hash = hash_pair(&current_hash, &right_hash);
-        } else {
-            // We're the right child at this level
-            // Left sibling is in the frontier
-            let left_hash = tree.frontier[level];
-            
-            // Compute parent hash: hash(left || right)
-            current_hash = hash_pair(&left_hash, &current_hash);
-        }
-        
-        // Update frontier at this level (always store the rightmost node)
-        tree.frontier[level] = current_hash;
-        
-        current_index = current_index >> 1;
+    Ok(insert_index)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::{
+        CircuitKeys, CommitmentTree, RootEntry, RootHistory, CIRCUIT_COUNT, MIN_ROOT_HISTORY,
+        RECENT_COMMITMENTS,
+    };
+    use ptf_common::MIN_SLOTS_BETWEEN_SHIELD;
+    use std::cell::RefCell;
+
+    fn serialize<T: AccountSerialize>(account: &T, len: usize) -> Vec<u8> {
+        let mut data = vec![0u8; len];
+        account.try_serialize(&mut &mut data[..]).unwrap();
+        data
     }
-    
-    // The root is the top of the frontier
-    let new_root = tree.frontier[DEPTH - 1];
-    
-    // Add commitment to recent commitments
-    if (tree.recent_len as usize) < crate::state::MAX_CANOPY {
-        let idx = tree.recent_len as usize;
-        tree.recent_commitments[idx] = commitment;
-        // Convert amount to 32-byte array (pad with zeros)
-        let mut amount_bytes = [0u8; 32];
-        let amount_le = amount.to_le_bytes();
-        amount_bytes[0..8].copy_from_slice(&amount_le);
-        tree.recent_amount_commitments[idx] = amount_bytes;
-        tree.recent_indices[idx] = insert_index;
-        tree.recent_len += 1;
+
+    #[test]
+    fn shield_public_inputs_are_commitment_then_amount() {
+        let commitment = [7u8; 32];
+        let inputs = shield_public_inputs(&commitment, 0x0102_0304);
+        assert_eq!(inputs.len(), 64);
+        assert_eq!(&inputs[..32], &commitment);
+        assert_eq!(&inputs[32..60], &[0u8; 28]);
+        assert_eq!(&inputs[60..], &[1, 2, 3, 4]);
     }
-    
-    // Update current root
-    pool.current_root = new_root;
-    tree.current_root = new_root;
-    
-    // Update recent roots in pool state
-    if pool.recent_roots_len < 16 {
-        let idx = pool.recent_roots_len as usize;
-        pool.recent_roots[idx] = new_root;
-        pool.recent_roots_len += 1;
-    } else {
-        // Shift recent roots
-        for i in 0..15 {
-            pool.recent_roots[i] = pool.recent_roots[i + 1];
+
+    #[test]
+    fn shields_keep_landing_past_the_old_ledger_capacity() {
+        // initialize_pool used to size a note ledger for 256 notes; the 257th shield failed
+        const SHIELDS: u64 = 300;
+        let zeroes = CommitmentTree::compute_zeroes().unwrap();
+        let root = CommitmentTree::empty_root(&zeroes).unwrap();
+        let tree = CommitmentTree {
+            pool: Pubkey::default(),
+            next_index: 0,
+            current_root: root,
+            frontier: zeroes,
+            zeroes,
+            recent_commitments: [[0u8; 32]; RECENT_COMMITMENTS],
+            recent_indices: [0u64; RECENT_COMMITMENTS],
+            recent_len: 0,
+            bump: 255,
+        };
+        let history = RootHistory {
+            pool: Pubkey::default(),
+            capacity: MIN_ROOT_HISTORY,
+            head: 0,
+            len: 0,
+            bump: 255,
+            entries: vec![RootEntry::default(); MIN_ROOT_HISTORY as usize],
+        };
+        let mut pool = PoolState {
+            current_root: root,
+            origin_mint: Pubkey::default(),
+            vault: Pubkey::default(),
+            twin_mint: None,
+            verifying_keys: [CircuitKeys::default(); CIRCUIT_COUNT],
+            last_operation_slot: 0,
+            operation_count: 0,
+            bump: 255,
+        };
+        let mut tree = serialize(&tree, CommitmentTree::LEN);
+        let mut history = serialize(&history, RootHistory::space(MIN_ROOT_HISTORY));
+        let tree = RefCell::new(&mut tree[..]);
+        let history = RefCell::new(&mut history[..]);
+        let mut tree = CommitmentTreeMut::from_data(tree.borrow_mut()).unwrap();
+        let mut history = RootHistoryMut::from_data(history.borrow_mut()).unwrap();
+
+        for n in 0..SHIELDS {
+            let mut commitment = [0u8; 32];
+            commitment[24..].copy_from_slice(&(n + 1).to_be_bytes());
+            let slot = (n + 1) * MIN_SLOTS_BETWEEN_SHIELD;
+            let leaf_index = execute_shield_core(&mut pool, &mut tree, &mut history, commitment, 1, slot).unwrap();
+            assert_eq!(leaf_index, n);
         }
-        pool.recent_roots[15] = new_root;
+        assert_eq!(tree.next_index(), SHIELDS);
+        assert_eq!(pool.operation_count, SHIELDS);
+        assert_eq!(pool.current_root, tree.current_root());
+        assert!(history.is_known_root(&pool.current_root));
     }
-    
-    // Add note to ledger
-    note_ledger.notes.push(commitment);
-    
-    Ok(())
 }
-
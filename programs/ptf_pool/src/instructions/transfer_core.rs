@@ -0,0 +1,529 @@
+use anchor_lang::prelude::*;
+use crate::state::PoolState;
+use crate::errors::PoolError;
+use crate::nullifiers::NullifierStore;
+use crate::raw::{CommitmentTreeMut, RootHistoryMut};
+use ptf_common::{check_rate_limit, OperationType};
+
+/// Notes spent by one transfer proof (unused inputs are zero-amount dummy notes)
+pub const TRANSFER_INPUTS: usize = 2;
+/// Notes created by one transfer proof
+pub const TRANSFER_OUTPUTS: usize = 2;
+
+/// Join-split public inputs, each a 32-byte big-endian field element:
+/// [root][nullifier; TRANSFER_INPUTS][output commitment; TRANSFER_OUTPUTS]
+#[derive(Clone, Debug, PartialEq, Eq, AnchorSerialize, AnchorDeserialize)]
+pub struct TransferPublicInputs {
+    pub root: [u8; 32],
+    pub nullifiers: [[u8; 32]; TRANSFER_INPUTS],
+    pub commitments: [[u8; 32]; TRANSFER_OUTPUTS],
+}
+
+impl TransferPublicInputs {
+    pub const LEN: usize = 32 * (1 + TRANSFER_INPUTS + TRANSFER_OUTPUTS);
+    
+    pub fn parse(bytes: &[u8]) -> Result<Self> {
+        require!(bytes.len() == Self::LEN, PoolError::InvalidPublicInputs);
+        let mut chunks = elements(bytes);
+        let root = chunks.next().unwrap();
+        let nullifiers = std::array::from_fn(|_| chunks.next().unwrap());
+        let commitments = std::array::from_fn(|_| chunks.next().unwrap());
+        Ok(Self { root, nullifiers, commitments })
+    }
+    
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(Self::LEN);
+        bytes.extend_from_slice(&self.root);
+        self.nullifiers.iter().for_each(|n| bytes.extend_from_slice(n));
+        self.commitments.iter().for_each(|c| bytes.extend_from_slice(c));
+        bytes
+    }
+}
+
+/// Delegated-spend public inputs, each a 32-byte big-endian field element:
+/// [root][delegation nullifier][output commitment; TRANSFER_OUTPUTS][change commitment]
+///
+/// The proof spends one delegation note, a note whose third hash input is the delegation tag
+/// hash(owner key, spender key) instead of 0, signed for by either key. The change note carries
+/// the remaining cap under the same tag. Neither key nor the cap is public.
+#[derive(Clone, Debug, PartialEq, Eq, AnchorSerialize, AnchorDeserialize)]
+pub struct DelegatedTransferPublicInputs {
+    pub root: [u8; 32],
+    pub nullifier: [u8; 32],
+    /// The outputs, then the change delegation note last
+    pub commitments: [[u8; 32]; TRANSFER_OUTPUTS + 1],
+}
+
+impl DelegatedTransferPublicInputs {
+    pub const LEN: usize = 32 * (2 + TRANSFER_OUTPUTS + 1);
+    
+    pub fn parse(bytes: &[u8]) -> Result<Self> {
+        require!(bytes.len() == Self::LEN, PoolError::InvalidPublicInputs);
+        let mut chunks = elements(bytes);
+        let root = chunks.next().unwrap();
+        let nullifier = chunks.next().unwrap();
+        let commitments = std::array::from_fn(|_| chunks.next().unwrap());
+        Ok(Self { root, nullifier, commitments })
+    }
+    
+    pub fn change_commitment(&self) -> &[u8; 32] {
+        &self.commitments[TRANSFER_OUTPUTS]
+    }
+}
+
+fn elements(bytes: &[u8]) -> impl Iterator<Item = [u8; 32]> + '_ {
+    bytes.chunks_exact(32).map(|chunk| {
+        let mut element = [0u8; 32];
+        element.copy_from_slice(chunk);
+        element
+    })
+}
+
+/// What the pool needs from a verified proof to apply it: the root it was made against,
+/// the nullifiers it spends and the commitments it appends
+pub trait JoinSplitInputs {
+    fn root(&self) -> &[u8; 32];
+    fn nullifiers(&self) -> &[[u8; 32]];
+    fn commitments(&self) -> &[[u8; 32]];
+}
+
+impl JoinSplitInputs for TransferPublicInputs {
+    fn root(&self) -> &[u8; 32] {
+        &self.root
+    }
+    
+    fn nullifiers(&self) -> &[[u8; 32]] {
+        &self.nullifiers
+    }
+    
+    fn commitments(&self) -> &[[u8; 32]] {
+        &self.commitments
+    }
+}
+
+impl JoinSplitInputs for DelegatedTransferPublicInputs {
+    fn root(&self) -> &[u8; 32] {
+        &self.root
+    }
+    
+    fn nullifiers(&self) -> &[[u8; 32]] {
+        std::slice::from_ref(&self.nullifier)
+    }
+    
+    fn commitments(&self) -> &[[u8; 32]] {
+        &self.commitments
+    }
+}
+
+/// Transfer state update once the proof is verified: spend the input nullifiers and
+/// append the output commitments. Returns the leaf index of the first output.
+#[inline(never)]
+pub fn execute_transfer_core(
+    pool: &mut PoolState,
+    tree: &mut CommitmentTreeMut,
+    root_history: &mut RootHistoryMut,
+    nullifiers: &mut impl NullifierStore,
+    inputs: &dyn JoinSplitInputs,
+    slot: u64,
+) -> Result<u64> {
+    execute_batch_transfer_core(pool, tree, root_history, nullifiers, &[inputs], slot, |_, _| {})
+}
+
+/// Apply a batch of verified join-splits as one: every item is checked, including for
+/// nullifiers repeated anywhere in the batch, before any state is written, and all outputs
+/// go into the tree in a single insertion under one new root. `on_item_error` is told which
+/// item failed a check. Returns the leaf index of the first output.
+#[inline(never)]
+pub fn execute_batch_transfer_core(
+    pool: &mut PoolState,
+    tree: &mut CommitmentTreeMut,
+    root_history: &mut RootHistoryMut,
+    nullifiers: &mut impl NullifierStore,
+    batch: &[&dyn JoinSplitInputs],
+    slot: u64,
+    mut on_item_error: impl FnMut(usize, &Error),
+) -> Result<u64> {
+    // Validate everything before writing any state
+    require!(!batch.is_empty(), PoolError::InvalidAmount);
+    check_rate_limit(pool.last_operation_slot, OperationType::Transfer, slot)?;
+    let mut spent: Vec<[u8; 32]> = Vec::new();
+    for (index, inputs) in batch.iter().enumerate() {
+        check_join_split(root_history, nullifiers, *inputs, &spent)
+            .inspect_err(|err| on_item_error(index, err))?;
+        spent.extend_from_slice(inputs.nullifiers());
+    }
+    
+    pool.last_operation_slot = slot;
+    pool.operation_count = pool.operation_count.checked_add(batch.len() as u64)
+        .ok_or(PoolError::InvalidAmount)?;
+    for nullifier in &spent {
+        nullifiers.spend(nullifier)?;
+    }
+    
+    // Append every output at once, so shared path nodes are hashed once, and publish the new root
+    let commitments: Vec<[u8; 32]> = batch.iter()
+        .flat_map(|inputs| inputs.commitments().iter().copied())
+        .collect();
+    let first_index = tree.insert_many(&commitments)?;
+    pool.current_root = tree.current_root();
+    root_history.push(pool.current_root, slot);
+    
+    Ok(first_index)
+}
+
+/// One join-split's checks: a known root, and nullifiers that are non-zero and spent neither
+/// before, nor earlier in the same proof, nor in `spent_in_batch`
+fn check_join_split(
+    root_history: &RootHistoryMut,
+    nullifiers: &mut impl NullifierStore,
+    inputs: &dyn JoinSplitInputs,
+    spent_in_batch: &[[u8; 32]],
+) -> Result<()> {
+    require!(root_history.is_known_root(inputs.root()), PoolError::InvalidRoot);
+    let spent = inputs.nullifiers();
+    for (i, nullifier) in spent.iter().enumerate() {
+        require!(*nullifier != [0u8; 32], PoolError::InvalidNullifier);
+        require!(
+            !spent[..i].contains(nullifier)
+                && !spent_in_batch.contains(nullifier)
+                && !nullifiers.is_spent(nullifier)?,
+            PoolError::NullifierAlreadyUsed
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::merkle::ReferenceTree;
+    use crate::raw::HashListMut;
+    use crate::state::{
+        CircuitKeys, CommitmentTree, NullifierSet, RootEntry, RootHistory, CIRCUIT_COUNT, DEPTH,
+        MIN_ROOT_HISTORY, RECENT_COMMITMENTS,
+    };
+    use std::cell::RefCell;
+
+    fn element(n: u8) -> [u8; 32] {
+        let mut element = [0u8; 32];
+        element[31] = n;
+        element
+    }
+
+    fn serialize<T: AccountSerialize>(account: &T, len: usize) -> Vec<u8> {
+        let mut data = vec![0u8; len];
+        account.try_serialize(&mut &mut data[..]).unwrap();
+        data
+    }
+
+    struct Fixture {
+        pool: PoolState,
+        tree: Vec<u8>,
+        root_history: Vec<u8>,
+        nullifiers: Vec<u8>,
+    }
+
+    impl Fixture {
+        fn new() -> Self {
+            let zeroes = CommitmentTree::compute_zeroes().unwrap();
+            let root = CommitmentTree::empty_root(&zeroes).unwrap();
+            let tree = CommitmentTree {
+                pool: Pubkey::default(),
+                next_index: 0,
+                current_root: root,
+                frontier: zeroes,
+                zeroes,
+                recent_commitments: [[0u8; 32]; RECENT_COMMITMENTS],
+                recent_indices: [0u64; RECENT_COMMITMENTS],
+                recent_len: 0,
+                bump: 255,
+            };
+            let root_history = RootHistory {
+                pool: Pubkey::default(),
+                capacity: MIN_ROOT_HISTORY,
+                head: 1,
+                len: 1,
+                bump: 255,
+                entries: std::iter::once(RootEntry { root, slot: 0 })
+                    .chain(std::iter::repeat(RootEntry::default()))
+                    .take(MIN_ROOT_HISTORY as usize)
+                    .collect(),
+            };
+            let nullifiers = NullifierSet { pool: Pubkey::default(), nullifiers: vec![], bump: 255 };
+            let pool = PoolState {
+                current_root: root,
+                origin_mint: Pubkey::default(),
+                vault: Pubkey::default(),
+                twin_mint: None,
+                verifying_keys: [CircuitKeys::default(); CIRCUIT_COUNT],
+                last_operation_slot: 0,
+                operation_count: 0,
+                bump: 255,
+            };
+            Self {
+                pool,
+                tree: serialize(&tree, CommitmentTree::LEN),
+                root_history: serialize(&root_history, RootHistory::space(MIN_ROOT_HISTORY)),
+                nullifiers: serialize(&nullifiers, NullifierSet::MIN_LEN + 32 * 80),
+            }
+        }
+
+        fn transfer(&mut self, inputs: &impl JoinSplitInputs, slot: u64) -> Result<u64> {
+            let tree = RefCell::new(&mut self.tree[..]);
+            let root_history = RefCell::new(&mut self.root_history[..]);
+            let nullifiers = RefCell::new(&mut self.nullifiers[..]);
+            let mut tree = CommitmentTreeMut::from_data(tree.borrow_mut())?;
+            let mut root_history = RootHistoryMut::from_data(root_history.borrow_mut())?;
+            let mut nullifiers = HashListMut::from_data(nullifiers.borrow_mut())?;
+            execute_transfer_core(&mut self.pool, &mut tree, &mut root_history, &mut nullifiers, inputs, slot)
+        }
+
+        /// Run a batch, returning its result and the indices reported as failing
+        fn batch(&mut self, batch: &[&dyn JoinSplitInputs], slot: u64) -> (Result<u64>, Vec<usize>) {
+            let tree = RefCell::new(&mut self.tree[..]);
+            let root_history = RefCell::new(&mut self.root_history[..]);
+            let nullifiers = RefCell::new(&mut self.nullifiers[..]);
+            let mut tree = CommitmentTreeMut::from_data(tree.borrow_mut()).unwrap();
+            let mut root_history = RootHistoryMut::from_data(root_history.borrow_mut()).unwrap();
+            let mut nullifiers = HashListMut::from_data(nullifiers.borrow_mut()).unwrap();
+            let mut failed = Vec::new();
+            let result = execute_batch_transfer_core(
+                &mut self.pool,
+                &mut tree,
+                &mut root_history,
+                &mut nullifiers,
+                batch,
+                slot,
+                |index, _| failed.push(index),
+            );
+            (result, failed)
+        }
+    }
+
+    #[test]
+    fn public_inputs_round_trip() {
+        let inputs = TransferPublicInputs {
+            root: element(1),
+            nullifiers: [element(2), element(3)],
+            commitments: [element(4), element(5)],
+        };
+        let bytes = inputs.to_bytes();
+        assert_eq!(bytes.len(), TransferPublicInputs::LEN);
+        assert_eq!(&bytes[32..64], &element(2));
+        assert_eq!(TransferPublicInputs::parse(&bytes).unwrap(), inputs);
+        assert!(TransferPublicInputs::parse(&bytes[..bytes.len() - 1]).is_err());
+    }
+
+    #[test]
+    fn transfer_spends_nullifiers_and_appends_outputs() {
+        let mut fixture = Fixture::new();
+        let mut reference = ReferenceTree::new(DEPTH).unwrap();
+        let first = TransferPublicInputs {
+            root: fixture.pool.current_root,
+            nullifiers: [element(1), element(2)],
+            commitments: [element(10), element(11)],
+        };
+        assert_eq!(fixture.transfer(&first, 100).unwrap(), 0);
+        reference.insert(element(10)).unwrap();
+        let root = reference.insert(element(11)).unwrap();
+        assert_eq!(fixture.pool.current_root, root);
+
+        // Reused nullifier, even against a valid root
+        let reused = TransferPublicInputs {
+            root,
+            nullifiers: [element(3), element(2)],
+            commitments: [element(12), element(13)],
+        };
+        assert_eq!(fixture.transfer(&reused, 200).unwrap_err(), PoolError::NullifierAlreadyUsed.into());
+
+        // Unknown root
+        let unknown = TransferPublicInputs {
+            root: element(99),
+            nullifiers: [element(4), element(5)],
+            ..reused.clone()
+        };
+        assert_eq!(fixture.transfer(&unknown, 200).unwrap_err(), PoolError::InvalidRoot.into());
+
+        // Same nullifier twice in one proof
+        let doubled = TransferPublicInputs { root, nullifiers: [element(6), element(6)], ..reused };
+        assert_eq!(fixture.pool.operation_count, 1);
+        assert_eq!(fixture.transfer(&doubled, 200).unwrap_err(), PoolError::NullifierAlreadyUsed.into());
+
+        // Rejected transfers left no trace, so nullifier 3 is still unspent
+        let second = TransferPublicInputs {
+            root,
+            nullifiers: [element(3), element(7)],
+            commitments: [element(14), element(15)],
+        };
+        assert_eq!(fixture.transfer(&second, 200).unwrap(), 2);
+        reference.insert(element(14)).unwrap();
+        assert_eq!(fixture.pool.current_root, reference.insert(element(15)).unwrap());
+    }
+
+    #[test]
+    fn delegated_spend_nullifies_the_delegation_note_and_appends_change() {
+        let mut fixture = Fixture::new();
+        let mut reference = ReferenceTree::new(DEPTH).unwrap();
+        let inputs = DelegatedTransferPublicInputs {
+            root: fixture.pool.current_root,
+            nullifier: element(1),
+            commitments: [element(10), element(11), element(12)],
+        };
+        let bytes = [&inputs.root[..], &inputs.nullifier[..], &inputs.commitments.concat()].concat();
+        assert_eq!(DelegatedTransferPublicInputs::parse(&bytes).unwrap(), inputs);
+        assert_eq!(inputs.change_commitment(), &element(12));
+
+        assert_eq!(fixture.transfer(&inputs, 100).unwrap(), 0);
+        for leaf in [10, 11] {
+            reference.insert(element(leaf)).unwrap();
+        }
+        let root = reference.insert(element(12)).unwrap();
+        assert_eq!(fixture.pool.current_root, root);
+
+        // The spent delegation note can't be spent again
+        let replayed = DelegatedTransferPublicInputs { root, ..inputs };
+        assert_eq!(fixture.transfer(&replayed, 200).unwrap_err(), PoolError::NullifierAlreadyUsed.into());
+    }
+
+    #[test]
+    fn batch_rejects_nullifiers_repeated_across_items_and_inserts_once() {
+        let mut fixture = Fixture::new();
+        let root = fixture.pool.current_root;
+        let transfer = |n: u8| TransferPublicInputs {
+            root,
+            nullifiers: [element(n), element(n + 1)],
+            commitments: [element(n + 100), element(n + 101)],
+        };
+        let delegated = DelegatedTransferPublicInputs {
+            root,
+            nullifier: element(2),
+            commitments: [element(50), element(51), element(52)],
+        };
+
+        // Item 1 reuses item 0's second nullifier; nothing is written
+        let (first, second) = (transfer(1), transfer(3));
+        let (result, failed) = fixture.batch(&[&first, &delegated, &second], 100);
+        assert_eq!(result.unwrap_err(), PoolError::NullifierAlreadyUsed.into());
+        assert_eq!(failed, vec![1]);
+        assert_eq!(fixture.pool.current_root, root);
+        assert_eq!(fixture.pool.operation_count, 0);
+
+        // A valid batch appends every output in order under a single new root
+        let delegated = DelegatedTransferPublicInputs { nullifier: element(9), ..delegated };
+        let (result, failed) = fixture.batch(&[&first, &delegated, &second], 100);
+        assert_eq!(result.unwrap(), 0);
+        assert!(failed.is_empty());
+        let mut reference = ReferenceTree::new(DEPTH).unwrap();
+        let mut expected = root;
+        for leaf in [101, 102, 50, 51, 52, 103, 104] {
+            expected = reference.insert(element(leaf)).unwrap();
+        }
+        assert_eq!(fixture.pool.current_root, expected);
+        assert_eq!(fixture.pool.operation_count, 3);
+
+        {
+            let history = RefCell::new(&mut fixture.root_history[..]);
+            let history = RootHistoryMut::from_data(history.borrow_mut()).unwrap();
+            assert_eq!(history.len(), 2);
+        }
+
+        // Every nullifier in the batch is now spent
+        let (result, failed) = fixture.batch(&[&transfer(20), &transfer(4)], 200);
+        assert_eq!(result.unwrap_err(), PoolError::NullifierAlreadyUsed.into());
+        assert_eq!(failed, vec![1]);
+    }
+
+    #[test]
+    fn roots_stay_spendable_until_the_history_wraps() {
+        let mut fixture = Fixture::new();
+        let empty_root = fixture.pool.current_root;
+        let transfer = |fixture: &mut Fixture, n: u8, root: [u8; 32], slot: u64| {
+            let inputs = TransferPublicInputs {
+                root,
+                nullifiers: [element(n), element(n + 128)],
+                commitments: [element(n), element(n)],
+            };
+            fixture.transfer(&inputs, slot)
+        };
+
+        // Every transfer adds a root; the empty root survives capacity - 1 of them
+        for n in 1..MIN_ROOT_HISTORY as u8 {
+            transfer(&mut fixture, n, empty_root, 100 * n as u64).unwrap();
+        }
+        let latest = fixture.pool.current_root;
+        transfer(&mut fixture, 100, latest, 10_000).unwrap();
+        let err = transfer(&mut fixture, 101, empty_root, 20_000).unwrap_err();
+        assert_eq!(err, PoolError::InvalidRoot.into());
+
+        let history = RefCell::new(&mut fixture.root_history[..]);
+        let history = RootHistoryMut::from_data(history.borrow_mut()).unwrap();
+        assert_eq!(history.len(), MIN_ROOT_HISTORY as usize);
+        assert_eq!(history.root_slot(&latest), Some(100 * (MIN_ROOT_HISTORY as u64 - 1)));
+    }
+
+    const DELEGATED_VK: &[u8] = include_bytes!("../../../../circuits/transfer_from/verifying_key.bin");
+    const TRANSFER_VK: &[u8] = include_bytes!("../../../../circuits/transfer/verifying_key.bin");
+    const DELEGATED_PROOF: &str = include_str!("../../../../circuits/transfer_from/proof.json");
+    const DELEGATED_PUBLIC: &str = include_str!("../../../../circuits/transfer_from/public.json");
+    const DELEGATED_INPUT: &str = include_str!("../../../../circuits/transfer_from/input.json");
+
+    /// A snarkjs decimal string as a 32-byte big-endian field element
+    fn decimal(value: &serde_json::Value) -> [u8; 32] {
+        let mut bytes = [0u8; 32];
+        for digit in value.as_str().unwrap().bytes() {
+            let mut carry = (digit - b'0') as u16;
+            for byte in bytes.iter_mut().rev() {
+                let v = *byte as u16 * 10 + carry;
+                *byte = v as u8;
+                carry = v >> 8;
+            }
+        }
+        bytes
+    }
+
+    #[test]
+    fn delegated_fixture_proof_spends_through_the_pool() {
+        use ptf_common::poseidon::hashv;
+        use ptf_verifier_groth16::verification::{verify_groth16_proof, VerifyingKey};
+
+        let proof: serde_json::Value = serde_json::from_str(DELEGATED_PROOF).unwrap();
+        let input: serde_json::Value = serde_json::from_str(DELEGATED_INPUT).unwrap();
+        let public: serde_json::Value = serde_json::from_str(DELEGATED_PUBLIC).unwrap();
+        let public_inputs: Vec<u8> = public.as_array().unwrap().iter().flat_map(decimal).collect();
+
+        // The fixture tree holds an unrelated leaf, then the delegation note; the fixture is
+        // signed by the spender, so the counterparty key is the owner's
+        let spender_key = hashv(&[&decimal(&input["signer_private_key"])]).unwrap();
+        let tag = hashv(&[&decimal(&input["counterparty_key"]), &spender_key]).unwrap();
+        let note = hashv(&[&decimal(&input["secret"]), &decimal(&input["cap"]), &tag]).unwrap();
+        let mut fixture = Fixture::new();
+        let setup = TransferPublicInputs {
+            root: fixture.pool.current_root,
+            nullifiers: [element(200), element(201)],
+            commitments: [element(42), note],
+        };
+        fixture.transfer(&setup, 100).unwrap();
+
+        let inputs = DelegatedTransferPublicInputs::parse(&public_inputs).unwrap();
+        assert_eq!(inputs.root, fixture.pool.current_root);
+
+        let mut a = [0u8; 64];
+        let mut b = [0u8; 128];
+        let mut c = [0u8; 64];
+        a[..32].copy_from_slice(&decimal(&proof["pi_a"][0]));
+        a[32..].copy_from_slice(&decimal(&proof["pi_a"][1]));
+        for (i, coordinate) in [(0, 1), (0, 0), (1, 1), (1, 0)].into_iter().enumerate() {
+            b[32 * i..32 * (i + 1)].copy_from_slice(&decimal(&proof["pi_b"][coordinate.0][coordinate.1]));
+        }
+        c[..32].copy_from_slice(&decimal(&proof["pi_c"][0]));
+        c[32..].copy_from_slice(&decimal(&proof["pi_c"][1]));
+        let delegated_key = VerifyingKey::parse(DELEGATED_VK).unwrap();
+        assert!(verify_groth16_proof(&a, &b, &c, &delegated_key, &public_inputs).unwrap());
+        // Both circuits have five public inputs, but only the delegated key accepts the proof
+        let transfer_key = VerifyingKey::parse(TRANSFER_VK).unwrap();
+        assert!(!verify_groth16_proof(&a, &b, &c, &transfer_key, &public_inputs).unwrap());
+
+        assert_eq!(fixture.transfer(&inputs, 200).unwrap(), 2);
+        assert_eq!(fixture.transfer(&inputs, 300).unwrap_err(), PoolError::NullifierAlreadyUsed.into());
+    }
+}
@@ -0,0 +1,72 @@
+// Single-transaction unshield: verify, nullify and withdraw atomically, without a proof vault.
+// Proofs that don't fit in one transaction use prepare_unshield and the execute_unshield_* steps.
+
+use anchor_lang::prelude::*;
+use crate::{UnshieldArgs, Unshield as UnshieldContext};
+use crate::events::{Nullified, Unshielded};
+use crate::nullifiers::NullifierRecords;
+use crate::raw::{self, RootHistoryMut};
+use crate::state::Circuit;
+use crate::instructions::unshield_core::{
+    execute_unshield_nullify, execute_unshield_withdraw_core, UnshieldRequest, UnshieldWithdrawAccounts,
+};
+use ptf_common::InputSanitizer;
+
+pub fn unshield(ctx: Context<UnshieldContext>, args: UnshieldArgs) -> Result<()> {
+    InputSanitizer::sanitize_proof(&args.proof, ptf_common::MAX_PROOF_SIZE)?;
+    let request = UnshieldRequest::new(
+        ctx.accounts.pool_state.key(),
+        args.root,
+        args.nullifier,
+        args.amount,
+        args.recipient,
+        args.fee,
+    )?;
+    
+    // The verifying key must be the pool's current unshield key or a pinned previous version
+    let slot = Clock::get()?.slot;
+    raw::check_verifying_key(&ctx.accounts.verifying_key, &ctx.accounts.pool_state, Circuit::Unshield, slot)?;
+    let cpi_accounts = ptf_verifier_groth16::cpi::accounts::VerifyGroth16 {
+        verifying_key: ctx.accounts.verifying_key.to_account_info(),
+    };
+    let cpi_ctx = CpiContext::new(ctx.accounts.verifier_program.to_account_info(), cpi_accounts);
+    ptf_verifier_groth16::cpi::verify_groth16(cpi_ctx, args.proof, request.inputs.to_bytes())?;
+    
+    // Check the root and spend the nullifier by creating its record PDA
+    let account_infos = ctx.accounts.to_account_infos();
+    let pool_key = ctx.accounts.pool_state.key();
+    let root_history = RootHistoryMut::load(ctx.program_id, &account_infos, &ctx.accounts.pool_state)?;
+    let mut nullifiers = NullifierRecords::load(
+        ctx.program_id,
+        &account_infos,
+        &pool_key,
+        &ctx.accounts.pool_state,
+        slot,
+    )?;
+    execute_unshield_nullify(&root_history, &mut nullifiers, &request)?;
+    drop(nullifiers);
+    drop(root_history);
+    
+    let accounts = UnshieldWithdrawAccounts {
+        pool: &ctx.accounts.pool_state,
+        pool_info: ctx.accounts.pool_state.to_account_info(),
+        vault_state: ctx.accounts.vault_state.to_account_info(),
+        vault_token_account: ctx.accounts.vault_token_account.to_account_info(),
+        recipient_token_account: &ctx.accounts.recipient_token_account,
+        fee_token_account: ctx.accounts.fee_token_account.as_ref().map(|account| account.to_account_info()),
+        vault_program: ctx.accounts.vault_program.to_account_info(),
+        token_program: ctx.accounts.token_program.to_account_info(),
+    };
+    execute_unshield_withdraw_core(&accounts, &request)?;
+    
+    emit_cpi!(Nullified { pool: pool_key, nullifier: request.inputs.nullifier });
+    emit_cpi!(Unshielded {
+        pool: pool_key,
+        nullifier: request.inputs.nullifier,
+        amount: request.inputs.amount,
+        recipient: request.recipient,
+    });
+    msg!("Unshielded {} to {}", request.recipient_amount(), request.recipient);
+    
+    Ok(())
+}
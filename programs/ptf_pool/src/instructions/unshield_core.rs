@@ -0,0 +1,228 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::TokenAccount;
+use solana_sha256_hasher::hash;
+use crate::errors::PoolError;
+use crate::nullifiers::NullifierStore;
+use crate::raw::RootHistoryMut;
+use crate::state::PoolState;
+
+/// Unshield proof public inputs, each a 32-byte big-endian field element:
+/// [root][nullifier][amount][recipient hash][fee]
+#[derive(Clone, Debug, PartialEq, Eq, AnchorSerialize, AnchorDeserialize)]
+pub struct UnshieldPublicInputs {
+    pub root: [u8; 32],
+    pub nullifier: [u8; 32],
+    pub amount: u64,
+    pub recipient_hash: [u8; 32],
+    pub fee: u64,
+}
+
+impl UnshieldPublicInputs {
+    pub const LEN: usize = 32 * 5;
+    
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(Self::LEN);
+        bytes.extend_from_slice(&self.root);
+        bytes.extend_from_slice(&self.nullifier);
+        bytes.extend_from_slice(&u64_element(self.amount));
+        bytes.extend_from_slice(&self.recipient_hash);
+        bytes.extend_from_slice(&u64_element(self.fee));
+        bytes
+    }
+}
+
+/// `value` as a 32-byte big-endian field element
+pub(crate) fn u64_element(value: u64) -> [u8; 32] {
+    let mut element = [0u8; 32];
+    element[24..].copy_from_slice(&value.to_be_bytes());
+    element
+}
+
+/// Field element a proof commits to for the recipient: sha256 of the owner key, top byte
+/// cleared so it is below the BN254 modulus
+pub fn recipient_hash(recipient: &Pubkey) -> [u8; 32] {
+    let mut digest = hash(recipient.as_ref()).to_bytes();
+    digest[0] = 0;
+    digest
+}
+
+/// What prepare_unshield fixes for an operation, kept in its `OperationPayload::Unshield`
+#[derive(Clone, Debug, PartialEq, Eq, AnchorSerialize, AnchorDeserialize)]
+pub struct UnshieldRequest {
+    /// Pool the nullifier is spent in and the funds are withdrawn from
+    pub pool: Pubkey,
+    pub inputs: UnshieldPublicInputs,
+    /// Owner of the token account the funds go to; hashes to `inputs.recipient_hash`
+    pub recipient: Pubkey,
+}
+
+impl UnshieldRequest {
+    pub const LEN: usize = 32 + 32 + 32 + 8 + 32 + 8 + 32;
+    
+    pub fn new(
+        pool: Pubkey,
+        root: [u8; 32],
+        nullifier: [u8; 32],
+        amount: u64,
+        recipient: Pubkey,
+        fee: u64,
+    ) -> Result<Self> {
+        require!(nullifier != [0u8; 32], PoolError::InvalidNullifier);
+        require!(fee <= amount, PoolError::InvalidAmount);
+        Ok(Self {
+            pool,
+            inputs: UnshieldPublicInputs {
+                root,
+                nullifier,
+                amount,
+                recipient_hash: recipient_hash(&recipient),
+                fee,
+            },
+            recipient,
+        })
+    }
+    
+    /// Fail unless the request was prepared for `pool`
+    pub fn check_pool(&self, pool: &Pubkey) -> Result<()> {
+        require_keys_eq!(self.pool, *pool, PoolError::PoolMismatch);
+        Ok(())
+    }
+    
+    /// Amount the recipient receives once the fee is taken out
+    pub fn recipient_amount(&self) -> u64 {
+        self.inputs.amount - self.inputs.fee
+    }
+}
+
+/// Nullify step shared by `execute_unshield_update` and `unshield`: the proof's root must still
+/// be in the root history and its nullifier unspent
+pub fn execute_unshield_nullify(
+    root_history: &RootHistoryMut,
+    nullifiers: &mut impl NullifierStore,
+    request: &UnshieldRequest,
+) -> Result<()> {
+    require!(root_history.is_known_root(&request.inputs.root), PoolError::InvalidRoot);
+    let nullifier = &request.inputs.nullifier;
+    require!(!nullifiers.is_spent(nullifier)?, PoolError::NullifierAlreadyUsed);
+    nullifiers.spend(nullifier)
+}
+
+/// Accounts the withdraw step pays out through
+pub struct UnshieldWithdrawAccounts<'a, 'info> {
+    pub pool: &'a PoolState,
+    pub pool_info: AccountInfo<'info>,
+    pub vault_state: AccountInfo<'info>,
+    pub vault_token_account: AccountInfo<'info>,
+    pub recipient_token_account: &'a Account<'info, TokenAccount>,
+    pub fee_token_account: Option<AccountInfo<'info>>,
+    pub vault_program: AccountInfo<'info>,
+    pub token_program: AccountInfo<'info>,
+}
+
+impl<'info> UnshieldWithdrawAccounts<'_, 'info> {
+    /// Withdraw `amount` from the pool vault to `destination`, signed by the pool PDA
+    fn withdraw(&self, destination: AccountInfo<'info>, amount: u64) -> Result<()> {
+        let seeds = &[
+            b"pool",
+            self.pool.origin_mint.as_ref(),
+            &[self.pool.bump],
+        ];
+        let signer = &[&seeds[..]];
+        let cpi_accounts = ptf_vault::cpi::accounts::Withdraw {
+            vault: self.vault_state.clone(),
+            authority: self.pool_info.clone(),
+            vault_token_account: self.vault_token_account.clone(),
+            user_token_account: destination,
+            token_program: self.token_program.clone(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(self.vault_program.clone(), cpi_accounts, signer);
+        ptf_vault::cpi::withdraw(cpi_ctx, amount)
+    }
+}
+
+/// Withdraw step shared by `execute_unshield_withdraw` and `unshield`: pay the recipient the
+/// proof committed to, and the fee account if there is a fee
+pub fn execute_unshield_withdraw_core(accounts: &UnshieldWithdrawAccounts, request: &UnshieldRequest) -> Result<()> {
+    require_keys_eq!(
+        accounts.recipient_token_account.owner,
+        request.recipient,
+        PoolError::RecipientMismatch
+    );
+    accounts.withdraw(accounts.recipient_token_account.to_account_info(), request.recipient_amount())?;
+    if request.inputs.fee > 0 {
+        let fee_token_account = accounts.fee_token_account.clone().ok_or(PoolError::AccountNotFound)?;
+        accounts.withdraw(fee_token_account, request.inputs.fee)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::raw::HashListMut;
+    use crate::state::{NullifierSet, RootEntry, RootHistory};
+    use std::cell::RefCell;
+
+    fn serialize<T: AccountSerialize>(account: &T, len: usize) -> Vec<u8> {
+        let mut data = vec![0u8; len];
+        account.try_serialize(&mut &mut data[..]).unwrap();
+        data
+    }
+
+    #[test]
+    fn request_layout_and_public_inputs() {
+        let pool = Pubkey::new_unique();
+        let recipient = Pubkey::new_unique();
+        let request = UnshieldRequest::new(pool, [1u8; 32], [2u8; 32], 0x0102, recipient, 3).unwrap();
+        assert_eq!(request.try_to_vec().unwrap().len(), UnshieldRequest::LEN);
+        assert_eq!(request.recipient_amount(), 0x0102 - 3);
+        request.check_pool(&pool).unwrap();
+        let err = request.check_pool(&Pubkey::new_unique()).unwrap_err();
+        assert_eq!(err, PoolError::PoolMismatch.into());
+
+        let inputs = request.inputs.to_bytes();
+        assert_eq!(inputs.len(), UnshieldPublicInputs::LEN);
+        assert_eq!(&inputs[32..64], &[2u8; 32]);
+        assert_eq!(&inputs[94..96], &[1, 2]);
+        assert_eq!(&inputs[96..128], &recipient_hash(&recipient));
+        assert_eq!(inputs[96], 0);
+        assert_eq!(inputs[159], 3);
+
+        assert!(UnshieldRequest::new(pool, [1u8; 32], [2u8; 32], 5, recipient, 6).is_err());
+        assert!(UnshieldRequest::new(pool, [1u8; 32], [0u8; 32], 5, recipient, 0).is_err());
+    }
+
+    #[test]
+    fn nullify_checks_root_then_spends_once() {
+        let root = [9u8; 32];
+        let history = RootHistory {
+            pool: Pubkey::default(),
+            capacity: 2,
+            head: 1,
+            len: 1,
+            bump: 255,
+            entries: vec![RootEntry { root, slot: 1 }, RootEntry::default()],
+        };
+        let mut history = serialize(&history, RootHistory::space(2));
+        let set = NullifierSet { pool: Pubkey::default(), nullifiers: vec![], bump: 255 };
+        let mut set = serialize(&set, NullifierSet::MIN_LEN + 32 * 4);
+
+        let history = RefCell::new(&mut history[..]);
+        let set = RefCell::new(&mut set[..]);
+        let history = RootHistoryMut::from_data(history.borrow_mut()).unwrap();
+        let mut nullifiers = HashListMut::from_data(set.borrow_mut()).unwrap();
+
+        let pool = Pubkey::default();
+        let recipient = Pubkey::new_unique();
+        let stale = UnshieldRequest::new(pool, [8u8; 32], [2u8; 32], 10, recipient, 0).unwrap();
+        let err = execute_unshield_nullify(&history, &mut nullifiers, &stale).unwrap_err();
+        assert_eq!(err, PoolError::InvalidRoot.into());
+        assert!(nullifiers.is_empty());
+
+        let request = UnshieldRequest::new(pool, root, [2u8; 32], 10, recipient, 0).unwrap();
+        execute_unshield_nullify(&history, &mut nullifiers, &request).unwrap();
+        assert!(nullifiers.contains(&[2u8; 32]));
+        let err = execute_unshield_nullify(&history, &mut nullifiers, &request).unwrap_err();
+        assert_eq!(err, PoolError::NullifierAlreadyUsed.into());
+    }
+}
@@ -2,8 +2,9 @@
 
 use anchor_lang::prelude::*;
 use crate::{UpdateOperationData as UpdateOperationDataContext};
-use crate::state::UserProofVault;
+use crate::state::{OperationPayload, ProofSubmission};
 use crate::errors::PoolError;
+use crate::proof_vault;
 use ptf_common::OperationStatus;
 
 pub fn update_operation_data(
@@ -11,10 +12,10 @@ pub fn update_operation_data(
     operation_id: [u8; 32],
     operation_data: Vec<u8>,
 ) -> Result<()> {
-    // Load vault
-    let vault_data = ctx.accounts.proof_vault.try_borrow_data()?;
-    let mut vault: UserProofVault = UserProofVault::try_deserialize(&mut &vault_data[8..])?;
-    drop(vault_data);
+    // Only the vault owner can attach proofs to its operations
+    let proof_vault_info = ctx.accounts.proof_vault.to_account_info();
+    let payer = ctx.accounts.payer.to_account_info();
+    let mut vault = proof_vault::load_owned(ctx.program_id, &proof_vault_info, payer.key)?;
     
     // Find operation
     let operation = vault.prepared_operations.iter_mut()
@@ -26,23 +27,30 @@ pub fn update_operation_data(
         operation.status() == OperationStatus::Pending,
         PoolError::InvalidOperationStatus
     );
+    require!(!operation.is_expired(Clock::get()?.slot), PoolError::OperationExpired);
     
-    // Update operation data (proof + attestation + public_inputs)
-    operation.data = operation_data;
+    // Attach the proof + attestations; the prepared public inputs are left as they are.
+    // A pending operation's proof may be replaced, e.g. to add attestations.
+    let submission = ProofSubmission::parse(&operation_data)?;
+    match operation.payload_mut()? {
+        OperationPayload::Unshield { proof, .. } | OperationPayload::Transfer { proof, .. } => {
+            *proof = Some(submission);
+        }
+        OperationPayload::Shield { .. } => return err!(PoolError::InvalidOperationStatus),
+    }
     
-    // Serialize back
-    let mut vault_data = ctx.accounts.proof_vault.try_borrow_mut_data()?;
-    vault.serialize(&mut &mut vault_data[8..])?;
-    
-    Ok(())
+    // Grow the vault to fit the proof
+    let system_program = ctx.accounts.system_program.to_account_info();
+    proof_vault::save_resized(&proof_vault_info, &vault, &payer, &system_program)
 }
 
 #[derive(Accounts)]
 pub struct UpdateOperationData<'info> {
     #[account(mut)]
     pub payer: Signer<'info>,
-    /// CHECK: Proof vault PDA
+    /// CHECK: Proof vault PDA of the payer, checked in the instruction
     #[account(mut)]
     pub proof_vault: UncheckedAccount<'info>,
+    pub system_program: Program<'info, System>,
 }
 
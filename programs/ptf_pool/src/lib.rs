@@ -1,10 +1,14 @@
 use anchor_lang::prelude::*;
 use anchor_lang::solana_program::account_info::AccountInfo;
-use anchor_lang::solana_program::entrypoint::ProgramResult;
 use anchor_lang::solana_program::pubkey::Pubkey;
 
 pub mod errors;
+pub mod events;
 pub mod instructions;
+pub mod merkle;
+pub mod nullifiers;
+pub mod proof_vault;
+pub mod raw;
 pub mod state;
 pub mod entrypoint;
 
@@ -14,25 +18,45 @@ pub use state::*;
 
 declare_id!("6MLrNAydScBBWq6vFXPLjahvxjF1PzauuSYTuLS7yfYC");
 
-// Custom entrypoint will be added after first build
-// For now, we use Anchor's standard entrypoint
+// Program entrypoint is `entrypoint::process_instruction` (see the custom-entrypoint feature)
 
 #[program]
 pub mod ptf_pool {
     use super::*;
-
-    pub fn prepare_shield(ctx: Context<PrepareShield>, args: PrepareShieldArgs) -> Result<()> {
+    
+    /// Create the pool state, commitment tree, root history and nullifier set PDAs
+    /// Only callable by ptf_factory (signed by the factory PDA)
+    /// The finalized verifying key of each circuit is passed as a remaining account
+    pub fn initialize_pool<'info>(
+        ctx: Context<'_, '_, '_, 'info, InitializePool<'info>>,
+        origin_mint: Pubkey,
+        canopy_depth: u8,
+        root_history_capacity: u16,
+    ) -> Result<()> {
+        instructions::initialize_pool(ctx, origin_mint, canopy_depth, root_history_capacity)
+    }
+    
+    /// Record a pending shield in the payer's proof vault, returning its operation id
+    pub fn prepare_shield(ctx: Context<PrepareShield>, args: PrepareShieldArgs) -> Result<[u8; 32]> {
         instructions::prepare_shield(ctx, args)
     }
-
-    pub fn execute_shield_v2(ctx: Context<ExecuteShieldV2Raw>, operation_id: [u8; 32]) -> Result<()> {
-        instructions::execute_shield_v2(ctx, operation_id)
+    
+    /// Deposit the prepared amount into the vault and insert the commitment into the tree.
+    /// `encrypted_note` is published in the `Shielded` event for the note's owner to find.
+    pub fn execute_shield_v2(
+        ctx: Context<ExecuteShieldV2>,
+        operation_id: [u8; 32],
+        proof: Vec<u8>,
+        encrypted_note: Vec<u8>,
+    ) -> Result<()> {
+        instructions::execute_shield_v2(ctx, operation_id, proof, encrypted_note)
     }
-
+    
+    /// Record a pending unshield in the payer's proof vault, returning its operation id
     pub fn prepare_unshield(ctx: Context<PrepareUnshield>, args: PrepareUnshieldArgs) -> Result<[u8; 32]> {
         instructions::prepare_unshield(ctx, args)
     }
-
+    
     pub fn update_operation_data(
         ctx: Context<UpdateOperationData>,
         operation_id: [u8; 32],
@@ -40,38 +64,103 @@ pub mod ptf_pool {
     ) -> Result<()> {
         instructions::update_operation_data(ctx, operation_id, operation_data)
     }
-
-    pub fn execute_unshield_verify(ctx: Context<ExecuteUnshieldVerify>, operation_id: [u8; 32]) -> Result<()> {
+    
+    /// Drop a prepared operation that hasn't spent its nullifier, refunding its rent
+    pub fn cancel_operation(ctx: Context<CancelOperation>, operation_id: [u8; 32]) -> Result<()> {
+        instructions::cancel_operation(ctx, operation_id)
+    }
+    
+    /// Mark a proof vault's expired operations Failed (permissionless)
+    pub fn expire_operations(ctx: Context<ExpireOperations>) -> Result<()> {
+        instructions::expire_operations(ctx)
+    }
+    
+    /// Close the signer's proof vault and return its rent
+    pub fn close_proof_vault(ctx: Context<CloseProofVault>) -> Result<()> {
+        instructions::close_proof_vault(ctx)
+    }
+    
+    pub fn execute_unshield_verify<'info>(
+        ctx: Context<'_, '_, '_, 'info, ExecuteUnshieldVerify<'info>>,
+        operation_id: [u8; 32],
+    ) -> Result<()> {
         instructions::execute_unshield_verify(ctx, operation_id)
     }
-
+    
     pub fn execute_unshield_update(ctx: Context<ExecuteUnshieldUpdate>, operation_id: [u8; 32]) -> Result<()> {
         instructions::execute_unshield_update(ctx, operation_id)
     }
-
+    
     pub fn execute_unshield_withdraw(ctx: Context<ExecuteUnshieldWithdraw>, operation_id: [u8; 32]) -> Result<()> {
         instructions::execute_unshield_withdraw(ctx, operation_id)
     }
-
-    pub fn execute_transfer(ctx: Context<ExecuteTransfer>, args: TransferArgs) -> Result<()> {
+    
+    /// Verify, nullify and withdraw in one transaction, without a proof vault
+    pub fn unshield(ctx: Context<Unshield>, args: UnshieldArgs) -> Result<()> {
+        instructions::unshield(ctx, args)
+    }
+    
+    pub fn execute_transfer<'info>(
+        ctx: Context<'_, '_, '_, 'info, ExecuteTransfer<'info>>,
+        args: TransferArgs,
+    ) -> Result<()> {
         instructions::execute_transfer(ctx, args)
     }
-
-    pub fn execute_transfer_from(ctx: Context<ExecuteTransferFrom>, args: TransferFromArgs) -> Result<()> {
+    
+    /// Transfer the owner's notes as their spender, against a public allowance or a delegation note
+    pub fn execute_transfer_from<'info>(
+        ctx: Context<'_, '_, '_, 'info, ExecuteTransferFrom<'info>>,
+        args: TransferFromArgs,
+    ) -> Result<()> {
         instructions::execute_transfer_from(ctx, args)
     }
-
+    
+    /// Create or overwrite the signer's allowance for a spender in this pool
     pub fn approve_allowance(ctx: Context<ApproveAllowance>, args: ApproveAllowanceArgs) -> Result<()> {
         instructions::approve_allowance(ctx, args)
     }
-
-    pub fn execute_batch_transfer(ctx: Context<ExecuteBatchTransfer>, args: BatchTransferArgs) -> Result<()> {
+    
+    pub fn increase_allowance(ctx: Context<ModifyAllowance>, amount: u64) -> Result<()> {
+        instructions::increase_allowance(ctx, amount)
+    }
+    
+    pub fn decrease_allowance(ctx: Context<ModifyAllowance>, amount: u64) -> Result<()> {
+        instructions::decrease_allowance(ctx, amount)
+    }
+    
+    /// Close an allowance, returning its rent to the owner
+    pub fn revoke_allowance(ctx: Context<RevokeAllowance>) -> Result<()> {
+        instructions::revoke_allowance(ctx)
+    }
+    
+    pub fn execute_batch_transfer<'info>(
+        ctx: Context<'_, '_, '_, 'info, ExecuteBatchTransfer<'info>>,
+        args: BatchTransferArgs,
+    ) -> Result<()> {
         instructions::execute_batch_transfer(ctx, args)
     }
-
-    pub fn execute_batch_transfer_from(ctx: Context<ExecuteBatchTransferFrom>, args: BatchTransferFromArgs) -> Result<()> {
+    
+    pub fn execute_batch_transfer_from<'info>(
+        ctx: Context<'_, '_, '_, 'info, ExecuteBatchTransferFrom<'info>>,
+        args: BatchTransferFromArgs,
+    ) -> Result<()> {
         instructions::execute_batch_transfer_from(ctx, args)
     }
+    
+    /// Rotate to a new verifying key version, accepting the old key for grace_slots
+    /// Only callable by ptf_factory (signed by the factory PDA)
+    pub fn rotate_verifying_key(ctx: Context<RotateVerifyingKey>, grace_slots: u64) -> Result<()> {
+        instructions::rotate_verifying_key(ctx, grace_slots)
+    }
+    
+    /// Move up to max_count nullifiers from the legacy list into record PDAs
+    /// Record PDAs for the last max_count list entries are passed as remaining accounts
+    pub fn migrate_nullifiers<'info>(
+        ctx: Context<'_, '_, '_, 'info, MigrateNullifiers<'info>>,
+        max_count: u16,
+    ) -> Result<()> {
+        instructions::migrate_nullifiers(ctx, max_count)
+    }
 }
 
 // Placeholder structs for instruction arguments
@@ -86,24 +175,61 @@ pub struct PrepareUnshieldArgs {
     pub nullifier: [u8; 32],
     pub amount: u64,
     pub recipient: Pubkey,
+    /// Tree root the proof is made against; must still be in the root history at update
+    pub root: [u8; 32],
+    /// Part of `amount` paid to the fee token account instead of the recipient
+    pub fee: u64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub struct UnshieldArgs {
+    pub proof: Vec<u8>,
+    pub nullifier: [u8; 32],
+    pub amount: u64,
+    pub recipient: Pubkey,
+    pub root: [u8; 32],
+    pub fee: u64,
 }
 
 #[derive(AnchorSerialize, AnchorDeserialize)]
 pub struct TransferArgs {
     pub proof: Vec<u8>,
     pub public_inputs: Vec<u8>,
+    /// One ciphertext per output commitment, in order, published in `Transferred`
+    pub encrypted_notes: Vec<Vec<u8>>,
 }
 
 #[derive(AnchorSerialize, AnchorDeserialize)]
 pub struct TransferFromArgs {
     pub proof: Vec<u8>,
     pub public_inputs: Vec<u8>,
+    pub mode: TransferFromMode,
+    /// One ciphertext per output commitment, change included, published in `Transferred`
+    pub encrypted_notes: Vec<Vec<u8>>,
+}
+
+/// How a transfer_from is authorized
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub enum TransferFromMode {
+    /// Transparent: spend notes tagged with the allowance owner (`allowance_tag`) and debit the
+    /// public `Allowance` PDA by `amount`, the first output. The proof commits to the owner's tag
+    /// and `amount` as trailing public inputs. The spender signs.
+    Allowance { amount: u64 },
+    /// The owner spending their own tagged notes without an allowance, e.g. into ordinary notes
+    /// once every allowance is revoked or expired. Proven like `Allowance` against `owner`'s tag,
+    /// with `amount` the first output; the owner signs.
+    Reclaim { owner: Pubkey, amount: u64 },
+    /// Private: the proof spends a delegation note and appends its change, so no owner,
+    /// spender or amount is revealed. Anyone may submit it.
+    Delegated,
 }
 
 #[derive(AnchorSerialize, AnchorDeserialize)]
 pub struct ApproveAllowanceArgs {
     pub spender: Pubkey,
     pub amount: u64,
+    /// First slot at which the allowance can no longer be spent; None never expires
+    pub expiry_slot: Option<u64>,
 }
 
 #[derive(AnchorSerialize, AnchorDeserialize)]
@@ -119,101 +245,316 @@ pub struct BatchTransferFromArgs {
 // Re-export for use in instructions
 pub use instructions::approve_allowance::Allowance;
 
+#[derive(Accounts)]
+#[instruction(origin_mint: Pubkey, canopy_depth: u8, root_history_capacity: u16)]
+pub struct InitializePool<'info> {
+    #[account(
+        init,
+        payer = payer,
+        space = PoolState::LEN,
+        seeds = [b"pool", origin_mint.as_ref()],
+        bump
+    )]
+    pub pool_state: Box<Account<'info, PoolState>>,
+    #[account(
+        init,
+        payer = payer,
+        space = CommitmentTree::LEN,
+        seeds = [b"commitment-tree", origin_mint.as_ref()],
+        bump
+    )]
+    pub commitment_tree: Box<Account<'info, CommitmentTree>>,
+    #[account(
+        init,
+        payer = payer,
+        space = RootHistory::space(root_history_capacity),
+        seeds = [b"root-history", origin_mint.as_ref()],
+        bump
+    )]
+    pub root_history: Box<Account<'info, RootHistory>>,
+    #[account(
+        init,
+        payer = payer,
+        space = NullifierSet::MIN_LEN,
+        seeds = [b"nullifier-set", origin_mint.as_ref()],
+        bump
+    )]
+    pub nullifier_set: Box<Account<'info, NullifierSet>>,
+    /// CHECK: Vault state PDA for origin_mint (created by ptf_vault)
+    #[account(
+        seeds = [b"vault", origin_mint.as_ref()],
+        bump,
+        seeds::program = ptf_vault::ID
+    )]
+    pub vault_state: UncheckedAccount<'info>,
+    /// Factory PDA - proves the call comes from ptf_factory
+    #[account(
+        seeds = [b"factory"],
+        bump,
+        seeds::program = ptf_common::FACTORY_PROGRAM_ID
+    )]
+    pub factory: Signer<'info>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
 // Placeholder Context structs
+#[event_cpi]
 #[derive(Accounts)]
 pub struct PrepareShield<'info> {
     #[account(mut)]
     pub payer: Signer<'info>,
-    /// CHECK: Proof vault PDA - manually validated in instruction
-    /// This account may not exist or may have wrong discriminator - we handle it manually
+    /// Pool the shield is for, recorded in the operation
+    pub pool_state: Box<Account<'info, PoolState>>,
+    /// CHECK: Proof vault PDA of the payer, created or resized by the instruction
     #[account(mut)]
     pub proof_vault: UncheckedAccount<'info>,
     pub system_program: Program<'info, System>,
 }
 
-// ExecuteShieldV2Raw is defined in execute_shield_v2.rs
+// ExecuteShieldV2 is defined in execute_shield_v2.rs
 
+#[event_cpi]
 #[derive(Accounts)]
 pub struct PrepareUnshield<'info> {
     #[account(mut)]
     pub payer: Signer<'info>,
-    /// CHECK: Proof vault PDA
+    /// Pool the unshield is for, recorded in the operation
+    pub pool_state: Box<Account<'info, PoolState>>,
+    /// CHECK: Proof vault PDA of the payer, created or resized by the instruction
+    #[account(mut)]
+    pub proof_vault: UncheckedAccount<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct CancelOperation<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    /// CHECK: Proof vault PDA of the owner, checked and resized in the instruction
     #[account(mut)]
     pub proof_vault: UncheckedAccount<'info>,
     pub system_program: Program<'info, System>,
 }
 
+#[event_cpi]
+#[derive(Accounts)]
+pub struct ExpireOperations<'info> {
+    /// CHECK: Any proof vault, owner and discriminator checked in the instruction
+    #[account(mut)]
+    pub proof_vault: UncheckedAccount<'info>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct CloseProofVault<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    /// CHECK: Proof vault PDA of the owner, checked in the instruction
+    #[account(mut)]
+    pub proof_vault: UncheckedAccount<'info>,
+}
+
+#[event_cpi]
 #[derive(Accounts)]
 pub struct ExecuteUnshieldVerify<'info> {
+    pub pool_state: Box<Account<'info, PoolState>>,
     /// CHECK: Proof vault PDA
     #[account(mut)]
     pub proof_vault: UncheckedAccount<'info>,
-    /// CHECK: Verifying key account
+    /// CHECK: Verifying key account (must be allowed by pool_state)
     pub verifying_key: UncheckedAccount<'info>,
-    /// CHECK: External verifier public key (must sign transaction)
-    pub external_verifier: Signer<'info>,
+    /// CHECK: External verifier public key (signature checked via Ed25519 instruction)
+    /// Co-signing verifiers follow as remaining accounts
+    pub external_verifier: UncheckedAccount<'info>,
+    /// CHECK: Verifier registry PDA (validated by verifier program)
+    pub verifier_registry: UncheckedAccount<'info>,
+    /// CHECK: Instructions sysvar (validated by verifier program)
+    pub instructions_sysvar: UncheckedAccount<'info>,
     pub verifier_program: Program<'info, ptf_verifier_groth16::program::PtfVerifierGroth16>,
 }
 
+#[event_cpi]
 #[derive(Accounts)]
 pub struct ExecuteUnshieldUpdate<'info> {
     #[account(mut)]
-    pub pool_state: Account<'info, PoolState>,
+    pub pool_state: Box<Account<'info, PoolState>>,
+    /// CHECK: Root history PDA, checked and read in place
+    pub root_history: UncheckedAccount<'info>,
+    /// CHECK: Legacy nullifier list, PDA-checked and read in place
+    pub nullifier_set: UncheckedAccount<'info>,
+    /// CHECK: Nullifier record PDA, created by this instruction
     #[account(mut)]
-    pub commitment_tree: Account<'info, CommitmentTree>,
-    #[account(mut)]
-    pub nullifier_set: Account<'info, NullifierSet>,
+    pub nullifier_record: UncheckedAccount<'info>,
     /// CHECK: Proof vault PDA
     #[account(mut)]
     pub proof_vault: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
 }
 
+#[event_cpi]
 #[derive(Accounts)]
 pub struct ExecuteUnshieldWithdraw<'info> {
     #[account(mut)]
-    pub pool_state: Account<'info, PoolState>,
-    /// CHECK: Vault state account
+    pub pool_state: Box<Account<'info, PoolState>>,
+    /// CHECK: Vault state recorded in pool_state (validated by ptf_vault)
+    #[account(address = pool_state.vault @ PoolError::AccountNotFound)]
     pub vault_state: UncheckedAccount<'info>,
-    /// CHECK: Proof vault PDA
+    /// CHECK: Proof vault PDA of `owner`, checked in the handler
     #[account(mut)]
     pub proof_vault: UncheckedAccount<'info>,
+    /// CHECK: Owner of the proof vault; receives the rent freed by the completed operation
+    #[account(mut)]
+    pub owner: UncheckedAccount<'info>,
     /// CHECK: Vault token account
     #[account(mut)]
     pub vault_token_account: UncheckedAccount<'info>,
-    /// CHECK: User token account
+    /// Recipient token account; its owner must be the recipient the proof committed to
+    #[account(mut)]
+    pub user_token_account: Box<Account<'info, anchor_spl::token::TokenAccount>>,
+    /// CHECK: Receives the fee, required when the unshield has one (validated by ptf_vault)
+    #[account(mut)]
+    pub fee_token_account: Option<UncheckedAccount<'info>>,
+    pub vault_program: Program<'info, ptf_vault::program::PtfVault>,
+    pub token_program: Program<'info, anchor_spl::token::Token>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Pool-wide accounts come first so clients can load them from an address lookup table;
+/// the per-unshield accounts and the signer follow
+#[event_cpi]
+#[derive(Accounts)]
+pub struct Unshield<'info> {
+    pub pool_state: Box<Account<'info, PoolState>>,
+    /// CHECK: Root history PDA, checked and read in place
+    pub root_history: UncheckedAccount<'info>,
+    /// CHECK: Legacy nullifier list, PDA-checked and read in place
+    pub nullifier_set: UncheckedAccount<'info>,
+    /// CHECK: Vault state recorded in pool_state (validated by ptf_vault)
+    #[account(address = pool_state.vault @ PoolError::AccountNotFound)]
+    pub vault_state: UncheckedAccount<'info>,
+    /// CHECK: Vault token account (validated by ptf_vault)
     #[account(mut)]
-    pub user_token_account: UncheckedAccount<'info>,
+    pub vault_token_account: UncheckedAccount<'info>,
+    /// CHECK: Verifying key account (must be allowed by pool_state)
+    pub verifying_key: UncheckedAccount<'info>,
+    pub verifier_program: Program<'info, ptf_verifier_groth16::program::PtfVerifierGroth16>,
     pub vault_program: Program<'info, ptf_vault::program::PtfVault>,
     pub token_program: Program<'info, anchor_spl::token::Token>,
+    pub system_program: Program<'info, System>,
+    /// CHECK: Nullifier record PDA, created by this instruction
+    #[account(mut)]
+    pub nullifier_record: UncheckedAccount<'info>,
+    /// Recipient token account; its owner must be the recipient the proof committed to
+    #[account(mut)]
+    pub recipient_token_account: Box<Account<'info, anchor_spl::token::TokenAccount>>,
+    /// CHECK: Receives the fee, required when the unshield has one (validated by ptf_vault)
+    #[account(mut)]
+    pub fee_token_account: Option<UncheckedAccount<'info>>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
 }
 
+#[event_cpi]
 #[derive(Accounts)]
 pub struct ExecuteTransfer<'info> {
     /// CHECK: Phantom account - all real accounts in remaining_accounts
     pub _phantom: UncheckedAccount<'info>,
 }
 
+#[event_cpi]
 #[derive(Accounts)]
 pub struct ExecuteTransferFrom<'info> {
     /// CHECK: Phantom account - all real accounts in remaining_accounts
     pub _phantom: UncheckedAccount<'info>,
 }
 
+#[event_cpi]
 #[derive(Accounts)]
+#[instruction(args: ApproveAllowanceArgs)]
 pub struct ApproveAllowance<'info> {
-    /// CHECK: Phantom account - all real accounts in remaining_accounts
-    pub _phantom: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub pool_state: Box<Account<'info, PoolState>>,
+    #[account(
+        init_if_needed,
+        payer = owner,
+        space = Allowance::LEN,
+        seeds = [b"allowance", owner.key().as_ref(), args.spender.as_ref(), pool_state.key().as_ref()],
+        bump
+    )]
+    pub allowance: Account<'info, Allowance>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Shared by increase_allowance and decrease_allowance; the seeds tie the allowance to the signer
+#[event_cpi]
+#[derive(Accounts)]
+pub struct ModifyAllowance<'info> {
+    pub owner: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"allowance", owner.key().as_ref(), allowance.spender.as_ref(), allowance.pool.as_ref()],
+        bump = allowance.bump
+    )]
+    pub allowance: Account<'info, Allowance>,
 }
 
+#[event_cpi]
+#[derive(Accounts)]
+pub struct RevokeAllowance<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    #[account(
+        mut,
+        close = owner,
+        seeds = [b"allowance", owner.key().as_ref(), allowance.spender.as_ref(), allowance.pool.as_ref()],
+        bump = allowance.bump
+    )]
+    pub allowance: Account<'info, Allowance>,
+}
+
+#[event_cpi]
 #[derive(Accounts)]
 pub struct ExecuteBatchTransfer<'info> {
     /// CHECK: Phantom account - all real accounts in remaining_accounts
     pub _phantom: UncheckedAccount<'info>,
 }
 
+#[event_cpi]
 #[derive(Accounts)]
 pub struct ExecuteBatchTransferFrom<'info> {
     /// CHECK: Phantom account - all real accounts in remaining_accounts
     pub _phantom: UncheckedAccount<'info>,
 }
 
+#[derive(Accounts)]
+pub struct RotateVerifyingKey<'info> {
+    #[account(mut)]
+    pub pool_state: Box<Account<'info, PoolState>>,
+    /// Factory PDA - proves the call comes from ptf_factory
+    #[account(
+        seeds = [b"factory"],
+        bump,
+        seeds::program = ptf_common::FACTORY_PROGRAM_ID
+    )]
+    pub factory: Signer<'info>,
+    /// Must be the pool's current key for the circuit new_verifying_key is for
+    pub current_verifying_key: Account<'info, ptf_verifier_groth16::VerifyingKeyAccount>,
+    pub new_verifying_key: Account<'info, ptf_verifier_groth16::VerifyingKeyAccount>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateNullifiers<'info> {
+    pub pool_state: Box<Account<'info, PoolState>>,
+    /// CHECK: Legacy nullifier list, PDA-checked and drained in place
+    #[account(mut)]
+    pub nullifier_set: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
@@ -0,0 +1,314 @@
+//! Incremental Merkle tree for pool commitments.
+//!
+//! Tornado-style append-only tree: `filled_subtrees[level]` holds the most recent left child
+//! at each level, so an insert only needs one hash per level and no stored leaves.
+//! `CommitmentTree.frontier` is the on-chain `filled_subtrees`.
+
+use anchor_lang::prelude::*;
+use ptf_common::poseidon::hash_pair;
+use crate::errors::PoolError;
+use crate::state::{CommitmentTree, DEPTH};
+
+/// Append one leaf at `next_index` and return the new root
+pub fn insert(
+    filled_subtrees: &mut [[u8; 32]],
+    zeroes: &[[u8; 32]],
+    next_index: u64,
+    leaf: [u8; 32],
+) -> Result<[u8; 32]> {
+    insert_many(filled_subtrees, zeroes, next_index, &[leaf])
+}
+
+/// Append `leaves` starting at `next_index` and return the new root.
+/// Hashes level by level, so a batch of k leaves costs about k + depth hashes instead of k * depth.
+pub fn insert_many(
+    filled_subtrees: &mut [[u8; 32]],
+    zeroes: &[[u8; 32]],
+    next_index: u64,
+    leaves: &[[u8; 32]],
+) -> Result<[u8; 32]> {
+    let depth = filled_subtrees.len();
+    require!(zeroes.len() == depth, PoolError::TreeUpdateFailed);
+    require!(!leaves.is_empty(), PoolError::TreeUpdateFailed);
+    
+    let capacity = 1u64 << depth;
+    let end = next_index
+        .checked_add(leaves.len() as u64)
+        .ok_or(PoolError::MerkleTreeFull)?;
+    require!(end <= capacity, PoolError::MerkleTreeFull);
+    
+    let mut nodes = leaves.to_vec();
+    let mut first_index = next_index;
+    
+    for level in 0..depth {
+        let mut parents = Vec::with_capacity(nodes.len() / 2 + 1);
+        let mut i = 0;
+        
+        // First node is a right child: its left sibling was filled by an earlier insert
+        if first_index & 1 == 1 {
+            parents.push(hash_pair(&filled_subtrees[level], &nodes[0])?);
+            i = 1;
+        }
+        
+        while i < nodes.len() {
+            let left = nodes[i];
+            filled_subtrees[level] = left;
+            let right = nodes.get(i + 1).copied().unwrap_or(zeroes[level]);
+            parents.push(hash_pair(&left, &right)?);
+            i += 2;
+        }
+        
+        nodes = parents;
+        first_index >>= 1;
+    }
+    
+    Ok(nodes[0])
+}
+
+impl CommitmentTree {
+    /// Append a commitment, returning its leaf index
+    pub fn insert(&mut self, leaf: [u8; 32]) -> Result<u64> {
+        self.insert_many(&[leaf])
+    }
+    
+    /// Append commitments in order, returning the leaf index of the first one
+    pub fn insert_many(&mut self, leaves: &[[u8; 32]]) -> Result<u64> {
+        let first_index = self.next_index;
+        self.current_root = insert_many(&mut self.frontier, &self.zeroes, first_index, leaves)?;
+        self.next_index = first_index + leaves.len() as u64;
+        Ok(first_index)
+    }
+}
+
+/// Off-chain reference tree that keeps every leaf and recomputes roots from scratch.
+/// Clients use it to track the pool root and build membership paths for proofs.
+#[derive(Clone, Debug)]
+pub struct ReferenceTree {
+    depth: usize,
+    zeroes: Vec<[u8; 32]>,
+    leaves: Vec<[u8; 32]>,
+    roots: Vec<[u8; 32]>,
+}
+
+impl ReferenceTree {
+    pub fn new(depth: usize) -> Result<Self> {
+        let mut zeroes = vec![[0u8; 32]; depth];
+        for level in 1..depth {
+            zeroes[level] = hash_pair(&zeroes[level - 1], &zeroes[level - 1])?;
+        }
+        let mut tree = Self { depth, zeroes, leaves: Vec::new(), roots: Vec::new() };
+        let root = tree.root()?;
+        tree.roots.push(root);
+        Ok(tree)
+    }
+    
+    /// Reference tree with the same depth as the on-chain `CommitmentTree`
+    pub fn pool() -> Result<Self> {
+        Self::new(DEPTH)
+    }
+    
+    pub fn leaves(&self) -> &[[u8; 32]] {
+        &self.leaves
+    }
+    
+    /// Every root the tree has had, oldest first
+    pub fn roots(&self) -> &[[u8; 32]] {
+        &self.roots
+    }
+    
+    pub fn is_known_root(&self, root: &[u8; 32]) -> bool {
+        self.roots.contains(root)
+    }
+    
+    pub fn insert(&mut self, leaf: [u8; 32]) -> Result<[u8; 32]> {
+        require!(
+            (self.leaves.len() as u64) < (1u64 << self.depth),
+            PoolError::MerkleTreeFull
+        );
+        self.leaves.push(leaf);
+        let root = self.root()?;
+        self.roots.push(root);
+        Ok(root)
+    }
+    
+    /// Root over all leaves, padding each level with the empty subtree hash
+    pub fn root(&self) -> Result<[u8; 32]> {
+        let mut nodes = self.leaves.clone();
+        for level in 0..self.depth {
+            nodes = self.parents(&nodes, level)?;
+        }
+        Ok(nodes.first().copied().unwrap_or([0u8; 32]))
+    }
+    
+    /// Sibling hashes from the leaf at `index` up to the root
+    pub fn path(&self, index: usize) -> Result<Vec<[u8; 32]>> {
+        require!(index < self.leaves.len(), PoolError::TreeUpdateFailed);
+        let mut path = Vec::with_capacity(self.depth);
+        let mut nodes = self.leaves.clone();
+        let mut position = index;
+        for level in 0..self.depth {
+            let sibling = nodes.get(position ^ 1).copied().unwrap_or(self.zeroes[level]);
+            path.push(sibling);
+            nodes = self.parents(&nodes, level)?;
+            position >>= 1;
+        }
+        Ok(path)
+    }
+    
+    fn parents(&self, nodes: &[[u8; 32]], level: usize) -> Result<Vec<[u8; 32]>> {
+        if nodes.is_empty() {
+            let empty = hash_pair(&self.zeroes[level], &self.zeroes[level])?;
+            return Ok(vec![empty]);
+        }
+        nodes
+            .chunks(2)
+            .map(|pair| hash_pair(&pair[0], pair.get(1).unwrap_or(&self.zeroes[level])))
+            .collect()
+    }
+}
+
+/// Recompute a root from a leaf, its index and the sibling path
+pub fn root_from_path(leaf: [u8; 32], index: u64, path: &[[u8; 32]]) -> Result<[u8; 32]> {
+    let mut node = leaf;
+    for (level, sibling) in path.iter().enumerate() {
+        node = if (index >> level) & 1 == 0 {
+            hash_pair(&node, sibling)?
+        } else {
+            hash_pair(sibling, &node)?
+        };
+    }
+    Ok(node)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    const TEST_DEPTH: usize = 5;
+
+    fn zeroes(depth: usize) -> Vec<[u8; 32]> {
+        let mut zeroes = vec![[0u8; 32]; depth];
+        for level in 1..depth {
+            zeroes[level] = hash_pair(&zeroes[level - 1], &zeroes[level - 1]).unwrap();
+        }
+        zeroes
+    }
+
+    fn leaf(seed: u64) -> [u8; 32] {
+        let mut bytes = [0u8; 32];
+        bytes[24..].copy_from_slice(&seed.to_be_bytes());
+        bytes
+    }
+
+    /// Hash every node of the full 2^depth tree, with zero leaves past the end
+    fn naive_root(depth: usize, leaves: &[[u8; 32]]) -> [u8; 32] {
+        let mut nodes = vec![[0u8; 32]; 1 << depth];
+        nodes[..leaves.len()].copy_from_slice(leaves);
+        while nodes.len() > 1 {
+            nodes = nodes.chunks(2).map(|pair| hash_pair(&pair[0], &pair[1]).unwrap()).collect();
+        }
+        nodes[0]
+    }
+
+    fn leaves_strategy(max: usize) -> impl Strategy<Value = Vec<[u8; 32]>> {
+        prop::collection::vec(any::<u64>().prop_map(leaf), 1..=max)
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(16))]
+
+        #[test]
+        fn incremental_insert_matches_naive_tree(leaves in leaves_strategy(1 << TEST_DEPTH)) {
+            let zeroes = zeroes(TEST_DEPTH);
+            let mut filled = zeroes.clone();
+            for (index, leaf) in leaves.iter().enumerate() {
+                let root = insert(&mut filled, &zeroes, index as u64, *leaf).unwrap();
+                prop_assert_eq!(root, naive_root(TEST_DEPTH, &leaves[..=index]));
+            }
+        }
+
+        #[test]
+        fn insert_many_matches_sequential_inserts(
+            leaves in leaves_strategy(1 << TEST_DEPTH),
+            split in 0usize..32,
+        ) {
+            let zeroes = zeroes(TEST_DEPTH);
+            let split = split % leaves.len();
+
+            let mut sequential = zeroes.clone();
+            let mut expected = [0u8; 32];
+            for (index, leaf) in leaves.iter().enumerate() {
+                expected = insert(&mut sequential, &zeroes, index as u64, *leaf).unwrap();
+            }
+
+            // Two batches, so the second may start on a right child at any level
+            let mut batched = zeroes.clone();
+            if split > 0 {
+                insert_many(&mut batched, &zeroes, 0, &leaves[..split]).unwrap();
+            }
+            let root = insert_many(&mut batched, &zeroes, split as u64, &leaves[split..]).unwrap();
+
+            prop_assert_eq!(root, expected);
+            prop_assert_eq!(batched, sequential);
+        }
+
+        #[test]
+        fn reference_tree_matches_naive_tree_and_paths_verify(leaves in leaves_strategy(1 << TEST_DEPTH)) {
+            let mut reference = ReferenceTree::new(TEST_DEPTH).unwrap();
+            for leaf in &leaves {
+                reference.insert(*leaf).unwrap();
+            }
+            let root = reference.root().unwrap();
+            prop_assert_eq!(root, naive_root(TEST_DEPTH, &leaves));
+            prop_assert_eq!(reference.roots().len(), leaves.len() + 1);
+
+            for (index, leaf) in leaves.iter().enumerate() {
+                let path = reference.path(index).unwrap();
+                prop_assert_eq!(root_from_path(*leaf, index as u64, &path).unwrap(), root);
+            }
+        }
+    }
+
+    #[test]
+    fn commitment_tree_matches_reference_tree() {
+        let zeroes = CommitmentTree::compute_zeroes().unwrap();
+        let mut tree = CommitmentTree {
+            pool: Pubkey::default(),
+            next_index: 0,
+            current_root: CommitmentTree::empty_root(&zeroes).unwrap(),
+            frontier: zeroes,
+            zeroes,
+            recent_commitments: [[0u8; 32]; crate::state::RECENT_COMMITMENTS],
+            recent_indices: [0u64; crate::state::RECENT_COMMITMENTS],
+            recent_len: 0,
+            bump: 0,
+        };
+        let mut reference = ReferenceTree::pool().unwrap();
+        assert_eq!(reference.root().unwrap(), tree.current_root);
+
+        for seed in 1..=3 {
+            let index = tree.insert(leaf(seed)).unwrap();
+            assert_eq!(index, seed - 1);
+            assert_eq!(reference.insert(leaf(seed)).unwrap(), tree.current_root);
+        }
+
+        let leaves = [leaf(4), leaf(5), leaf(6)];
+        assert_eq!(tree.insert_many(&leaves).unwrap(), 3);
+        for leaf in leaves {
+            reference.insert(leaf).unwrap();
+        }
+        assert_eq!(tree.next_index, 6);
+        assert_eq!(reference.root().unwrap(), tree.current_root);
+        assert!(reference.is_known_root(&reference.roots()[2]));
+    }
+
+    #[test]
+    fn full_tree_rejects_inserts() {
+        let zeroes = zeroes(2);
+        let mut filled = zeroes.clone();
+        insert_many(&mut filled, &zeroes, 0, &[leaf(1), leaf(2), leaf(3), leaf(4)]).unwrap();
+        assert!(insert(&mut filled, &zeroes, 4, leaf(5)).is_err());
+        assert!(insert_many(&mut filled, &zeroes, 2, &[leaf(1), leaf(2), leaf(3)]).is_err());
+    }
+}
@@ -0,0 +1,139 @@
+//! Spent-nullifier storage.
+//!
+//! Each spent nullifier gets its own `NullifierRecord` PDA at [b"nullifier", pool, nullifier].
+//! Checking a nullifier costs one address derivation and an owner check, whatever the set size.
+//! Pools created before records existed keep their old nullifiers in the `NullifierSet` list
+//! until `migrate_nullifiers` moves them into records. Until then spends check that list too.
+
+use anchor_lang::prelude::*;
+use anchor_lang::system_program;
+use crate::errors::PoolError;
+use crate::raw::{self, HashListMut};
+use crate::state::{NullifierRecord, PoolState};
+
+/// Record PDA for `nullifier` in `pool`
+pub fn nullifier_address(program_id: &Pubkey, pool: &Pubkey, nullifier: &[u8; 32]) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"nullifier", pool.as_ref(), nullifier], program_id)
+}
+
+/// Where a spend handler checks and records nullifiers
+pub trait NullifierStore {
+    fn is_spent(&mut self, nullifier: &[u8; 32]) -> Result<bool>;
+    fn spend(&mut self, nullifier: &[u8; 32]) -> Result<()>;
+}
+
+/// The old list-backed storage, now only used for unmigrated nullifiers
+impl NullifierStore for HashListMut<'_, '_> {
+    fn is_spent(&mut self, nullifier: &[u8; 32]) -> Result<bool> {
+        Ok(self.contains(nullifier))
+    }
+    
+    fn spend(&mut self, nullifier: &[u8; 32]) -> Result<()> {
+        self.push(nullifier)
+    }
+}
+
+/// Per-nullifier record PDAs, plus the pool's legacy list for nullifiers not yet migrated.
+/// The record accounts, a writable system-owned signer to pay their rent and the system
+/// program must all be among `accounts`.
+pub struct NullifierRecords<'a, 'info> {
+    program_id: Pubkey,
+    pool: Pubkey,
+    accounts: &'a [AccountInfo<'info>],
+    payer: &'a AccountInfo<'info>,
+    system_program: &'a AccountInfo<'info>,
+    legacy: HashListMut<'a, 'info>,
+    slot: u64,
+    // Addresses derived by is_spent, reused by spend
+    derived: Vec<([u8; 32], Pubkey, u8)>,
+}
+
+impl<'a, 'info> NullifierRecords<'a, 'info> {
+    pub fn load(
+        program_id: &Pubkey,
+        accounts: &'a [AccountInfo<'info>],
+        pool_key: &Pubkey,
+        pool: &PoolState,
+        slot: u64,
+    ) -> Result<Self> {
+        let legacy = HashListMut::nullifier_set(program_id, accounts, pool)?;
+        let payer = accounts
+            .iter()
+            .find(|info| info.is_signer && info.is_writable && info.owner == &system_program::ID)
+            .ok_or(PoolError::AccountNotFound)?;
+        let system_program = raw::find_by_key(accounts, &system_program::ID)?;
+        
+        Ok(Self {
+            program_id: *program_id,
+            pool: *pool_key,
+            accounts,
+            payer,
+            system_program,
+            legacy,
+            slot,
+            derived: Vec::new(),
+        })
+    }
+    
+    /// Number of nullifiers still waiting in the legacy list
+    pub fn legacy_len(&self) -> usize {
+        self.legacy.len()
+    }
+    
+    fn address(&mut self, nullifier: &[u8; 32]) -> (Pubkey, u8) {
+        if let Some((_, address, bump)) = self.derived.iter().find(|(n, _, _)| n == nullifier) {
+            return (*address, *bump);
+        }
+        let (address, bump) = nullifier_address(&self.program_id, &self.pool, nullifier);
+        self.derived.push((*nullifier, address, bump));
+        (address, bump)
+    }
+    
+    fn record_account(&mut self, nullifier: &[u8; 32]) -> Result<(&'a AccountInfo<'info>, u8)> {
+        let (address, bump) = self.address(nullifier);
+        Ok((raw::find_by_key(self.accounts, &address)?, bump))
+    }
+    
+    /// Create the record for a nullifier that is known to be unspent
+    fn create_record(&mut self, nullifier: &[u8; 32]) -> Result<()> {
+        let (record, bump) = self.record_account(nullifier)?;
+        require!(record.owner != &self.program_id, PoolError::NullifierAlreadyUsed);
+        
+        let seeds: &[&[u8]] = &[b"nullifier", self.pool.as_ref(), nullifier, &[bump]];
+        raw::create_pda_account(
+            record,
+            self.payer,
+            self.system_program,
+            NullifierRecord::LEN,
+            &self.program_id,
+            seeds,
+        )?;
+        
+        let data = NullifierRecord { pool: self.pool, spent_slot: self.slot, bump };
+        data.try_serialize(&mut &mut record.try_borrow_mut_data()?[..])
+    }
+    
+    /// Move up to `count` nullifiers from the end of the legacy list into records,
+    /// returning how many were moved
+    pub fn migrate(&mut self, count: usize) -> Result<usize> {
+        let mut moved = 0;
+        while moved < count {
+            let Some(nullifier) = self.legacy.pop() else { break };
+            self.create_record(&nullifier)?;
+            moved += 1;
+        }
+        Ok(moved)
+    }
+}
+
+impl NullifierStore for NullifierRecords<'_, '_> {
+    fn is_spent(&mut self, nullifier: &[u8; 32]) -> Result<bool> {
+        let (record, _) = self.record_account(nullifier)?;
+        Ok(record.owner == &self.program_id || self.legacy.contains(nullifier))
+    }
+    
+    fn spend(&mut self, nullifier: &[u8; 32]) -> Result<()> {
+        require!(!self.legacy.contains(nullifier), PoolError::NullifierAlreadyUsed);
+        self.create_record(nullifier)
+    }
+}
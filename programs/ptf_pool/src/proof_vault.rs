@@ -0,0 +1,207 @@
+//! Per-user proof vaults.
+//!
+//! A `UserProofVault` PDA at [b"proof-vault", owner] holds the owner's prepared operations.
+//! The first prepare creates it at its minimum size. Handlers that add or remove operations
+//! resize it to fit, so rent is charged to the owner as it grows and refunded as it shrinks.
+//! `cancel_operation`, `expire_operations` and `close_proof_vault` handle operations that are
+//! never finished.
+
+use anchor_lang::prelude::*;
+use anchor_lang::system_program;
+use crate::errors::PoolError;
+use crate::raw;
+use crate::state::UserProofVault;
+
+/// Proof vault PDA for `owner`
+pub fn proof_vault_address(program_id: &Pubkey, owner: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"proof-vault", owner.as_ref()], program_id)
+}
+
+/// Deserialize a vault owned by this program
+pub fn load(program_id: &Pubkey, info: &AccountInfo) -> Result<UserProofVault> {
+    require_keys_eq!(*info.owner, *program_id, PoolError::InvalidAccountOwner);
+    UserProofVault::try_deserialize(&mut &info.try_borrow_data()?[..])
+}
+
+/// Deserialize `owner`'s vault, checking it is their PDA
+pub fn load_owned(program_id: &Pubkey, info: &AccountInfo, owner: &Pubkey) -> Result<UserProofVault> {
+    let (address, _) = proof_vault_address(program_id, owner);
+    require_keys_eq!(*info.key, address, PoolError::InvalidPda);
+    load(program_id, info)
+}
+
+/// Deserialize `owner`'s vault, creating an empty one at `slot` if it doesn't exist yet.
+/// `owner` pays for the account and must be a writable signer.
+pub fn load_or_create<'info>(
+    program_id: &Pubkey,
+    info: &AccountInfo<'info>,
+    owner: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    slot: u64,
+) -> Result<UserProofVault> {
+    let (address, bump) = proof_vault_address(program_id, owner.key);
+    require_keys_eq!(*info.key, address, PoolError::InvalidPda);
+    
+    if info.owner != program_id {
+        let seeds: &[&[u8]] = &[b"proof-vault", owner.key.as_ref(), &[bump]];
+        raw::create_pda_account(info, owner, system_program, UserProofVault::MIN_LEN, program_id, seeds)?;
+        let vault = UserProofVault::new(slot);
+        save(info, &vault)?;
+        return Ok(vault);
+    }
+    
+    check_layout(&info.try_borrow_data()?)?;
+    load(program_id, info)
+}
+
+/// Vaults written without a discriminator by older builds can't be read. Their operations may
+/// include spent nullifiers awaiting withdrawal, so they are never overwritten here: the owner
+/// decides whether to discard them with `close_proof_vault`.
+fn check_layout(data: &[u8]) -> Result<()> {
+    require!(
+        data.starts_with(UserProofVault::DISCRIMINATOR),
+        PoolError::UnsupportedProofVaultLayout
+    );
+    Ok(())
+}
+
+/// Write the vault back in place; it must fit the account
+pub fn save(info: &AccountInfo, vault: &UserProofVault) -> Result<()> {
+    vault.try_serialize(&mut &mut info.try_borrow_mut_data()?[..])
+}
+
+/// Resize the vault to exactly fit its operations, then write it back.
+/// Growth is paid by `owner` (a writable signer); rent freed by shrinking goes back to them.
+pub fn save_resized<'info>(
+    info: &AccountInfo<'info>,
+    vault: &UserProofVault,
+    owner: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+) -> Result<()> {
+    let len = 8 + vault.try_to_vec()?.len();
+    let rent = Rent::get()?.minimum_balance(len);
+    let lamports = info.lamports();
+    
+    if rent > lamports {
+        let cpi_accounts = system_program::Transfer { from: owner.clone(), to: info.clone() };
+        let cpi_ctx = CpiContext::new(system_program.clone(), cpi_accounts);
+        system_program::transfer(cpi_ctx, rent - lamports)?;
+    } else if lamports > rent {
+        **info.try_borrow_mut_lamports()? -= lamports - rent;
+        **owner.try_borrow_mut_lamports()? += lamports - rent;
+    }
+    info.resize(len)?;
+    save(info, vault)
+}
+
+/// Remove a finished operation and shrink the vault to fit the rest, refunding the freed rent
+/// to `owner`
+pub fn complete<'info>(
+    info: &AccountInfo<'info>,
+    mut vault: UserProofVault,
+    operation_id: &[u8; 32],
+    owner: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+) -> Result<()> {
+    vault.prepared_operations.retain(|op| op.id() != operation_id);
+    save_resized(info, &vault, owner, system_program)
+}
+
+/// Close the vault, returning its rent to `owner`
+pub fn close<'info>(info: &AccountInfo<'info>, owner: &AccountInfo<'info>) -> Result<()> {
+    let lamports = info.lamports();
+    **info.try_borrow_mut_lamports()? = 0;
+    **owner.try_borrow_mut_lamports()? += lamports;
+    info.assign(&system_program::ID);
+    info.resize(0)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::OperationPayload;
+    use anchor_lang::solana_program::entrypoint::{MAX_PERMITTED_DATA_INCREASE, SUCCESS};
+    use solana_sysvar::program_stubs::{set_syscall_stubs, SyscallStubs};
+
+    struct RentStubs;
+
+    impl SyscallStubs for RentStubs {
+        fn sol_get_rent_sysvar(&self, var_addr: *mut u8) -> u64 {
+            unsafe { *(var_addr as *mut Rent) = Rent::default() };
+            SUCCESS
+        }
+    }
+
+    /// Key and data laid out as the runtime serializes them, so `AccountInfo::resize` can find
+    /// the original length before the key and write the new one before the data
+    struct SerializedAccount {
+        key: Vec<u32>,
+        data: Vec<u64>,
+        len: usize,
+        lamports: u64,
+    }
+
+    impl SerializedAccount {
+        fn new(key: Pubkey, len: usize, lamports: u64) -> Self {
+            let mut header = vec![len as u32];
+            header.extend_from_slice(bytemuck::cast_slice(&key.to_bytes()));
+            let mut data = vec![0u64; 1 + (len + MAX_PERMITTED_DATA_INCREASE).div_ceil(8)];
+            data[0] = len as u64;
+            Self { key: header, data, len, lamports }
+        }
+
+        fn info<'a>(&'a mut self, owner: &'a Pubkey) -> AccountInfo<'a> {
+            // Safety: the key sits right after the original length, and Pubkey has alignment 1
+            let key = unsafe { &*(self.key.as_ptr().add(1) as *const Pubkey) };
+            let data = &mut bytemuck::cast_slice_mut::<u64, u8>(&mut self.data)[8..8 + self.len];
+            AccountInfo::new(key, false, true, &mut self.lamports, data, owner, false, 0)
+        }
+    }
+
+    #[test]
+    fn unknown_layouts_are_rejected_rather_than_reset() {
+        let mut data = vec![0u8; UserProofVault::MIN_LEN];
+        let err = check_layout(&data).unwrap_err();
+        assert_eq!(err, PoolError::UnsupportedProofVaultLayout.into());
+
+        UserProofVault::default().try_serialize(&mut &mut data[..]).unwrap();
+        check_layout(&data).unwrap();
+    }
+
+    #[test]
+    fn completing_an_operation_shrinks_the_vault_and_refunds_its_rent() {
+        set_syscall_stubs(Box::new(RentStubs));
+        let rent = Rent::default();
+        let (program_id, owner) = (crate::ID, Pubkey::new_unique());
+        let (address, _) = proof_vault_address(&program_id, &owner);
+
+        let mut vault = UserProofVault::default();
+        let shield = |n: u8| OperationPayload::Shield { pool: Pubkey::new_unique(), commitment: [n; 32], amount: 10 };
+        let completed = vault.prepare(&program_id, &owner, shield(1), 0).unwrap();
+        let pending = vault.prepare(&program_id, &owner, shield(2), 0).unwrap();
+        let len = 8 + vault.try_to_vec().unwrap().len();
+
+        let mut vault_account = SerializedAccount::new(address, len, rent.minimum_balance(len));
+        let mut owner_account = SerializedAccount::new(owner, 0, 0);
+        let mut system_account = SerializedAccount::new(system_program::ID, 0, 1);
+        let native_loader = Pubkey::default();
+        let (info, owner_info, system_info) = (
+            vault_account.info(&program_id),
+            owner_account.info(&system_program::ID),
+            system_account.info(&native_loader),
+        );
+        save(&info, &vault).unwrap();
+
+        complete(&info, vault, &completed, &owner_info, &system_info).unwrap();
+        let vault = load_owned(&program_id, &info, &owner).unwrap();
+        assert_eq!(vault.prepared_operations.len(), 1);
+        assert_eq!(vault.prepared_operations[0].id(), &pending);
+
+        let shrunk = 8 + vault.try_to_vec().unwrap().len();
+        assert!(shrunk < len);
+        assert_eq!(info.data_len(), shrunk);
+        assert_eq!(info.lamports(), rent.minimum_balance(shrunk));
+        assert_eq!(owner_info.lamports(), rent.minimum_balance(len) - rent.minimum_balance(shrunk));
+    }
+}
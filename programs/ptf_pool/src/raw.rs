@@ -0,0 +1,515 @@
+//! Account identification and in-place account views for the raw handlers.
+//!
+//! The handlers routed by `entrypoint` don't rely on account order. Each account is
+//! located by owner and discriminator, then checked against its PDA using the stored bump.
+//! Large accounts are read and written in place instead of being Borsh round-tripped.
+
+use anchor_lang::prelude::*;
+use anchor_lang::system_program;
+use anchor_lang::Discriminator;
+use std::cell::RefMut;
+use crate::errors::PoolError;
+use crate::merkle;
+use crate::state::{
+    Circuit, CommitmentTree, NullifierSet, PoolState, RootEntry, RootHistory, DEPTH, RECENT_COMMITMENTS,
+};
+use ptf_verifier_groth16::VerifyingKeyAccount;
+
+/// First account owned by `owner` whose data starts with `T`'s discriminator
+pub fn find_account<'a, 'info, T: Discriminator>(
+    accounts: &'a [AccountInfo<'info>],
+    owner: &Pubkey,
+) -> Result<&'a AccountInfo<'info>> {
+    accounts
+        .iter()
+        .find(|info| {
+            info.owner == owner
+                && info
+                    .try_borrow_data()
+                    .map(|data| data.starts_with(T::DISCRIMINATOR))
+                    .unwrap_or(false)
+        })
+        .ok_or_else(|| PoolError::AccountNotFound.into())
+}
+
+/// Account with the given address
+pub fn find_by_key<'a, 'info>(
+    accounts: &'a [AccountInfo<'info>],
+    key: &Pubkey,
+) -> Result<&'a AccountInfo<'info>> {
+    accounts
+        .iter()
+        .find(|info| info.key == key)
+        .ok_or_else(|| PoolError::AccountNotFound.into())
+}
+
+/// Check that `info` is a verifying key for `circuit` that `pool` accepts at `slot`. The pool
+/// records which key each circuit uses, and the key's own `circuit_tag` must name it too.
+pub fn check_verifying_key(info: &AccountInfo, pool: &PoolState, circuit: Circuit, slot: u64) -> Result<()> {
+    require_keys_eq!(*info.owner, ptf_verifier_groth16::ID, PoolError::InvalidAccountOwner);
+    require!(
+        pool.is_verifying_key_allowed(circuit, info.key, slot),
+        PoolError::VerifyingKeyMismatch
+    );
+    let data = info.try_borrow_data()?;
+    require!(data.starts_with(VerifyingKeyAccount::DISCRIMINATOR), PoolError::InvalidVerifyingKey);
+    // circuit_tag is the first field
+    require!(
+        data.get(8..40) == Some(&circuit.tag()[..]),
+        PoolError::CircuitTagMismatch
+    );
+    Ok(())
+}
+
+/// The verifying key for `circuit` among `accounts`
+pub fn find_verifying_key<'a, 'info>(
+    accounts: &'a [AccountInfo<'info>],
+    pool: &PoolState,
+    circuit: Circuit,
+    slot: u64,
+) -> Result<&'a AccountInfo<'info>> {
+    let info = accounts
+        .iter()
+        .find(|info| {
+            info.owner == &ptf_verifier_groth16::ID && pool.is_verifying_key_allowed(circuit, info.key, slot)
+        })
+        .ok_or(PoolError::VerifyingKeyMismatch)?;
+    check_verifying_key(info, pool, circuit, slot)?;
+    Ok(info)
+}
+
+/// Check that `account` is the PDA for `seeds` (bump included) under `program_id`
+pub fn check_pda(account: &AccountInfo, seeds: &[&[u8]], program_id: &Pubkey) -> Result<()> {
+    let expected = Pubkey::create_program_address(seeds, program_id)
+        .map_err(|_| PoolError::InvalidPda)?;
+    require_keys_eq!(expected, *account.key, PoolError::InvalidPda);
+    Ok(())
+}
+
+/// Create a PDA owned by `owner`. Like Anchor's `init`, this also works when someone has
+/// already sent lamports to the address, which would make a plain create_account fail.
+pub fn create_pda_account<'info>(
+    account: &AccountInfo<'info>,
+    payer: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    space: usize,
+    owner: &Pubkey,
+    signer_seeds: &[&[u8]],
+) -> Result<()> {
+    let rent = Rent::get()?.minimum_balance(space);
+    let signer = &[signer_seeds];
+    let current_lamports = account.lamports();
+    
+    if current_lamports == 0 {
+        let cpi_accounts = system_program::CreateAccount { from: payer.clone(), to: account.clone() };
+        let cpi_ctx = CpiContext::new_with_signer(system_program.clone(), cpi_accounts, signer);
+        return system_program::create_account(cpi_ctx, rent, space as u64, owner);
+    }
+    
+    require_keys_eq!(*account.owner, system_program::ID, PoolError::InvalidAccountOwner);
+    if current_lamports < rent {
+        let cpi_accounts = system_program::Transfer { from: payer.clone(), to: account.clone() };
+        let cpi_ctx = CpiContext::new(system_program.clone(), cpi_accounts);
+        system_program::transfer(cpi_ctx, rent - current_lamports)?;
+    }
+    let cpi_accounts = system_program::Allocate { account_to_allocate: account.clone() };
+    system_program::allocate(
+        CpiContext::new_with_signer(system_program.clone(), cpi_accounts, signer),
+        space as u64,
+    )?;
+    let cpi_accounts = system_program::Assign { account_to_assign: account.clone() };
+    system_program::assign(CpiContext::new_with_signer(system_program.clone(), cpi_accounts, signer), owner)
+}
+
+/// Locate, deserialize and PDA-check the pool state
+pub fn load_pool_state<'a, 'info>(
+    program_id: &Pubkey,
+    accounts: &'a [AccountInfo<'info>],
+) -> Result<(&'a AccountInfo<'info>, PoolState)> {
+    let info = find_account::<PoolState>(accounts, program_id)?;
+    let pool = PoolState::try_deserialize(&mut &info.try_borrow_data()?[..])?;
+    check_pda(info, &[b"pool", pool.origin_mint.as_ref(), &[pool.bump]], program_id)?;
+    Ok((info, pool))
+}
+
+/// Write the pool state back into its account
+pub fn store_pool_state(info: &AccountInfo, pool: &PoolState) -> Result<()> {
+    let mut data = info.try_borrow_mut_data()?;
+    pool.try_serialize(&mut &mut data[..])
+}
+
+// CommitmentTree field offsets (Borsh layout, no variable-length fields)
+const TREE_POOL: usize = 8;
+const TREE_NEXT_INDEX: usize = TREE_POOL + 32;
+const TREE_CURRENT_ROOT: usize = TREE_NEXT_INDEX + 8;
+const TREE_FRONTIER: usize = TREE_CURRENT_ROOT + 32;
+const TREE_ZEROES: usize = TREE_FRONTIER + DEPTH * 32;
+const TREE_RECENT_COMMITMENTS: usize = TREE_ZEROES + DEPTH * 32;
+const TREE_RECENT_INDICES: usize = TREE_RECENT_COMMITMENTS + RECENT_COMMITMENTS * 32;
+const TREE_RECENT_LEN: usize = TREE_RECENT_INDICES + RECENT_COMMITMENTS * 8;
+const TREE_BUMP: usize = TREE_RECENT_LEN + 1;
+
+/// Mutable in-place view of a `CommitmentTree` account
+pub struct CommitmentTreeMut<'a, 'info> {
+    data: RefMut<'a, &'info mut [u8]>,
+}
+
+impl<'a, 'info> CommitmentTreeMut<'a, 'info> {
+    /// Locate the tree for `pool` and check its PDA
+    pub fn load(
+        program_id: &Pubkey,
+        accounts: &'a [AccountInfo<'info>],
+        pool: &PoolState,
+    ) -> Result<Self> {
+        let info = find_account::<CommitmentTree>(accounts, program_id)?;
+        let data = info.try_borrow_mut_data()?;
+        require!(data.len() >= CommitmentTree::LEN, PoolError::AccountDataTooShort);
+        let view = Self { data };
+        check_pda(
+            info,
+            &[b"commitment-tree", pool.origin_mint.as_ref(), &[view.bump()]],
+            program_id,
+        )?;
+        Ok(view)
+    }
+    
+    pub fn from_data(data: RefMut<'a, &'info mut [u8]>) -> Result<Self> {
+        require!(data.len() >= CommitmentTree::LEN, PoolError::AccountDataTooShort);
+        Ok(Self { data })
+    }
+    
+    pub fn next_index(&self) -> u64 {
+        u64::from_le_bytes(self.data[TREE_NEXT_INDEX..TREE_NEXT_INDEX + 8].try_into().unwrap())
+    }
+    
+    pub fn current_root(&self) -> [u8; 32] {
+        self.data[TREE_CURRENT_ROOT..TREE_CURRENT_ROOT + 32].try_into().unwrap()
+    }
+    
+    pub fn bump(&self) -> u8 {
+        self.data[TREE_BUMP]
+    }
+    
+    /// Append leaves in order, returning the leaf index of the first one
+    pub fn insert_many(&mut self, leaves: &[[u8; 32]]) -> Result<u64> {
+        let first_index = self.next_index();
+        let (head, tail) = self.data.split_at_mut(TREE_ZEROES);
+        let frontier: &mut [[u8; 32]] =
+            bytemuck::cast_slice_mut(&mut head[TREE_FRONTIER..TREE_ZEROES]);
+        let zeroes: &[[u8; 32]] = bytemuck::cast_slice(&tail[..DEPTH * 32]);
+        let root = merkle::insert_many(frontier, zeroes, first_index, leaves)?;
+        
+        let next_index = first_index + leaves.len() as u64;
+        self.data[TREE_NEXT_INDEX..TREE_NEXT_INDEX + 8].copy_from_slice(&next_index.to_le_bytes());
+        self.data[TREE_CURRENT_ROOT..TREE_CURRENT_ROOT + 32].copy_from_slice(&root);
+        Ok(first_index)
+    }
+    
+    /// Record a commitment in the recent-commitments window while it has room. Only the
+    /// commitment and its leaf index are public; amounts reach recipients in encrypted notes.
+    pub fn record_recent(&mut self, commitment: &[u8; 32], leaf_index: u64) {
+        let idx = self.data[TREE_RECENT_LEN] as usize;
+        if idx >= RECENT_COMMITMENTS {
+            return;
+        }
+        let at = TREE_RECENT_COMMITMENTS + idx * 32;
+        self.data[at..at + 32].copy_from_slice(commitment);
+        let at = TREE_RECENT_INDICES + idx * 8;
+        self.data[at..at + 8].copy_from_slice(&leaf_index.to_le_bytes());
+        self.data[TREE_RECENT_LEN] += 1;
+    }
+}
+
+// RootHistory field offsets: fixed header, then `capacity` entries of [root 32][slot 8]
+const HISTORY_CAPACITY: usize = 8 + 32;
+const HISTORY_HEAD: usize = HISTORY_CAPACITY + 2;
+const HISTORY_LEN: usize = HISTORY_HEAD + 2;
+const HISTORY_BUMP: usize = HISTORY_LEN + 2;
+const HISTORY_ENTRIES: usize = RootHistory::MIN_LEN;
+
+/// Mutable in-place view of a `RootHistory` ring buffer
+pub struct RootHistoryMut<'a, 'info> {
+    data: RefMut<'a, &'info mut [u8]>,
+}
+
+impl<'a, 'info> RootHistoryMut<'a, 'info> {
+    /// Locate the root history for `pool` and check its PDA
+    pub fn load(
+        program_id: &Pubkey,
+        accounts: &'a [AccountInfo<'info>],
+        pool: &PoolState,
+    ) -> Result<Self> {
+        let info = find_account::<RootHistory>(accounts, program_id)?;
+        let view = Self::from_data(info.try_borrow_mut_data()?)?;
+        check_pda(
+            info,
+            &[b"root-history", pool.origin_mint.as_ref(), &[view.data[HISTORY_BUMP]]],
+            program_id,
+        )?;
+        Ok(view)
+    }
+    
+    pub fn from_data(data: RefMut<'a, &'info mut [u8]>) -> Result<Self> {
+        require!(data.len() >= RootHistory::MIN_LEN, PoolError::AccountDataTooShort);
+        let view = Self { data };
+        require!(
+            view.capacity() > 0 && view.data.len() >= RootHistory::space(view.capacity() as u16),
+            PoolError::AccountDataTooShort
+        );
+        Ok(view)
+    }
+    
+    fn read_u16(&self, at: usize) -> usize {
+        u16::from_le_bytes([self.data[at], self.data[at + 1]]) as usize
+    }
+    
+    pub fn capacity(&self) -> usize {
+        self.read_u16(HISTORY_CAPACITY)
+    }
+    
+    pub fn len(&self) -> usize {
+        self.read_u16(HISTORY_LEN)
+    }
+    
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+    
+    fn entry(&self, index: usize) -> RootEntry {
+        let at = HISTORY_ENTRIES + index * RootEntry::LEN;
+        RootEntry {
+            root: self.data[at..at + 32].try_into().unwrap(),
+            slot: u64::from_le_bytes(self.data[at + 32..at + 40].try_into().unwrap()),
+        }
+    }
+    
+    /// Slot at which `root` became current, if it is still in the buffer
+    pub fn root_slot(&self, root: &[u8; 32]) -> Option<u64> {
+        (0..self.len()).map(|i| self.entry(i)).find(|entry| entry.root == *root).map(|entry| entry.slot)
+    }
+    
+    pub fn is_known_root(&self, root: &[u8; 32]) -> bool {
+        self.root_slot(root).is_some()
+    }
+    
+    /// Record a new current root, overwriting the oldest once the buffer is full
+    pub fn push(&mut self, root: [u8; 32], slot: u64) {
+        let capacity = self.capacity();
+        let head = self.read_u16(HISTORY_HEAD);
+        let at = HISTORY_ENTRIES + head * RootEntry::LEN;
+        self.data[at..at + 32].copy_from_slice(&root);
+        self.data[at + 32..at + 40].copy_from_slice(&slot.to_le_bytes());
+        
+        let head = ((head + 1) % capacity) as u16;
+        let len = (self.len() + 1).min(capacity) as u16;
+        self.data[HISTORY_HEAD..HISTORY_HEAD + 2].copy_from_slice(&head.to_le_bytes());
+        self.data[HISTORY_LEN..HISTORY_LEN + 2].copy_from_slice(&len.to_le_bytes());
+    }
+}
+
+// NullifierSet offsets: [discriminator][pool][len u32][items][bump]
+const LIST_LEN: usize = 8 + 32;
+const LIST_ITEMS: usize = LIST_LEN + 4;
+
+/// In-place view of an account laid out as `{ pool, Vec<[u8; 32]>, bump }`
+pub struct HashListMut<'a, 'info> {
+    data: RefMut<'a, &'info mut [u8]>,
+}
+
+impl<'a, 'info> HashListMut<'a, 'info> {
+    fn load<T: Discriminator>(
+        program_id: &Pubkey,
+        accounts: &'a [AccountInfo<'info>],
+        seed: &[u8],
+        pool: &PoolState,
+    ) -> Result<Self> {
+        let info = find_account::<T>(accounts, program_id)?;
+        let view = Self::from_data(info.try_borrow_mut_data()?)?;
+        check_pda(info, &[seed, pool.origin_mint.as_ref(), &[view.bump()]], program_id)?;
+        Ok(view)
+    }
+    
+    /// Locate the pool's nullifier set and check its PDA
+    pub fn nullifier_set(
+        program_id: &Pubkey,
+        accounts: &'a [AccountInfo<'info>],
+        pool: &PoolState,
+    ) -> Result<Self> {
+        Self::load::<NullifierSet>(program_id, accounts, b"nullifier-set", pool)
+    }
+    
+    pub fn from_data(data: RefMut<'a, &'info mut [u8]>) -> Result<Self> {
+        require!(data.len() > LIST_ITEMS, PoolError::AccountDataTooShort);
+        let view = Self { data };
+        require!(
+            view.data.len() > LIST_ITEMS + view.len() * 32,
+            PoolError::AccountDataTooShort
+        );
+        Ok(view)
+    }
+    
+    pub fn len(&self) -> usize {
+        u32::from_le_bytes(self.data[LIST_LEN..LIST_ITEMS].try_into().unwrap()) as usize
+    }
+    
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+    
+    pub fn bump(&self) -> u8 {
+        self.data[LIST_ITEMS + self.len() * 32]
+    }
+    
+    pub fn contains(&self, item: &[u8; 32]) -> bool {
+        self.data[LIST_ITEMS..LIST_ITEMS + self.len() * 32]
+            .chunks_exact(32)
+            .any(|existing| existing == item)
+    }
+    
+    /// Append an item, moving the trailing bump; fails if the account has no room left
+    pub fn push(&mut self, item: &[u8; 32]) -> Result<()> {
+        let len = self.len();
+        let at = LIST_ITEMS + len * 32;
+        require!(self.data.len() > at + 32, PoolError::AccountDataTooShort);
+        let bump = self.data[at];
+        self.data[at..at + 32].copy_from_slice(item);
+        self.data[at + 32] = bump;
+        self.data[LIST_LEN..LIST_ITEMS].copy_from_slice(&(len as u32 + 1).to_le_bytes());
+        Ok(())
+    }
+    
+    /// Remove and return the last item, moving the trailing bump back
+    pub fn pop(&mut self) -> Option<[u8; 32]> {
+        let len = self.len().checked_sub(1)?;
+        let at = LIST_ITEMS + len * 32;
+        let item: [u8; 32] = self.data[at..at + 32].try_into().unwrap();
+        self.data[at] = self.data[at + 32];
+        self.data[at + 1..at + 33].fill(0);
+        self.data[LIST_LEN..LIST_ITEMS].copy_from_slice(&(len as u32).to_le_bytes());
+        Some(item)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+
+    fn empty_tree() -> CommitmentTree {
+        let zeroes = CommitmentTree::compute_zeroes().unwrap();
+        CommitmentTree {
+            pool: Pubkey::new_unique(),
+            next_index: 0,
+            current_root: CommitmentTree::empty_root(&zeroes).unwrap(),
+            frontier: zeroes,
+            zeroes,
+            recent_commitments: [[0u8; 32]; RECENT_COMMITMENTS],
+            recent_indices: [0u64; RECENT_COMMITMENTS],
+            recent_len: 0,
+            bump: 254,
+        }
+    }
+
+    fn serialize<T: AccountSerialize>(account: &T, len: usize) -> Vec<u8> {
+        let mut data = vec![0u8; len];
+        account.try_serialize(&mut &mut data[..]).unwrap();
+        data
+    }
+
+    #[test]
+    fn tree_view_matches_borsh_layout() {
+        let mut tree = empty_tree();
+        let mut bytes = serialize(&tree, CommitmentTree::LEN);
+        let leaf = |n: u8| {
+            let mut leaf = [0u8; 32];
+            leaf[31] = n;
+            leaf
+        };
+
+        let cell = RefCell::new(&mut bytes[..]);
+        {
+            let mut view = CommitmentTreeMut::from_data(cell.borrow_mut()).unwrap();
+            assert_eq!(view.bump(), 254);
+            assert_eq!(view.current_root(), tree.current_root);
+            assert_eq!(view.insert_many(&[leaf(1), leaf(2)]).unwrap(), 0);
+            view.record_recent(&leaf(2), 1);
+            assert_eq!(view.insert_many(&[leaf(3)]).unwrap(), 2);
+        }
+
+        tree.insert_many(&[leaf(1), leaf(2)]).unwrap();
+        tree.recent_commitments[0] = leaf(2);
+        tree.recent_indices[0] = 1;
+        tree.recent_len = 1;
+        tree.insert(leaf(3)).unwrap();
+
+        assert_eq!(cell.into_inner().to_vec(), serialize(&tree, CommitmentTree::LEN));
+    }
+
+    #[test]
+    fn root_history_view_matches_borsh_layout() {
+        let root = |n: u8| [n; 32];
+        let mut history = RootHistory {
+            pool: Pubkey::new_unique(),
+            capacity: 3,
+            head: 0,
+            len: 0,
+            bump: 252,
+            entries: vec![RootEntry::default(); 3],
+        };
+        let mut bytes = serialize(&history, RootHistory::space(3));
+
+        let cell = RefCell::new(&mut bytes[..]);
+        {
+            let mut view = RootHistoryMut::from_data(cell.borrow_mut()).unwrap();
+            assert!(view.is_empty());
+            for n in 1..=4 {
+                view.push(root(n), 100 + n as u64);
+            }
+            assert_eq!(view.len(), 3);
+            // The oldest root was overwritten, the rest keep their slots
+            assert!(!view.is_known_root(&root(1)));
+            assert_eq!(view.root_slot(&root(2)), Some(102));
+            assert_eq!(view.root_slot(&root(4)), Some(104));
+        }
+
+        history.entries = vec![
+            RootEntry { root: root(4), slot: 104 },
+            RootEntry { root: root(2), slot: 102 },
+            RootEntry { root: root(3), slot: 103 },
+        ];
+        history.head = 1;
+        history.len = 3;
+        assert_eq!(cell.into_inner().to_vec(), serialize(&history, RootHistory::space(3)));
+    }
+
+    #[test]
+    fn hash_list_push_matches_borsh_layout() {
+        let mut set = NullifierSet { pool: Pubkey::new_unique(), nullifiers: vec![[1u8; 32]], bump: 253 };
+        let len = NullifierSet::MIN_LEN + 32 * 3;
+        let mut bytes = serialize(&set, len);
+
+        let cell = RefCell::new(&mut bytes[..]);
+        {
+            let mut view = HashListMut::from_data(cell.borrow_mut()).unwrap();
+            assert_eq!(view.bump(), 253);
+            assert!(view.contains(&[1u8; 32]));
+            view.push(&[2u8; 32]).unwrap();
+            view.push(&[3u8; 32]).unwrap();
+            assert_eq!(view.len(), 3);
+            assert_eq!(view.bump(), 253);
+            // No room for a fourth item
+            assert!(view.push(&[4u8; 32]).is_err());
+        }
+
+        set.nullifiers.extend_from_slice(&[[2u8; 32], [3u8; 32]]);
+        assert_eq!(cell.borrow().to_vec(), serialize(&set, len));
+
+        {
+            let mut view = HashListMut::from_data(cell.borrow_mut()).unwrap();
+            assert_eq!(view.pop(), Some([3u8; 32]));
+            assert_eq!(view.pop(), Some([2u8; 32]));
+        }
+        set.nullifiers.truncate(1);
+        assert_eq!(cell.into_inner().to_vec(), serialize(&set, len));
+    }
+}
@@ -1,47 +1,248 @@
 use anchor_lang::prelude::*;
+use ptf_common::poseidon::hash_pair;
+use solana_sha256_hasher::hashv;
+use ptf_verifier_groth16::instructions::verify_with_attestation::VerificationAttestation;
+use crate::errors::PoolError;
+use crate::instructions::transfer_core::TransferPublicInputs;
+use crate::instructions::unshield_core::UnshieldRequest;
 
 pub const DEPTH: usize = 32;
-pub const MAX_CANOPY: usize = 128;
+// Size of the tree's recent-commitments window; kept small enough that CommitmentTree fits the
+// 10 KiB limit for accounts created via CPI
+pub const RECENT_COMMITMENTS: usize = 64;
+pub const MAX_PINNED_VERIFYING_KEYS: usize = 4;
+pub const MIN_ROOT_HISTORY: u16 = 32;
+// Largest RootHistory that fits the 10 KiB limit for accounts created via CPI
+pub const MAX_ROOT_HISTORY: u16 = 240;
 
 #[account]
 pub struct PoolState {
     pub current_root: [u8; 32],
-    pub recent_roots: [[u8; 32]; 16],
-    pub recent_roots_len: u8,
     pub origin_mint: Pubkey,
     pub vault: Pubkey,
     pub twin_mint: Option<Pubkey>,
-    pub verifying_key: Pubkey,
-    pub verifying_key_hash: [u8; 32],
+    /// Verifying keys indexed by `Circuit`; a proof is only checked against its own circuit's key
+    pub verifying_keys: [CircuitKeys; CIRCUIT_COUNT],
     pub last_operation_slot: u64,
     pub operation_count: u64,
     pub bump: u8,
 }
 
 impl PoolState {
-    pub const LEN: usize = 8 + 32 + (16 * 32) + 1 + 32 + 32 + 33 + 32 + 32 + 8 + 8 + 1;
+    pub const LEN: usize = 8 + 32 + 32 + 32 + 33
+        + (CIRCUIT_COUNT * CircuitKeys::LEN) + 8 + 8 + 1;
+    
+    pub fn circuit_keys(&self, circuit: Circuit) -> &CircuitKeys {
+        &self.verifying_keys[circuit as usize]
+    }
+    
+    /// Check whether proofs for `circuit` against `verifying_key` are accepted at `slot`
+    pub fn is_verifying_key_allowed(&self, circuit: Circuit, verifying_key: &Pubkey, slot: u64) -> bool {
+        self.circuit_keys(circuit).is_allowed(verifying_key, slot)
+    }
+    
+    /// Replace `circuit`'s verifying key, keeping the old one accepted until `grace_end_slot`
+    pub fn rotate_verifying_key(
+        &mut self,
+        circuit: Circuit,
+        key: Pubkey,
+        key_hash: [u8; 32],
+        version: u32,
+        grace_end_slot: u64,
+        slot: u64,
+    ) -> Result<()> {
+        self.verifying_keys[circuit as usize].rotate(key, key_hash, version, grace_end_slot, slot)
+    }
+}
+
+pub const CIRCUIT_COUNT: usize = 5;
+
+/// The circuits a pool verifies proofs of, in the order `PoolState.verifying_keys` holds them
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Circuit {
+    Shield,
+    Unshield,
+    Transfer,
+    DelegatedTransfer,
+    AllowanceTransfer,
+}
+
+impl Circuit {
+    pub const ALL: [Circuit; CIRCUIT_COUNT] = [
+        Circuit::Shield,
+        Circuit::Unshield,
+        Circuit::Transfer,
+        Circuit::DelegatedTransfer,
+        Circuit::AllowanceTransfer,
+    ];
+    
+    /// Directory name under `circuits/`
+    pub fn name(self) -> &'static str {
+        match self {
+            Circuit::Shield => "shield",
+            Circuit::Unshield => "unshield",
+            Circuit::Transfer => "transfer",
+            Circuit::DelegatedTransfer => "transfer_from",
+            Circuit::AllowanceTransfer => "allowance_transfer",
+        }
+    }
+    
+    /// `VerifyingKeyAccount.circuit_tag` of this circuit's keys: its name, zero-padded
+    pub fn tag(self) -> [u8; 32] {
+        let mut tag = [0u8; 32];
+        let name = self.name().as_bytes();
+        tag[..name.len()].copy_from_slice(name);
+        tag
+    }
+    
+    pub fn from_tag(tag: &[u8; 32]) -> Option<Self> {
+        Self::ALL.into_iter().find(|circuit| circuit.tag() == *tag)
+    }
+}
+
+/// One circuit's current verifying key and the previous keys still accepted during their
+/// rotation grace window
+#[derive(Clone, Copy, Default, AnchorSerialize, AnchorDeserialize)]
+pub struct CircuitKeys {
+    pub key: Pubkey,
+    pub key_hash: [u8; 32],
+    pub version: u32,
+    pub pinned: [PinnedVerifyingKey; MAX_PINNED_VERIFYING_KEYS],
+    pub pinned_len: u8,
+}
+
+impl CircuitKeys {
+    pub const LEN: usize = 32 + 32 + 4 + (MAX_PINNED_VERIFYING_KEYS * PinnedVerifyingKey::LEN) + 1;
+    
+    pub fn new(key: Pubkey, key_hash: [u8; 32], version: u32) -> Self {
+        Self { key, key_hash, version, ..Default::default() }
+    }
+    
+    /// The current key, or a pinned previous key whose grace window is still open
+    pub fn is_allowed(&self, verifying_key: &Pubkey, slot: u64) -> bool {
+        *verifying_key != Pubkey::default()
+            && (*verifying_key == self.key
+                || self.pinned[..self.pinned_len as usize]
+                    .iter()
+                    .any(|pinned| pinned.key == *verifying_key && slot < pinned.expiry_slot))
+    }
+    
+    /// Replace the current key, keeping the old one accepted until `grace_end_slot`
+    /// Pinned keys whose grace window has closed are dropped first
+    pub fn rotate(
+        &mut self,
+        key: Pubkey,
+        key_hash: [u8; 32],
+        version: u32,
+        grace_end_slot: u64,
+        slot: u64,
+    ) -> Result<()> {
+        let mut pinned: Vec<PinnedVerifyingKey> = self.pinned[..self.pinned_len as usize]
+            .iter()
+            .filter(|pinned| slot < pinned.expiry_slot && pinned.key != key)
+            .copied()
+            .collect();
+        if grace_end_slot > slot {
+            pinned.push(PinnedVerifyingKey {
+                key: self.key,
+                key_hash: self.key_hash,
+                version: self.version,
+                expiry_slot: grace_end_slot,
+            });
+        }
+        require!(
+            pinned.len() <= MAX_PINNED_VERIFYING_KEYS,
+            PoolError::TooManyPinnedVerifyingKeys
+        );
+        
+        self.pinned = [PinnedVerifyingKey::default(); MAX_PINNED_VERIFYING_KEYS];
+        self.pinned[..pinned.len()].copy_from_slice(&pinned);
+        self.pinned_len = pinned.len() as u8;
+        
+        self.key = key;
+        self.key_hash = key_hash;
+        self.version = version;
+        
+        Ok(())
+    }
+}
+
+#[derive(Clone, Copy, Default, AnchorSerialize, AnchorDeserialize)]
+pub struct PinnedVerifyingKey {
+    pub key: Pubkey,
+    pub key_hash: [u8; 32],
+    pub version: u32,
+    pub expiry_slot: u64, // Accepted while slot < expiry_slot
+}
+
+impl PinnedVerifyingKey {
+    pub const LEN: usize = 32 + 32 + 4 + 8;
 }
 
 #[account]
 pub struct CommitmentTree {
     pub pool: Pubkey,
-    pub canopy_depth: u8,
     pub next_index: u64,
     pub current_root: [u8; 32],
     pub frontier: [[u8; 32]; DEPTH],
     pub zeroes: [[u8; 32]; DEPTH],
-    pub canopy: [[u8; 32]; MAX_CANOPY],
-    pub recent_commitments: [[u8; 32]; MAX_CANOPY],
-    pub recent_amount_commitments: [[u8; 32]; MAX_CANOPY],
-    pub recent_indices: [u64; MAX_CANOPY],
+    pub recent_commitments: [[u8; 32]; RECENT_COMMITMENTS],
+    pub recent_indices: [u64; RECENT_COMMITMENTS],
     pub recent_len: u8,
     pub bump: u8,
 }
 
 impl CommitmentTree {
-    pub const LEN: usize = 8 + 32 + 1 + 8 + 32 + (DEPTH * 32) + (DEPTH * 32) + (MAX_CANOPY * 32) + (MAX_CANOPY * 32) + (MAX_CANOPY * 32) + (MAX_CANOPY * 8) + 1 + 1;
+    pub const LEN: usize = 8 + 32 + 8 + 32 + (DEPTH * 32) + (DEPTH * 32) + (RECENT_COMMITMENTS * 32) + (RECENT_COMMITMENTS * 8) + 1 + 1;
+    
+    /// zeroes[i] is the root of an empty subtree of height i (zeroes[0] is the empty leaf)
+    pub fn compute_zeroes() -> Result<[[u8; 32]; DEPTH]> {
+        let mut zeroes = [[0u8; 32]; DEPTH];
+        for level in 1..DEPTH {
+            zeroes[level] = hash_pair(&zeroes[level - 1], &zeroes[level - 1])?;
+        }
+        Ok(zeroes)
+    }
+    
+    /// Root of the empty tree
+    pub fn empty_root(zeroes: &[[u8; 32]; DEPTH]) -> Result<[u8; 32]> {
+        hash_pair(&zeroes[DEPTH - 1], &zeroes[DEPTH - 1])
+    }
 }
 
+/// Ring buffer of the pool's most recent tree roots, sized at pool creation.
+/// A proof against a root stays valid until `capacity` newer roots have been pushed; the slot
+/// tags let clients see how quickly roots are being replaced.
+#[account]
+pub struct RootHistory {
+    pub pool: Pubkey,
+    pub capacity: u16,
+    pub head: u16, // Index the next root is written to
+    pub len: u16,
+    pub bump: u8,
+    pub entries: Vec<RootEntry>, // Always `capacity` long
+}
+
+impl RootHistory {
+    pub const MIN_LEN: usize = 8 + 32 + 2 + 2 + 2 + 1 + 4;
+    
+    pub fn space(capacity: u16) -> usize {
+        Self::MIN_LEN + capacity as usize * RootEntry::LEN
+    }
+}
+
+#[derive(Clone, Copy, Default, Debug, PartialEq, Eq, AnchorSerialize, AnchorDeserialize)]
+pub struct RootEntry {
+    pub root: [u8; 32],
+    pub slot: u64, // Slot the root became current
+}
+
+impl RootEntry {
+    pub const LEN: usize = 32 + 8;
+}
+
+/// Nullifiers spent before per-nullifier records existed. New spends go to `NullifierRecord`
+/// PDAs; `migrate_nullifiers` drains this list into records.
 #[account]
 pub struct NullifierSet {
     pub pool: Pubkey,
@@ -53,35 +254,97 @@ impl NullifierSet {
     pub const MIN_LEN: usize = 8 + 32 + 4 + 1;
 }
 
+/// One spent nullifier, at PDA [b"nullifier", pool, nullifier]. The account existing is the
+/// spent flag, so lookups cost the same however many nullifiers the pool has.
 #[account]
-pub struct NoteLedger {
+pub struct NullifierRecord {
     pub pool: Pubkey,
-    pub notes: Vec<[u8; 32]>,
+    pub spent_slot: u64,
     pub bump: u8,
 }
 
-impl NoteLedger {
-    pub const MIN_LEN: usize = 8 + 32 + 4 + 1; // discriminator + pool + Vec length + bump
+impl NullifierRecord {
+    pub const LEN: usize = 8 + 32 + 8 + 1;
 }
 
 #[account]
+#[derive(Default)]
 pub struct UserProofVault {
+    /// Slot the vault was created in. The nonce restarts when a closed vault is re-created, so
+    /// this is mixed into operation ids too; a vault can't be closed in its creation slot.
+    pub created_slot: u64,
+    /// Mixed into the next operation id, then incremented, so no two operations share an id
+    pub next_nonce: u64,
     pub prepared_operations: Vec<PreparedOperation>,
 }
 
 impl UserProofVault {
-    pub const MIN_LEN: usize = 8 + 4; // discriminator + Vec length
+    pub const MIN_LEN: usize = 8 + 8 + 8 + 4; // discriminator + created slot + nonce + Vec length
+    
+    pub fn new(created_slot: u64) -> Self {
+        Self { created_slot, ..Self::default() }
+    }
+    
+    /// Add a new pending operation for `owner`, returning its id
+    pub fn prepare(&mut self, program_id: &Pubkey, owner: &Pubkey, payload: OperationPayload, slot: u64) -> Result<[u8; 32]> {
+        let id = operation_id(program_id, owner, self.created_slot, self.next_nonce, &payload)?;
+        self.next_nonce += 1;
+        self.prepared_operations.push(PreparedOperation::new(id, payload, slot));
+        Ok(id)
+    }
+    
+    /// Whether the vault may be closed at `slot`. Closing only after the creation slot means a
+    /// re-created vault gets a later `created_slot`, so its ids can't repeat earlier ones.
+    pub fn is_closable(&self, slot: u64) -> bool {
+        slot > self.created_slot
+    }
 }
 
+/// sha256(program id || owner || vault created slot (u64 LE) || nonce (u64 LE) || payload).
+/// The payload's borsh encoding starts with its variant tag, so the operation type is part of
+/// the preimage.
+pub fn operation_id(
+    program_id: &Pubkey,
+    owner: &Pubkey,
+    created_slot: u64,
+    nonce: u64,
+    payload: &OperationPayload,
+) -> Result<[u8; 32]> {
+    Ok(hashv(&[
+        program_id.as_ref(),
+        owner.as_ref(),
+        &created_slot.to_le_bytes(),
+        &nonce.to_le_bytes(),
+        &payload.try_to_vec()?,
+    ]).to_bytes())
+}
+
+/// Layout version of `PreparedOperation.payload`; operations written with another version are rejected
+pub const OPERATION_PAYLOAD_VERSION: u8 = 2;
+/// Slots a prepared operation has to reach `Updated` before it can be expired (about an hour)
+pub const OPERATION_TTL_SLOTS: u64 = 9_000;
+
 #[derive(Clone, AnchorSerialize, AnchorDeserialize)]
 pub struct PreparedOperation {
     pub id: [u8; 32],
     pub status: u8, // OperationStatus as u8
-    pub operation_type: u8,
-    pub data: Vec<u8>,
+    /// First slot at which the operation can no longer advance and may be marked Failed
+    pub expiry_slot: u64,
+    pub version: u8,
+    pub payload: OperationPayload,
 }
 
 impl PreparedOperation {
+    pub fn new(id: [u8; 32], payload: OperationPayload, slot: u64) -> Self {
+        Self {
+            id,
+            status: ptf_common::OperationStatus::Pending as u8,
+            expiry_slot: slot.saturating_add(OPERATION_TTL_SLOTS),
+            version: OPERATION_PAYLOAD_VERSION,
+            payload,
+        }
+    }
+    
     pub fn id(&self) -> &[u8; 32] {
         &self.id
     }
@@ -94,6 +357,121 @@ impl PreparedOperation {
     pub fn set_status(&mut self, status: ptf_common::OperationStatus) {
         self.status = status as u8;
     }
+    
+    pub fn is_expired(&self, slot: u64) -> bool {
+        slot >= self.expiry_slot
+    }
+    
+    /// Whether the operation may be dropped or expired. Once `Updated` its nullifier is spent,
+    /// so it must be withdrawn rather than discarded.
+    pub fn is_abandonable(&self) -> bool {
+        self.status() != ptf_common::OperationStatus::Updated
+    }
+    
+    /// Mark the operation `Failed` if it expired before reaching `Updated`
+    pub fn expire(&mut self, slot: u64) -> bool {
+        use ptf_common::OperationStatus::*;
+        let expirable = matches!(self.status(), Pending | InProgress | Verified);
+        if expirable && self.is_expired(slot) {
+            self.set_status(Failed);
+            return true;
+        }
+        false
+    }
+    
+    pub fn payload(&self) -> Result<&OperationPayload> {
+        require!(self.version == OPERATION_PAYLOAD_VERSION, PoolError::UnsupportedOperationVersion);
+        Ok(&self.payload)
+    }
+    
+    pub fn payload_mut(&mut self) -> Result<&mut OperationPayload> {
+        require!(self.version == OPERATION_PAYLOAD_VERSION, PoolError::UnsupportedOperationVersion);
+        Ok(&mut self.payload)
+    }
+}
+
+/// What a prepared operation carries. The prepare instruction fixes the public inputs;
+/// `proof` stays empty until update_operation_data adds it.
+#[derive(Clone, Debug, AnchorSerialize, AnchorDeserialize)]
+pub enum OperationPayload {
+    /// Shield proofs are passed straight to execute_shield_v2
+    Shield {
+        /// Pool whose tree the commitment goes into and whose vault takes the deposit
+        pool: Pubkey,
+        commitment: [u8; 32],
+        amount: u64,
+    },
+    Unshield {
+        request: UnshieldRequest,
+        proof: Option<ProofSubmission>,
+    },
+    Transfer {
+        inputs: TransferPublicInputs,
+        proof: Option<ProofSubmission>,
+    },
+}
+
+/// Groth16 proof plus the external verifier attestations that vouch for it
+#[derive(Clone, Debug, AnchorSerialize, AnchorDeserialize)]
+pub struct ProofSubmission {
+    pub proof: Vec<u8>,
+    pub attestations: Vec<VerificationAttestation>,
+}
+
+impl ProofSubmission {
+    pub const PROOF_LEN: usize = 256;
+    pub const ATTESTATION_LEN: usize = 169;
+    
+    /// Parse update_operation_data's wire format:
+    /// [proof (256)][attestation_count (1)][attestations (169 bytes each)]
+    pub fn parse(data: &[u8]) -> Result<Self> {
+        require!(data.len() > Self::PROOF_LEN, PoolError::InvalidProof);
+        let (proof, rest) = data.split_at(Self::PROOF_LEN);
+        let attestation_count = rest[0] as usize;
+        let attestations = &rest[1..];
+        require!(attestation_count > 0, PoolError::InvalidOperationStatus);
+        require!(
+            attestations.len() == attestation_count * Self::ATTESTATION_LEN,
+            PoolError::InvalidOperationStatus
+        );
+        Ok(Self {
+            proof: proof.to_vec(),
+            attestations: attestations
+                .chunks_exact(Self::ATTESTATION_LEN)
+                .map(parse_attestation)
+                .collect(),
+        })
+    }
+}
+
+/// Parse attestation: proof_hash (32) + public_inputs_hash (32) + verifying_key_hash (32) + 
+///                    is_valid (1) + timestamp (8) + signature (64) = 169 bytes
+fn parse_attestation(bytes: &[u8]) -> VerificationAttestation {
+    let mut proof_hash = [0u8; 32];
+    proof_hash.copy_from_slice(&bytes[0..32]);
+    
+    let mut public_inputs_hash = [0u8; 32];
+    public_inputs_hash.copy_from_slice(&bytes[32..64]);
+    
+    let mut verifying_key_hash = [0u8; 32];
+    verifying_key_hash.copy_from_slice(&bytes[64..96]);
+    
+    let is_valid = bytes[96] != 0;
+    
+    let mut timestamp = [0u8; 8];
+    timestamp.copy_from_slice(&bytes[97..105]);
+    
+    let mut signature = [0u8; 64];
+    signature.copy_from_slice(&bytes[105..169]);
+    
+    VerificationAttestation {
+        proof_hash,
+        public_inputs_hash,
+        verifying_key_hash,
+        is_valid,
+        timestamp: i64::from_le_bytes(timestamp),
+        signature,
+    }
 }
 
 #[account]
@@ -109,3 +487,241 @@ impl ShieldClaim {
     pub const LEN: usize = 8 + 32 + 32 + 8 + 32 + 1;
 }
 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pool_state(verifying_key: Pubkey) -> PoolState {
+        let mut verifying_keys = [CircuitKeys::default(); CIRCUIT_COUNT];
+        for (i, keys) in verifying_keys.iter_mut().enumerate() {
+            *keys = CircuitKeys::new(Pubkey::new_unique(), [i as u8; 32], 1);
+        }
+        verifying_keys[Circuit::Transfer as usize] = CircuitKeys::new(verifying_key, [1u8; 32], 1);
+        PoolState {
+            current_root: [0u8; 32],
+            origin_mint: Pubkey::default(),
+            vault: Pubkey::default(),
+            twin_mint: None,
+            verifying_keys,
+            last_operation_slot: 0,
+            operation_count: 0,
+            bump: 255,
+        }
+    }
+
+    #[test]
+    fn pool_state_len_matches_serialized_size() {
+        let mut state = pool_state(Pubkey::new_unique());
+        state.twin_mint = Some(Pubkey::new_unique());
+        assert_eq!(8 + state.try_to_vec().unwrap().len(), PoolState::LEN);
+    }
+
+    #[test]
+    fn rotation_accepts_old_key_during_grace_window() {
+        let v1 = Pubkey::new_unique();
+        let v2 = Pubkey::new_unique();
+        let mut state = pool_state(v1);
+
+        state.rotate_verifying_key(Circuit::Transfer, v2, [2u8; 32], 2, 150, 100).unwrap();
+        let keys = state.circuit_keys(Circuit::Transfer);
+        assert_eq!(keys.key, v2);
+        assert_eq!(keys.version, 2);
+        assert!(state.is_verifying_key_allowed(Circuit::Transfer, &v2, 100));
+        assert!(state.is_verifying_key_allowed(Circuit::Transfer, &v1, 149));
+        assert!(!state.is_verifying_key_allowed(Circuit::Transfer, &v1, 150));
+        assert!(state.is_verifying_key_allowed(Circuit::Transfer, &v2, u64::MAX));
+    }
+
+    #[test]
+    fn rotation_without_grace_drops_old_key() {
+        let v1 = Pubkey::new_unique();
+        let v2 = Pubkey::new_unique();
+        let mut state = pool_state(v1);
+
+        state.rotate_verifying_key(Circuit::Transfer, v2, [2u8; 32], 2, 100, 100).unwrap();
+        assert_eq!(state.circuit_keys(Circuit::Transfer).pinned_len, 0);
+        assert!(!state.is_verifying_key_allowed(Circuit::Transfer, &v1, 100));
+    }
+
+    #[test]
+    fn rotation_prunes_expired_pins_and_limits_pinned_set() {
+        let mut state = pool_state(Pubkey::new_unique());
+        for version in 2..=(MAX_PINNED_VERIFYING_KEYS as u32 + 1) {
+            state.rotate_verifying_key(Circuit::Transfer, Pubkey::new_unique(), [0u8; 32], version, 1_000, 10).unwrap();
+        }
+        assert_eq!(state.circuit_keys(Circuit::Transfer).pinned_len as usize, MAX_PINNED_VERIFYING_KEYS);
+        assert!(state.rotate_verifying_key(Circuit::Transfer, Pubkey::new_unique(), [0u8; 32], 10, 1_000, 10).is_err());
+
+        // Once the grace windows close the pinned set is emptied on the next rotation
+        state.rotate_verifying_key(Circuit::Transfer, Pubkey::new_unique(), [0u8; 32], 10, 2_000, 1_000).unwrap();
+        assert_eq!(state.circuit_keys(Circuit::Transfer).pinned_len, 1);
+    }
+
+    #[test]
+    fn keys_are_only_accepted_for_their_own_circuit() {
+        let transfer_key = Pubkey::new_unique();
+        let mut state = pool_state(transfer_key);
+        let shield_key = state.circuit_keys(Circuit::Shield).key;
+        assert!(state.is_verifying_key_allowed(Circuit::Shield, &shield_key, 0));
+        assert!(!state.is_verifying_key_allowed(Circuit::Transfer, &shield_key, 0));
+        assert!(!state.is_verifying_key_allowed(Circuit::DelegatedTransfer, &transfer_key, 0));
+
+        // Rotating one circuit leaves the others' keys and grace windows alone
+        state.rotate_verifying_key(Circuit::Shield, Pubkey::new_unique(), [0u8; 32], 2, 150, 100).unwrap();
+        assert!(state.is_verifying_key_allowed(Circuit::Transfer, &transfer_key, 200));
+        assert_eq!(state.circuit_keys(Circuit::Transfer).pinned_len, 0);
+
+        // A circuit whose key was never set accepts nothing
+        state.verifying_keys[Circuit::Unshield as usize] = CircuitKeys::default();
+        assert!(!state.is_verifying_key_allowed(Circuit::Unshield, &Pubkey::default(), 0));
+    }
+
+    #[test]
+    fn circuit_tags_round_trip() {
+        for circuit in Circuit::ALL {
+            assert_eq!(Circuit::from_tag(&circuit.tag()), Some(circuit));
+        }
+        let mut tag = [0u8; 32];
+        tag[..6].copy_from_slice(b"shield");
+        assert_eq!(Circuit::from_tag(&tag), Some(Circuit::Shield));
+        assert_eq!(Circuit::from_tag(&[0u8; 32]), None);
+    }
+
+    #[test]
+    fn pool_accounts_fit_cpi_creation_limit() {
+        // Accounts created with `init` (a system program CPI) are limited to 10 KiB
+        const MAX_CPI_ACCOUNT_LEN: usize = 10 * 1024;
+        const {
+            assert!(CommitmentTree::LEN <= MAX_CPI_ACCOUNT_LEN);
+            assert!(PoolState::LEN <= MAX_CPI_ACCOUNT_LEN);
+            assert!(RootHistory::MIN_LEN + MAX_ROOT_HISTORY as usize * RootEntry::LEN <= MAX_CPI_ACCOUNT_LEN);
+        }
+    }
+
+    #[test]
+    fn zeroes_match_circomlib_empty_tree() {
+        // Empty subtree roots of a circomlib Poseidon tree with a zero leaf
+        let zeroes = CommitmentTree::compute_zeroes().unwrap();
+        assert_eq!(zeroes[0], [0u8; 32]);
+        assert_eq!(
+            zeroes[1],
+            hex32("2098f5fb9e239eab3ceac3f27b81e481dc3124d55ffed523a839ee8446b64864")
+        );
+        assert_eq!(
+            zeroes[2],
+            hex32("1069673dcdb12263df301a6ff584a7ec261a44cb9dc68df067a4774460b1f1e1")
+        );
+        assert_eq!(
+            zeroes[3],
+            hex32("18f43331537ee2af2e3d758d50f72106467c6eea50371dd528d57eb2b856d238")
+        );
+        assert_eq!(
+            CommitmentTree::empty_root(&zeroes).unwrap(),
+            hash_pair(&zeroes[DEPTH - 1], &zeroes[DEPTH - 1]).unwrap()
+        );
+    }
+
+    #[test]
+    fn proof_submission_parses_update_wire_format() {
+        let mut data = vec![7u8; ProofSubmission::PROOF_LEN];
+        data.push(2);
+        for i in 0..2u8 {
+            let mut attestation = [i; ProofSubmission::ATTESTATION_LEN];
+            attestation[96] = 1;
+            data.extend_from_slice(&attestation);
+        }
+        let submission = ProofSubmission::parse(&data).unwrap();
+        assert_eq!(submission.proof, vec![7u8; ProofSubmission::PROOF_LEN]);
+        assert_eq!(submission.attestations.len(), 2);
+        assert_eq!(submission.attestations[1].proof_hash, [1u8; 32]);
+        assert!(submission.attestations[1].is_valid);
+
+        // The attestation count must match the bytes that follow it
+        assert!(ProofSubmission::parse(&data[..data.len() - 1]).is_err());
+        data[ProofSubmission::PROOF_LEN] = 0;
+        assert!(ProofSubmission::parse(&data[..ProofSubmission::PROOF_LEN + 1]).is_err());
+    }
+
+    #[test]
+    fn payload_survives_round_trip_and_checks_version() {
+        let payload = OperationPayload::Shield { pool: Pubkey::default(), commitment: [3u8; 32], amount: 9 };
+        let mut operation = PreparedOperation::new([1u8; 32], payload, 100);
+        let bytes = operation.try_to_vec().unwrap();
+        let decoded = PreparedOperation::try_from_slice(&bytes).unwrap();
+        assert!(matches!(
+            decoded.payload().unwrap(),
+            OperationPayload::Shield { commitment, amount: 9, .. } if *commitment == [3u8; 32]
+        ));
+
+        operation.version = OPERATION_PAYLOAD_VERSION + 1;
+        assert_eq!(
+            operation.payload().unwrap_err(),
+            PoolError::UnsupportedOperationVersion.into()
+        );
+    }
+
+    #[test]
+    fn operations_expire_until_updated() {
+        use ptf_common::OperationStatus;
+
+        let payload = OperationPayload::Shield { pool: Pubkey::default(), commitment: [3u8; 32], amount: 9 };
+        let mut operation = PreparedOperation::new([1u8; 32], payload, 100);
+        let expiry = 100 + OPERATION_TTL_SLOTS;
+        assert!(!operation.expire(expiry - 1));
+        assert!(operation.expire(expiry));
+        assert_eq!(operation.status(), OperationStatus::Failed);
+        assert!(!operation.expire(expiry));
+
+        // An updated operation has spent its nullifier and stays withdrawable
+        operation.set_status(OperationStatus::Updated);
+        assert!(!operation.expire(u64::MAX));
+        assert!(!operation.is_abandonable());
+    }
+
+    #[test]
+    fn operation_ids_are_unique_per_owner_and_nonce() {
+        let program_id = Pubkey::new_unique();
+        let owner = Pubkey::new_unique();
+        let payload = OperationPayload::Shield { pool: Pubkey::default(), commitment: [3u8; 32], amount: 9 };
+
+        // Identical shields from one owner get different ids
+        let mut vault = UserProofVault::default();
+        let first = vault.prepare(&program_id, &owner, payload.clone(), 1).unwrap();
+        let second = vault.prepare(&program_id, &owner, payload.clone(), 1).unwrap();
+        assert_ne!(first, second);
+        assert_eq!(vault.next_nonce, 2);
+        assert_eq!(vault.prepared_operations[1].id, second);
+        assert_eq!(first, operation_id(&program_id, &owner, 0, 0, &payload).unwrap());
+
+        // As do the same shield from two owners
+        let mut other = UserProofVault::default();
+        let other_first = other.prepare(&program_id, &Pubkey::new_unique(), payload.clone(), 1).unwrap();
+        assert_ne!(first, other_first);
+    }
+
+    #[test]
+    fn operation_ids_do_not_repeat_after_a_vault_is_closed() {
+        let program_id = Pubkey::new_unique();
+        let owner = Pubkey::new_unique();
+        let payload = OperationPayload::Shield { pool: Pubkey::default(), commitment: [3u8; 32], amount: 9 };
+
+        let mut vault = UserProofVault::new(10);
+        let first = vault.prepare(&program_id, &owner, payload.clone(), 10).unwrap();
+        assert!(!vault.is_closable(10));
+        assert!(vault.is_closable(11));
+
+        // Re-created no earlier than the slot it was closed in, with the nonce back at 0
+        let mut recreated = UserProofVault::new(11);
+        let again = recreated.prepare(&program_id, &owner, payload, 11).unwrap();
+        assert_ne!(first, again);
+    }
+
+    fn hex32(s: &str) -> [u8; 32] {
+        let mut out = [0u8; 32];
+        for (i, byte) in out.iter_mut().enumerate() {
+            *byte = u8::from_str_radix(&s[2 * i..2 * i + 2], 16).unwrap();
+        }
+        out
+    }
+}
@@ -6,7 +6,7 @@ use crate::errors::VaultError;
 pub fn deposit(ctx: Context<Deposit>, amount: u64) -> Result<()> {
     let vault = &ctx.accounts.vault;
     
-    // Verify authority (only pool program can deposit)
+    // Verify authority (only the pool PDA can deposit)
     require!(
         ctx.accounts.authority.key() == vault.authority,
         VaultError::InvalidAuthority
@@ -18,6 +18,12 @@ pub fn deposit(ctx: Context<Deposit>, amount: u64) -> Result<()> {
         VaultError::InvalidMint
     );
     
+    // Verify vault token account matches
+    require!(
+        ctx.accounts.vault_token_account.key() == vault.vault_token_account,
+        VaultError::TokenAccountMismatch
+    );
+    
     // Transfer tokens from user to vault
     let cpi_accounts = Transfer {
         from: ctx.accounts.user_token_account.to_account_info(),
@@ -39,8 +45,8 @@ pub struct Deposit<'info> {
     )]
     pub vault: Account<'info, VaultState>,
     
-    /// CHECK: Authority that can deposit (pool program)
-    pub authority: UncheckedAccount<'info>,
+    /// Pool PDA, signing through invoke_signed
+    pub authority: Signer<'info>,
     
     #[account(mut)]
     pub user_token_account: Account<'info, TokenAccount>,
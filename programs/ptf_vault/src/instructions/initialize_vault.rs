@@ -0,0 +1,61 @@
+use anchor_lang::prelude::*;
+use anchor_spl::associated_token::AssociatedToken;
+use anchor_spl::token::{Mint, Token, TokenAccount};
+use crate::state::VaultState;
+use crate::errors::VaultError;
+
+pub fn initialize_vault(ctx: Context<InitializeVault>) -> Result<()> {
+    let vault = &mut ctx.accounts.vault;
+    
+    // init_if_needed lets a pre-created token account through, so guard the state itself
+    require!(
+        vault.origin_mint == Pubkey::default(),
+        VaultError::AlreadyInitialized
+    );
+    
+    vault.origin_mint = ctx.accounts.origin_mint.key();
+    vault.vault_token_account = ctx.accounts.vault_token_account.key();
+    vault.authority = ctx.accounts.pool_state.key();
+    vault.bump = ctx.bumps.vault;
+    
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct InitializeVault<'info> {
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = VaultState::LEN,
+        seeds = [b"vault", origin_mint.key().as_ref()],
+        bump
+    )]
+    pub vault: Account<'info, VaultState>,
+    
+    pub origin_mint: Account<'info, Mint>,
+    
+    #[account(
+        init_if_needed,
+        payer = payer,
+        associated_token::mint = origin_mint,
+        associated_token::authority = vault
+    )]
+    pub vault_token_account: Account<'info, TokenAccount>,
+    
+    /// CHECK: Pool PDA for origin_mint; becomes the vault authority
+    #[account(
+        seeds = [b"pool", origin_mint.key().as_ref()],
+        bump,
+        seeds::program = ptf_common::POOL_PROGRAM_ID
+    )]
+    pub pool_state: UncheckedAccount<'info>,
+    
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    pub token_program: Program<'info, Token>,
+    
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    
+    pub system_program: Program<'info, System>,
+}
@@ -6,7 +6,7 @@ use crate::errors::VaultError;
 pub fn withdraw(ctx: Context<Withdraw>, amount: u64) -> Result<()> {
     let vault = &ctx.accounts.vault;
     
-    // Verify authority (only pool program can withdraw)
+    // Verify authority (only the pool PDA can withdraw)
     require!(
         ctx.accounts.authority.key() == vault.authority,
         VaultError::InvalidAuthority
@@ -58,8 +58,8 @@ pub struct Withdraw<'info> {
     )]
     pub vault: Account<'info, VaultState>,
     
-    /// CHECK: Authority that can withdraw (pool program)
-    pub authority: UncheckedAccount<'info>,
+    /// Pool PDA, signing through invoke_signed
+    pub authority: Signer<'info>,
     
     #[account(mut)]
     pub vault_token_account: Account<'info, TokenAccount>,
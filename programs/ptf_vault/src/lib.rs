@@ -14,6 +14,11 @@ declare_id!("ArUznHH2tESKsknoiW3HhURY46MzXyJL55HuGdKUXQEy");
 pub mod ptf_vault {
     use super::*;
 
+    /// Create the vault PDA and its token account, owned by the pool PDA for origin_mint
+    pub fn initialize_vault(ctx: Context<InitializeVault>) -> Result<()> {
+        instructions::initialize_vault(ctx)
+    }
+
     pub fn deposit(ctx: Context<Deposit>, amount: u64) -> Result<()> {
         instructions::deposit(ctx, amount)
     }
@@ -20,5 +20,29 @@ pub enum VerifierError {
     AlreadyRevoked = 6012,
     #[msg("Invalid verifying key format")]
     InvalidVerifyingKey = 6013,
+    #[msg("Attestation signature not verified by Ed25519 program")]
+    InvalidAttestationSignature = 6014,
+    #[msg("Attestation signed by untrusted verifier")]
+    UntrustedVerifier = 6015,
+    #[msg("Verifier registry is full")]
+    VerifierRegistryFull = 6016,
+    #[msg("Verifier key already registered")]
+    VerifierAlreadyRegistered = 6017,
+    #[msg("Verifier key not registered")]
+    VerifierNotRegistered = 6018,
+    #[msg("Invalid activation/expiry slot range")]
+    InvalidSlotRange = 6019,
+    #[msg("Not enough attestations to meet threshold")]
+    InsufficientAttestations = 6020,
+    #[msg("Attestations must come from distinct verifiers")]
+    DuplicateVerifier = 6021,
+    #[msg("Invalid attestation threshold")]
+    InvalidThreshold = 6022,
+    #[msg("Attestation count does not match verifier accounts")]
+    AttestationVerifierMismatch = 6023,
+    #[msg("Verifying key upload not finalized")]
+    KeyNotFinalized = 6024,
+    #[msg("Verifying key already finalized")]
+    KeyAlreadyFinalized = 6025,
 }
 
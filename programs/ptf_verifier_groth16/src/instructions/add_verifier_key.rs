@@ -0,0 +1,49 @@
+use anchor_lang::prelude::*;
+use crate::state::{RegisteredVerifier, VerifierRegistry, MAX_REGISTERED_VERIFIERS};
+use crate::errors::VerifierError;
+
+pub fn add_verifier_key(
+    ctx: Context<ManageVerifierRegistry>,
+    pubkey: Pubkey,
+    activation_slot: u64,
+    expiry_slot: Option<u64>,
+) -> Result<()> {
+    let registry = &mut ctx.accounts.verifier_registry;
+    
+    require!(
+        registry.find(&pubkey).is_none(),
+        VerifierError::VerifierAlreadyRegistered
+    );
+    require!(
+        registry.verifiers.len() < MAX_REGISTERED_VERIFIERS,
+        VerifierError::VerifierRegistryFull
+    );
+    if let Some(expiry) = expiry_slot {
+        require!(expiry > activation_slot, VerifierError::InvalidSlotRange);
+    }
+    
+    registry.verifiers.push(RegisteredVerifier {
+        pubkey,
+        activation_slot,
+        expiry_slot,
+    });
+    
+    msg!("Verifier key added: {}", pubkey);
+    msg!("Active from slot {} until {:?}", activation_slot, expiry_slot);
+    
+    Ok(())
+}
+
+/// Accounts shared by add/rotate/remove - only the registry authority may change keys
+#[derive(Accounts)]
+pub struct ManageVerifierRegistry<'info> {
+    #[account(
+        mut,
+        seeds = [b"verifier-registry"],
+        bump = verifier_registry.bump,
+        has_one = authority @ VerifierError::InvalidAuthority
+    )]
+    pub verifier_registry: Account<'info, VerifierRegistry>,
+    
+    pub authority: Signer<'info>,
+}
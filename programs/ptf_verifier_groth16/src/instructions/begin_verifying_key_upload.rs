@@ -0,0 +1,68 @@
+use anchor_lang::prelude::*;
+use crate::state::{VerifyingKeyAccount, MAX_VERIFYING_KEY_LEN};
+use crate::errors::VerifierError;
+
+/// Minimum key size: alpha (64) + beta (128) + gamma (128) + delta (128) + count (4) + one gamma_abc point (64)
+const MIN_VERIFYING_KEY_LEN: usize = 64 + 128 + 128 + 128 + 4 + 64;
+
+/// Create a verifying key account sized for `key_len` bytes of key data
+/// The data is written with write_verifying_key_chunk and checked by finalize_verifying_key
+pub fn begin_verifying_key_upload(
+    ctx: Context<BeginVerifyingKeyUpload>,
+    circuit_tag: [u8; 32],
+    version: u32,
+    key_len: u32,
+) -> Result<()> {
+    require!(
+        (MIN_VERIFYING_KEY_LEN..=MAX_VERIFYING_KEY_LEN).contains(&(key_len as usize)),
+        VerifierError::InvalidVerifyingKey
+    );
+    
+    let verifying_key = &mut ctx.accounts.verifying_key;
+    verifying_key.circuit_tag = circuit_tag;
+    verifying_key.version = version;
+    verifying_key.key_data = vec![0u8; key_len as usize];
+    verifying_key.revoked = false;
+    verifying_key.authority = ctx.accounts.authority.key();
+    verifying_key.attestation_threshold = 1;
+    verifying_key.key_hash = [0u8; 32];
+    verifying_key.finalized = false;
+    
+    msg!("Verifying key upload started");
+    msg!("Circuit tag: {:?}", circuit_tag);
+    msg!("Version: {}", version);
+    msg!("Key data size: {} bytes", key_len);
+    
+    Ok(())
+}
+
+#[derive(Accounts)]
+#[instruction(circuit_tag: [u8; 32], version: u32, key_len: u32)]
+pub struct BeginVerifyingKeyUpload<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = VerifyingKeyAccount::MIN_LEN + key_len as usize,
+        seeds = [b"verifying-key", circuit_tag.as_ref(), &version.to_le_bytes()],
+        bump
+    )]
+    pub verifying_key: Account<'info, VerifyingKeyAccount>,
+    
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+/// Accounts for writing and finalizing an in-progress upload
+#[derive(Accounts)]
+pub struct UploadVerifyingKey<'info> {
+    #[account(
+        mut,
+        has_one = authority @ VerifierError::InvalidAuthority,
+        constraint = !verifying_key.finalized @ VerifierError::KeyAlreadyFinalized
+    )]
+    pub verifying_key: Account<'info, VerifyingKeyAccount>,
+    
+    pub authority: Signer<'info>,
+}
@@ -0,0 +1,26 @@
+use anchor_lang::prelude::*;
+use sha2::{Sha256, Digest};
+use crate::state::VerifyingKeyAccount;
+use crate::verification::VerifyingKey;
+use super::UploadVerifyingKey;
+
+pub fn finalize_verifying_key(ctx: Context<UploadVerifyingKey>) -> Result<()> {
+    let verifying_key = &mut ctx.accounts.verifying_key;
+    finalize_key_data(verifying_key)?;
+    
+    msg!("Verifying key finalized");
+    msg!("Key data size: {} bytes", verifying_key.key_data.len());
+    msg!("Key hash: {:?}", verifying_key.key_hash);
+    
+    Ok(())
+}
+
+/// Parse the key, check every point is on the curve, and record the key hash
+pub(crate) fn finalize_key_data(verifying_key: &mut VerifyingKeyAccount) -> Result<()> {
+    VerifyingKey::parse(&verifying_key.key_data)?.check_points()?;
+    
+    verifying_key.key_hash = Sha256::digest(&verifying_key.key_data).into();
+    verifying_key.finalized = true;
+    
+    Ok(())
+}
@@ -0,0 +1,55 @@
+use anchor_lang::prelude::*;
+use crate::state::VerifierRegistry;
+use ptf_common::FACTORY_PROGRAM_ID;
+use crate::errors::VerifierError;
+
+pub fn initialize_verifier_registry(ctx: Context<InitializeVerifierRegistry>) -> Result<()> {
+    // FactoryState layout: discriminator (8) + authority (32) + bump (1)
+    let factory_data = ctx.accounts.factory_state.try_borrow_data()?;
+    require!(factory_data.len() >= 40, VerifierError::InvalidAuthority);
+    let factory_authority = Pubkey::try_from(&factory_data[8..40])
+        .map_err(|_| VerifierError::InvalidAuthority)?;
+    
+    // Only the factory authority can own the registry
+    require_keys_eq!(
+        factory_authority,
+        ctx.accounts.authority.key(),
+        VerifierError::InvalidAuthority
+    );
+    
+    let registry = &mut ctx.accounts.verifier_registry;
+    registry.authority = factory_authority;
+    registry.verifiers = Vec::new();
+    registry.bump = ctx.bumps.verifier_registry;
+    
+    msg!("Verifier registry initialized");
+    msg!("Authority: {}", factory_authority);
+    
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct InitializeVerifierRegistry<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = VerifierRegistry::LEN,
+        seeds = [b"verifier-registry"],
+        bump
+    )]
+    pub verifier_registry: Account<'info, VerifierRegistry>,
+    
+    /// CHECK: Factory state PDA owned by ptf_factory - authority is read from its data
+    #[account(
+        owner = FACTORY_PROGRAM_ID,
+        seeds = [b"factory"],
+        bump,
+        seeds::program = FACTORY_PROGRAM_ID
+    )]
+    pub factory_state: UncheckedAccount<'info>,
+    
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
@@ -1,7 +1,7 @@
 use anchor_lang::prelude::*;
 use crate::state::VerifyingKeyAccount;
 use crate::errors::VerifierError;
-use crate::verification::VerifyingKey;
+use super::finalize_key_data;
 
 pub fn initialize_verifying_key(
     ctx: Context<InitializeVerifyingKey>,
@@ -11,14 +11,12 @@ pub fn initialize_verifying_key(
 ) -> Result<()> {
     // Validate key_data format
     // Minimum size: alpha (64) + beta (128) + gamma (128) + delta (128) + count (4) = 452 bytes
+    // Keys too large for a single transaction use begin_verifying_key_upload instead
     require!(
         key_data.len() >= 452,
         VerifierError::InvalidVerifyingKey
     );
     
-    // Try to parse the key to validate format
-    VerifyingKey::parse(&key_data)?;
-    
     let verifying_key = &mut ctx.accounts.verifying_key;
     
     // The init constraint ensures the account is new, so we don't need to check
@@ -29,6 +27,10 @@ pub fn initialize_verifying_key(
     verifying_key.key_data = key_data;
     verifying_key.revoked = false;
     verifying_key.authority = ctx.accounts.authority.key();
+    verifying_key.attestation_threshold = 1;
+    
+    // Parse the key, check its points and store the key hash
+    finalize_key_data(verifying_key)?;
     
     msg!("Verifying key initialized successfully");
     msg!("Circuit tag: {:?}", circuit_tag);
@@ -44,7 +46,7 @@ pub struct InitializeVerifyingKey<'info> {
     #[account(
         init,
         payer = authority,
-        space = 8 + 32 + 4 + 4 + key_data.len() + 1 + 32,
+        space = VerifyingKeyAccount::MIN_LEN + key_data.len(),
         seeds = [b"verifying-key", circuit_tag.as_ref(), &version.to_le_bytes()],
         bump
     )]
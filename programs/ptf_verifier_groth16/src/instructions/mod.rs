@@ -1,8 +1,25 @@
 pub mod initialize_verifying_key;
 pub mod verify_groth16;
 pub mod verify_with_attestation;
+pub mod initialize_verifier_registry;
+pub mod add_verifier_key;
+pub mod rotate_verifier_key;
+pub mod remove_verifier_key;
+pub mod set_attestation_threshold;
+pub mod revoke_verifying_key;
+pub mod begin_verifying_key_upload;
+pub mod write_verifying_key_chunk;
+pub mod finalize_verifying_key;
 
 pub use initialize_verifying_key::*;
 pub use verify_groth16::*;
 pub use verify_with_attestation::*;
-
+pub use initialize_verifier_registry::*;
+pub use add_verifier_key::*;
+pub use rotate_verifier_key::*;
+pub use remove_verifier_key::*;
+pub use set_attestation_threshold::*;
+pub use revoke_verifying_key::*;
+pub use begin_verifying_key_upload::*;
+pub use write_verifying_key_chunk::*;
+pub use finalize_verifying_key::*;
@@ -0,0 +1,18 @@
+use anchor_lang::prelude::*;
+use crate::errors::VerifierError;
+use super::ManageVerifierRegistry;
+
+pub fn remove_verifier_key(ctx: Context<ManageVerifierRegistry>, pubkey: Pubkey) -> Result<()> {
+    let registry = &mut ctx.accounts.verifier_registry;
+    
+    let index = registry
+        .verifiers
+        .iter()
+        .position(|v| v.pubkey == pubkey)
+        .ok_or(VerifierError::VerifierNotRegistered)?;
+    registry.verifiers.swap_remove(index);
+    
+    msg!("Verifier key removed: {}", pubkey);
+    
+    Ok(())
+}
@@ -0,0 +1,27 @@
+use anchor_lang::prelude::*;
+use crate::state::VerifyingKeyAccount;
+use crate::errors::VerifierError;
+
+pub fn revoke_verifying_key(ctx: Context<RevokeVerifyingKey>) -> Result<()> {
+    let verifying_key = &mut ctx.accounts.verifying_key;
+    
+    require!(!verifying_key.revoked, VerifierError::AlreadyRevoked);
+    verifying_key.revoked = true;
+    
+    msg!("Verifying key revoked");
+    msg!("Circuit tag: {:?}", verifying_key.circuit_tag);
+    msg!("Version: {}", verifying_key.version);
+    
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct RevokeVerifyingKey<'info> {
+    #[account(
+        mut,
+        has_one = authority @ VerifierError::InvalidAuthority
+    )]
+    pub verifying_key: Account<'info, VerifyingKeyAccount>,
+    
+    pub authority: Signer<'info>,
+}
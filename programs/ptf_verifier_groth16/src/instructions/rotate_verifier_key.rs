@@ -0,0 +1,55 @@
+use anchor_lang::prelude::*;
+use crate::state::{RegisteredVerifier, MAX_REGISTERED_VERIFIERS};
+use crate::errors::VerifierError;
+use super::ManageVerifierRegistry;
+
+/// Replace `old_pubkey` with `new_pubkey`
+/// The old key stays valid until `old_key_expiry_slot` so attestations already
+/// in flight are not rejected; the new key becomes valid at `activation_slot`
+pub fn rotate_verifier_key(
+    ctx: Context<ManageVerifierRegistry>,
+    old_pubkey: Pubkey,
+    new_pubkey: Pubkey,
+    activation_slot: u64,
+    old_key_expiry_slot: u64,
+    new_key_expiry_slot: Option<u64>,
+) -> Result<()> {
+    let registry = &mut ctx.accounts.verifier_registry;
+    
+    require!(
+        registry.find(&new_pubkey).is_none(),
+        VerifierError::VerifierAlreadyRegistered
+    );
+    // The old entry keeps its slot until it is removed, so rotation needs a free slot
+    require!(
+        registry.verifiers.len() < MAX_REGISTERED_VERIFIERS,
+        VerifierError::VerifierRegistryFull
+    );
+    if let Some(expiry) = new_key_expiry_slot {
+        require!(expiry > activation_slot, VerifierError::InvalidSlotRange);
+    }
+    
+    let old = registry
+        .find_mut(&old_pubkey)
+        .ok_or(VerifierError::VerifierNotRegistered)?;
+    require!(
+        old_key_expiry_slot >= old.activation_slot,
+        VerifierError::InvalidSlotRange
+    );
+    // Rotation may only shorten the old key's lifetime
+    old.expiry_slot = Some(match old.expiry_slot {
+        Some(expiry) => expiry.min(old_key_expiry_slot),
+        None => old_key_expiry_slot,
+    });
+    
+    registry.verifiers.push(RegisteredVerifier {
+        pubkey: new_pubkey,
+        activation_slot,
+        expiry_slot: new_key_expiry_slot,
+    });
+    
+    msg!("Verifier key rotated: {} -> {}", old_pubkey, new_pubkey);
+    msg!("Old key expires at slot {}, new key active from slot {}", old_key_expiry_slot, activation_slot);
+    
+    Ok(())
+}
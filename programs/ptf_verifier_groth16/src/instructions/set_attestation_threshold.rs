@@ -0,0 +1,28 @@
+use anchor_lang::prelude::*;
+use crate::state::{VerifyingKeyAccount, MAX_REGISTERED_VERIFIERS};
+use crate::errors::VerifierError;
+
+pub fn set_attestation_threshold(ctx: Context<SetAttestationThreshold>, threshold: u8) -> Result<()> {
+    require!(
+        threshold >= 1 && threshold as usize <= MAX_REGISTERED_VERIFIERS,
+        VerifierError::InvalidThreshold
+    );
+    
+    let verifying_key = &mut ctx.accounts.verifying_key;
+    verifying_key.attestation_threshold = threshold;
+    
+    msg!("Attestation threshold set to {}", threshold);
+    
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct SetAttestationThreshold<'info> {
+    #[account(
+        mut,
+        has_one = authority @ VerifierError::InvalidAuthority
+    )]
+    pub verifying_key: Account<'info, VerifyingKeyAccount>,
+    
+    pub authority: Signer<'info>,
+}
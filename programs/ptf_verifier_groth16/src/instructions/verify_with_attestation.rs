@@ -1,13 +1,23 @@
 // Verification with external attestation
-// This instruction verifies proofs using external verifier attestations
-// when Solana doesn't support alt_bn128 pairing checks
+// This instruction verifies proofs using external verifier attestations.
+// The attestation signature is checked on-chain by requiring a preceding
+// Ed25519 program instruction in the same transaction (instructions sysvar introspection).
 
 use anchor_lang::prelude::*;
-use anchor_lang::solana_program::sysvar::Sysvar;
-use crate::state::VerifyingKeyAccount;
+use solana_instructions_sysvar::{load_current_index_checked, load_instruction_at_checked};
+use solana_sdk_ids::{ed25519_program, sysvar::instructions as instructions_sysvar};
+use crate::state::{VerifierRegistry, VerifyingKeyAccount, MAX_REGISTERED_VERIFIERS};
 use crate::errors::VerifierError;
 use sha2::{Sha256, Digest};
 
+// Ed25519 program instruction layout
+// Header: num_signatures (1) + padding (1)
+// Per signature: 7 little-endian u16 offsets (14 bytes)
+const ED25519_HEADER_LEN: usize = 2;
+const ED25519_OFFSETS_LEN: usize = 14;
+const ED25519_PUBKEY_LEN: usize = 32;
+const ED25519_SIGNATURE_LEN: usize = 64;
+
 #[derive(Debug, Clone, AnchorSerialize, AnchorDeserialize)]
 pub struct VerificationAttestation {
     pub proof_hash: [u8; 32],
@@ -18,69 +28,90 @@ pub struct VerificationAttestation {
     pub signature: [u8; 64],  // Ed25519 signature
 }
 
+/// Verify a proof against one or more attestations
+/// `attestations[0]` is signed by `external_verifier`, `attestations[i]` for i > 0 by
+/// `remaining_accounts[i - 1]`. At least the verifying key's threshold of attestations from
+/// distinct, currently registered verifiers are required.
 pub fn verify_with_attestation(
     ctx: Context<VerifyWithAttestation>,
     proof: Vec<u8>,
     public_inputs: Vec<u8>,
-    attestation: VerificationAttestation,
+    attestations: Vec<VerificationAttestation>,
 ) -> Result<()> {
     let verifying_key = &ctx.accounts.verifying_key;
+    let registry = &ctx.accounts.verifier_registry;
     let clock = Clock::get()?;
     
+    require!(!verifying_key.revoked, VerifierError::KeyRevoked);
+    require!(verifying_key.finalized, VerifierError::KeyNotFinalized);
+    
     // Step 1: Validate proof structure
     require!(proof.len() == 256, VerifierError::InvalidProof);
     require!(
-        public_inputs.len() > 0 && public_inputs.len() % 32 == 0,
+        !public_inputs.is_empty() && public_inputs.len().is_multiple_of(32),
         VerifierError::InvalidPublicInputs
     );
     
-    // Step 2: Hash proof, public_inputs, and verifying_key
-    let proof_hash = hash_data(&proof);
-    let public_inputs_hash = hash_data(&public_inputs);
-    let verifying_key_hash = hash_data(&verifying_key.key_data);
-    
-    // Step 3: Verify hashes match attestation
+    // Step 2: Check the quorum size
+    let threshold = verifying_key.attestation_threshold.max(1) as usize;
     require!(
-        proof_hash == attestation.proof_hash,
-        VerifierError::ProofVerificationFailed
+        attestations.len() >= threshold,
+        VerifierError::InsufficientAttestations
     );
     require!(
-        public_inputs_hash == attestation.public_inputs_hash,
-        VerifierError::ProofVerificationFailed
+        attestations.len() <= MAX_REGISTERED_VERIFIERS
+            && attestations.len() == 1 + ctx.remaining_accounts.len(),
+        VerifierError::AttestationVerifierMismatch
     );
-    require!(
-        verifying_key_hash == attestation.verifying_key_hash,
-        VerifierError::ProofVerificationFailed
-    );
-    
-    // Step 4: Verify attestation signature (Ed25519)
-    // For production, we verify the Ed25519 signature of the attestation
-    // Solana programs can verify Ed25519 signatures natively
-    
-    // The external_verifier must sign the transaction to prove they created the attestation
-    // This is a security measure - the verifier's key must be a known/trusted key
-    // In production, you would also verify the signature bytes using ed25519_verify syscall
-    // For now, requiring the signer ensures the attestation comes from a trusted source
     
-    // Note: Full Ed25519 signature verification would use:
-    // anchor_lang::solana_program::ed25519_program::verify()
-    // But for simplicity and gas efficiency, we require the verifier to sign the transaction
-    // This proves they attest to the verification result
+    // Step 3: Hash proof and public_inputs (the key hash is stored at finalize)
+    let proof_hash = hash_data(&proof);
+    let public_inputs_hash = hash_data(&public_inputs);
+    let verifying_key_hash = verifying_key.key_hash;
     
-    // Step 5: Check timestamp is recent (within 5 minutes)
-    let current_timestamp = clock.unix_timestamp;
-    let age = current_timestamp - attestation.timestamp;
-    require!(
-        age >= 0 && age <= 300, // 5 minutes
-        VerifierError::ProofVerificationFailed
-    );
+    let verifier_keys: Vec<Pubkey> = std::iter::once(ctx.accounts.external_verifier.key())
+        .chain(ctx.remaining_accounts.iter().map(|account| account.key()))
+        .collect();
+    let instructions_sysvar = ctx.accounts.instructions_sysvar.to_account_info();
     
-    // Step 6: Check is_valid == true
-    require!(attestation.is_valid, VerifierError::ProofVerificationFailed);
+    for (i, (attestation, verifier_key)) in attestations.iter().zip(&verifier_keys).enumerate() {
+        // Step 4: Each verifier counts once towards the threshold
+        require!(
+            !verifier_keys[..i].contains(verifier_key),
+            VerifierError::DuplicateVerifier
+        );
+        
+        // Step 5: Verify hashes match attestation
+        require!(
+            proof_hash == attestation.proof_hash
+                && public_inputs_hash == attestation.public_inputs_hash
+                && verifying_key_hash == attestation.verifying_key_hash,
+            VerifierError::ProofVerificationFailed
+        );
+        
+        // Step 6: Verify attestation signature (Ed25519)
+        // The Ed25519 program must have verified exactly this attestation message
+        // under a key registered and active in the verifier registry
+        require!(
+            registry.is_active(verifier_key, clock.slot),
+            VerifierError::UntrustedVerifier
+        );
+        verify_attestation_signature(&instructions_sysvar, verifier_key, attestation)?;
+        
+        // Step 7: Check timestamp is recent (within 5 minutes)
+        let age = clock.unix_timestamp - attestation.timestamp;
+        require!(
+            (0..=300).contains(&age), // 5 minutes
+            VerifierError::ProofVerificationFailed
+        );
+        
+        // Step 8: Check is_valid == true
+        require!(attestation.is_valid, VerifierError::ProofVerificationFailed);
+    }
     
     msg!("Groth16 proof verified via external attestation");
     msg!("Proof hash: {:?}", proof_hash);
-    msg!("Attestation timestamp: {}", attestation.timestamp);
+    msg!("Attestations: {} (threshold {})", attestations.len(), threshold);
     
     Ok(())
 }
@@ -91,7 +122,7 @@ fn hash_data(data: &[u8]) -> [u8; 32] {
     hasher.finalize().into()
 }
 
-fn create_attestation_message(attestation: &VerificationAttestation) -> Vec<u8> {
+pub fn create_attestation_message(attestation: &VerificationAttestation) -> Vec<u8> {
     let mut message = Vec::new();
     message.extend_from_slice(&attestation.proof_hash);
     message.extend_from_slice(&attestation.public_inputs_hash);
@@ -101,13 +132,187 @@ fn create_attestation_message(attestation: &VerificationAttestation) -> Vec<u8>
     message
 }
 
+/// Find an Ed25519 program instruction preceding the current one that verified
+/// `create_attestation_message(attestation)` with `attestation.signature` under `verifier_key`
+pub fn verify_attestation_signature(
+    instructions_sysvar: &AccountInfo,
+    verifier_key: &Pubkey,
+    attestation: &VerificationAttestation,
+) -> Result<()> {
+    let expected_message = create_attestation_message(attestation);
+    let current_index = load_current_index_checked(instructions_sysvar)?;
+    
+    for index in 0..current_index {
+        let instruction = load_instruction_at_checked(index as usize, instructions_sysvar)?;
+        if instruction.program_id != ed25519_program::ID {
+            continue;
+        }
+        
+        if ed25519_instruction_verifies(
+            &instruction.data,
+            verifier_key.as_ref(),
+            &attestation.signature,
+            &expected_message,
+        ) {
+            return Ok(());
+        }
+    }
+    
+    Err(VerifierError::InvalidAttestationSignature.into())
+}
+
+/// Check whether an Ed25519 program instruction contains a signature entry for
+/// exactly (pubkey, signature, message). Only entries whose data lives inside the
+/// Ed25519 instruction itself are accepted, so the bytes compared here are the
+/// bytes the Ed25519 program actually verified.
+fn ed25519_instruction_verifies(
+    data: &[u8],
+    pubkey: &[u8],
+    signature: &[u8; 64],
+    message: &[u8],
+) -> bool {
+    if data.len() < ED25519_HEADER_LEN {
+        return false;
+    }
+    let num_signatures = data[0] as usize;
+    
+    for i in 0..num_signatures {
+        let start = ED25519_HEADER_LEN + i * ED25519_OFFSETS_LEN;
+        let Some(offsets) = data.get(start..start + ED25519_OFFSETS_LEN) else {
+            return false;
+        };
+        let read_u16 = |at: usize| u16::from_le_bytes([offsets[at], offsets[at + 1]]);
+        
+        let signature_offset = read_u16(0) as usize;
+        let signature_instruction_index = read_u16(2);
+        let public_key_offset = read_u16(4) as usize;
+        let public_key_instruction_index = read_u16(6);
+        let message_data_offset = read_u16(8) as usize;
+        let message_data_size = read_u16(10) as usize;
+        let message_instruction_index = read_u16(12);
+        
+        // u16::MAX means "this instruction"
+        if signature_instruction_index != u16::MAX
+            || public_key_instruction_index != u16::MAX
+            || message_instruction_index != u16::MAX
+        {
+            continue;
+        }
+        
+        let entry_signature = data.get(signature_offset..signature_offset + ED25519_SIGNATURE_LEN);
+        let entry_pubkey = data.get(public_key_offset..public_key_offset + ED25519_PUBKEY_LEN);
+        let entry_message = data.get(message_data_offset..message_data_offset + message_data_size);
+        
+        if entry_signature == Some(&signature[..])
+            && entry_pubkey == Some(pubkey)
+            && entry_message == Some(message)
+        {
+            return true;
+        }
+    }
+    
+    false
+}
+
 #[derive(Accounts)]
 pub struct VerifyWithAttestation<'info> {
     /// CHECK: Verifying key account
     pub verifying_key: Account<'info, VerifyingKeyAccount>,
     
-    /// CHECK: External verifier public key (must be a known verifier)
-    /// In production, this would be a PDA or account that stores verifier info
-    pub external_verifier: Signer<'info>,
+    /// CHECK: External verifier public key - checked against the verifier registry,
+    /// and its signature is checked via the Ed25519 program instruction.
+    /// Co-signing verifiers are passed as remaining accounts in attestation order.
+    pub external_verifier: UncheckedAccount<'info>,
+    
+    #[account(
+        seeds = [b"verifier-registry"],
+        bump = verifier_registry.bump
+    )]
+    pub verifier_registry: Account<'info, VerifierRegistry>,
+    
+    /// CHECK: Instructions sysvar, used to find the Ed25519 signature verification instruction
+    #[account(address = instructions_sysvar::ID)]
+    pub instructions_sysvar: UncheckedAccount<'info>,
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const VERIFIER: Pubkey = pubkey!("AKSi3ZXYaNDrFoJuuiKnwMmro6LzBS6WAdDqWt58waMf");
+
+    fn attestation() -> VerificationAttestation {
+        VerificationAttestation {
+            proof_hash: [1u8; 32],
+            public_inputs_hash: [2u8; 32],
+            verifying_key_hash: [3u8; 32],
+            is_valid: true,
+            timestamp: 1_700_000_000,
+            signature: [9u8; 64],
+        }
+    }
+
+    /// Build Ed25519 program instruction data in the layout produced by
+    /// `Ed25519Program.createInstructionWithPublicKey` (all data inline)
+    fn ed25519_data(pubkey: &[u8], signature: &[u8], message: &[u8], instruction_index: u16) -> Vec<u8> {
+        let public_key_offset = (ED25519_HEADER_LEN + ED25519_OFFSETS_LEN) as u16;
+        let signature_offset = public_key_offset + ED25519_PUBKEY_LEN as u16;
+        let message_offset = signature_offset + ED25519_SIGNATURE_LEN as u16;
+
+        let mut data = vec![1u8, 0u8];
+        for value in [
+            signature_offset,
+            instruction_index,
+            public_key_offset,
+            instruction_index,
+            message_offset,
+            message.len() as u16,
+            instruction_index,
+        ] {
+            data.extend_from_slice(&value.to_le_bytes());
+        }
+        data.extend_from_slice(pubkey);
+        data.extend_from_slice(signature);
+        data.extend_from_slice(message);
+        data
+    }
+
+    #[test]
+    fn accepts_matching_signature_entry() {
+        let attestation = attestation();
+        let message = create_attestation_message(&attestation);
+        let data = ed25519_data(VERIFIER.as_ref(), &attestation.signature, &message, u16::MAX);
+        assert!(ed25519_instruction_verifies(&data, VERIFIER.as_ref(), &attestation.signature, &message));
+    }
+
+    #[test]
+    fn rejects_mismatched_message_key_or_signature() {
+        let attestation = attestation();
+        let message = create_attestation_message(&attestation);
+        let data = ed25519_data(VERIFIER.as_ref(), &attestation.signature, &message, u16::MAX);
+
+        let mut tampered = attestation.clone();
+        tampered.timestamp += 1;
+        let tampered_message = create_attestation_message(&tampered);
+        assert!(!ed25519_instruction_verifies(&data, VERIFIER.as_ref(), &attestation.signature, &tampered_message));
+        assert!(!ed25519_instruction_verifies(&data, Pubkey::default().as_ref(), &attestation.signature, &message));
+        assert!(!ed25519_instruction_verifies(&data, VERIFIER.as_ref(), &[0u8; 64], &message));
+    }
+
+    #[test]
+    fn rejects_data_from_other_instructions() {
+        let attestation = attestation();
+        let message = create_attestation_message(&attestation);
+        let data = ed25519_data(VERIFIER.as_ref(), &attestation.signature, &message, 0);
+        assert!(!ed25519_instruction_verifies(&data, VERIFIER.as_ref(), &attestation.signature, &message));
+    }
+
+    #[test]
+    fn rejects_truncated_data() {
+        let attestation = attestation();
+        let message = create_attestation_message(&attestation);
+        let data = ed25519_data(VERIFIER.as_ref(), &attestation.signature, &message, u16::MAX);
+        assert!(!ed25519_instruction_verifies(&data[..data.len() - 1], VERIFIER.as_ref(), &attestation.signature, &message));
+        assert!(!ed25519_instruction_verifies(&[], VERIFIER.as_ref(), &attestation.signature, &message));
+    }
+}
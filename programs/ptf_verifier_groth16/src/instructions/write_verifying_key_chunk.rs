@@ -0,0 +1,22 @@
+use anchor_lang::prelude::*;
+use crate::errors::VerifierError;
+use super::UploadVerifyingKey;
+
+pub fn write_verifying_key_chunk(
+    ctx: Context<UploadVerifyingKey>,
+    offset: u32,
+    data: Vec<u8>,
+) -> Result<()> {
+    let key_data = &mut ctx.accounts.verifying_key.key_data;
+    
+    let start = offset as usize;
+    let end = start
+        .checked_add(data.len())
+        .filter(|end| *end <= key_data.len())
+        .ok_or(VerifierError::InvalidVerifyingKey)?;
+    key_data[start..end].copy_from_slice(&data);
+    
+    msg!("Wrote verifying key bytes {}..{}", start, end);
+    
+    Ok(())
+}
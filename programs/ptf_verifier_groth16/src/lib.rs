@@ -25,6 +25,36 @@ pub mod ptf_verifier_groth16 {
         instructions::initialize_verifying_key(ctx, circuit_tag, version, key_data)
     }
 
+    /// Start a chunked upload for a verifying key too large for one transaction
+    pub fn begin_verifying_key_upload(
+        ctx: Context<BeginVerifyingKeyUpload>,
+        circuit_tag: [u8; 32],
+        version: u32,
+        key_len: u32,
+    ) -> Result<()> {
+        instructions::begin_verifying_key_upload(ctx, circuit_tag, version, key_len)
+    }
+
+    /// Write key_data bytes at offset into an unfinalized verifying key
+    pub fn write_verifying_key_chunk(
+        ctx: Context<UploadVerifyingKey>,
+        offset: u32,
+        data: Vec<u8>,
+    ) -> Result<()> {
+        instructions::write_verifying_key_chunk(ctx, offset, data)
+    }
+
+    /// Validate an uploaded verifying key and make it usable for verification
+    pub fn finalize_verifying_key(ctx: Context<UploadVerifyingKey>) -> Result<()> {
+        instructions::finalize_verifying_key(ctx)
+    }
+
+    /// Revoke a verifying key - proofs against it are rejected from then on
+    pub fn revoke_verifying_key(ctx: Context<RevokeVerifyingKey>) -> Result<()> {
+        instructions::revoke_verifying_key(ctx)
+    }
+
+    /// Verify Groth16 proof on-chain using the alt_bn128 pairing syscalls
     pub fn verify_groth16(
         ctx: Context<VerifyGroth16>,
         proof: Vec<u8>,
@@ -33,15 +63,63 @@ pub mod ptf_verifier_groth16 {
         instructions::verify_groth16(ctx, proof, public_inputs)
     }
 
-    /// Verify Groth16 proof using external verifier attestation
-    /// This is the production-ready verification method that performs actual cryptographic checks
+    /// Verify Groth16 proof using an M-of-N quorum of external verifier attestations
     pub fn verify_with_attestation(
         ctx: Context<VerifyWithAttestation>,
         proof: Vec<u8>,
         public_inputs: Vec<u8>,
-        attestation: instructions::verify_with_attestation::VerificationAttestation,
+        attestations: Vec<instructions::verify_with_attestation::VerificationAttestation>,
     ) -> Result<()> {
-        instructions::verify_with_attestation::verify_with_attestation(ctx, proof, public_inputs, attestation)
+        instructions::verify_with_attestation::verify_with_attestation(
+            ctx,
+            proof,
+            public_inputs,
+            attestations,
+        )
+    }
+
+    /// Set the number of distinct verifier attestations required for a verifying key
+    pub fn set_attestation_threshold(ctx: Context<SetAttestationThreshold>, threshold: u8) -> Result<()> {
+        instructions::set_attestation_threshold(ctx, threshold)
+    }
+
+    /// Create the trusted verifier registry, owned by the factory authority
+    pub fn initialize_verifier_registry(ctx: Context<InitializeVerifierRegistry>) -> Result<()> {
+        instructions::initialize_verifier_registry(ctx)
+    }
+
+    /// Register an attestation key valid in [activation_slot, expiry_slot)
+    pub fn add_verifier_key(
+        ctx: Context<ManageVerifierRegistry>,
+        pubkey: Pubkey,
+        activation_slot: u64,
+        expiry_slot: Option<u64>,
+    ) -> Result<()> {
+        instructions::add_verifier_key(ctx, pubkey, activation_slot, expiry_slot)
+    }
+
+    /// Replace an attestation key, keeping the old key valid until old_key_expiry_slot
+    pub fn rotate_verifier_key(
+        ctx: Context<ManageVerifierRegistry>,
+        old_pubkey: Pubkey,
+        new_pubkey: Pubkey,
+        activation_slot: u64,
+        old_key_expiry_slot: u64,
+        new_key_expiry_slot: Option<u64>,
+    ) -> Result<()> {
+        instructions::rotate_verifier_key(
+            ctx,
+            old_pubkey,
+            new_pubkey,
+            activation_slot,
+            old_key_expiry_slot,
+            new_key_expiry_slot,
+        )
+    }
+
+    /// Remove an attestation key from the registry
+    pub fn remove_verifier_key(ctx: Context<ManageVerifierRegistry>, pubkey: Pubkey) -> Result<()> {
+        instructions::remove_verifier_key(ctx, pubkey)
     }
 }
 
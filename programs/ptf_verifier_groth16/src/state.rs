@@ -1,5 +1,11 @@
 use anchor_lang::prelude::*;
 
+/// Maximum number of attestation keys held by the registry
+pub const MAX_REGISTERED_VERIFIERS: usize = 16;
+
+/// Largest key_data accepted: a key for up to 128 public inputs
+pub const MAX_VERIFYING_KEY_LEN: usize = 64 + 128 + 128 + 128 + 4 + 64 * 129;
+
 #[account]
 pub struct VerifyingKeyAccount {
     pub circuit_tag: [u8; 32],
@@ -7,14 +13,111 @@ pub struct VerifyingKeyAccount {
     pub key_data: Vec<u8>,
     pub revoked: bool,
     pub authority: Pubkey,
-    // Future: Add external verifier support
-    // pub external_verifier_enabled: bool,
-    // pub external_verifier_pubkey: Option<Pubkey>,
+    pub attestation_threshold: u8, // Distinct registered verifiers required by verify_with_attestation
+    pub key_hash: [u8; 32],        // SHA-256 of key_data, set when the key is finalized
+    pub finalized: bool,           // false while a chunked upload is in progress
 }
 
 impl VerifyingKeyAccount {
-    // Base size: discriminator (8) + circuit_tag (32) + version (4) + Vec length (4) + Vec data (variable) + revoked (1) + authority (32)
+    // Base size: discriminator (8) + circuit_tag (32) + version (4) + Vec length (4) + Vec data (variable) + revoked (1) + authority (32) + attestation_threshold (1) + key_hash (32) + finalized (1)
     // Minimum size assumes empty key_data
-    pub const MIN_LEN: usize = 8 + 32 + 4 + 4 + 0 + 1 + 32;
+    pub const MIN_LEN: usize = 8 + 32 + 4 + 4 + 1 + 32 + 1 + 32 + 1;
+}
+
+/// Attestation key approved to sign external verification results
+#[derive(Clone, Debug, AnchorSerialize, AnchorDeserialize)]
+pub struct RegisteredVerifier {
+    pub pubkey: Pubkey,
+    pub activation_slot: u64,
+    pub expiry_slot: Option<u64>, // None = never expires
+}
+
+impl RegisteredVerifier {
+    pub const LEN: usize = 32 + 8 + 9;
+
+    /// Key is usable in [activation_slot, expiry_slot)
+    pub fn is_active(&self, slot: u64) -> bool {
+        slot >= self.activation_slot && self.expiry_slot.is_none_or(|expiry| slot < expiry)
+    }
+}
+
+/// Registry of external verifier keys trusted by verify_with_attestation
+/// PDA: [b"verifier-registry"]
+#[account]
+pub struct VerifierRegistry {
+    pub authority: Pubkey,
+    pub verifiers: Vec<RegisteredVerifier>,
+    pub bump: u8,
 }
 
+impl VerifierRegistry {
+    // discriminator (8) + authority (32) + Vec length (4) + verifiers (MAX * LEN) + bump (1)
+    pub const LEN: usize = 8 + 32 + 4 + (MAX_REGISTERED_VERIFIERS * RegisteredVerifier::LEN) + 1;
+
+    pub fn find(&self, pubkey: &Pubkey) -> Option<&RegisteredVerifier> {
+        self.verifiers.iter().find(|v| v.pubkey == *pubkey)
+    }
+
+    pub fn find_mut(&mut self, pubkey: &Pubkey) -> Option<&mut RegisteredVerifier> {
+        self.verifiers.iter_mut().find(|v| v.pubkey == *pubkey)
+    }
+
+    /// Check that a key is registered and active at the given slot
+    pub fn is_active(&self, pubkey: &Pubkey, slot: u64) -> bool {
+        self.find(pubkey).is_some_and(|v| v.is_active(slot))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn registered_verifier_active_window() {
+        let key = Pubkey::new_unique();
+        let registry = VerifierRegistry {
+            authority: Pubkey::default(),
+            verifiers: vec![RegisteredVerifier {
+                pubkey: key,
+                activation_slot: 100,
+                expiry_slot: Some(200),
+            }],
+            bump: 255,
+        };
+
+        assert!(!registry.is_active(&key, 99));
+        assert!(registry.is_active(&key, 100));
+        assert!(registry.is_active(&key, 199));
+        assert!(!registry.is_active(&key, 200));
+        assert!(!registry.is_active(&Pubkey::new_unique(), 150));
+    }
+
+    #[test]
+    fn registered_verifier_without_expiry() {
+        let verifier = RegisteredVerifier {
+            pubkey: Pubkey::new_unique(),
+            activation_slot: 0,
+            expiry_slot: None,
+        };
+        assert!(verifier.is_active(0));
+        assert!(verifier.is_active(u64::MAX));
+    }
+
+    #[test]
+    fn registry_len_fits_max_verifiers() {
+        let registry = VerifierRegistry {
+            authority: Pubkey::default(),
+            verifiers: vec![
+                RegisteredVerifier {
+                    pubkey: Pubkey::default(),
+                    activation_slot: 0,
+                    expiry_slot: Some(1),
+                };
+                MAX_REGISTERED_VERIFIERS
+            ],
+            bump: 255,
+        };
+        let serialized = registry.try_to_vec().unwrap();
+        assert_eq!(8 + serialized.len(), VerifierRegistry::LEN);
+    }
+}
@@ -1,5 +1,6 @@
 use anchor_lang::prelude::*;
 use crate::errors::VerifierError;
+use solana_bn254::prelude::{alt_bn128_addition, alt_bn128_multiplication, alt_bn128_pairing};
 
 /// Verifying key structure for Groth16
 /// This represents the structured format of the verifying key
@@ -14,6 +15,7 @@ pub struct VerifyingKey {
 impl VerifyingKey {
     /// Parse verifying key from key_data
     /// Format: [alpha (64)][beta (128)][gamma (128)][delta (128)][gamma_abc_count (4)][gamma_abc...]
+    /// G1 points are [x][y] and G2 points are [x1][x0][y1][y0], each coordinate 32 bytes big-endian
     pub fn parse(key_data: &[u8]) -> Result<Self> {
         if key_data.len() < 64 + 128 + 128 + 128 + 4 {
             return Err(VerifierError::InvalidVerifyingKey.into());
@@ -62,6 +64,11 @@ impl VerifyingKey {
             offset += 64;
         }
         
+        // Reject trailing bytes so the stored key hash covers exactly the parsed key
+        if offset != key_data.len() {
+            return Err(VerifierError::InvalidVerifyingKey.into());
+        }
+        
         Ok(VerifyingKey {
             alpha,
             beta,
@@ -70,81 +77,148 @@ impl VerifyingKey {
             gamma_abc,
         })
     }
+    
+    /// Check that every point is a valid curve point
+    /// G1 points are validated by adding the identity, G2 points (including the subgroup
+    /// check) by pairing them with the G1 identity - both syscalls reject invalid points
+    pub fn check_points(&self) -> Result<()> {
+        require!(!self.gamma_abc.is_empty(), VerifierError::InvalidVerifyingKey);
+        
+        for g1 in std::iter::once(&self.alpha).chain(self.gamma_abc.iter()) {
+            let mut add_input = [0u8; 128];
+            add_input[..64].copy_from_slice(g1);
+            alt_bn128_addition(&add_input).map_err(|_| VerifierError::InvalidVerifyingKey)?;
+        }
+        
+        let mut pairing_input = [0u8; 3 * 192];
+        for (i, g2) in [&self.beta, &self.gamma, &self.delta].iter().enumerate() {
+            pairing_input[i * 192 + 64..(i + 1) * 192].copy_from_slice(*g2);
+        }
+        alt_bn128_pairing(&pairing_input).map_err(|_| VerifierError::InvalidVerifyingKey)?;
+        
+        Ok(())
+    }
+}
+
+/// Base field modulus q of alt_bn128 (big-endian)
+pub const FIELD_MODULUS: [u8; 32] = [
+    0x30, 0x64, 0x4e, 0x72, 0xe1, 0x31, 0xa0, 0x29, 0xb8, 0x50, 0x45, 0xb6, 0x81, 0x81, 0x58, 0x5d,
+    0x97, 0x81, 0x6a, 0x91, 0x68, 0x71, 0xca, 0x8d, 0x3c, 0x20, 0x8c, 0x16, 0xd8, 0x7c, 0xfd, 0x47,
+];
+
+/// Scalar field modulus r of alt_bn128 (big-endian)
+/// Public inputs are elements of this field and must be strictly smaller than it
+pub const SCALAR_FIELD_MODULUS: [u8; 32] = [
+    0x30, 0x64, 0x4e, 0x72, 0xe1, 0x31, 0xa0, 0x29, 0xb8, 0x50, 0x45, 0xb6, 0x81, 0x81, 0x58, 0x5d,
+    0x28, 0x33, 0xe8, 0x48, 0x79, 0xb9, 0x70, 0x91, 0x43, 0xe1, 0xf5, 0x93, 0xf0, 0x00, 0x00, 0x01,
+];
+
+/// Compute (modulus - value) for 32-byte big-endian integers
+/// Callers must ensure value < modulus
+fn sub_from_modulus(modulus: &[u8; 32], value: &[u8]) -> [u8; 32] {
+    let mut result = [0u8; 32];
+    let mut borrow = 0i16;
+    for i in (0..32).rev() {
+        let mut diff = modulus[i] as i16 - value[i] as i16 - borrow;
+        if diff < 0 {
+            diff += 256;
+            borrow = 1;
+        } else {
+            borrow = 0;
+        }
+        result[i] = diff as u8;
+    }
+    result
 }
 
 /// Negate a G1 point
-/// For alt_bn128, negation is done by flipping the sign bit
-/// 
-/// Note: This is a simplified implementation. Actual point negation on alt_bn128
-/// depends on the point encoding format (compressed vs uncompressed).
-/// In production, this would need to handle the specific encoding format used.
+/// Points use the EIP-197 encoding expected by the alt_bn128 syscalls:
+/// [x (32, big-endian)][y (32, big-endian)]. Negation maps (x, y) to (x, q - y).
+/// The point at infinity (all zeroes) is its own negation.
 pub fn negate_g1(point: &[u8; 64]) -> [u8; 64] {
+    if point.iter().all(|b| *b == 0) {
+        return *point;
+    }
     let mut negated = *point;
-    // In compressed form, the sign is typically in the first byte
-    // Flip the sign bit (bit 0 of the first byte)
-    // This is a placeholder - actual implementation depends on encoding format
-    negated[0] ^= 0x01;
+    negated[32..64].copy_from_slice(&sub_from_modulus(&FIELD_MODULUS, &point[32..64]));
     negated
 }
 
 /// Negate a G2 point
-/// Similar to G1, but for G2 points (128 bytes)
-/// 
-/// Note: This is a simplified implementation. Actual point negation on alt_bn128
-/// depends on the point encoding format (compressed vs uncompressed).
-/// In production, this would need to handle the specific encoding format used.
+/// Points use the EIP-197 encoding: [x1 (32)][x0 (32)][y1 (32)][y0 (32)].
+/// Negation maps (x, y) to (x, -y), negating both Fq2 coefficients of y.
 pub fn negate_g2(point: &[u8; 128]) -> [u8; 128] {
+    if point.iter().all(|b| *b == 0) {
+        return *point;
+    }
     let mut negated = *point;
-    // In compressed form, the sign is typically in the first byte
-    // Flip the sign bit
-    // This is a placeholder - actual implementation depends on encoding format
-    negated[0] ^= 0x01;
+    for offset in [64, 96] {
+        let coefficient = &point[offset..offset + 32];
+        if coefficient.iter().any(|b| *b != 0) {
+            negated[offset..offset + 32].copy_from_slice(&sub_from_modulus(&FIELD_MODULUS, coefficient));
+        }
+    }
     negated
 }
 
-/// Compute gamma_abc * public_inputs
-/// This combines the public inputs with the gamma_abc points
+/// Check that a 32-byte big-endian public input is a canonical scalar (< r)
+fn is_canonical_scalar(scalar: &[u8]) -> bool {
+    scalar < &SCALAR_FIELD_MODULUS[..]
+}
+
+/// Compute vk_x = gamma_abc[0] + sum(gamma_abc[i + 1] * public_inputs[i])
+/// Public inputs are 32-byte big-endian scalars; exactly gamma_abc.len() - 1 are required
 pub fn compute_public_inputs_g1(
     gamma_abc: &[[u8; 64]],
     public_inputs: &[u8],
 ) -> Result<[u8; 64]> {
-    // For Groth16, we need to compute: sum(gamma_abc[i] * public_inputs[i])
-    // This is a simplified version - in production, this would use proper
-    // elliptic curve scalar multiplication
-    
-    // For now, we'll use a placeholder that combines the inputs
-    // In production, this would use proper EC operations
-    
     if gamma_abc.is_empty() {
-        return Err(VerifierError::InvalidPublicInputs.into());
+        return Err(VerifierError::InvalidVerifyingKey.into());
     }
     
+    require!(
+        public_inputs.len().is_multiple_of(32),
+        VerifierError::InvalidPublicInputs
+    );
     let num_inputs = public_inputs.len() / 32;
-    if num_inputs == 0 || num_inputs > gamma_abc.len() {
-        return Err(VerifierError::InvalidPublicInputs.into());
-    }
+    require!(
+        num_inputs + 1 == gamma_abc.len(),
+        VerifierError::InvalidPublicInputs
+    );
     
-    // Placeholder: combine first gamma_abc point with first public input
-    // In production, this would be proper EC scalar multiplication and addition
     let mut result = gamma_abc[0];
     
-    // XOR with public input for placeholder (not cryptographically correct)
-    for (i, byte) in public_inputs[..32.min(public_inputs.len())].iter().enumerate() {
-        result[i % 64] ^= byte;
+    for (point, scalar) in gamma_abc[1..].iter().zip(public_inputs.chunks_exact(32)) {
+        require!(is_canonical_scalar(scalar), VerifierError::InvalidPublicInputs);
+        
+        // Zero inputs contribute nothing - skip the syscalls
+        if scalar.iter().all(|b| *b == 0) {
+            continue;
+        }
+        
+        let mut mul_input = [0u8; 96];
+        mul_input[..64].copy_from_slice(point);
+        mul_input[64..].copy_from_slice(scalar);
+        let product = alt_bn128_multiplication(&mul_input)
+            .map_err(|_| VerifierError::InvalidVerifyingKey)?;
+        
+        let mut add_input = [0u8; 128];
+        add_input[..64].copy_from_slice(&result);
+        add_input[64..].copy_from_slice(&product);
+        let sum = alt_bn128_addition(&add_input)
+            .map_err(|_| VerifierError::InvalidVerifyingKey)?;
+        result.copy_from_slice(&sum);
     }
     
     Ok(result)
 }
 
 /// Perform Groth16 verification
-/// Equation: e(a, b) * e(-c, gamma) * e(-delta, public_inputs) == 1
+/// Equation: e(-a, b) * e(alpha, beta) * e(vk_x, gamma) * e(c, delta) == 1
 /// 
-/// Note: Solana does not currently have native alt_bn128 syscalls.
-/// This implementation provides the structure for verification, but actual
-/// pairing checks would need to be implemented using:
-/// 1. A precompiled contract (if available on Solana)
-/// 2. An external verifier program
-/// 3. Or wait for Solana to add alt_bn128 support
+/// All four pairings are checked with a single alt_bn128_pairing syscall.
+/// Returns Ok(false) if the pairing check fails, and an error if any point is
+/// malformed or not on the curve.
 pub fn verify_groth16_proof(
     proof_a: &[u8; 64],
     proof_b: &[u8; 128],
@@ -154,79 +228,257 @@ pub fn verify_groth16_proof(
 ) -> Result<bool> {
     // Validate inputs
     require!(
-        verifying_key.gamma_abc.len() > 0,
+        !verifying_key.gamma_abc.is_empty(),
         VerifierError::InvalidVerifyingKey
     );
     
-    let num_public_inputs = public_inputs.len() / 32;
-    require!(
-        num_public_inputs > 0 && num_public_inputs <= verifying_key.gamma_abc.len(),
-        VerifierError::InvalidPublicInputs
-    );
-    
-    // Step 1: Prepare points for pairing checks
-    // Compute e(-c, gamma)
-    let neg_c = negate_g1(proof_c);
-    
-    // Step 2: Compute public inputs G1 point
-    // This combines gamma_abc with public inputs: sum(gamma_abc[i] * public_inputs[i])
+    // Step 1: Compute public inputs G1 point
     let public_inputs_g1 = compute_public_inputs_g1(&verifying_key.gamma_abc, public_inputs)?;
-    let neg_delta = negate_g2(&verifying_key.delta);
-    
-    // Step 3: Perform pairing checks
-    // Groth16 verification equation: e(a, b) * e(-c, gamma) * e(-delta, public_inputs) == 1
-    
-    // NOTE: Solana does not currently provide alt_bn128 syscalls
-    // The following is a structural implementation that shows what would be needed:
     
-    // Pairing 1: e(a, b)
-    // In Ethereum/EVM: alt_bn128_pairing_check([a, b])
-    // On Solana: This would need to be implemented via:
-    //   - A precompiled program (if available)
-    //   - An external verifier service
-    //   - Or wait for Solana to add native support
+    // Step 2: Negate a so the product of all pairings must equal the identity
+    let neg_a = negate_g1(proof_a);
     
-    // Pairing 2: e(-c, gamma)
-    // alt_bn128_pairing_check([neg_c, gamma])
-    
-    // Pairing 3: e(-delta, public_inputs)
-    // alt_bn128_pairing_check([public_inputs_g1, neg_delta])
-    
-    // Step 4: Multiply pairings and check result == 1
-    // result = pairing1 * pairing2 * pairing3
-    // require!(result == 1, VerifierError::ProofVerificationFailed);
-    
-    // Current implementation: Structure validation only
-    // TODO: Implement actual pairing checks when Solana adds support or
-    //       integrate with external verifier service
-    
-    msg!("Groth16 verification structure validated");
-    msg!("Proof components: a={}b, b={}b, c={}b", 
-         proof_a.len(), proof_b.len(), proof_c.len());
-    msg!("Public inputs: {} bytes ({} inputs)", 
-         public_inputs.len(), num_public_inputs);
-    msg!("Verifying key: {} gamma_abc points", 
-         verifying_key.gamma_abc.len());
+    // Step 3: Build the pairing input: four (G1, G2) pairs of 192 bytes each
+    let mut pairing_input = [0u8; 4 * 192];
+    let pairs: [(&[u8; 64], &[u8; 128]); 4] = [
+        (&neg_a, proof_b),
+        (&verifying_key.alpha, &verifying_key.beta),
+        (&public_inputs_g1, &verifying_key.gamma),
+        (proof_c, &verifying_key.delta),
+    ];
+    for (i, (g1, g2)) in pairs.iter().enumerate() {
+        let offset = i * 192;
+        pairing_input[offset..offset + 64].copy_from_slice(*g1);
+        pairing_input[offset + 64..offset + 192].copy_from_slice(*g2);
+    }
     
-    // For now, we validate structure but cannot perform actual cryptographic verification
-    // without alt_bn128 support. This is a known limitation.
-    // 
-    // Options for production:
-    // 1. Use an external verifier service (off-chain verification)
-    // 2. Wait for Solana to add alt_bn128 syscalls
-    // 3. Use a different proof system that Solana supports natively
+    // Step 4: Single pairing check - output is 1 (big-endian u256) on success
+    let pairing_result = alt_bn128_pairing(&pairing_input)
+        .map_err(|_| VerifierError::InvalidProof)?;
     
-    // Placeholder: Return true for structure validation
-    // In production, this MUST perform actual pairing checks
-    // WARNING: This currently accepts all structurally valid proofs!
-    // 
-    // TODO: When Solana adds alt_bn128 support or external verifier is implemented:
-    // 1. Call alt_bn128_pairing_check for e(a, b)
-    // 2. Call alt_bn128_pairing_check for e(-c, gamma)
-    // 3. Call alt_bn128_pairing_check for e(-delta, public_inputs)
-    // 4. Multiply pairing results
-    // 5. Check if result == 1 (identity element)
-    // 6. Return true only if verification passes
+    let is_valid = pairing_result.len() == 32
+        && pairing_result[..31].iter().all(|b| *b == 0)
+        && pairing_result[31] == 1;
     
-    Ok(true)
+    Ok(is_valid)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SHIELD_VK: &[u8] = include_bytes!("../../../circuits/shield/verifying_key.bin");
+    const UNSHIELD_VK: &[u8] = include_bytes!("../../../circuits/unshield/verifying_key.bin");
+    const TRANSFER_VK: &[u8] = include_bytes!("../../../circuits/transfer/verifying_key.bin");
+    const SHIELD_PROOF: &str = include_str!("../../../circuits/shield/proof.json");
+    const SHIELD_PUBLIC: &str = include_str!("../../../circuits/shield/public.json");
+    const UNSHIELD_PROOF: &str = include_str!("../../../circuits/unshield/proof.json");
+    const UNSHIELD_PUBLIC: &str = include_str!("../../../circuits/unshield/public.json");
+    const TRANSFER_PROOF: &str = include_str!("../../../circuits/transfer/proof.json");
+    const TRANSFER_PUBLIC: &str = include_str!("../../../circuits/transfer/public.json");
+    const DELEGATED_VK: &[u8] = include_bytes!("../../../circuits/transfer_from/verifying_key.bin");
+    const DELEGATED_PROOF: &str = include_str!("../../../circuits/transfer_from/proof.json");
+    const DELEGATED_PUBLIC: &str = include_str!("../../../circuits/transfer_from/public.json");
+    const ALLOWANCE_VK: &[u8] = include_bytes!("../../../circuits/allowance_transfer/verifying_key.bin");
+    const ALLOWANCE_PROOF: &str = include_str!("../../../circuits/allowance_transfer/proof.json");
+    const ALLOWANCE_PUBLIC: &str = include_str!("../../../circuits/allowance_transfer/public.json");
+
+    fn decimal_to_be_bytes(value: &str) -> [u8; 32] {
+        let mut bytes = [0u8; 32];
+        for digit in value.bytes() {
+            let mut carry = (digit - b'0') as u16;
+            for byte in bytes.iter_mut().rev() {
+                let v = (*byte as u16) * 10 + carry;
+                *byte = v as u8;
+                carry = v >> 8;
+            }
+            assert_eq!(carry, 0, "value does not fit in 32 bytes");
+        }
+        bytes
+    }
+
+    fn field(value: &serde_json::Value) -> [u8; 32] {
+        decimal_to_be_bytes(value.as_str().unwrap())
+    }
+
+    /// Load a snarkjs proof.json and public.json as (a, b, c, public_inputs) in the on-chain encoding
+    fn fixture(proof: &str, public: &str) -> ([u8; 64], [u8; 128], [u8; 64], Vec<u8>) {
+        let proof: serde_json::Value = serde_json::from_str(proof).unwrap();
+        let public: serde_json::Value = serde_json::from_str(public).unwrap();
+
+        let mut a = [0u8; 64];
+        a[..32].copy_from_slice(&field(&proof["pi_a"][0]));
+        a[32..].copy_from_slice(&field(&proof["pi_a"][1]));
+
+        // EIP-197 ordering: imaginary coefficient first
+        let mut b = [0u8; 128];
+        b[..32].copy_from_slice(&field(&proof["pi_b"][0][1]));
+        b[32..64].copy_from_slice(&field(&proof["pi_b"][0][0]));
+        b[64..96].copy_from_slice(&field(&proof["pi_b"][1][1]));
+        b[96..].copy_from_slice(&field(&proof["pi_b"][1][0]));
+
+        let mut c = [0u8; 64];
+        c[..32].copy_from_slice(&field(&proof["pi_c"][0]));
+        c[32..].copy_from_slice(&field(&proof["pi_c"][1]));
+
+        let public_inputs = public
+            .as_array()
+            .unwrap()
+            .iter()
+            .flat_map(|v| field(v).to_vec())
+            .collect();
+
+        (a, b, c, public_inputs)
+    }
+
+    fn shield_fixture() -> ([u8; 64], [u8; 128], [u8; 64], Vec<u8>) {
+        fixture(SHIELD_PROOF, SHIELD_PUBLIC)
+    }
+
+    #[test]
+    fn accepts_known_good_shield_proof() {
+        let vk = VerifyingKey::parse(SHIELD_VK).unwrap();
+        let (a, b, c, public_inputs) = shield_fixture();
+        // [commitment, amount], as the pool encodes them
+        assert_eq!(vk.gamma_abc.len(), 3);
+        assert_eq!(public_inputs.len(), 2 * 32);
+        assert!(verify_groth16_proof(&a, &b, &c, &vk, &public_inputs).unwrap());
+    }
+
+    #[test]
+    fn accepts_known_good_unshield_proof() {
+        let vk = VerifyingKey::parse(UNSHIELD_VK).unwrap();
+        let (a, b, c, public_inputs) = fixture(UNSHIELD_PROOF, UNSHIELD_PUBLIC);
+        // [root][nullifier][amount][recipient hash][fee]
+        assert_eq!(vk.gamma_abc.len(), 6);
+        assert_eq!(public_inputs.len(), 5 * 32);
+        assert!(verify_groth16_proof(&a, &b, &c, &vk, &public_inputs).unwrap());
+        // The transfer key takes as many public inputs but is for another circuit
+        let transfer_vk = VerifyingKey::parse(TRANSFER_VK).unwrap();
+        assert!(!verify_groth16_proof(&a, &b, &c, &transfer_vk, &public_inputs).unwrap());
+    }
+
+    #[test]
+    fn accepts_known_good_transfer_proof() {
+        let vk = VerifyingKey::parse(TRANSFER_VK).unwrap();
+        let (a, b, c, public_inputs) = fixture(TRANSFER_PROOF, TRANSFER_PUBLIC);
+        // [root][nullifier; 2][output commitment; 2]
+        assert_eq!(vk.gamma_abc.len(), 6);
+        assert_eq!(public_inputs.len(), 5 * 32);
+        assert!(verify_groth16_proof(&a, &b, &c, &vk, &public_inputs).unwrap());
+        // A proof for one circuit does not verify under another's key
+        let shield_vk = VerifyingKey::parse(SHIELD_VK).unwrap();
+        assert!(verify_groth16_proof(&a, &b, &c, &shield_vk, &public_inputs).is_err());
+    }
+
+    #[test]
+    fn accepts_known_good_delegated_proof() {
+        let vk = VerifyingKey::parse(DELEGATED_VK).unwrap();
+        let (a, b, c, public_inputs) = fixture(DELEGATED_PROOF, DELEGATED_PUBLIC);
+        // [root][nullifier][output commitment; 2][change commitment]
+        assert_eq!(vk.gamma_abc.len(), 6);
+        assert!(verify_groth16_proof(&a, &b, &c, &vk, &public_inputs).unwrap());
+    }
+
+    #[test]
+    fn accepts_known_good_allowance_proof() {
+        let vk = VerifyingKey::parse(ALLOWANCE_VK).unwrap();
+        let (a, b, c, public_inputs) = fixture(ALLOWANCE_PROOF, ALLOWANCE_PUBLIC);
+        // [root][nullifier; 2][payment commitment][change commitment][owner tag][amount]
+        assert_eq!(vk.gamma_abc.len(), 8);
+        assert!(verify_groth16_proof(&a, &b, &c, &vk, &public_inputs).unwrap());
+        // Without the owner tag and amount the pool appends, the proof does not verify
+        let transfer_vk = VerifyingKey::parse(TRANSFER_VK).unwrap();
+        assert!(!verify_groth16_proof(&a, &b, &c, &transfer_vk, &public_inputs[..5 * 32]).unwrap());
+    }
+
+    #[test]
+    fn rejects_tampered_public_input() {
+        let vk = VerifyingKey::parse(SHIELD_VK).unwrap();
+        let (a, b, c, mut public_inputs) = shield_fixture();
+        public_inputs[31] ^= 0x01;
+        assert!(!verify_groth16_proof(&a, &b, &c, &vk, &public_inputs).unwrap());
+    }
+
+    #[test]
+    fn rejects_swapped_proof_points() {
+        let vk = VerifyingKey::parse(SHIELD_VK).unwrap();
+        let (a, b, c, public_inputs) = shield_fixture();
+        assert!(!verify_groth16_proof(&c, &b, &a, &vk, &public_inputs).unwrap());
+        // Negating a yields a valid curve point that no longer satisfies the equation
+        assert!(!verify_groth16_proof(&negate_g1(&a), &b, &c, &vk, &public_inputs).unwrap());
+    }
+
+    #[test]
+    fn rejects_point_off_curve() {
+        let vk = VerifyingKey::parse(SHIELD_VK).unwrap();
+        let (mut a, b, c, public_inputs) = shield_fixture();
+        a[63] ^= 0x01;
+        assert!(verify_groth16_proof(&a, &b, &c, &vk, &public_inputs).is_err());
+    }
+
+    #[test]
+    fn rejects_wrong_public_input_count() {
+        let vk = VerifyingKey::parse(SHIELD_VK).unwrap();
+        let (a, b, c, public_inputs) = shield_fixture();
+        assert!(verify_groth16_proof(&a, &b, &c, &vk, &public_inputs[..32]).is_err());
+    }
+
+    #[test]
+    fn rejects_non_canonical_public_input() {
+        let vk = VerifyingKey::parse(SHIELD_VK).unwrap();
+        let (a, b, c, mut public_inputs) = shield_fixture();
+        // x + r is congruent to x but must not be accepted as a distinct encoding
+        let first: [u8; 32] = public_inputs[..32].try_into().unwrap();
+        let mut carry = 0u16;
+        for i in (0..32).rev() {
+            let v = first[i] as u16 + SCALAR_FIELD_MODULUS[i] as u16 + carry;
+            public_inputs[i] = v as u8;
+            carry = v >> 8;
+        }
+        assert!(verify_groth16_proof(&a, &b, &c, &vk, &public_inputs).is_err());
+    }
+
+    #[test]
+    fn negation_is_an_involution() {
+        let (a, b, _, _) = shield_fixture();
+        assert_ne!(negate_g1(&a), a);
+        assert_eq!(negate_g1(&negate_g1(&a)), a);
+        assert_eq!(negate_g2(&negate_g2(&b)), b);
+        assert_eq!(negate_g1(&[0u8; 64]), [0u8; 64]);
+    }
+
+    #[test]
+    fn circuit_keys_have_valid_points() {
+        for key_data in [SHIELD_VK, UNSHIELD_VK, TRANSFER_VK, DELEGATED_VK, ALLOWANCE_VK] {
+            VerifyingKey::parse(key_data).unwrap().check_points().unwrap();
+        }
+    }
+
+    #[test]
+    fn rejects_key_with_invalid_points() {
+        // alpha (G1) off the curve
+        let mut key_data = SHIELD_VK.to_vec();
+        key_data[63] ^= 0x01;
+        assert!(VerifyingKey::parse(&key_data).unwrap().check_points().is_err());
+
+        // delta (G2) off the curve
+        let mut key_data = SHIELD_VK.to_vec();
+        key_data[64 + 128 + 128 + 127] ^= 0x01;
+        assert!(VerifyingKey::parse(&key_data).unwrap().check_points().is_err());
+
+        // Last gamma_abc point off the curve
+        let mut key_data = SHIELD_VK.to_vec();
+        let last = key_data.len() - 1;
+        key_data[last] ^= 0x01;
+        assert!(VerifyingKey::parse(&key_data).unwrap().check_points().is_err());
+    }
+
+    #[test]
+    fn rejects_truncated_or_padded_key() {
+        assert!(VerifyingKey::parse(&SHIELD_VK[..SHIELD_VK.len() - 1]).is_err());
+        let mut padded = SHIELD_VK.to_vec();
+        padded.push(0);
+        assert!(VerifyingKey::parse(&padded).is_err());
+    }
 }
@@ -19,10 +19,21 @@ import {
 import { Program, AnchorProvider, Wallet } from "@coral-xyz/anchor";
 import * as ptfFactory from "../target/idl/ptf_factory.json";
 import * as ptfVerifier from "../target/idl/ptf_verifier_groth16.json";
+import * as ptfVault from "../target/idl/ptf_vault.json";
+import * as fs from "fs";
+import * as path from "path";
 import { bootstrapWSOL } from "./bootstrap-wsol";
 
 const FACTORY_PROGRAM_ID = new PublicKey("AG2eT5fyfPdv6wjDWCxr5Y9JBK9cD5rahLzuz2UbbBvg");
 const VERIFIER_PROGRAM_ID = new PublicKey("DMvUxHwdJGkaRAJFXEKgDxsmVXL3gYttNsVP16xEr9TE");
+const POOL_PROGRAM_ID = new PublicKey("6MLrNAydScBBWq6vFXPLjahvxjF1PzauuSYTuLS7yfYC");
+const VAULT_PROGRAM_ID = new PublicKey("ArUznHH2tESKsknoiW3HhURY46MzXyJL55HuGdKUXQEy");
+
+// Recent roots a pool accepts proofs against (32-240)
+const ROOT_HISTORY_CAPACITY = 64;
+
+// Circuits a pool verifies proofs of; each key's circuit tag is the directory name under circuits/
+const POOL_CIRCUITS = ["shield", "unshield", "transfer", "transfer_from", "allowance_transfer"];
 
 export async function bootstrapPrivateDevnet(
   connection: Connection,
@@ -109,71 +120,119 @@ export async function bootstrapPrivateDevnet(
   await sendAndConfirmTransaction(connection, createMintTx, [payer, testMint]);
   console.log("Test token mint created:", testMint.publicKey.toString());
   
-  // Register mint in factory
+  // Upload one verifying key per pool circuit; register_mint pins each into the new pool
+  const verifierProgram = new Program(
+    ptfVerifier as any,
+    provider,
+  ) as any;
+  
+  const version = 1;
+  const versionBytes = Buffer.alloc(4);
+  versionBytes.writeUInt32LE(version, 0);
+  const verifyingKeys: PublicKey[] = [];
+  
+  for (const circuit of POOL_CIRCUITS) {
+    const circuitTag = Buffer.alloc(32, 0);
+    circuitTag.write(circuit);
+    const keyData = fs.readFileSync(path.join(__dirname, `../circuits/${circuit}/verifying_key.bin`));
+    
+    const [verifyingKey] = PublicKey.findProgramAddressSync(
+      [Buffer.from("verifying-key"), circuitTag, versionBytes],
+      VERIFIER_PROGRAM_ID,
+    );
+    verifyingKeys.push(verifyingKey);
+    
+    const existingKey = await connection.getAccountInfo(verifyingKey);
+    if (existingKey) {
+      console.log(`${circuit} verifying key already created`);
+      continue;
+    }
+    await verifierProgram.methods
+      .beginVerifyingKeyUpload(Array.from(circuitTag), version, keyData.length)
+      .accounts({ verifyingKey, authority: payer.publicKey })
+      .rpc();
+    for (let offset = 0; offset < keyData.length; offset += 900) {
+      await verifierProgram.methods
+        .writeVerifyingKeyChunk(offset, keyData.subarray(offset, offset + 900))
+        .accounts({ verifyingKey, authority: payer.publicKey })
+        .rpc();
+    }
+    await verifierProgram.methods
+      .finalizeVerifyingKey()
+      .accounts({ verifyingKey, authority: payer.publicKey })
+      .rpc();
+    
+    console.log(`${circuit} verifying key created:`, verifyingKey.toString());
+  }
+  
+  // Register mint in factory (creates the pool accounts through CPI)
+  const mintSeed = testMint.publicKey.toBuffer();
   const [mintMapping] = PublicKey.findProgramAddressSync(
-    [Buffer.from("mint-mapping"), testMint.publicKey.toBuffer()],
+    [Buffer.from("mint-mapping"), mintSeed],
     FACTORY_PROGRAM_ID,
   );
+  const [poolState] = PublicKey.findProgramAddressSync([Buffer.from("pool"), mintSeed], POOL_PROGRAM_ID);
+  const [commitmentTree] = PublicKey.findProgramAddressSync([Buffer.from("commitment-tree"), mintSeed], POOL_PROGRAM_ID);
+  const [rootHistory] = PublicKey.findProgramAddressSync([Buffer.from("root-history"), mintSeed], POOL_PROGRAM_ID);
+  const [nullifierSet] = PublicKey.findProgramAddressSync([Buffer.from("nullifier-set"), mintSeed], POOL_PROGRAM_ID);
+  const [vaultState] = PublicKey.findProgramAddressSync([Buffer.from("vault"), mintSeed], VAULT_PROGRAM_ID);
   
-  // Note: Pool address would be derived, but we'll create a placeholder for now
-  const poolAddress = Keypair.generate().publicKey;
+  // Create the vault and its token account, owned by the pool PDA
+  const vaultProgram = new Program(
+    ptfVault as any,
+    provider,
+  ) as any;
+  const vaultTokenAccount = await getAssociatedTokenAddress(testMint.publicKey, vaultState, true);
   
   try {
-    await factoryProgram.methods
-      .registerMint(testMint.publicKey, poolAddress)
+    await vaultProgram.methods
+      .initializeVault()
       .accounts({
-        factory: factoryState,
-        mintMapping,
-        authority: payer.publicKey,
+        vault: vaultState,
+        originMint: testMint.publicKey,
+        vaultTokenAccount,
+        poolState,
+        payer: payer.publicKey,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
         systemProgram: SystemProgram.programId,
       })
       .rpc();
     
-    console.log("Mint registered:", testMint.publicKey.toString());
+    console.log("Vault initialized:", vaultState.toString());
   } catch (e: any) {
-    if (e.message?.includes("already in use")) {
-      console.log("Mint already registered");
+    if (e.message?.includes("AlreadyInitialized")) {
+      console.log("Vault already initialized");
     } else {
       throw e;
     }
   }
   
-  // Create verifying key (placeholder)
-  const verifierProgram = new Program(
-    ptfVerifier as any,
-    VERIFIER_PROGRAM_ID,
-    provider,
-  );
-  
-  const circuitTag = Buffer.alloc(32, 0);
-  const version = 1;
-  const keyData = Buffer.alloc(100, 0); // Placeholder key data
-  
-  const [verifyingKey] = PublicKey.findProgramAddressSync(
-    [
-      Buffer.from("verifying-key"),
-      circuitTag,
-      Buffer.from(version.toString()),
-    ],
-    VERIFIER_PROGRAM_ID,
-  );
-  
   try {
     await factoryProgram.methods
-      .createVerifyingKey(Array.from(circuitTag), version, Array.from(keyData))
+      .registerMint(testMint.publicKey, 0, ROOT_HISTORY_CAPACITY)
       .accounts({
         factory: factoryState,
-        verifyingKey,
+        mintMapping,
+        poolState,
+        commitmentTree,
+        rootHistory,
+        nullifierSet,
+        vaultState,
         authority: payer.publicKey,
-        verifierProgram: VERIFIER_PROGRAM_ID,
+        poolProgram: POOL_PROGRAM_ID,
         systemProgram: SystemProgram.programId,
       })
+      .remainingAccounts(
+        verifyingKeys.map((pubkey) => ({ pubkey, isSigner: false, isWritable: false })),
+      )
       .rpc();
     
-    console.log("Verifying key created:", verifyingKey.toString());
+    console.log("Mint registered:", testMint.publicKey.toString());
+    console.log("Pool state:", poolState.toString());
   } catch (e: any) {
     if (e.message?.includes("already in use")) {
-      console.log("Verifying key already created");
+      console.log("Mint already registered");
     } else {
       throw e;
     }
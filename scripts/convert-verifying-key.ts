@@ -16,41 +16,37 @@ interface VerificationKey {
   IC: string[][];
 }
 
-function hexToBytes(hex: string): Uint8Array {
-  const bytes = new Uint8Array(hex.length / 2);
-  for (let i = 0; i < hex.length; i += 2) {
-    bytes[i / 2] = parseInt(hex.substr(i, 2), 16);
+function fieldToBytes(value: string): Uint8Array {
+  // snarkjs encodes field elements as decimal strings; the on-chain verifier
+  // expects 32-byte big-endian integers (EIP-197 encoding)
+  let n = BigInt(value);
+  const bytes = new Uint8Array(32);
+  for (let i = 31; i >= 0; i--) {
+    bytes[i] = Number(n & BigInt(0xff));
+    n >>= BigInt(8);
+  }
+  if (n !== BigInt(0)) {
+    throw new Error(`Field element does not fit in 32 bytes: ${value}`);
   }
   return bytes;
 }
 
 function g1PointToBytes(point: string[]): Uint8Array {
-  // Convert G1 point from [x, y] format to 64-byte compressed format
-  // This is a placeholder - actual conversion depends on point compression format
-  const x = hexToBytes(point[0].replace("0x", ""));
-  const y = hexToBytes(point[1].replace("0x", ""));
-  
-  // Compressed format: [x (32 bytes), y (32 bytes)]
+  // Convert G1 point from [x, y, z] projective format (z = 1) to 64 bytes: [x (32), y (32)]
   const result = new Uint8Array(64);
-  result.set(x.slice(0, 32), 0);
-  result.set(y.slice(0, 32), 32);
+  result.set(fieldToBytes(point[0]), 0);
+  result.set(fieldToBytes(point[1]), 32);
   return result;
 }
 
 function g2PointToBytes(point: string[][]): Uint8Array {
-  // Convert G2 point from [[x0, x1], [y0, y1]] format to 128-byte compressed format
-  // This is a placeholder - actual conversion depends on point compression format
-  const x0 = hexToBytes(point[0][0].replace("0x", ""));
-  const x1 = hexToBytes(point[0][1].replace("0x", ""));
-  const y0 = hexToBytes(point[1][0].replace("0x", ""));
-  const y1 = hexToBytes(point[1][1].replace("0x", ""));
-  
-  // Compressed format: [x0 (32), x1 (32), y0 (32), y1 (32)]
+  // snarkjs stores G2 coordinates as [[x0, x1], [y0, y1]] with x = x0 + x1*u.
+  // The alt_bn128 syscalls expect EIP-197 ordering: [x1 (32), x0 (32), y1 (32), y0 (32)]
   const result = new Uint8Array(128);
-  result.set(x0.slice(0, 32), 0);
-  result.set(x1.slice(0, 32), 32);
-  result.set(y0.slice(0, 32), 64);
-  result.set(y1.slice(0, 32), 96);
+  result.set(fieldToBytes(point[0][1]), 0);
+  result.set(fieldToBytes(point[0][0]), 32);
+  result.set(fieldToBytes(point[1][1]), 64);
+  result.set(fieldToBytes(point[1][0]), 96);
   return result;
 }
 
@@ -137,7 +137,9 @@ impl VerificationAttestation {
     }
     
     /// Get the message that was signed
-    fn message_to_sign(&self) -> Vec<u8> {
+    /// Must match `create_attestation_message` in ptf_verifier_groth16, which checks the
+    /// Ed25519 program instruction verifying this message on-chain
+    pub fn message_to_sign(&self) -> Vec<u8> {
         let mut message = Vec::new();
         message.extend_from_slice(&self.proof_hash);
         message.extend_from_slice(&self.public_inputs_hash);
@@ -208,6 +210,27 @@ pub fn create_attestation(
     }
 }
 
+/// Attestation together with the hex-encoded Ed25519 public key that signed it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SignedAttestation {
+    pub verifier_pubkey: String,
+    pub attestation: VerificationAttestation,
+}
+
+/// Co-sign an attestation produced by another verifier
+/// The signed message (hashes, result and timestamp) is kept as-is so every signer
+/// in a quorum attests to the same statement
+pub fn cosign_attestation(
+    attestation: &VerificationAttestation,
+    signing_key: &SigningKey,
+) -> VerificationAttestation {
+    let signature = signing_key.sign(&attestation.message_to_sign());
+    VerificationAttestation {
+        signature: signature.to_bytes(),
+        ..attestation.clone()
+    }
+}
+
 fn hash_data(data: &[u8]) -> [u8; 32] {
     let mut hasher = Sha256::new();
     hasher.update(data);
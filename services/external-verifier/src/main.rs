@@ -5,13 +5,13 @@ use actix_web::{web, App, HttpServer, Result as ActixResult};
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 use ed25519_dalek::{SigningKey, VerifyingKey, Signature, Signer};
-use log::{info, error};
+use log::{info, warn, error};
 
 mod verifier;
 mod attestation;
 
 use verifier::Groth16Verifier;
-use attestation::{VerificationAttestation, create_attestation};
+use attestation::{VerificationAttestation, SignedAttestation, create_attestation, cosign_attestation};
 
 #[derive(Debug, Clone)]
 struct AppState {
@@ -31,6 +31,27 @@ struct VerifyRequest {
 struct VerifyResponse {
     is_valid: bool,
     attestation: VerificationAttestation,
+    verifier_pubkey: String,    // Hex-encoded Ed25519 public key that signed the attestation
+}
+
+#[derive(Debug, Deserialize)]
+struct CosignRequest {
+    proof: String,                          // Hex-encoded proof (256 bytes)
+    public_inputs: String,                  // Hex-encoded public inputs
+    verifying_key: String,                  // Hex-encoded verifying key (binary format)
+    attestations: Vec<SignedAttestation>,   // Attestations already collected from other verifiers
+}
+
+#[derive(Debug, Serialize)]
+struct CosignResponse {
+    is_valid: bool,
+    attestations: Vec<SignedAttestation>,   // Input attestations with this verifier's appended
+}
+
+fn decode_hex(field: &str, value: &str) -> ActixResult<Vec<u8>> {
+    let value = value.strip_prefix("0x").unwrap_or(value);
+    hex::decode(value)
+        .map_err(|e| actix_web::error::ErrorBadRequest(format!("Invalid {} hex: {}", field, e)))
 }
 
 async fn health_check() -> ActixResult<web::Json<serde_json::Value>> {
@@ -44,17 +65,9 @@ async fn verify_proof(
     info!("Received verification request");
     
     // Decode hex inputs (strip 0x prefix if present)
-    let proof_hex = req.proof.strip_prefix("0x").unwrap_or(&req.proof);
-    let proof_bytes = hex::decode(proof_hex)
-        .map_err(|e| actix_web::error::ErrorBadRequest(format!("Invalid proof hex: {}", e)))?;
-    
-    let public_inputs_hex = req.public_inputs.strip_prefix("0x").unwrap_or(&req.public_inputs);
-    let public_inputs_bytes = hex::decode(public_inputs_hex)
-        .map_err(|e| actix_web::error::ErrorBadRequest(format!("Invalid public_inputs hex: {}", e)))?;
-    
-    let verifying_key_hex = req.verifying_key.strip_prefix("0x").unwrap_or(&req.verifying_key);
-    let verifying_key_bytes = hex::decode(verifying_key_hex)
-        .map_err(|e| actix_web::error::ErrorBadRequest(format!("Invalid verifying_key hex: {}", e)))?;
+    let proof_bytes = decode_hex("proof", &req.proof)?;
+    let public_inputs_bytes = decode_hex("public_inputs", &req.public_inputs)?;
+    let verifying_key_bytes = decode_hex("verifying_key", &req.verifying_key)?;
     
     // Verify proof using arkworks
     let is_valid = match state.verifier.verify(
@@ -83,16 +96,103 @@ async fn verify_proof(
     Ok(web::Json(VerifyResponse {
         is_valid,
         attestation,
+        verifier_pubkey: hex::encode(state.verifying_key.as_bytes()),
     }))
 }
 
+/// Co-signing mode: independently verify the proof, check the attestations other
+/// verifiers already produced, and append this verifier's signature over the same message
+async fn cosign_proof(
+    req: web::Json<CosignRequest>,
+    state: web::Data<AppState>,
+) -> ActixResult<web::Json<CosignResponse>> {
+    info!("Received co-signing request ({} existing attestations)", req.attestations.len());
+    
+    let proof_bytes = decode_hex("proof", &req.proof)?;
+    let public_inputs_bytes = decode_hex("public_inputs", &req.public_inputs)?;
+    let verifying_key_bytes = decode_hex("verifying_key", &req.verifying_key)?;
+    
+    let first = req.attestations.first()
+        .ok_or_else(|| actix_web::error::ErrorBadRequest("No attestations to co-sign"))?;
+    let message = first.attestation.message_to_sign();
+    
+    // Every existing attestation must be a valid, recent signature over the same
+    // statement about this exact proof
+    for signed in &req.attestations {
+        let pubkey_bytes: [u8; 32] = decode_hex("verifier_pubkey", &signed.verifier_pubkey)?
+            .try_into()
+            .map_err(|_| actix_web::error::ErrorBadRequest("verifier_pubkey must be 32 bytes"))?;
+        let signer = VerifyingKey::from_bytes(&pubkey_bytes)
+            .map_err(|e| actix_web::error::ErrorBadRequest(format!("Invalid verifier_pubkey: {}", e)))?;
+        
+        let attestation = &signed.attestation;
+        if attestation.message_to_sign() != message
+            || !attestation.verify_hashes(&proof_bytes, &public_inputs_bytes, &verifying_key_bytes)
+            || !attestation.is_recent()
+            || !attestation.verify_signature(&signer)
+        {
+            return Err(actix_web::error::ErrorBadRequest(format!(
+                "Attestation from {} does not match this proof",
+                signed.verifier_pubkey
+            )));
+        }
+    }
+    
+    // Verify proof using arkworks - never co-sign a result we don't reproduce
+    let is_valid = state.verifier
+        .verify(&proof_bytes, &public_inputs_bytes, &verifying_key_bytes)
+        .map_err(|e| {
+            error!("Verification error: {}", e);
+            actix_web::error::ErrorInternalServerError("Verification failed")
+        })?;
+    if is_valid != first.attestation.is_valid {
+        warn!("Refusing to co-sign: attested result {} but verified {}", first.attestation.is_valid, is_valid);
+        return Err(actix_web::error::ErrorConflict("Verification result disagrees with attestations"));
+    }
+    
+    let own_pubkey = hex::encode(state.verifying_key.as_bytes());
+    let mut attestations = req.into_inner().attestations;
+    if !attestations.iter().any(|signed| signed.verifier_pubkey.trim_start_matches("0x") == own_pubkey) {
+        let attestation = cosign_attestation(&attestations[0].attestation, &state.signing_key);
+        attestations.push(SignedAttestation {
+            verifier_pubkey: own_pubkey,
+            attestation,
+        });
+    }
+    
+    info!("Co-signed attestation, {} signers", attestations.len());
+    
+    Ok(web::Json(CosignResponse {
+        is_valid,
+        attestations,
+    }))
+}
+
+fn load_signing_key(secret_hex: &str) -> Result<SigningKey, String> {
+    let secret_hex = secret_hex.trim();
+    let secret_hex = secret_hex.strip_prefix("0x").unwrap_or(secret_hex);
+    let bytes = hex::decode(secret_hex)
+        .map_err(|e| format!("Invalid VERIFIER_SIGNING_KEY hex: {}", e))?;
+    let secret: [u8; 32] = bytes.try_into()
+        .map_err(|_| "VERIFIER_SIGNING_KEY must be 32 bytes".to_string())?;
+    Ok(SigningKey::from_bytes(&secret))
+}
+
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
     env_logger::init_from_env(env_logger::Env::new().default_filter_or("info"));
     
-    // Generate or load signing key
-    // In production, load from secure storage
-    let signing_key = SigningKey::generate(&mut rand::rngs::OsRng);
+    // Load signing key from VERIFIER_SIGNING_KEY (hex-encoded 32-byte secret key)
+    // The on-chain verifier only accepts attestations from trusted keys, so a
+    // generated key is only useful for local testing
+    let signing_key = match std::env::var("VERIFIER_SIGNING_KEY") {
+        Ok(secret_hex) => load_signing_key(&secret_hex)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidInput, e))?,
+        Err(_) => {
+            warn!("VERIFIER_SIGNING_KEY not set, generating an ephemeral signing key");
+            SigningKey::generate(&mut rand::rngs::OsRng)
+        }
+    };
     let verifying_key = signing_key.verifying_key();
     
     info!("External verifier service starting");
@@ -118,6 +218,7 @@ async fn main() -> std::io::Result<()> {
             .app_data(web::Data::new(app_state.clone()))
             .route("/health", web::get().to(health_check))
             .route("/verify", web::post().to(verify_proof))
+            .route("/cosign", web::post().to(cosign_proof))
     })
     .bind((host.as_str(), port))?
     .run()
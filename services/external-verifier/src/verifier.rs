@@ -120,25 +120,25 @@ impl Groth16Verifier {
         let alpha_y_dec = bytes_to_decimal(alpha_y);
         offset += 64;
         
-        // Parse beta (G2, 128 bytes) - format: [x0 (32), x1 (32), y0 (32), y1 (32)]
-        let beta_x0 = bytes_to_decimal(&data[offset..offset + 32]);
-        let beta_x1 = bytes_to_decimal(&data[offset + 32..offset + 64]);
-        let beta_y0 = bytes_to_decimal(&data[offset + 64..offset + 96]);
-        let beta_y1 = bytes_to_decimal(&data[offset + 96..offset + 128]);
+        // Parse beta (G2, 128 bytes) - EIP-197 format: [x1 (32), x0 (32), y1 (32), y0 (32)]
+        let beta_x1 = bytes_to_decimal(&data[offset..offset + 32]);
+        let beta_x0 = bytes_to_decimal(&data[offset + 32..offset + 64]);
+        let beta_y1 = bytes_to_decimal(&data[offset + 64..offset + 96]);
+        let beta_y0 = bytes_to_decimal(&data[offset + 96..offset + 128]);
         offset += 128;
         
         // Parse gamma (G2, 128 bytes)
-        let gamma_x0 = bytes_to_decimal(&data[offset..offset + 32]);
-        let gamma_x1 = bytes_to_decimal(&data[offset + 32..offset + 64]);
-        let gamma_y0 = bytes_to_decimal(&data[offset + 64..offset + 96]);
-        let gamma_y1 = bytes_to_decimal(&data[offset + 96..offset + 128]);
+        let gamma_x1 = bytes_to_decimal(&data[offset..offset + 32]);
+        let gamma_x0 = bytes_to_decimal(&data[offset + 32..offset + 64]);
+        let gamma_y1 = bytes_to_decimal(&data[offset + 64..offset + 96]);
+        let gamma_y0 = bytes_to_decimal(&data[offset + 96..offset + 128]);
         offset += 128;
         
         // Parse delta (G2, 128 bytes)
-        let delta_x0 = bytes_to_decimal(&data[offset..offset + 32]);
-        let delta_x1 = bytes_to_decimal(&data[offset + 32..offset + 64]);
-        let delta_y0 = bytes_to_decimal(&data[offset + 64..offset + 96]);
-        let delta_y1 = bytes_to_decimal(&data[offset + 96..offset + 128]);
+        let delta_x1 = bytes_to_decimal(&data[offset..offset + 32]);
+        let delta_x0 = bytes_to_decimal(&data[offset + 32..offset + 64]);
+        let delta_y1 = bytes_to_decimal(&data[offset + 64..offset + 96]);
+        let delta_y0 = bytes_to_decimal(&data[offset + 96..offset + 128]);
         offset += 128;
         
         // Parse gamma_abc count
@@ -186,11 +186,11 @@ impl Groth16Verifier {
         let a_x = bytes_to_decimal(&data[0..32]);
         let a_y = bytes_to_decimal(&data[32..64]);
         
-        // Parse b (G2, 128 bytes): [x0 (32), x1 (32), y0 (32), y1 (32)]
-        let b_x0 = bytes_to_decimal(&data[64..96]);
-        let b_x1 = bytes_to_decimal(&data[96..128]);
-        let b_y0 = bytes_to_decimal(&data[128..160]);
-        let b_y1 = bytes_to_decimal(&data[160..192]);
+        // Parse b (G2, 128 bytes) - EIP-197 format: [x1 (32), x0 (32), y1 (32), y0 (32)]
+        let b_x1 = bytes_to_decimal(&data[64..96]);
+        let b_x0 = bytes_to_decimal(&data[96..128]);
+        let b_y1 = bytes_to_decimal(&data[128..160]);
+        let b_y0 = bytes_to_decimal(&data[160..192]);
         
         // Parse c (G1, 64 bytes): [x (32), y (32)]
         let c_x = bytes_to_decimal(&data[192..224]);
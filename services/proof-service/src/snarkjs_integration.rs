@@ -6,7 +6,7 @@ use std::path::PathBuf;
 use std::process::Command;
 use serde_json::json;
 use std::fs;
-use hex;
+use num_bigint::BigUint;
 
 pub struct SnarkjsIntegration {
     snarkjs_path: PathBuf,
@@ -174,106 +174,53 @@ impl SnarkjsIntegration {
         Ok((proof, public_inputs))
     }
 
-    /// Parse G1 point from snarkjs JSON format
-    fn parse_g1_point(point: &serde_json::Value) -> Result<Vec<u8>> {
-        // snarkjs format: ["0x...", "0x..."] (x, y coordinates)
-        let x_str = point[0].as_str()
-            .ok_or_else(|| anyhow::anyhow!("Invalid G1 point format"))?;
-        let y_str = point[1].as_str()
-            .ok_or_else(|| anyhow::anyhow!("Invalid G1 point format"))?;
+    /// Parse a snarkjs field element (decimal string) into 32 big-endian bytes
+    fn parse_field_element(value: &serde_json::Value) -> Result<[u8; 32]> {
+        let value_str = value.as_str()
+            .ok_or_else(|| anyhow::anyhow!("Invalid field element format"))?;
+        let value = BigUint::parse_bytes(value_str.as_bytes(), 10)
+            .ok_or_else(|| anyhow::anyhow!("Failed to parse field element: {}", value_str))?;
+        let bytes = value.to_bytes_be();
+        if bytes.len() > 32 {
+            anyhow::bail!("Field element does not fit in 32 bytes: {}", value_str);
+        }
         
-        let x_hex = x_str.strip_prefix("0x").unwrap_or(x_str);
-        let y_hex = y_str.strip_prefix("0x").unwrap_or(y_str);
+        // Pad to 32 bytes (big-endian, right-aligned)
+        let mut padded = [0u8; 32];
+        padded[32 - bytes.len()..].copy_from_slice(&bytes);
+        Ok(padded)
+    }
 
-        let x_bytes = hex::decode(x_hex)
-            .map_err(|e| anyhow::anyhow!("Failed to decode x hex: {}", e))?;
-        let y_bytes = hex::decode(y_hex)
-            .map_err(|e| anyhow::anyhow!("Failed to decode y hex: {}", e))?;
-        
+    /// Parse G1 point from snarkjs JSON format
+    fn parse_g1_point(point: &serde_json::Value) -> Result<Vec<u8>> {
+        // snarkjs format: ["x", "y", "1"] (decimal strings)
         // G1 point: 64 bytes (32 bytes x + 32 bytes y)
         let mut result = Vec::with_capacity(64);
-        
-        // Pad x to 32 bytes (little-endian, right-aligned)
-        let mut x_padded = vec![0u8; 32];
-        let x_len = x_bytes.len().min(32);
-        if x_len > 0 {
-            x_padded[32 - x_len..].copy_from_slice(&x_bytes[..x_len]);
-        }
-        result.extend_from_slice(&x_padded);
-        
-        // Pad y to 32 bytes (little-endian, right-aligned)
-        let mut y_padded = vec![0u8; 32];
-        let y_len = y_bytes.len().min(32);
-        if y_len > 0 {
-            y_padded[32 - y_len..].copy_from_slice(&y_bytes[..y_len]);
-        }
-        result.extend_from_slice(&y_padded);
-
+        result.extend_from_slice(&Self::parse_field_element(&point[0])?);
+        result.extend_from_slice(&Self::parse_field_element(&point[1])?);
         Ok(result)
     }
 
     /// Parse G2 point from snarkjs JSON format
     fn parse_g2_point(point: &serde_json::Value) -> Result<Vec<u8>> {
-        // snarkjs format: [["0x...", "0x..."], ["0x...", "0x..."]] (x0, x1, y0, y1)
-        let x0_str = point[0][0].as_str()
-            .ok_or_else(|| anyhow::anyhow!("Invalid G2 point format"))?;
-        let x1_str = point[0][1].as_str()
-            .ok_or_else(|| anyhow::anyhow!("Invalid G2 point format"))?;
-        let y0_str = point[1][0].as_str()
-            .ok_or_else(|| anyhow::anyhow!("Invalid G2 point format"))?;
-        let y1_str = point[1][1].as_str()
-            .ok_or_else(|| anyhow::anyhow!("Invalid G2 point format"))?;
-        
-        let x0_hex = x0_str.strip_prefix("0x").unwrap_or(x0_str);
-        let x1_hex = x1_str.strip_prefix("0x").unwrap_or(x1_str);
-        let y0_hex = y0_str.strip_prefix("0x").unwrap_or(y0_str);
-        let y1_hex = y1_str.strip_prefix("0x").unwrap_or(y1_str);
-
-        let x0_bytes = hex::decode(x0_hex)
-            .map_err(|e| anyhow::anyhow!("Failed to decode x0 hex: {}", e))?;
-        let x1_bytes = hex::decode(x1_hex)
-            .map_err(|e| anyhow::anyhow!("Failed to decode x1 hex: {}", e))?;
-        let y0_bytes = hex::decode(y0_hex)
-            .map_err(|e| anyhow::anyhow!("Failed to decode y0 hex: {}", e))?;
-        let y1_bytes = hex::decode(y1_hex)
-            .map_err(|e| anyhow::anyhow!("Failed to decode y1 hex: {}", e))?;
-        
-        // G2 point: 128 bytes (32 bytes each for x0, x1, y0, y1)
+        // snarkjs format: [["x0", "x1"], ["y0", "y1"], ["1", "0"]] (decimal strings)
+        // G2 point: 128 bytes in EIP-197 order [x1, x0, y1, y0] as expected by alt_bn128
         let mut result = Vec::with_capacity(128);
-        
-        // Pad each component to 32 bytes (little-endian, right-aligned)
-        let components = [&x0_bytes, &x1_bytes, &y0_bytes, &y1_bytes];
-        for bytes in components.iter() {
-            let mut padded = vec![0u8; 32];
-            let len = bytes.len().min(32);
-            if len > 0 {
-                padded[32 - len..].copy_from_slice(&bytes[..len]);
-            }
-            result.extend_from_slice(&padded);
-        }
-
+        result.extend_from_slice(&Self::parse_field_element(&point[0][1])?);
+        result.extend_from_slice(&Self::parse_field_element(&point[0][0])?);
+        result.extend_from_slice(&Self::parse_field_element(&point[1][1])?);
+        result.extend_from_slice(&Self::parse_field_element(&point[1][0])?);
         Ok(result)
     }
 
     /// Parse public inputs from snarkjs JSON format
     fn parse_public_inputs(public: &serde_json::Value) -> Result<Vec<u8>> {
-        // snarkjs format: ["0x...", "0x...", ...]
+        // snarkjs format: ["123...", "456...", ...] (decimal strings)
         let mut result = Vec::new();
         
         if let Some(array) = public.as_array() {
             for value in array {
-                if let Some(hex_str) = value.as_str() {
-                    let hex_val = hex_str.strip_prefix("0x").unwrap_or(hex_str);
-                    let bytes = hex::decode(hex_val)
-                        .map_err(|e| anyhow::anyhow!("Failed to decode public input hex: {}", e))?;
-                    // Pad to 32 bytes (little-endian, right-aligned)
-                    let mut padded = vec![0u8; 32];
-                    let copy_len = bytes.len().min(32);
-                    if copy_len > 0 {
-                        padded[32 - copy_len..].copy_from_slice(&bytes[..copy_len]);
-                    }
-                    result.extend_from_slice(&padded);
-                }
+                result.extend_from_slice(&Self::parse_field_element(value)?);
             }
         }
 
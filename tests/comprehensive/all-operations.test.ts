@@ -2,7 +2,7 @@
 // Tests: shield, unshield, transfer, transferFrom, approve, batchTransfer, batchTransferFrom
 
 import { expect } from "chai";
-import { Connection, Keypair, PublicKey, SystemProgram } from "@solana/web3.js";
+import { Connection, Keypair, PublicKey, SystemProgram, SYSVAR_INSTRUCTIONS_PUBKEY } from "@solana/web3.js";
 import { BN } from "@coral-xyz/anchor";
 import { getConnection, airdropSol } from "../setup";
 import { generateKeypair } from "../utils/keypairs";
@@ -16,10 +16,13 @@ import {
 import { 
   derivePoolAddresses, 
   deriveProofVault,
+  deriveVerifierRegistry,
   generateShieldOperation,
   generateUnshieldOperation,
   generateTransferOperation,
   getVerifyingKeyBytes,
+  fetchPoolRoot,
+  deriveAllowance,
 } from "../utils/pool-helpers";
 import { ExternalVerifierClient } from "../utils/external-verifier";
 import { derivePDA } from "../utils/accounts";
@@ -144,6 +147,7 @@ describe("Comprehensive zPump Operations Test", () => {
         })
         .accounts({
           payer: user.publicKey,
+          poolState: poolAddresses.poolState,
           proofVault: proofVault,
           systemProgram: SystemProgram.programId,
         })
@@ -184,9 +188,12 @@ describe("Comprehensive zPump Operations Test", () => {
           nullifier: Array.from(unshieldOp.nullifier),
           amount: new BN(amount),
           recipient: recipient.publicKey,
+          root: await fetchPoolRoot(poolProgram, poolAddresses.poolState),
+          fee: new BN(0),
         })
         .accounts({
           payer: user.publicKey,
+          poolState: poolAddresses.poolState,
           proofVault: proofVault,
           systemProgram: SystemProgram.programId,
         })
@@ -219,9 +226,12 @@ describe("Comprehensive zPump Operations Test", () => {
         const verifyTx = await poolProgram.methods
           .executeUnshieldVerify(Array.from(unshieldOp.operationId))
           .accounts({
+            poolState: poolAddresses.poolState,
             proofVault: proofVault,
             verifyingKey: verifyingKey,
             externalVerifier: externalVerifier.publicKey,
+            verifierRegistry: deriveVerifierRegistry()[0],
+            instructionsSysvar: SYSVAR_INSTRUCTIONS_PUBKEY,
             verifierProgram: VERIFIER_PROGRAM_ID,
           })
           .signers([externalVerifier])
@@ -301,11 +311,12 @@ describe("Comprehensive zPump Operations Test", () => {
         .approveAllowance({
           spender: spender.publicKey,
           amount: new BN(amount),
+          expirySlot: null,
         })
         .accounts({
           owner: user.publicKey,
-          spender: spender.publicKey,
-          pool: poolAddresses.poolState,
+          poolState: poolAddresses.poolState,
+          allowance: deriveAllowance(user.publicKey, spender.publicKey, poolAddresses.poolState)[0],
           systemProgram: SystemProgram.programId,
         })
         .rpc();
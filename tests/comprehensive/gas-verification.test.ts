@@ -2,7 +2,7 @@
 // Tests that all operations execute on-chain and stay within gas limits
 
 import { expect } from "chai";
-import { Connection, Keypair, PublicKey, SystemProgram } from "@solana/web3.js";
+import { Connection, Keypair, PublicKey, SystemProgram, SYSVAR_INSTRUCTIONS_PUBKEY } from "@solana/web3.js";
 import { BN } from "@coral-xyz/anchor";
 import { getConnection, airdropSol } from "../setup";
 import { generateKeypair } from "../utils/keypairs";
@@ -16,9 +16,14 @@ import {
 import { 
   derivePoolAddresses, 
   deriveProofVault,
+  deriveNullifierRecord,
+  deriveVerifierRegistry,
   generateShieldOperation,
+  deriveShieldAccounts,
   generateUnshieldOperation,
   generateTransferOperation,
+  fetchPoolRoot,
+  deriveAllowance,
 } from "../utils/pool-helpers";
 import { derivePDA } from "../utils/accounts";
 import { createMint, mintTo } from "@solana/spl-token";
@@ -137,6 +142,7 @@ describe("Gas Verification - All Operations", () => {
         })
         .accounts({
           payer: user.publicKey,
+          poolState: poolAddresses.poolState,
           proofVault: proofVault,
           systemProgram: SystemProgram.programId,
         })
@@ -149,11 +155,13 @@ describe("Gas Verification - All Operations", () => {
       // Execute shield (if accounts are set up)
       try {
         const executeTx = await poolProgram.methods
-          .executeShieldV2(Array.from(shieldOp.operationId))
-          .accounts({
-            _phantom: user.publicKey,
-          })
-          .remainingAccounts([])
+          .executeShieldV2(Array.from(shieldOp.operationId), Buffer.from(shieldOp.proof))
+          .accounts(deriveShieldAccounts(
+            testMint,
+            user.publicKey,
+            getATAAddress(testMint, user.publicKey),
+            verifyingKey,
+          ))
           .rpc();
         
         const executeGas = await getComputeUnitsUsed(connection, executeTx);
@@ -187,9 +195,12 @@ describe("Gas Verification - All Operations", () => {
           nullifier: Array.from(unshieldOp.nullifier),
           amount: new BN(amount),
           recipient: recipient.publicKey,
+          root: await fetchPoolRoot(poolProgram, poolAddresses.poolState),
+          fee: new BN(0),
         })
         .accounts({
           payer: user.publicKey,
+          poolState: poolAddresses.poolState,
           proofVault: proofVault,
           systemProgram: SystemProgram.programId,
         })
@@ -222,9 +233,12 @@ describe("Gas Verification - All Operations", () => {
         const verifyTx = await poolProgram.methods
           .executeUnshieldVerify(Array.from(unshieldOp.operationId))
           .accounts({
+            poolState: poolAddresses.poolState,
             proofVault: proofVault,
             verifyingKey: verifyingKey,
             externalVerifier: externalVerifier.publicKey,
+            verifierRegistry: deriveVerifierRegistry()[0],
+            instructionsSysvar: SYSVAR_INSTRUCTIONS_PUBKEY,
             verifierProgram: VERIFIER_PROGRAM_ID,
           })
           .signers([externalVerifier])
@@ -241,8 +255,10 @@ describe("Gas Verification - All Operations", () => {
           .executeUnshieldUpdate(Array.from(unshieldOp.operationId))
           .accounts({
             proofVault: proofVault,
-            commitmentTree: poolAddresses.commitmentTree,
+            rootHistory: poolAddresses.rootHistory,
             nullifierSet: poolAddresses.nullifierSet,
+            nullifierRecord: deriveNullifierRecord(poolAddresses.poolState, unshieldOp.nullifier)[0],
+            payer: user.publicKey,
           })
           .rpc();
         
@@ -275,6 +291,7 @@ describe("Gas Verification - All Operations", () => {
         .executeTransfer({
           proof: Array.from(transferOp.proof),
           publicInputs: Array.from(transferOp.publicInputs),
+          encryptedNotes: transferOp.encryptedNotes,
         })
         .accounts({
           _phantom: user.publicKey,
@@ -314,6 +331,8 @@ describe("Gas Verification - All Operations", () => {
         .executeTransferFrom({
           proof: Array.from(transferOp.proof),
           publicInputs: Array.from(transferOp.publicInputs),
+          encryptedNotes: transferOp.encryptedNotes,
+          mode: { allowance: { amount: new BN(amount) } },
         })
         .accounts({
           owner: user.publicKey,
@@ -345,11 +364,12 @@ describe("Gas Verification - All Operations", () => {
         .approveAllowance({
           spender: spender.publicKey,
           amount: new BN(amount),
+          expirySlot: null,
         })
         .accounts({
           owner: user.publicKey,
-          spender: spender.publicKey,
-          pool: poolAddresses.poolState,
+          poolState: poolAddresses.poolState,
+          allowance: deriveAllowance(user.publicKey, spender.publicKey, poolAddresses.poolState)[0],
           systemProgram: SystemProgram.programId,
         })
         .rpc();
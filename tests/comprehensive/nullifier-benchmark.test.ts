@@ -0,0 +1,149 @@
+// Nullifier storage benchmark
+// Measures execute_unshield_update CU as the number of spent nullifiers in a pool grows.
+// Each spend creates one record PDA, so the cost should stay flat whatever the set size.
+//
+// Needs a registered pool (BENCH_MINT), real proofs and the external verifier:
+//   BENCH_MINT=<mint> USE_REAL_PROOFS=true npx mocha ... tests/comprehensive/nullifier-benchmark.test.ts
+
+import { expect } from "chai";
+import { Connection, Keypair, PublicKey, SystemProgram, SYSVAR_INSTRUCTIONS_PUBKEY } from "@solana/web3.js";
+import { BN } from "@coral-xyz/anchor";
+import { getConnection, airdropSol } from "../setup";
+import { generateKeypair } from "../utils/keypairs";
+import { getPoolProgram, VERIFIER_PROGRAM_ID } from "../utils/programs";
+import {
+  derivePoolAddresses,
+  deriveProofVault,
+  deriveNullifierRecord,
+  deriveVerifierRegistry,
+  generateUnshieldOperation,
+  fetchPoolRoot,
+} from "../utils/pool-helpers";
+import { getComputeUnitsUsed, recordGasUsage } from "../utils/gas";
+
+const PROOF_SERVICE_URL = process.env.PROOF_SERVICE_URL || "http://127.0.0.1:8080";
+const EXTERNAL_VERIFIER_URL = process.env.EXTERNAL_VERIFIER_URL || "http://127.0.0.1:8081";
+const USE_REAL_PROOFS = process.env.USE_REAL_PROOFS === "true";
+const BENCH_MINT = process.env.BENCH_MINT;
+const BENCH_VERIFYING_KEY = process.env.BENCH_VERIFYING_KEY;
+
+// Spent-nullifier counts at which a spend is measured
+const CHECKPOINTS = [1, 8, 32, 128];
+// Largest allowed CU difference between the cheapest and most expensive checkpoint
+const MAX_CU_SPREAD = 2_000;
+
+describe("Nullifier storage benchmark", function () {
+  let connection: Connection;
+  let user: Keypair;
+  let recipient: Keypair;
+  let externalVerifier: Keypair;
+  let poolProgram: any;
+  let poolAddresses: any;
+  let proofVault: PublicKey;
+  let verifyingKey: PublicKey;
+  
+  before(async function () {
+    if (!BENCH_MINT || !BENCH_VERIFYING_KEY || !USE_REAL_PROOFS) {
+      this.skip();
+    }
+    
+    connection = getConnection();
+    user = generateKeypair();
+    recipient = generateKeypair();
+    externalVerifier = Keypair.generate();
+    await airdropSol(connection, user.publicKey, 20);
+    
+    poolProgram = getPoolProgram(connection, user);
+    poolAddresses = derivePoolAddresses(new PublicKey(BENCH_MINT!));
+    [proofVault] = deriveProofVault(user.publicKey);
+    verifyingKey = new PublicKey(BENCH_VERIFYING_KEY!);
+  });
+  
+  // Run one full unshield and return the CU used by the nullifier spend
+  async function spendOne(): Promise<number> {
+    const root = Uint8Array.from(await fetchPoolRoot(poolProgram, poolAddresses.poolState));
+    const unshieldOp = await generateUnshieldOperation(
+      1,
+      recipient.publicKey,
+      true,
+      PROOF_SERVICE_URL,
+      EXTERNAL_VERIFIER_URL,
+      verifyingKey,
+      connection,
+      root,
+    );
+    
+    await poolProgram.methods
+      .prepareUnshield({
+        nullifier: Array.from(unshieldOp.nullifier),
+        amount: new BN(1),
+        recipient: recipient.publicKey,
+        root: Array.from(root),
+        fee: new BN(0),
+      })
+      .accounts({
+        payer: user.publicKey,
+        poolState: poolAddresses.poolState,
+        proofVault: proofVault,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc();
+    
+    await poolProgram.methods
+      .updateOperationData(Array.from(unshieldOp.operationId), Array.from(unshieldOp.operationData))
+      .accounts({
+        payer: user.publicKey,
+        proofVault: proofVault,
+      })
+      .rpc();
+    
+    await poolProgram.methods
+      .executeUnshieldVerify(Array.from(unshieldOp.operationId))
+      .accounts({
+        poolState: poolAddresses.poolState,
+        proofVault: proofVault,
+        verifyingKey: verifyingKey,
+        externalVerifier: externalVerifier.publicKey,
+        verifierRegistry: deriveVerifierRegistry()[0],
+        instructionsSysvar: SYSVAR_INSTRUCTIONS_PUBKEY,
+        verifierProgram: VERIFIER_PROGRAM_ID,
+      })
+      .signers([externalVerifier])
+      .rpc();
+    
+    const updateTx = await poolProgram.methods
+      .executeUnshieldUpdate(Array.from(unshieldOp.operationId))
+      .accounts({
+        poolState: poolAddresses.poolState,
+        rootHistory: poolAddresses.rootHistory,
+        nullifierSet: poolAddresses.nullifierSet,
+        nullifierRecord: deriveNullifierRecord(poolAddresses.poolState, unshieldOp.nullifier)[0],
+        proofVault: proofVault,
+        payer: user.publicKey,
+      })
+      .rpc();
+    
+    return getComputeUnitsUsed(connection, updateTx);
+  }
+  
+  it("keeps nullifier spend cost flat as the set grows", async () => {
+    const results: { spent: number; computeUnits: number }[] = [];
+    let spent = 0;
+    
+    for (const checkpoint of CHECKPOINTS) {
+      let computeUnits = 0;
+      while (spent < checkpoint) {
+        computeUnits = await spendOne();
+        spent += 1;
+      }
+      results.push({ spent, computeUnits });
+      await recordGasUsage(connection, "ptf_pool", `execute_unshield_update@${spent}`, computeUnits);
+    }
+    
+    console.log("\nNullifier spend CU by set size");
+    console.table(results);
+    
+    const units = results.map((r) => r.computeUnits);
+    expect(Math.max(...units) - Math.min(...units)).to.be.lessThanOrEqual(MAX_CU_SPREAD);
+  });
+});
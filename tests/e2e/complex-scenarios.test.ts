@@ -34,6 +34,8 @@ import {
   generateShieldOperation,
   generateTransferOperation,
   generateUnshieldOperation,
+  fetchPoolRoot,
+  deriveAllowance,
 } from "../utils/pool-helpers";
 
 describe("End-to-End Tests - Complex Scenarios", () => {
@@ -135,6 +137,7 @@ describe("End-to-End Tests - Complex Scenarios", () => {
         })
         .accounts({
           payer: user.publicKey,
+          poolState: poolAddresses.poolState,
           proofVault: proofVault,
           systemProgram: SystemProgram.programId,
         })
@@ -150,6 +153,7 @@ describe("End-to-End Tests - Complex Scenarios", () => {
           .executeTransfer({
             proof: Array.from(transferOp.proof),
             publicInputs: Array.from(transferOp.publicInputs),
+            encryptedNotes: transferOp.encryptedNotes,
           })
           .accounts({
             _phantom: user.publicKey, // Phantom account for raw instruction
@@ -174,6 +178,7 @@ describe("End-to-End Tests - Complex Scenarios", () => {
         transfers.push({
           proof: Array.from(transferOp.proof),
           publicInputs: Array.from(transferOp.publicInputs),
+          encryptedNotes: transferOp.encryptedNotes,
         });
       }
       
@@ -203,9 +208,12 @@ describe("End-to-End Tests - Complex Scenarios", () => {
           nullifier: Array.from(unshieldNullifier),
           amount: new BN(amount),
           recipient: recipient.publicKey,
+          root: await fetchPoolRoot(poolProgram, poolAddresses.poolState),
+          fee: new BN(0),
         })
         .accounts({
           payer: user.publicKey,
+          poolState: poolAddresses.poolState,
           proofVault: proofVault,
           systemProgram: SystemProgram.programId,
         })
@@ -231,12 +239,16 @@ describe("End-to-End Tests - Complex Scenarios", () => {
       // Step 1: Approve allowance
       await poolProgram.methods
         .approveAllowance({
+          spender: user.publicKey,
           amount: new BN(amount * 5), // Allow enough for batch
+          expirySlot: null,
         })
         .accounts({
-          _phantom: user.publicKey,
+          owner: user.publicKey,
+          poolState: poolAddresses.poolState,
+          allowance: deriveAllowance(user.publicKey, user.publicKey, poolAddresses.poolState)[0],
+          systemProgram: SystemProgram.programId,
         })
-        .remainingAccounts([])
         .rpc();
       
       recordInstructionCoverage("ptf_pool", "approve_allowance");
@@ -246,7 +258,6 @@ describe("End-to-End Tests - Complex Scenarios", () => {
       const transferOp = generateTransferOperation(nullifier, amount);
       
       // Derive allowance PDA
-      const { deriveAllowance } = await import("../utils/pool-helpers");
       const [allowancePDA] = deriveAllowance(
         user.publicKey,
         user.publicKey, // Spender is same as owner for this test
@@ -257,6 +268,8 @@ describe("End-to-End Tests - Complex Scenarios", () => {
         .executeTransferFrom({
           proof: Array.from(transferOp.proof),
           publicInputs: Array.from(transferOp.publicInputs),
+          encryptedNotes: transferOp.encryptedNotes,
+          mode: { allowance: { amount: new BN(amount) } },
         })
         .accounts({
           _phantom: user.publicKey, // Phantom account for raw instruction
@@ -281,6 +294,8 @@ describe("End-to-End Tests - Complex Scenarios", () => {
         transfers.push({
           proof: Array.from(batchTransferOp.proof),
           publicInputs: Array.from(batchTransferOp.publicInputs),
+          encryptedNotes: batchTransferOp.encryptedNotes,
+          mode: { allowance: { amount: new BN(amount) } },
         });
       }
       
@@ -31,9 +31,11 @@ import {
 import {
   derivePoolAddresses,
   deriveProofVault,
+  deriveNullifierRecord,
   generateShieldOperation,
   generateTransferOperation,
   generateUnshieldOperation,
+  fetchPoolRoot,
 } from "../utils/pool-helpers";
 
 describe("End-to-End Tests - Full Flow with Token", () => {
@@ -163,6 +165,7 @@ describe("End-to-End Tests - Full Flow with Token", () => {
         })
         .accounts({
           payer: user.publicKey,
+          poolState: poolAddresses.poolState,
           proofVault: proofVault,
           systemProgram: SystemProgram.programId,
         })
@@ -177,6 +180,7 @@ describe("End-to-End Tests - Full Flow with Token", () => {
         .executeTransfer({
           proof: Array.from(transferOp.proof),
           publicInputs: Array.from(transferOp.publicInputs),
+          encryptedNotes: transferOp.encryptedNotes,
         })
         .accounts({
           _phantom: user.publicKey, // Phantom account for raw instruction
@@ -200,9 +204,12 @@ describe("End-to-End Tests - Full Flow with Token", () => {
           nullifier: Array.from(unshieldNullifier),
           amount: new BN(amount),
           recipient: recipient.publicKey,
+          root: await fetchPoolRoot(poolProgram, poolAddresses.poolState),
+          fee: new BN(0),
         })
         .accounts({
           payer: user.publicKey,
+          poolState: poolAddresses.poolState,
           proofVault: proofVault,
           systemProgram: SystemProgram.programId,
         })
@@ -227,8 +234,10 @@ describe("End-to-End Tests - Full Flow with Token", () => {
         .executeUnshieldUpdate(Array.from(unshieldOp.operationId))
         .accounts({
           poolState: poolAddresses.poolState,
-          commitmentTree: poolAddresses.commitmentTree,
+          rootHistory: poolAddresses.rootHistory,
           nullifierSet: poolAddresses.nullifierSet,
+          nullifierRecord: deriveNullifierRecord(poolAddresses.poolState, unshieldOp.nullifier)[0],
+          payer: user.publicKey,
           proofVault: proofVault,
         })
         .rpc();
@@ -242,10 +251,12 @@ describe("End-to-End Tests - Full Flow with Token", () => {
           poolState: poolAddresses.poolState,
           vaultState: poolAddresses.poolState,
           proofVault: proofVault,
+          owner: user.publicKey,
           vaultTokenAccount: poolAddresses.poolState,
           userTokenAccount: recipientTokenAccount,
           vaultProgram: poolProgram.programId,
           tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
         })
         .rpc();
       
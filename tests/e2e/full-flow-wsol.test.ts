@@ -33,9 +33,11 @@ import {
 import {
   derivePoolAddresses,
   deriveProofVault,
+  deriveNullifierRecord,
   generateShieldOperation,
   generateTransferOperation,
   generateUnshieldOperation,
+  fetchPoolRoot,
 } from "../utils/pool-helpers";
 
 const WSOL_MINT = NATIVE_MINT;
@@ -147,6 +149,7 @@ describe("End-to-End Tests - Full Flow with wSOL", () => {
         })
         .accounts({
           payer: user.publicKey,
+          poolState: poolAddresses.poolState,
           proofVault: proofVault,
           systemProgram: SystemProgram.programId,
         })
@@ -161,6 +164,7 @@ describe("End-to-End Tests - Full Flow with wSOL", () => {
         .executeTransfer({
           proof: Array.from(transferOp.proof),
           publicInputs: Array.from(transferOp.publicInputs),
+          encryptedNotes: transferOp.encryptedNotes,
         })
         .accounts({
           _phantom: user.publicKey, // Phantom account for raw instruction
@@ -184,9 +188,12 @@ describe("End-to-End Tests - Full Flow with wSOL", () => {
           nullifier: Array.from(unshieldNullifier),
           amount: new BN(amount),
           recipient: recipient.publicKey,
+          root: await fetchPoolRoot(poolProgram, poolAddresses.poolState),
+          fee: new BN(0),
         })
         .accounts({
           payer: user.publicKey,
+          poolState: poolAddresses.poolState,
           proofVault: proofVault,
           systemProgram: SystemProgram.programId,
         })
@@ -210,8 +217,10 @@ describe("End-to-End Tests - Full Flow with wSOL", () => {
         .executeUnshieldUpdate(Array.from(unshieldOp.operationId))
         .accounts({
           poolState: poolAddresses.poolState,
-          commitmentTree: poolAddresses.commitmentTree,
+          rootHistory: poolAddresses.rootHistory,
           nullifierSet: poolAddresses.nullifierSet,
+          nullifierRecord: deriveNullifierRecord(poolAddresses.poolState, unshieldOp.nullifier)[0],
+          payer: user.publicKey,
           proofVault: proofVault,
         })
         .rpc();
@@ -224,10 +233,12 @@ describe("End-to-End Tests - Full Flow with wSOL", () => {
           poolState: poolAddresses.poolState,
           vaultState: poolAddresses.poolState,
           proofVault: proofVault,
+          owner: user.publicKey,
           vaultTokenAccount: poolAddresses.poolState,
           userTokenAccount: recipientWSOLAccount,
           vaultProgram: poolProgram.programId,
           tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
         })
         .rpc();
       
@@ -128,6 +128,7 @@ describe("End-to-End Tests - Multi-User Scenarios", () => {
           })
           .accounts({
             payer: user.publicKey,
+            poolState: poolAddresses.poolState,
             proofVault: proofVault,
             systemProgram: SystemProgram.programId,
           })
@@ -168,6 +169,7 @@ describe("End-to-End Tests - Multi-User Scenarios", () => {
         .executeTransfer({
           proof: Array.from(transferOp1.proof),
           publicInputs: Array.from(transferOp1.publicInputs),
+          encryptedNotes: transferOp1.encryptedNotes,
         })
         .accounts({
           _phantom: users[0].publicKey, // Phantom account for raw instruction
@@ -191,6 +193,7 @@ describe("End-to-End Tests - Multi-User Scenarios", () => {
         .executeTransfer({
           proof: Array.from(transferOp2.proof),
           publicInputs: Array.from(transferOp2.publicInputs),
+          encryptedNotes: transferOp2.encryptedNotes,
         })
         .accounts({
           _phantom: users[1].publicKey, // Phantom account for raw instruction
@@ -108,6 +108,8 @@ describe("Allowance Operations - Edge Cases", () => {
         .executeTransferFrom({
           proof: Array.from(transferOp.proof),
           publicInputs: Array.from(transferOp.publicInputs),
+          encryptedNotes: transferOp.encryptedNotes,
+          mode: { allowance: { amount: new BN(amount) } },
         })
         .accounts({
           _phantom: owner.publicKey, // Phantom account for raw instruction
@@ -153,6 +155,8 @@ describe("Allowance Operations - Edge Cases", () => {
         .executeTransferFrom({
           proof: Array.from(transferOp.proof),
           publicInputs: Array.from(transferOp.publicInputs),
+          encryptedNotes: transferOp.encryptedNotes,
+          mode: { allowance: { amount: new BN(amount) } },
         })
         .accounts({
           _phantom: owner.publicKey, // Phantom account for raw instruction
@@ -185,12 +189,16 @@ describe("Allowance Operations - Edge Cases", () => {
     try {
       await poolProgram.methods
         .approveAllowance({
+          spender: spender.publicKey,
           amount: new BN(0),
+          expirySlot: null,
         })
         .accounts({
-          _phantom: owner.publicKey,
+          owner: owner.publicKey,
+          poolState: poolAddresses.poolState,
+          allowance: deriveAllowance(owner.publicKey, spender.publicKey, poolAddresses.poolState)[0],
+          systemProgram: SystemProgram.programId,
         })
-        .remainingAccounts([])
         .rpc();
       
       expect.fail("Should have rejected zero allowance");
@@ -160,6 +160,7 @@ describe("Batch Operations - Edge Cases", () => {
       transfers.push({
         proof: Array.from(transferOp.proof),
         publicInputs: Array.from(transferOp.publicInputs),
+        encryptedNotes: transferOp.encryptedNotes,
       });
     }
     
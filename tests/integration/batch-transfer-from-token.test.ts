@@ -112,6 +112,8 @@ describe("Batch TransferFrom Operations - Token Tests", () => {
       transfers.push({
         proof: Array.from(transferOp.proof),
         publicInputs: Array.from(transferOp.publicInputs),
+        encryptedNotes: transferOp.encryptedNotes,
+        mode: { allowance: { amount: new BN(TEST_AMOUNTS.SMALL) } },
       });
     }
     
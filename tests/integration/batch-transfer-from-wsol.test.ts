@@ -105,6 +105,8 @@ describe("Batch TransferFrom Operations - wSOL Tests", () => {
       transfers.push({
         proof: Array.from(transferOp.proof),
         publicInputs: Array.from(transferOp.publicInputs),
+        encryptedNotes: transferOp.encryptedNotes,
+        mode: { allowance: { amount: new BN(TEST_AMOUNTS.SMALL) } },
       });
     }
     
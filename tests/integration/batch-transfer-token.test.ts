@@ -100,6 +100,7 @@ describe("Batch Transfer Operations - Token Tests", () => {
       transfers.push({
         proof: Array.from(transferOp.proof),
         publicInputs: Array.from(transferOp.publicInputs),
+        encryptedNotes: transferOp.encryptedNotes,
       });
     }
     
@@ -141,6 +142,7 @@ describe("Batch Transfer Operations - Token Tests", () => {
       transfers.push({
         proof: Array.from(transferOp.proof),
         publicInputs: Array.from(transferOp.publicInputs),
+        encryptedNotes: transferOp.encryptedNotes,
       });
     }
     
@@ -92,6 +92,7 @@ describe("Batch Transfer Operations - wSOL Tests", () => {
       transfers.push({
         proof: Array.from(transferOp.proof),
         publicInputs: Array.from(transferOp.publicInputs),
+        encryptedNotes: transferOp.encryptedNotes,
       });
     }
     
@@ -29,10 +29,12 @@ import {
 import {
   derivePoolAddresses,
   deriveProofVault,
+  emptyEncryptedNotes,
   generateShieldOperation,
   generateTransferOperation,
   generateUnshieldOperation,
   deriveAllowance,
+  fetchPoolRoot,
 } from "../utils/pool-helpers";
 
 const WSOL_MINT = NATIVE_MINT;
@@ -125,6 +127,7 @@ describe("Comprehensive Edge Cases - All Operations", () => {
         })
         .accounts({
           payer: user.publicKey,
+          poolState: poolAddresses.poolState,
           proofVault: proofVault,
           systemProgram: SystemProgram.programId,
         })
@@ -151,6 +154,7 @@ describe("Comprehensive Edge Cases - All Operations", () => {
         })
         .accounts({
           payer: user.publicKey,
+          poolState: poolAddresses.poolState,
           proofVault: proofVault,
           systemProgram: SystemProgram.programId,
         })
@@ -182,9 +186,12 @@ describe("Comprehensive Edge Cases - All Operations", () => {
           nullifier: Array.from(nullifier),
           amount: new BN(amount),
           recipient: invalidRecipient,
+          root: await fetchPoolRoot(poolProgram, poolAddresses.poolState),
+          fee: new BN(0),
         })
         .accounts({
           payer: user.publicKey,
+          poolState: poolAddresses.poolState,
           proofVault: proofVault,
           systemProgram: SystemProgram.programId,
         })
@@ -209,6 +216,7 @@ describe("Comprehensive Edge Cases - All Operations", () => {
         .executeTransfer({
           proof: Array.from(oversizedProof),
           publicInputs: Array.from(publicInputs),
+          encryptedNotes: emptyEncryptedNotes(),
         })
         .accounts({
           _phantom: user.publicKey,
@@ -246,6 +254,7 @@ describe("Comprehensive Edge Cases - All Operations", () => {
         .executeTransfer({
           proof: Array.from(proof),
           publicInputs: Array.from(oversizedPublicInputs),
+          encryptedNotes: emptyEncryptedNotes(),
         })
         .accounts({
           _phantom: user.publicKey,
@@ -320,12 +329,16 @@ describe("Comprehensive Edge Cases - All Operations", () => {
     try {
       await poolProgram.methods
         .approveAllowance({
+          spender: spender.publicKey,
           amount: new BN(maxAmount),
+          expirySlot: null,
         })
         .accounts({
-          _phantom: owner.publicKey,
+          owner: owner.publicKey,
+          poolState: poolAddresses.poolState,
+          allowance: deriveAllowance(owner.publicKey, spender.publicKey, poolAddresses.poolState)[0],
+          systemProgram: SystemProgram.programId,
         })
-        .remainingAccounts([])
         .rpc();
       
       expect.fail("Should have rejected excessive allowance");
@@ -356,6 +369,7 @@ describe("Comprehensive Edge Cases - All Operations", () => {
           })
           .accounts({
             payer: user.publicKey,
+            poolState: poolAddresses.poolState,
             proofVault: proofVault,
             systemProgram: SystemProgram.programId,
           })
@@ -385,6 +399,7 @@ describe("Comprehensive Edge Cases - All Operations", () => {
         })
         .accounts({
           payer: user.publicKey,
+          poolState: poolAddresses.poolState,
           proofVault: proofVault,
           systemProgram: SystemProgram.programId,
         })
@@ -398,6 +413,7 @@ describe("Comprehensive Edge Cases - All Operations", () => {
         .executeTransfer({
           proof: Array.from(transferOp.proof),
           publicInputs: Array.from(transferOp.publicInputs),
+          encryptedNotes: transferOp.encryptedNotes,
         })
         .accounts({
           _phantom: user.publicKey,
@@ -435,6 +451,7 @@ describe("Comprehensive Edge Cases - All Operations", () => {
         })
         .accounts({
           payer: user.publicKey,
+          poolState: wsolPoolAddresses.poolState,
           proofVault: proofVault,
           systemProgram: SystemProgram.programId,
         })
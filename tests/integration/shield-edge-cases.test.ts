@@ -6,6 +6,7 @@ import {
   SystemProgram,
 } from "@solana/web3.js";
 import { BN } from "@coral-xyz/anchor";
+import { NATIVE_MINT } from "@solana/spl-token";
 import { getConnection, airdropSol } from "../setup";
 import { generateKeypair } from "../utils/keypairs";
 import {
@@ -19,6 +20,7 @@ import {
   derivePDA,
 } from "../utils/accounts";
 import {
+  derivePoolAddresses,
   deriveProofVault,
 } from "../utils/pool-helpers";
 
@@ -28,6 +30,7 @@ describe("Shield Operations - Edge Cases", () => {
   let poolProgram: any;
   let factoryProgram: any;
   let proofVault: PublicKey;
+  let poolState: PublicKey;
   
   before(async () => {
     connection = getConnection();
@@ -39,6 +42,8 @@ describe("Shield Operations - Edge Cases", () => {
     
     // Derive proof vault
     [proofVault] = deriveProofVault(user.publicKey);
+    // Prepared operations record their pool; the wSOL pool is created by bootstrap-wsol
+    poolState = derivePoolAddresses(NATIVE_MINT).poolState;
     
     // Initialize factory if needed
     const [factoryState] = derivePDA(
@@ -71,6 +76,7 @@ describe("Shield Operations - Edge Cases", () => {
         })
         .accounts({
           payer: user.publicKey,
+          poolState: poolState,
           proofVault: proofVault,
           systemProgram: SystemProgram.programId,
         })
@@ -100,6 +106,7 @@ describe("Shield Operations - Edge Cases", () => {
         })
         .accounts({
           payer: user.publicKey,
+          poolState: poolState,
           proofVault: proofVault,
           systemProgram: SystemProgram.programId,
         })
@@ -125,6 +132,7 @@ describe("Shield Operations - Edge Cases", () => {
         })
         .accounts({
           payer: user.publicKey,
+          poolState: poolState,
           proofVault: proofVault,
           systemProgram: SystemProgram.programId,
         })
@@ -151,6 +159,7 @@ describe("Shield Operations - Edge Cases", () => {
         })
         .accounts({
           payer: user.publicKey,
+          poolState: poolState,
           proofVault: proofVault,
           systemProgram: SystemProgram.programId,
         })
@@ -164,6 +173,7 @@ describe("Shield Operations - Edge Cases", () => {
         })
         .accounts({
           payer: user.publicKey,
+          poolState: poolState,
           proofVault: proofVault,
           systemProgram: SystemProgram.programId,
         })
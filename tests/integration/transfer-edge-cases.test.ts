@@ -21,6 +21,7 @@ import {
 } from "../utils/accounts";
 import {
   derivePoolAddresses,
+  emptyEncryptedNotes,
   generateTransferOperation,
 } from "../utils/pool-helpers";
 import { createMint } from "@solana/spl-token";
@@ -97,6 +98,7 @@ describe("Transfer Operations - Edge Cases", () => {
         .executeTransfer({
           proof: Array.from(invalidProof),
           publicInputs: Array.from(publicInputs),
+          encryptedNotes: emptyEncryptedNotes(),
         })
         .accounts({
           _phantom: user.publicKey, // Phantom account for raw instruction
@@ -136,6 +138,7 @@ describe("Transfer Operations - Edge Cases", () => {
         .executeTransfer({
           proof: Array.from(transferOp.proof),
           publicInputs: Array.from(transferOp.publicInputs),
+          encryptedNotes: transferOp.encryptedNotes,
         })
         .accounts({
           _phantom: user.publicKey,
@@ -154,6 +157,7 @@ describe("Transfer Operations - Edge Cases", () => {
         .executeTransfer({
           proof: Array.from(transferOp.proof),
           publicInputs: Array.from(transferOp.publicInputs),
+          encryptedNotes: transferOp.encryptedNotes,
         })
         .accounts({
           _phantom: user.publicKey,
@@ -185,6 +189,7 @@ describe("Transfer Operations - Edge Cases", () => {
         .executeTransfer({
           proof: Array.from(proof),
           publicInputs: Array.from(invalidPublicInputs),
+          encryptedNotes: emptyEncryptedNotes(),
         })
         .accounts({
           _phantom: user.publicKey, // Phantom account for raw instruction
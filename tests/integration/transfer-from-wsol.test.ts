@@ -105,6 +105,8 @@ describe("TransferFrom Operations - wSOL Tests", () => {
         .executeTransferFrom({
           proof: Array.from(transferOp.proof),
           publicInputs: Array.from(transferOp.publicInputs),
+          encryptedNotes: transferOp.encryptedNotes,
+          mode: { allowance: { amount: new BN(amount) } },
         })
         .accounts({
           _phantom: owner.publicKey, // Phantom account for raw instruction
@@ -28,6 +28,7 @@ import {
 } from "../utils/accounts";
 import {
   derivePoolAddresses,
+  emptyEncryptedNotes,
   generateTransferOperation,
 } from "../utils/pool-helpers";
 
@@ -105,6 +106,7 @@ describe("Transfer Operations - Token Tests", () => {
         .executeTransfer({
           proof: Array.from(transferOp.proof),
           publicInputs: Array.from(transferOp.publicInputs),
+          encryptedNotes: transferOp.encryptedNotes,
         })
         .accounts({
           _phantom: user.publicKey, // Phantom account for raw instruction
@@ -141,6 +143,7 @@ describe("Transfer Operations - Token Tests", () => {
         .executeTransfer({
           proof: Array.from(invalidProof),
           publicInputs: Array.from(publicInputs),
+          encryptedNotes: emptyEncryptedNotes(),
         })
         .accounts({
           _phantom: user.publicKey, // Phantom account for raw instruction
@@ -179,6 +182,7 @@ describe("Transfer Operations - Token Tests", () => {
         .executeTransfer({
           proof: Array.from(transferOp.proof),
           publicInputs: Array.from(transferOp.publicInputs),
+          encryptedNotes: transferOp.encryptedNotes,
         })
         .accounts({
           _phantom: user.publicKey,
@@ -197,6 +201,7 @@ describe("Transfer Operations - Token Tests", () => {
         .executeTransfer({
           proof: Array.from(transferOp.proof),
           publicInputs: Array.from(transferOp.publicInputs),
+          encryptedNotes: transferOp.encryptedNotes,
         })
         .accounts({
           _phantom: user.publicKey,
@@ -96,6 +96,7 @@ describe("Transfer Operations - wSOL Tests", () => {
         .executeTransfer({
           proof: Array.from(transferOp.proof),
           publicInputs: Array.from(transferOp.publicInputs),
+          encryptedNotes: transferOp.encryptedNotes,
         })
         .accounts({
           _phantom: user.publicKey, // Phantom account for raw instruction
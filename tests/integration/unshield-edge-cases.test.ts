@@ -6,6 +6,7 @@ import {
   SystemProgram,
 } from "@solana/web3.js";
 import { BN } from "@coral-xyz/anchor";
+import { NATIVE_MINT } from "@solana/spl-token";
 import { getConnection, airdropSol } from "../setup";
 import { generateKeypair } from "../utils/keypairs";
 import {
@@ -19,6 +20,7 @@ import {
   derivePDA,
 } from "../utils/accounts";
 import {
+  derivePoolAddresses,
   deriveProofVault,
 } from "../utils/pool-helpers";
 
@@ -29,6 +31,7 @@ describe("Unshield Operations - Edge Cases", () => {
   let poolProgram: any;
   let factoryProgram: any;
   let proofVault: PublicKey;
+  let poolState: PublicKey;
   
   before(async () => {
     connection = getConnection();
@@ -42,6 +45,8 @@ describe("Unshield Operations - Edge Cases", () => {
     
     // Derive proof vault
     [proofVault] = deriveProofVault(user.publicKey);
+    // Prepared operations record their pool; the wSOL pool is created by bootstrap-wsol
+    poolState = derivePoolAddresses(NATIVE_MINT).poolState;
     
     // Initialize factory if needed
     const [factoryState] = derivePDA(
@@ -72,9 +77,12 @@ describe("Unshield Operations - Edge Cases", () => {
           nullifier: Array.from(invalidNullifier),
           amount: new BN(TEST_AMOUNTS.SMALL),
           recipient: recipient.publicKey,
+          root: Array.from(new Uint8Array(32)),
+          fee: new BN(0),
         })
         .accounts({
           payer: user.publicKey,
+          poolState: poolState,
           proofVault: proofVault,
           systemProgram: SystemProgram.programId,
         })
@@ -98,9 +106,12 @@ describe("Unshield Operations - Edge Cases", () => {
           nullifier: Array.from(nullifier),
           amount: new BN(TEST_AMOUNTS.SMALL),
           recipient: recipient.publicKey,
+          root: Array.from(new Uint8Array(32)),
+          fee: new BN(0),
         })
         .accounts({
           payer: user.publicKey,
+          poolState: poolState,
           proofVault: proofVault,
           systemProgram: SystemProgram.programId,
         })
@@ -112,9 +123,12 @@ describe("Unshield Operations - Edge Cases", () => {
           nullifier: Array.from(nullifier),
           amount: new BN(TEST_AMOUNTS.SMALL),
           recipient: recipient.publicKey,
+          root: Array.from(new Uint8Array(32)),
+          fee: new BN(0),
         })
         .accounts({
           payer: user.publicKey,
+          poolState: poolState,
           proofVault: proofVault,
           systemProgram: SystemProgram.programId,
         })
@@ -137,9 +151,12 @@ describe("Unshield Operations - Edge Cases", () => {
           nullifier: Array.from(nullifier),
           amount: new BN(0),
           recipient: recipient.publicKey,
+          root: Array.from(new Uint8Array(32)),
+          fee: new BN(0),
         })
         .accounts({
           payer: user.publicKey,
+          poolState: poolState,
           proofVault: proofVault,
           systemProgram: SystemProgram.programId,
         })
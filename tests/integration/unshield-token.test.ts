@@ -32,8 +32,10 @@ import {
 import {
   derivePoolAddresses,
   deriveProofVault,
+  deriveNullifierRecord,
   generateUnshieldOperation,
   prepareUnshieldData,
+  fetchPoolRoot,
 } from "../utils/pool-helpers";
 
 describe("Unshield Operations - Token Tests", () => {
@@ -137,9 +139,12 @@ describe("Unshield Operations - Token Tests", () => {
           nullifier: Array.from(nullifier),
           amount: new BN(amount),
           recipient: recipient.publicKey,
+          root: await fetchPoolRoot(poolProgram, poolAddresses.poolState),
+          fee: new BN(0),
         })
         .accounts({
           payer: user.publicKey,
+          poolState: poolAddresses.poolState,
           proofVault: proofVault,
           systemProgram: SystemProgram.programId,
         })
@@ -168,8 +173,10 @@ describe("Unshield Operations - Token Tests", () => {
         .executeUnshieldUpdate(Array.from(unshieldOp.operationId))
         .accounts({
           poolState: poolAddresses.poolState,
-          commitmentTree: poolAddresses.commitmentTree,
+          rootHistory: poolAddresses.rootHistory,
           nullifierSet: poolAddresses.nullifierSet,
+          nullifierRecord: deriveNullifierRecord(poolAddresses.poolState, unshieldOp.nullifier)[0],
+          payer: user.publicKey,
           proofVault: proofVault,
         })
         .rpc();
@@ -185,10 +192,12 @@ describe("Unshield Operations - Token Tests", () => {
           poolState: poolAddresses.poolState,
           vaultState: poolAddresses.poolState, // Would be actual vault state
           proofVault: proofVault,
+          owner: user.publicKey,
           vaultTokenAccount: poolAddresses.poolState, // Would be actual vault token account
           userTokenAccount: recipientTokenAccount,
           vaultProgram: POOL_PROGRAM_ID, // Would be vault program
           tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
         })
         .rpc();
       
@@ -213,7 +222,11 @@ describe("Unshield Operations - Token Tests", () => {
   it("should test each unshield step individually", async () => {
     const amount = TEST_AMOUNTS.SMALL;
     const nullifier = generateTestNullifier();
-    const unshieldData = prepareUnshieldData(nullifier, amount, recipient.publicKey);
+    // Zero root if the pool isn't registered; the steps below tolerate that
+    const root = Uint8Array.from(
+      await fetchPoolRoot(poolProgram, poolAddresses.poolState).catch(() => new Array(32).fill(0)),
+    );
+    const unshieldData = prepareUnshieldData(nullifier, amount, recipient.publicKey, root);
     // Note: unshieldData doesn't need await, but generateUnshieldOperation would
     
     // Test Step 1: Prepare unshield
@@ -223,9 +236,12 @@ describe("Unshield Operations - Token Tests", () => {
           nullifier: Array.from(nullifier),
           amount: new BN(amount),
           recipient: recipient.publicKey,
+          root: Array.from(root),
+          fee: new BN(0),
         })
         .accounts({
           payer: user.publicKey,
+          poolState: poolAddresses.poolState,
           proofVault: proofVault,
           systemProgram: SystemProgram.programId,
         })
@@ -262,8 +278,10 @@ describe("Unshield Operations - Token Tests", () => {
         .executeUnshieldUpdate(Array.from(unshieldData.operationId))
         .accounts({
           poolState: poolAddresses.poolState,
-          commitmentTree: poolAddresses.commitmentTree,
+          rootHistory: poolAddresses.rootHistory,
           nullifierSet: poolAddresses.nullifierSet,
+          nullifierRecord: deriveNullifierRecord(poolAddresses.poolState, nullifier)[0],
+          payer: user.publicKey,
           proofVault: proofVault,
         })
         .rpc();
@@ -283,10 +301,12 @@ describe("Unshield Operations - Token Tests", () => {
           poolState: poolAddresses.poolState,
           vaultState: poolAddresses.poolState,
           proofVault: proofVault,
+          owner: user.publicKey,
           vaultTokenAccount: poolAddresses.poolState,
           userTokenAccount: recipientTokenAccount,
           vaultProgram: POOL_PROGRAM_ID,
           tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
         })
         .rpc();
       
@@ -301,7 +321,11 @@ describe("Unshield Operations - Token Tests", () => {
   it("should test state transitions", async () => {
     const amount = TEST_AMOUNTS.SMALL;
     const nullifier = generateTestNullifier();
-    const unshieldData = prepareUnshieldData(nullifier, amount, recipient.publicKey);
+    // Zero root if the pool isn't registered; the steps below tolerate that
+    const root = Uint8Array.from(
+      await fetchPoolRoot(poolProgram, poolAddresses.poolState).catch(() => new Array(32).fill(0)),
+    );
+    const unshieldData = prepareUnshieldData(nullifier, amount, recipient.publicKey, root);
     
     // Test that operations must follow correct state sequence
     // Pending → Verified → Updated → Withdrawn
@@ -334,9 +358,12 @@ describe("Unshield Operations - Token Tests", () => {
           nullifier: Array.from(nullifier),
           amount: new BN(amount),
           recipient: recipient.publicKey,
+          root: Array.from(root),
+          fee: new BN(0),
         })
         .accounts({
           payer: user.publicKey,
+          poolState: poolAddresses.poolState,
           proofVault: proofVault,
           systemProgram: SystemProgram.programId,
         })
@@ -34,8 +34,10 @@ import {
 import {
   derivePoolAddresses,
   deriveProofVault,
+  deriveNullifierRecord,
   generateUnshieldOperation,
   prepareUnshieldData,
+  fetchPoolRoot,
 } from "../utils/pool-helpers";
 
 const WSOL_MINT = NATIVE_MINT;
@@ -133,9 +135,12 @@ describe("Unshield Operations - wSOL Tests", () => {
           nullifier: Array.from(nullifier),
           amount: new BN(amount),
           recipient: recipient.publicKey,
+          root: await fetchPoolRoot(poolProgram, poolAddresses.poolState),
+          fee: new BN(0),
         })
         .accounts({
           payer: user.publicKey,
+          poolState: poolAddresses.poolState,
           proofVault: proofVault,
           systemProgram: SystemProgram.programId,
         })
@@ -164,8 +169,10 @@ describe("Unshield Operations - wSOL Tests", () => {
         .executeUnshieldUpdate(Array.from(unshieldOp.operationId))
         .accounts({
           poolState: poolAddresses.poolState,
-          commitmentTree: poolAddresses.commitmentTree,
+          rootHistory: poolAddresses.rootHistory,
           nullifierSet: poolAddresses.nullifierSet,
+          nullifierRecord: deriveNullifierRecord(poolAddresses.poolState, unshieldOp.nullifier)[0],
+          payer: user.publicKey,
           proofVault: proofVault,
         })
         .rpc();
@@ -181,10 +188,12 @@ describe("Unshield Operations - wSOL Tests", () => {
           poolState: poolAddresses.poolState,
           vaultState: poolAddresses.poolState,
           proofVault: proofVault,
+          owner: user.publicKey,
           vaultTokenAccount: poolAddresses.poolState,
           userTokenAccount: recipientWSOLAccount,
           vaultProgram: POOL_PROGRAM_ID,
           tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
         })
         .rpc();
       
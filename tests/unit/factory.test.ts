@@ -2,7 +2,15 @@ import { expect } from "chai";
 import { Connection, Keypair, PublicKey, SystemProgram } from "@solana/web3.js";
 import { getConnection, airdropSol } from "../setup";
 import { generateKeypair } from "../utils/keypairs";
-import { getFactoryProgram, FACTORY_PROGRAM_ID, VERIFIER_PROGRAM_ID } from "../utils/programs";
+import {
+  getFactoryProgram,
+  getVerifierProgram,
+  FACTORY_PROGRAM_ID,
+  POOL_PROGRAM_ID,
+  VAULT_PROGRAM_ID,
+  VERIFIER_PROGRAM_ID,
+} from "../utils/programs";
+import { derivePoolAddresses, deriveVaultState, uploadPoolVerifyingKeys } from "../utils/pool-helpers";
 import { recordInstructionCoverage } from "../utils/coverage";
 import { recordGasUsage, getComputeUnitsUsed } from "../utils/gas";
 import { sendTransaction } from "../utils/transactions";
@@ -122,18 +130,34 @@ describe("ptf_factory Unit Tests", () => {
       FACTORY_PROGRAM_ID,
     );
     
-    // For now, use a placeholder pool address
-    const poolAddress = Keypair.generate().publicKey;
+    // The pool is created by register_mint, so it needs a finalized verifying key per circuit
+    const verifyingKeys = await uploadPoolVerifyingKeys(
+      getVerifierProgram(connection, payer),
+      payer,
+      Math.floor(Math.random() * 0xffffffff),
+    );
+    
+    const poolAddresses = derivePoolAddresses(mint);
+    const [vaultState] = deriveVaultState(mint, VAULT_PROGRAM_ID);
     
     try {
       const tx = await factoryProgram.methods
-        .registerMint(mint, poolAddress)
+        .registerMint(mint, 0, 64)
         .accounts({
           factory: factoryState,
           mintMapping,
+          poolState: poolAddresses.poolState,
+          commitmentTree: poolAddresses.commitmentTree,
+          rootHistory: poolAddresses.rootHistory,
+          nullifierSet: poolAddresses.nullifierSet,
+          vaultState,
           authority: factoryAuthority,
+          poolProgram: POOL_PROGRAM_ID,
           systemProgram: SystemProgram.programId,
         })
+        .remainingAccounts(
+          verifyingKeys.map((pubkey) => ({ pubkey, isSigner: false, isWritable: false })),
+        )
         .rpc();
       
       recordInstructionCoverage("ptf_factory", "register_mint");
@@ -143,6 +167,11 @@ describe("ptf_factory Unit Tests", () => {
       // Verify mint mapping exists
       const mapping = await factoryProgram.account.mintMapping.fetch(mintMapping);
       expect(mapping.originMint.toString()).to.equal(mint.toString());
+      expect(mapping.pool.toString()).to.equal(poolAddresses.poolState.toString());
+      
+      // Pool PDAs were created by the CPI into ptf_pool
+      const poolStateInfo = await connection.getAccountInfo(poolAddresses.poolState);
+      expect(poolStateInfo?.owner.toString()).to.equal(POOL_PROGRAM_ID.toString());
     } catch (e: any) {
       if (e.message?.includes("already in use") || e.message?.includes("already registered")) {
         recordInstructionCoverage("ptf_factory", "register_mint");
@@ -9,12 +9,15 @@ import {
 import {
   createMint,
   mintTo,
+  getAssociatedTokenAddressSync,
+  ASSOCIATED_TOKEN_PROGRAM_ID,
   TOKEN_PROGRAM_ID,
 } from "@solana/spl-token";
 import { BN } from "@coral-xyz/anchor";
 import { getConnection, airdropSol } from "../setup";
 import { generateKeypair } from "../utils/keypairs";
-import { getVaultProgram, VAULT_PROGRAM_ID, POOL_PROGRAM_ID } from "../utils/programs";
+import { getVaultProgram, VAULT_PROGRAM_ID } from "../utils/programs";
+import { derivePoolAddresses } from "../utils/pool-helpers";
 import { recordInstructionCoverage } from "../utils/coverage";
 import { recordGasUsage, getComputeUnitsUsed } from "../utils/gas";
 import {
@@ -33,6 +36,7 @@ describe("ptf_vault Unit Tests", () => {
   let vaultTokenAccount: PublicKey;
   let vaultState: PublicKey;
   let vaultBump: number;
+  let poolState: PublicKey;
   
   before(async () => {
     connection = getConnection();
@@ -83,207 +87,130 @@ describe("ptf_vault Unit Tests", () => {
       VAULT_PROGRAM_ID,
     );
     
-    // Vault token account is a regular token account, not an ATA
-    // It will be created by the pool program when initializing the vault
-    // For unit tests, we'll use a placeholder - the actual account will be created
-    // when the vault state is initialized by the pool program
-    // For now, we'll create a dummy keypair for the vault token account
-    // In real usage, this would be created by the pool program
-    const vaultTokenAccountKeypair = Keypair.generate();
-    vaultTokenAccount = vaultTokenAccountKeypair.publicKey;
-    
-    // Note: Vault state would normally be created by pool program
-    // For unit tests, we'll test the deposit/withdraw logic
-    // but may need vault state to exist first
+    // The vault token account is the ATA owned by the vault PDA
+    vaultTokenAccount = getAssociatedTokenAddressSync(testMint, vaultState, true);
+    poolState = derivePoolAddresses(testMint).poolState;
   });
   
-  it("should deposit tokens", async () => {
-    // First, ensure vault state exists (would be created by pool in real scenario)
-    // For testing, we'll create it if needed
-    const vaultStateInfo = await connection.getAccountInfo(vaultState);
-    if (!vaultStateInfo) {
-      // Vault state needs to be initialized - this would normally be done by pool
-      // For unit testing, we'll skip this test if vault doesn't exist
-      // or create a minimal setup
-      console.log("Vault state not initialized - would be created by pool program");
-      recordInstructionCoverage("ptf_vault", "deposit");
-      expect(true).to.be.true; // Skip for now
-      return;
-    }
-    
-    const depositAmount = 1000000; // 0.001 tokens
-    
-    // Get initial balances
-    const initialUserBalance = await getTokenBalance(connection, userTokenAccount);
-    const initialVaultBalance = await getTokenBalance(connection, vaultTokenAccount);
-    
+  it("should initialize vault", async () => {
+    const tx = await vaultProgram.methods
+      .initializeVault()
+      .accounts({
+        vault: vaultState,
+        originMint: testMint,
+        vaultTokenAccount,
+        poolState,
+        payer: payer.publicKey,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc();
+    
+    recordInstructionCoverage("ptf_vault", "initialize_vault");
+    const computeUnits = await getComputeUnitsUsed(connection, tx);
+    await recordGasUsage(connection, "ptf_vault", "initialize_vault", computeUnits);
+    
+    const vault = await vaultProgram.account.vaultState.fetch(vaultState);
+    expect(vault.originMint.toString()).to.equal(testMint.toString());
+    expect(vault.vaultTokenAccount.toString()).to.equal(vaultTokenAccount.toString());
+    expect(vault.authority.toString()).to.equal(poolState.toString());
+    expect(vault.bump).to.equal(vaultBump);
+    
+    const balance = await getTokenBalance(connection, vaultTokenAccount);
+    expect(balance.toString()).to.equal("0");
+  });
+  
+  it("should reject vault re-initialization", async () => {
     try {
-      const tx = await vaultProgram.methods
-        .deposit(new BN(depositAmount))
+      await vaultProgram.methods
+        .initializeVault()
         .accounts({
           vault: vaultState,
-          authority: POOL_PROGRAM_ID, // Pool program is the authority
-          userTokenAccount: userTokenAccount,
-          vaultTokenAccount: vaultTokenAccount,
-          userAuthority: payer.publicKey,
+          originMint: testMint,
+          vaultTokenAccount,
+          poolState,
+          payer: payer.publicKey,
           tokenProgram: TOKEN_PROGRAM_ID,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
         })
         .rpc();
       
-      recordInstructionCoverage("ptf_vault", "deposit");
-      const computeUnits = await getComputeUnitsUsed(connection, tx);
-      await recordGasUsage(connection, "ptf_vault", "deposit", computeUnits);
-      
-      // Verify balances
-      const finalUserBalance = await getTokenBalance(connection, userTokenAccount);
-      const finalVaultBalance = await getTokenBalance(connection, vaultTokenAccount);
-      
-      expect(finalUserBalance.toString()).to.equal(
-        (initialUserBalance - BigInt(depositAmount)).toString(),
-      );
-      expect(finalVaultBalance.toString()).to.equal(
-        (initialVaultBalance + BigInt(depositAmount)).toString(),
-      );
+      expect.fail("Should have failed with AlreadyInitialized");
     } catch (e: any) {
-      // If vault state doesn't exist or authority is wrong, that's expected
-      if (e.message?.includes("InvalidAuthority") || e.message?.includes("AccountNotFound")) {
-        recordInstructionCoverage("ptf_vault", "deposit");
-        expect(true).to.be.true; // Test structure is correct
-      } else {
-        throw e;
-      }
+      expect(e.message).to.include("AlreadyInitialized");
     }
   });
   
-  it("should withdraw tokens", async () => {
-    const vaultStateInfo = await connection.getAccountInfo(vaultState);
-    if (!vaultStateInfo) {
-      console.log("Vault state not initialized - would be created by pool program");
-      recordInstructionCoverage("ptf_vault", "withdraw");
-      expect(true).to.be.true;
-      return;
-    }
-    
-    const withdrawAmount = 500000; // 0.0005 tokens
-    
-    // Ensure vault has tokens first
-    const vaultBalance = await getTokenBalance(connection, vaultTokenAccount);
-    if (vaultBalance < BigInt(withdrawAmount)) {
-      // Deposit first
-      try {
-        await vaultProgram.methods
-          .deposit(new BN(withdrawAmount * 2))
-          .accounts({
-            vault: vaultState,
-            authority: POOL_PROGRAM_ID,
-            userTokenAccount: userTokenAccount,
-            vaultTokenAccount: vaultTokenAccount,
-            userAuthority: payer.publicKey,
-            tokenProgram: TOKEN_PROGRAM_ID,
-          })
-          .rpc();
-      } catch (e) {
-        // If deposit fails, skip withdraw test
-        recordInstructionCoverage("ptf_vault", "withdraw");
-        expect(true).to.be.true;
-        return;
-      }
-    }
-    
-    const initialUserBalance = await getTokenBalance(connection, userTokenAccount);
-    const initialVaultBalance = await getTokenBalance(connection, vaultTokenAccount);
+  it("should reject deposit not signed by the pool PDA", async () => {
+    // The pool PDA can only sign through invoke_signed; any other signer is refused
+    const impostor = generateKeypair();
     
     try {
-      const tx = await vaultProgram.methods
-        .withdraw(new BN(withdrawAmount))
+      await vaultProgram.methods
+        .deposit(new BN(1000000))
         .accounts({
           vault: vaultState,
-          authority: POOL_PROGRAM_ID,
-          vaultTokenAccount: vaultTokenAccount,
+          authority: impostor.publicKey,
           userTokenAccount: userTokenAccount,
+          vaultTokenAccount: vaultTokenAccount,
+          userAuthority: payer.publicKey,
           tokenProgram: TOKEN_PROGRAM_ID,
         })
+        .signers([impostor])
         .rpc();
       
-      recordInstructionCoverage("ptf_vault", "withdraw");
-      const computeUnits = await getComputeUnitsUsed(connection, tx);
-      await recordGasUsage(connection, "ptf_vault", "withdraw", computeUnits);
-      
-      // Verify balances
-      const finalUserBalance = await getTokenBalance(connection, userTokenAccount);
-      const finalVaultBalance = await getTokenBalance(connection, vaultTokenAccount);
-      
-      expect(finalUserBalance.toString()).to.equal(
-        (initialUserBalance + BigInt(withdrawAmount)).toString(),
-      );
-      expect(finalVaultBalance.toString()).to.equal(
-        (initialVaultBalance - BigInt(withdrawAmount)).toString(),
-      );
+      expect.fail("Should have failed with InvalidAuthority");
     } catch (e: any) {
-      if (e.message?.includes("InvalidAuthority") || e.message?.includes("AccountNotFound")) {
-        recordInstructionCoverage("ptf_vault", "withdraw");
-        expect(true).to.be.true;
-      } else {
-        throw e;
-      }
+      recordInstructionCoverage("ptf_vault", "deposit");
+      expect(e.message).to.include("InvalidAuthority");
     }
+    
+    const balance = await getTokenBalance(connection, vaultTokenAccount);
+    expect(balance.toString()).to.equal("0");
   });
   
-  it("should fail deposit with insufficient balance", async () => {
-    const vaultStateInfo = await connection.getAccountInfo(vaultState);
-    if (!vaultStateInfo) {
-      expect(true).to.be.true;
-      return;
-    }
-    
-    const hugeAmount = 999999999999999; // Way more than user has
+  it("should reject withdraw not signed by the pool PDA", async () => {
+    const impostor = generateKeypair();
     
     try {
       await vaultProgram.methods
-        .deposit(new BN(hugeAmount))
+        .withdraw(new BN(1000))
         .accounts({
           vault: vaultState,
-          authority: POOL_PROGRAM_ID,
-          userTokenAccount: userTokenAccount,
+          authority: impostor.publicKey,
           vaultTokenAccount: vaultTokenAccount,
-          userAuthority: payer.publicKey,
+          userTokenAccount: userTokenAccount,
           tokenProgram: TOKEN_PROGRAM_ID,
         })
+        .signers([impostor])
         .rpc();
       
-      expect.fail("Should have failed with insufficient balance");
+      expect.fail("Should have failed with InvalidAuthority");
     } catch (e: any) {
-      // Expected to fail
-      expect(e.message).to.include("insufficient");
+      recordInstructionCoverage("ptf_vault", "withdraw");
+      expect(e.message).to.include("InvalidAuthority");
     }
   });
   
-  it("should fail withdraw with invalid authority", async () => {
-    const vaultStateInfo = await connection.getAccountInfo(vaultState);
-    if (!vaultStateInfo) {
-      expect(true).to.be.true;
-      return;
-    }
-    
-    const invalidAuthority = generateKeypair().publicKey;
-    
+  it("should reject withdraw naming the pool PDA without its signature", async () => {
+    // Passing the real authority key is not enough; it must sign
     try {
       await vaultProgram.methods
         .withdraw(new BN(1000))
         .accounts({
           vault: vaultState,
-          authority: invalidAuthority,
+          authority: poolState,
           vaultTokenAccount: vaultTokenAccount,
           userTokenAccount: userTokenAccount,
           tokenProgram: TOKEN_PROGRAM_ID,
         })
         .rpc();
       
-      expect.fail("Should have failed with invalid authority");
+      expect.fail("Should have failed without the pool PDA signature");
     } catch (e: any) {
-      // Expected to fail
-      expect(e.message).to.include("authority") || expect(e.message).to.include("InvalidAuthority");
+      expect(e.message).to.match(/[Ss]ignature|signer/);
     }
   });
 });
-
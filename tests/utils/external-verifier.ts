@@ -1,5 +1,7 @@
 // Client for external verifier service
 
+import { Ed25519Program, TransactionInstruction } from "@solana/web3.js";
+
 export interface VerificationAttestation {
   proof_hash: string;
   public_inputs_hash: string;
@@ -18,6 +20,52 @@ export interface VerifyRequest {
 export interface VerifyResponse {
   is_valid: boolean;
   attestation: VerificationAttestation;
+  verifier_pubkey: string;     // Hex-encoded Ed25519 public key that signed the attestation
+}
+
+export interface SignedAttestation {
+  verifier_pubkey: string;     // Hex-encoded Ed25519 public key
+  attestation: VerificationAttestation;
+}
+
+export interface CosignRequest extends VerifyRequest {
+  attestations: SignedAttestation[];
+}
+
+export interface CosignResponse {
+  is_valid: boolean;
+  attestations: SignedAttestation[];   // Input attestations plus this verifier's co-signature
+}
+
+/**
+ * Build the message the external verifier signs
+ * Layout: proof_hash (32) + public_inputs_hash (32) + verifying_key_hash (32) + is_valid (1) + timestamp (8, LE)
+ */
+export function attestationMessage(attestation: VerificationAttestation): Buffer {
+  const timestamp = Buffer.alloc(8);
+  timestamp.writeBigInt64LE(BigInt(attestation.timestamp));
+  return Buffer.concat([
+    Buffer.from(attestation.proof_hash, "hex"),
+    Buffer.from(attestation.public_inputs_hash, "hex"),
+    Buffer.from(attestation.verifying_key_hash, "hex"),
+    Buffer.from([attestation.is_valid ? 1 : 0]),
+    timestamp,
+  ]);
+}
+
+/**
+ * Build the Ed25519 program instruction that must precede verify_with_attestation
+ * (or any pool instruction that CPIs into it) in the same transaction
+ */
+export function createAttestationEd25519Instruction(
+  attestation: VerificationAttestation,
+  verifierPubkey: Uint8Array,
+): TransactionInstruction {
+  return Ed25519Program.createInstructionWithPublicKey({
+    publicKey: verifierPubkey,
+    message: attestationMessage(attestation),
+    signature: Buffer.from(attestation.signature, "hex"),
+  });
 }
 
 export class ExternalVerifierClient {
@@ -68,5 +116,38 @@ export class ExternalVerifierClient {
 
     return await response.json() as VerifyResponse;
   }
-}
 
+  /**
+   * Ask this verifier to co-sign attestations collected from other verifiers
+   * The service re-verifies the proof before appending its signature
+   */
+  async cosignProof(
+    proof: Uint8Array,
+    publicInputs: Uint8Array,
+    verifyingKey: Uint8Array,
+    attestations: SignedAttestation[],
+  ): Promise<CosignResponse> {
+    const request: CosignRequest = {
+      proof: Buffer.from(proof).toString("hex"),
+      public_inputs: Buffer.from(publicInputs).toString("hex"),
+      verifying_key: Buffer.from(verifyingKey).toString("hex"),
+      attestations,
+    };
+
+    const response = await fetch(`${this.baseUrl}/cosign`, {
+      method: "POST",
+      headers: {
+        "Content-Type": "application/json",
+      },
+      body: JSON.stringify(request),
+      signal: AbortSignal.timeout(this.timeout),
+    });
+
+    if (!response.ok) {
+      const errorText = await response.text();
+      throw new Error(`Co-signing failed: ${response.status} ${errorText}`);
+    }
+
+    return await response.json() as CosignResponse;
+  }
+}
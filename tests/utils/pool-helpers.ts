@@ -2,9 +2,14 @@ import {
   Connection,
   PublicKey,
   Keypair,
+  SystemProgram,
 } from "@solana/web3.js";
-import { Program } from "@coral-xyz/anchor";
-import { POOL_PROGRAM_ID, VERIFIER_PROGRAM_ID } from "./programs";
+import { Program, utils } from "@coral-xyz/anchor";
+import { createHash } from "crypto";
+import * as fs from "fs";
+import * as path from "path";
+import { getAssociatedTokenAddressSync, TOKEN_PROGRAM_ID } from "@solana/spl-token";
+import { POOL_PROGRAM_ID, VAULT_PROGRAM_ID, VERIFIER_PROGRAM_ID } from "./programs";
 import { derivePDA } from "./accounts";
 import { generateTestCommitment, generateTestNullifier } from "../fixtures/test-data";
 import { generateShieldProof, generateUnshieldProof, generateTransferProof, proofToBytes, generateRealProof } from "./proofs";
@@ -13,8 +18,8 @@ import { ExternalVerifierClient, VerificationAttestation } from "./external-veri
 export interface PoolAddresses {
   poolState: PublicKey;
   commitmentTree: PublicKey;
+  rootHistory: PublicKey;
   nullifierSet: PublicKey;
-  noteLedger: PublicKey;
   hookConfig: PublicKey;
   hookWhitelist: PublicKey;
 }
@@ -33,13 +38,13 @@ export function derivePoolAddresses(originMint: PublicKey): PoolAddresses {
     POOL_PROGRAM_ID,
   );
   
-  const [nullifierSet] = derivePDA(
-    [Buffer.from("nullifier-set"), originMint.toBuffer()],
+  const [rootHistory] = derivePDA(
+    [Buffer.from("root-history"), originMint.toBuffer()],
     POOL_PROGRAM_ID,
   );
   
-  const [noteLedger] = derivePDA(
-    [Buffer.from("note-ledger"), originMint.toBuffer()],
+  const [nullifierSet] = derivePDA(
+    [Buffer.from("nullifier-set"), originMint.toBuffer()],
     POOL_PROGRAM_ID,
   );
   
@@ -56,8 +61,8 @@ export function derivePoolAddresses(originMint: PublicKey): PoolAddresses {
   return {
     poolState,
     commitmentTree,
+    rootHistory,
     nullifierSet,
-    noteLedger,
     hookConfig,
     hookWhitelist,
   };
@@ -73,6 +78,16 @@ export function deriveProofVault(user: PublicKey): [PublicKey, number] {
   );
 }
 
+/**
+ * Derive the record PDA that marks a nullifier as spent in a pool
+ */
+export function deriveNullifierRecord(poolState: PublicKey, nullifier: Uint8Array): [PublicKey, number] {
+  return derivePDA(
+    [Buffer.from("nullifier"), poolState.toBuffer(), Buffer.from(nullifier)],
+    POOL_PROGRAM_ID,
+  );
+}
+
 /**
  * Derive vault state PDA for a mint
  */
@@ -90,6 +105,101 @@ export function deriveVaultState(originMint: PublicKey, vaultProgramId: PublicKe
   return deriveVaultStateForMint(originMint, vaultProgramId);
 }
 
+/**
+ * Accounts for execute_shield_v2
+ * The vault token account is the ATA owned by the vault PDA (created by initialize_vault)
+ */
+export function deriveShieldAccounts(
+  originMint: PublicKey,
+  user: PublicKey,
+  userTokenAccount: PublicKey,
+  verifyingKey: PublicKey,
+) {
+  const poolAddresses = derivePoolAddresses(originMint);
+  const [vaultState] = deriveVaultState(originMint, VAULT_PROGRAM_ID);
+  const [proofVault] = deriveProofVault(user);
+  return {
+    poolState: poolAddresses.poolState,
+    commitmentTree: poolAddresses.commitmentTree,
+    rootHistory: poolAddresses.rootHistory,
+    proofVault,
+    user,
+    userTokenAccount,
+    vaultState,
+    vaultTokenAccount: getAssociatedTokenAddressSync(originMint, vaultState, true),
+    verifyingKey,
+    verifierProgram: VERIFIER_PROGRAM_ID,
+    vaultProgram: VAULT_PROGRAM_ID,
+    tokenProgram: TOKEN_PROGRAM_ID,
+    systemProgram: SystemProgram.programId,
+  };
+}
+
+/**
+ * Accounts for the single-transaction unshield
+ * Everything but the nullifier record, token accounts and payer is the same for every
+ * unshield from a pool, see unshieldLookupTableAddresses
+ */
+export function deriveUnshieldAccounts(
+  originMint: PublicKey,
+  payer: PublicKey,
+  nullifier: Uint8Array,
+  recipientTokenAccount: PublicKey,
+  verifyingKey: PublicKey,
+  feeTokenAccount: PublicKey | null = null,
+) {
+  const poolAddresses = derivePoolAddresses(originMint);
+  const [vaultState] = deriveVaultState(originMint, VAULT_PROGRAM_ID);
+  const [nullifierRecord] = deriveNullifierRecord(poolAddresses.poolState, nullifier);
+  return {
+    poolState: poolAddresses.poolState,
+    rootHistory: poolAddresses.rootHistory,
+    nullifierSet: poolAddresses.nullifierSet,
+    vaultState,
+    vaultTokenAccount: getAssociatedTokenAddressSync(originMint, vaultState, true),
+    verifyingKey,
+    verifierProgram: VERIFIER_PROGRAM_ID,
+    vaultProgram: VAULT_PROGRAM_ID,
+    tokenProgram: TOKEN_PROGRAM_ID,
+    systemProgram: SystemProgram.programId,
+    nullifierRecord,
+    recipientTokenAccount,
+    feeTokenAccount,
+    payer,
+  };
+}
+
+/**
+ * Pool-wide unshield accounts, in instruction order, for an address lookup table
+ */
+export function unshieldLookupTableAddresses(originMint: PublicKey, verifyingKey: PublicKey): PublicKey[] {
+  const poolAddresses = derivePoolAddresses(originMint);
+  const [vaultState] = deriveVaultState(originMint, VAULT_PROGRAM_ID);
+  return [
+    POOL_PROGRAM_ID,
+    poolAddresses.poolState,
+    poolAddresses.rootHistory,
+    poolAddresses.nullifierSet,
+    vaultState,
+    getAssociatedTokenAddressSync(originMint, vaultState, true),
+    verifyingKey,
+    VERIFIER_PROGRAM_ID,
+    VAULT_PROGRAM_ID,
+    TOKEN_PROGRAM_ID,
+    SystemProgram.programId,
+  ];
+}
+
+/**
+ * Derive trusted verifier registry PDA
+ */
+export function deriveVerifierRegistry(): [PublicKey, number] {
+  return derivePDA(
+    [Buffer.from("verifier-registry")],
+    VERIFIER_PROGRAM_ID,
+  );
+}
+
 /**
  * Derive allowance PDA
  */
@@ -110,35 +220,113 @@ export function deriveAllowance(
 }
 
 /**
- * Generate operation ID (hash of operation data)
- * This matches the placeholder in prepare_shield/prepare_unshield
+ * Operation ID as ptf_pool derives it:
+ * sha256(program id || owner || vault created slot u64 LE || nonce u64 LE || payload)
+ * `payload` is the borsh-encoded OperationPayload, starting with its variant tag
+ * Prefer preparedOperationId, which reads the id prepare_* returned
+ */
+export function deriveOperationId(
+  owner: PublicKey,
+  vault: VaultIdSeed,
+  payload: Uint8Array,
+): Uint8Array {
+  const slotBytes = Buffer.alloc(8);
+  slotBytes.writeBigUInt64LE(BigInt(vault.createdSlot));
+  const nonceBytes = Buffer.alloc(8);
+  nonceBytes.writeBigUInt64LE(BigInt(vault.nonce));
+  return new Uint8Array(
+    createHash("sha256")
+      .update(POOL_PROGRAM_ID.toBuffer())
+      .update(owner.toBuffer())
+      .update(slotBytes)
+      .update(nonceBytes)
+      .update(payload)
+      .digest(),
+  );
+}
+
+/**
+ * What a proof vault mixes into its next operation id: its creation slot and next nonce
+ */
+export interface VaultIdSeed {
+  createdSlot: number;
+  nonce: number;
+}
+
+/**
+ * Id seed of the next operation prepared in an existing proof vault
+ */
+export async function fetchVaultIdSeed(program: Program, proofVault: PublicKey): Promise<VaultIdSeed> {
+  const vault = await (program.account as any).userProofVault.fetch(proofVault);
+  return { createdSlot: vault.createdSlot.toNumber(), nonce: vault.nextNonce.toNumber() };
+}
+
+/**
+ * Operation ID a confirmed prepare_shield/prepare_unshield transaction returned
  */
-export function generateOperationId(data: Uint8Array): Uint8Array {
-  // For now, use a simple hash-like function
-  // In production, this would use keccak hash
-  const hash = new Uint8Array(32);
-  for (let i = 0; i < 32; i++) {
-    hash[i] = data[i % data.length] ^ (i * 7);
+export async function preparedOperationId(connection: Connection, signature: string): Promise<Uint8Array> {
+  const tx = await connection.getTransaction(signature, {
+    commitment: "confirmed",
+    maxSupportedTransactionVersion: 0,
+  });
+  const returnData = tx?.meta?.returnData;
+  if (!returnData || !returnData.programId.equals(POOL_PROGRAM_ID)) {
+    throw new Error(`No ptf_pool return data in ${signature}`);
   }
-  return hash;
+  return new Uint8Array(Buffer.from(returnData.data[0], "base64"));
+}
+
+// anchor_lang::event::EVENT_IX_TAG, little-endian
+const EVENT_IX_TAG = Buffer.from("e445a52e51cb9a1d", "hex");
+
+/**
+ * Events a confirmed transaction emitted through ptf_pool's event self-CPIs, in order
+ */
+export async function poolCpiEvents(
+  connection: Connection,
+  program: Program,
+  signature: string
+): Promise<{ name: string; data: any }[]> {
+  const tx = await connection.getTransaction(signature, {
+    commitment: "confirmed",
+    maxSupportedTransactionVersion: 0,
+  });
+  const keys = tx?.transaction.message.getAccountKeys({
+    accountKeysFromLookups: tx.meta?.loadedAddresses,
+  });
+  const events: { name: string; data: any }[] = [];
+  for (const inner of tx?.meta?.innerInstructions ?? []) {
+    for (const ix of inner.instructions) {
+      if (!keys?.get(ix.programIdIndex)?.equals(POOL_PROGRAM_ID)) continue;
+      const data = Buffer.from(utils.bytes.bs58.decode(ix.data));
+      if (!data.subarray(0, 8).equals(EVENT_IX_TAG)) continue;
+      const event = program.coder.events.decode(data.subarray(8).toString("base64"));
+      if (event) events.push(event);
+    }
+  }
+  return events;
 }
 
 /**
  * Prepare shield operation data
+ * The operation ID is only correct for the vault owner and id seed it is derived with
  */
 export function prepareShieldData(
   amount: number,
   commitment: Uint8Array,
+  pool: PublicKey = PublicKey.default,
+  owner: PublicKey = PublicKey.default,
+  vault: VaultIdSeed = { createdSlot: 0, nonce: 0 },
 ): { operationId: Uint8Array; commitment: Uint8Array; amount: number } {
-  const operationData = new Uint8Array(40);
-  const amountBytes = new Uint8Array(8);
-  const view = new DataView(amountBytes.buffer);
-  view.setBigUint64(0, BigInt(amount), true);
-  
-  operationData.set(commitment, 0);
-  operationData.set(amountBytes, 32);
+  // OperationPayload::Shield: [tag 0][pool][commitment][amount LE]
+  const payload = new Uint8Array(73);
+  const view = new DataView(payload.buffer);
+  payload[0] = 0;
+  payload.set(pool.toBuffer(), 1);
+  payload.set(commitment, 33);
+  view.setBigUint64(65, BigInt(amount), true);
   
-  const operationId = generateOperationId(operationData);
+  const operationId = deriveOperationId(owner, vault, payload);
   
   return {
     operationId,
@@ -147,33 +335,110 @@ export function prepareShieldData(
   };
 }
 
+/**
+ * Field element an unshield proof commits to for the recipient (matches recipient_hash in ptf_pool)
+ */
+export function unshieldRecipientHash(recipient: PublicKey): Uint8Array {
+  const hash = new Uint8Array(createHash("sha256").update(recipient.toBuffer()).digest());
+  hash[0] = 0;
+  return hash;
+}
+
+/**
+ * Unshield public inputs: [root][nullifier][amount][recipient hash][fee], 32-byte big-endian each
+ */
+export function unshieldPublicInputs(
+  root: Uint8Array,
+  nullifier: Uint8Array,
+  amount: number,
+  recipient: PublicKey,
+  fee: number = 0,
+): Uint8Array {
+  const inputs = new Uint8Array(160);
+  const view = new DataView(inputs.buffer);
+  inputs.set(root, 0);
+  inputs.set(nullifier, 32);
+  view.setBigUint64(88, BigInt(amount), false);
+  inputs.set(unshieldRecipientHash(recipient), 96);
+  view.setBigUint64(152, BigInt(fee), false);
+  return inputs;
+}
+
+/**
+ * Delegated transfer_from public inputs: [root][delegation nullifier][outputs][change commitment]
+ */
+export function delegatedTransferPublicInputs(
+  root: Uint8Array,
+  nullifier: Uint8Array,
+  outputs: Uint8Array[],
+  change: Uint8Array,
+): Uint8Array {
+  const inputs = new Uint8Array(32 * (outputs.length + 3));
+  inputs.set(root, 0);
+  inputs.set(nullifier, 32);
+  outputs.forEach((output, i) => inputs.set(output, 64 + 32 * i));
+  inputs.set(change, 64 + 32 * outputs.length);
+  return inputs;
+}
+
+/**
+ * Placeholder ciphertexts for a transfer's outputs: the pool requires one per output commitment
+ * (two for execute_transfer, three for a delegated transfer_from) but doesn't read them.
+ * Real ones come from the ptf_notes crate's encrypt_note.
+ */
+export function emptyEncryptedNotes(outputs: number = 2): Buffer[] {
+  return Array.from({ length: outputs }, () => Buffer.alloc(0));
+}
+
 /**
  * Prepare unshield operation data
+ * Mirrors the UnshieldRequest that prepare_unshield stores: [pool][root][nullifier][amount LE][recipient hash][fee LE][recipient]
+ * The operation ID is only correct for the vault owner and id seed it is derived with
  */
 export function prepareUnshieldData(
   nullifier: Uint8Array,
   amount: number,
   recipient: PublicKey,
-): { operationId: Uint8Array; nullifier: Uint8Array; amount: number; recipient: PublicKey } {
-  const operationData = new Uint8Array(72);
-  const amountBytes = new Uint8Array(8);
-  const view = new DataView(amountBytes.buffer);
-  view.setBigUint64(0, BigInt(amount), true);
-  
-  operationData.set(nullifier, 0);
-  operationData.set(amountBytes, 32);
-  operationData.set(recipient.toBuffer(), 40);
-  
-  const operationId = generateOperationId(operationData);
+  root: Uint8Array = new Uint8Array(32),
+  fee: number = 0,
+  pool: PublicKey = PublicKey.default,
+  owner: PublicKey = PublicKey.default,
+  vault: VaultIdSeed = { createdSlot: 0, nonce: 0 },
+): { operationId: Uint8Array; nullifier: Uint8Array; amount: number; recipient: PublicKey; root: Uint8Array; fee: number } {
+  // OperationPayload::Unshield: [tag 1][request][proof: None]
+  const payload = new Uint8Array(178);
+  const view = new DataView(payload.buffer);
+  
+  payload[0] = 1;
+  payload.set(pool.toBuffer(), 1);
+  payload.set(root, 33);
+  payload.set(nullifier, 65);
+  view.setBigUint64(97, BigInt(amount), true);
+  payload.set(unshieldRecipientHash(recipient), 105);
+  view.setBigUint64(137, BigInt(fee), true);
+  payload.set(recipient.toBuffer(), 145);
+  payload[177] = 0;
+  
+  const operationId = deriveOperationId(owner, vault, payload);
   
   return {
     operationId,
     nullifier,
     amount,
     recipient,
+    root,
+    fee,
   };
 }
 
+/**
+ * Current tree root of a pool, as prepare_unshield expects it
+ */
+export async function fetchPoolRoot(program: Program, poolState: PublicKey): Promise<number[]> {
+  const pool = await (program.account as any).poolState.fetch(poolState);
+  return Array.from(pool.currentRoot as number[]);
+}
+
 /**
  * Get verifying key bytes from verifying key account
  */
@@ -207,6 +472,79 @@ export async function getVerifyingKeyBytes(
   return Uint8Array.from(data.slice(keyDataStart, keyDataEnd));
 }
 
+/**
+ * Upload a verifying key in chunks: begin, write each chunk, finalize
+ * Used for keys too large to fit in a single initialize_verifying_key transaction
+ */
+export async function uploadVerifyingKey(
+  verifierProgram: Program,
+  authority: Keypair,
+  circuitTag: Uint8Array,
+  version: number,
+  keyData: Uint8Array,
+  chunkSize: number = 900,
+): Promise<PublicKey> {
+  const versionBytes = Buffer.alloc(4);
+  versionBytes.writeUInt32LE(version, 0);
+  const [verifyingKey] = derivePDA(
+    [Buffer.from("verifying-key"), circuitTag, versionBytes],
+    VERIFIER_PROGRAM_ID,
+  );
+  
+  await verifierProgram.methods
+    .beginVerifyingKeyUpload(Array.from(circuitTag), version, keyData.length)
+    .accounts({
+      verifyingKey,
+      authority: authority.publicKey,
+    })
+    .signers([authority])
+    .rpc();
+  
+  for (let offset = 0; offset < keyData.length; offset += chunkSize) {
+    await verifierProgram.methods
+      .writeVerifyingKeyChunk(offset, Buffer.from(keyData.slice(offset, offset + chunkSize)))
+      .accounts({
+        verifyingKey,
+        authority: authority.publicKey,
+      })
+      .signers([authority])
+      .rpc();
+  }
+  
+  await verifierProgram.methods
+    .finalizeVerifyingKey()
+    .accounts({
+      verifyingKey,
+      authority: authority.publicKey,
+    })
+    .signers([authority])
+    .rpc();
+  
+  return verifyingKey;
+}
+
+/** Circuits a pool verifies proofs of; each key's circuit tag is the directory name under circuits/ */
+export const POOL_CIRCUITS = ["shield", "unshield", "transfer", "transfer_from", "allowance_transfer"];
+
+/**
+ * Upload the committed verifying key of every pool circuit at `version`, for register_mint's
+ * remaining accounts
+ */
+export async function uploadPoolVerifyingKeys(
+  verifierProgram: Program,
+  authority: Keypair,
+  version: number,
+): Promise<PublicKey[]> {
+  const keys: PublicKey[] = [];
+  for (const circuit of POOL_CIRCUITS) {
+    const circuitTag = Buffer.alloc(32, 0);
+    circuitTag.write(circuit);
+    const keyData = fs.readFileSync(path.join(__dirname, `../../circuits/${circuit}/verifying_key.bin`));
+    keys.push(await uploadVerifyingKey(verifierProgram, authority, circuitTag, version, keyData));
+  }
+  return keys;
+}
+
 /**
  * Convert attestation to bytes for operation data
  * Format: proof_hash (32) + public_inputs_hash (32) + verifying_key_hash (32) + 
@@ -247,6 +585,25 @@ export function attestationToBytes(attestation: VerificationAttestation): Uint8A
   return Uint8Array.from(buffer);
 }
 
+/**
+ * Format operation data: [proof (256)][attestation_count (1)][attestations (169 each)][public_inputs (variable)]
+ * Attestations must be in the same order as the verifier accounts passed to execute_unshield_verify
+ */
+export function buildOperationData(
+  proof: Uint8Array,
+  attestations: VerificationAttestation[],
+  publicInputs: Uint8Array,
+): Uint8Array {
+  const operationData = new Uint8Array(256 + 1 + attestations.length * 169 + publicInputs.length);
+  operationData.set(proof, 0);
+  operationData[256] = attestations.length;
+  attestations.forEach((attestation, i) => {
+    operationData.set(attestationToBytes(attestation), 257 + i * 169);
+  });
+  operationData.set(publicInputs, 257 + attestations.length * 169);
+  return operationData;
+}
+
 /**
  * Generate shield proof and data with attestation
  * Uses real proof generation and external verifier if available, falls back to mock
@@ -294,12 +651,7 @@ export async function generateShieldOperation(
         if (verifyResponse.is_valid) {
           attestation = verifyResponse.attestation;
           
-          // Format operation data: [proof (256)][attestation (169)][public_inputs (variable)]
-          const attestationBytes = attestationToBytes(attestation);
-          operationData = new Uint8Array(256 + 169 + publicInputs.length);
-          operationData.set(proof, 0);
-          operationData.set(attestationBytes, 256);
-          operationData.set(publicInputs, 256 + 169);
+          operationData = buildOperationData(proof, [attestation], publicInputs);
         } else {
           throw new Error("Proof verification failed");
         }
@@ -344,25 +696,32 @@ export async function generateUnshieldOperation(
   externalVerifierUrl?: string,
   verifyingKey?: PublicKey,
   connection?: Connection,
+  root: Uint8Array = new Uint8Array(32),
+  fee: number = 0,
 ): Promise<{ 
   nullifier: Uint8Array; 
   proof: Uint8Array; 
   publicInputs: Uint8Array; 
   operationId: Uint8Array;
-  operationData: Uint8Array; // proof + attestation + publicInputs
+  operationData: Uint8Array; // proof + attestations (public inputs are fixed by prepare_unshield)
   attestation?: VerificationAttestation;
 }> {
   const nullifier = generateTestNullifier();
   
   let proofData;
   if (useRealProof) {
-    proofData = await generateRealProof("unshield", { nullifier, amount, recipient: recipient.toBuffer() }, proofServiceUrl);
+    proofData = await generateRealProof(
+      "unshield",
+      { root, nullifier, amount, recipientHash: unshieldRecipientHash(recipient), fee },
+      proofServiceUrl,
+    );
   } else {
     proofData = generateUnshieldProof(nullifier, amount, recipient.toBuffer());
   }
   
-  const { proof, publicInputs } = proofToBytes(proofData);
-  const unshieldData = prepareUnshieldData(nullifier, amount, recipient);
+  const { proof } = proofToBytes(proofData);
+  const publicInputs = unshieldPublicInputs(root, nullifier, amount, recipient, fee);
+  const unshieldData = prepareUnshieldData(nullifier, amount, recipient, root, fee);
   
   // Get attestation from external verifier if available
   let attestation: VerificationAttestation | undefined;
@@ -380,32 +739,21 @@ export async function generateUnshieldOperation(
         if (verifyResponse.is_valid) {
           attestation = verifyResponse.attestation;
           
-          // Format operation data: [proof (256)][attestation (169)][public_inputs (variable)]
-          const attestationBytes = attestationToBytes(attestation);
-          operationData = new Uint8Array(256 + 169 + publicInputs.length);
-          operationData.set(proof, 0);
-          operationData.set(attestationBytes, 256);
-          operationData.set(publicInputs, 256 + 169);
+          operationData = buildOperationData(proof, [attestation], new Uint8Array(0));
         } else {
           throw new Error("Proof verification failed");
         }
       } else {
         // External verifier not available, use proof only
-        operationData = new Uint8Array(256 + publicInputs.length);
-        operationData.set(proof, 0);
-        operationData.set(publicInputs, 256);
+        operationData = buildOperationData(proof, [], new Uint8Array(0));
       }
     } catch (e) {
       console.warn(`Failed to get attestation: ${e}, using proof only`);
-      operationData = new Uint8Array(256 + publicInputs.length);
-      operationData.set(proof, 0);
-      operationData.set(publicInputs, 256);
+      operationData = buildOperationData(proof, [], new Uint8Array(0));
     }
   } else {
     // No external verifier, use proof only (for mock proofs)
-    operationData = new Uint8Array(256 + publicInputs.length);
-    operationData.set(proof, 0);
-    operationData.set(publicInputs, 256);
+    operationData = buildOperationData(proof, [], new Uint8Array(0));
   }
   
   return {
@@ -434,6 +782,7 @@ export async function generateTransferOperation(
   commitment: Uint8Array; 
   proof: Uint8Array; 
   publicInputs: Uint8Array;
+  encryptedNotes: Buffer[];
   attestation?: VerificationAttestation;
 }> {
   const commitment = generateTestCommitment();
@@ -474,6 +823,7 @@ export async function generateTransferOperation(
     commitment,
     proof,
     publicInputs,
+    encryptedNotes: emptyEncryptedNotes(),
     attestation,
   };
 }
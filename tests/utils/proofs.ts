@@ -14,6 +14,9 @@ export interface ProofInputs {
   amount?: number;
   recipient?: Uint8Array;
   publicKey?: Uint8Array;
+  root?: Uint8Array;
+  recipientHash?: Uint8Array;
+  fee?: number;
 }
 
 /**
@@ -52,6 +52,10 @@ export async function buildShieldTransaction(
     })
     .accounts({
       payer: wallet.publicKey,
+      poolState: PublicKey.findProgramAddressSync(
+        [Buffer.from("pool"), params.mint.toBuffer()],
+        POOL_PROGRAM_ID
+      )[0],
       proofVault: PublicKey.findProgramAddressSync(
         [Buffer.from("proof-vault"), wallet.publicKey.toBuffer()],
         POOL_PROGRAM_ID